@@ -0,0 +1,415 @@
+use std::{fmt::Write, net::IpAddr, time::Duration};
+
+use bevy::{ecs::system::SystemState, prelude::*};
+use clap::{Parser, Subcommand};
+use hyperion::{
+    net::{ClientAddress, Compose, ConnectionId},
+    simulation::{IgnMap, Uuid},
+};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::reply};
+use ipnet::IpNet;
+use tracing::error;
+
+use crate::{Ban, BanStorage, kick};
+
+const DEFAULT_REASON: &str = "Banned by an operator";
+
+/// The name of the player who ran a command, which is stored as the source of bans
+fn source(world: &World, caller: Entity) -> String {
+    world
+        .entity(caller)
+        .get::<Name>()
+        .map_or_else(|| "unknown".to_string(), ToString::to_string)
+}
+
+/// A player given by the name of an online player or by UUID
+struct Target {
+    uuid: uuid::Uuid,
+    name: String,
+    entity: Option<Entity>,
+}
+
+/// Finds a player by name if they are online, or by UUID, telling the caller if neither matches
+fn find_target(world: &World, caller: Entity, player: &str) -> Option<Target> {
+    if let Some(&entity) = world.resource::<IgnMap>().get(player) {
+        let Some(uuid) = world.entity(entity).get::<Uuid>() else {
+            error!("failed to find player: player is missing Uuid component");
+            return None;
+        };
+
+        return Some(Target {
+            uuid: **uuid,
+            name: player.to_string(),
+            entity: Some(entity),
+        });
+    }
+
+    if let Ok(uuid) = uuid::Uuid::parse_str(player) {
+        return Some(Target {
+            uuid,
+            name: uuid.to_string(),
+            entity: None,
+        });
+    }
+
+    reply(
+        world,
+        caller,
+        format!("§c{player} is not online. Use their UUID instead."),
+    );
+    None
+}
+
+/// Parses an IP address or a network in CIDR notation
+fn parse_network(target: &str) -> Option<IpNet> {
+    target
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| target.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+fn describe_expiry(ban: &Ban) -> String {
+    ban.remaining(std::time::SystemTime::now()).map_or_else(
+        || "permanent".to_string(),
+        |remaining| format!("{remaining} left"),
+    )
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "ban")]
+#[command(about = "Ban a player permanently or for some time")]
+#[command_permission(group = "Moderator")]
+pub struct BanCommand {
+    /// The name of an online player or the UUID of any player
+    player: String,
+
+    /// How long the ban lasts, such as `30m` or `7d`. The ban is permanent if this is not given.
+    #[arg(short, long, value_parser = humantime::parse_duration)]
+    duration: Option<Duration>,
+
+    #[arg(trailing_var_arg = true)]
+    reason: Vec<String>,
+}
+
+impl MinecraftCommand for BanCommand {
+    type State = ();
+
+    fn execute(self, world: &World, _state: &mut Self::State, caller: Entity) {
+        let Some(target) = find_target(world, caller, &self.player) else {
+            return;
+        };
+
+        let reason = if self.reason.is_empty() {
+            DEFAULT_REASON.to_string()
+        } else {
+            self.reason.join(" ")
+        };
+
+        let Some(ban) = Ban::new(reason, source(world, caller), self.duration) else {
+            reply(world, caller, "§cThe ban duration is too long");
+            return;
+        };
+        let ban = ban.with_name(&target.name);
+
+        if let Err(e) = world.resource::<BanStorage>().set_ban(target.uuid, &ban) {
+            error!("ban command failed: failed to store ban: {e}");
+            reply(world, caller, "§cFailed to store the ban");
+            return;
+        }
+
+        if let Some(entity) = target.entity
+            && let Some(&target_connection) = world.entity(entity).get::<ConnectionId>()
+        {
+            kick(
+                world.resource::<Compose>(),
+                target_connection,
+                ban.disconnect_message(false),
+            );
+        }
+
+        let msg = match ban.remaining(ban.created) {
+            Some(remaining) => format!("§b{}§r has been banned for §e{remaining}", target.name),
+            None => format!("§b{}§r has been banned", target.name),
+        };
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "unban")]
+#[command(about = "Lift the ban of a player")]
+#[command_permission(group = "Moderator")]
+pub struct UnbanCommand {
+    /// The name the player had when banned, or their UUID
+    player: String,
+}
+
+impl MinecraftCommand for UnbanCommand {
+    type State = ();
+
+    fn execute(self, world: &World, _state: &mut Self::State, caller: Entity) {
+        let storage = world.resource::<BanStorage>();
+
+        let uuid = match uuid::Uuid::parse_str(&self.player) {
+            Ok(uuid) => Some(uuid),
+            Err(_) => match storage.bans() {
+                Ok(bans) => bans
+                    .into_iter()
+                    .find(|(_, ban)| {
+                        ban.name
+                            .as_deref()
+                            .is_some_and(|name| name.eq_ignore_ascii_case(&self.player))
+                    })
+                    .map(|(uuid, _)| uuid),
+                Err(e) => {
+                    error!("unban command failed: failed to read bans: {e}");
+                    None
+                }
+            },
+        };
+
+        let removed = uuid.map(|uuid| storage.remove_ban(uuid)).transpose();
+
+        let msg = match removed {
+            Ok(Some(true)) => format!("§b{}§r has been unbanned", self.player),
+            Ok(Some(false) | None) => format!("§c{} is not banned", self.player),
+            Err(e) => {
+                error!("unban command failed: failed to remove ban: {e}");
+                "§cFailed to remove the ban".to_string()
+            }
+        };
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "banip")]
+#[command(about = "Ban an IP address or a network such as 10.0.0.0/8")]
+#[command_permission(group = "Admin")]
+pub struct BanIpCommand {
+    /// An IP address, a network in CIDR notation, or an online player whose address is banned
+    target: String,
+
+    /// How long the ban lasts, such as `30m` or `7d`. The ban is permanent if this is not given.
+    #[arg(short, long, value_parser = humantime::parse_duration)]
+    duration: Option<Duration>,
+
+    #[arg(trailing_var_arg = true)]
+    reason: Vec<String>,
+}
+
+impl MinecraftCommand for BanIpCommand {
+    type State =
+        SystemState<Query<'static, 'static, (&'static ConnectionId, &'static ClientAddress)>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        let players = state.get(world);
+
+        let online_address = || {
+            let &entity = world.resource::<IgnMap>().get(self.target.as_str())?;
+            let address = world.entity(entity).get::<ClientAddress>()?;
+            Some(IpNet::from(**address))
+        };
+
+        let Some(network) = parse_network(&self.target).or_else(online_address) else {
+            let msg = format!("§c{} is not an IP address or an online player", self.target);
+            reply(world, caller, msg);
+            return;
+        };
+
+        let reason = if self.reason.is_empty() {
+            DEFAULT_REASON.to_string()
+        } else {
+            self.reason.join(" ")
+        };
+
+        let Some(ban) = Ban::new(reason, source(world, caller), self.duration) else {
+            reply(world, caller, "§cThe ban duration is too long");
+            return;
+        };
+
+        if let Err(e) = world.resource::<BanStorage>().set_ip_ban(network, &ban) {
+            error!("banip command failed: failed to store ban: {e}");
+            reply(world, caller, "§cFailed to store the ban");
+            return;
+        }
+
+        let compose = world.resource::<Compose>();
+        let mut kicked = 0;
+        for (&player_connection, address) in &players {
+            if network.contains(&**address) {
+                kick(compose, player_connection, ban.disconnect_message(true));
+                kicked += 1;
+            }
+        }
+
+        let msg = format!("§e{network}§r has been banned, kicking §e{kicked}§r player(s)");
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "unbanip")]
+#[command(about = "Lift the ban of an IP address or network")]
+#[command_permission(group = "Admin")]
+pub struct UnbanIpCommand {
+    /// The banned IP address or network in CIDR notation
+    target: String,
+}
+
+impl MinecraftCommand for UnbanIpCommand {
+    type State = ();
+
+    fn execute(self, world: &World, _state: &mut Self::State, caller: Entity) {
+        let Some(network) = parse_network(&self.target) else {
+            reply(
+                world,
+                caller,
+                format!("§c{} is not an IP address", self.target),
+            );
+            return;
+        };
+
+        let msg = match world.resource::<BanStorage>().remove_ip_ban(network) {
+            Ok(true) => format!("§e{network}§r has been unbanned"),
+            Ok(false) => format!("§c{network} is not banned"),
+            Err(e) => {
+                error!("unbanip command failed: failed to remove ban: {e}");
+                "§cFailed to remove the ban".to_string()
+            }
+        };
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "banlist")]
+#[command(about = "List the banned players and networks")]
+#[command_permission(group = "Moderator")]
+pub struct BanListCommand;
+
+impl MinecraftCommand for BanListCommand {
+    type State = ();
+
+    fn execute(self, world: &World, _state: &mut Self::State, caller: Entity) {
+        let storage = world.resource::<BanStorage>();
+
+        let (bans, ip_bans) = match (storage.bans(), storage.ip_bans()) {
+            (Ok(bans), Ok(ip_bans)) => (bans, ip_bans),
+            (Err(e), _) | (_, Err(e)) => {
+                error!("banlist command failed: failed to read bans: {e}");
+                reply(world, caller, "§cFailed to read the bans");
+                return;
+            }
+        };
+
+        let mut msg = format!("Banned players (§e{}§r):", bans.len());
+        for (uuid, ban) in &bans {
+            let name = ban.name.clone().unwrap_or_else(|| uuid.to_string());
+            let expiry = describe_expiry(ban);
+            write!(&mut msg, "\n §b{name}§r: {} §7({expiry})", ban.reason).unwrap();
+        }
+
+        write!(&mut msg, "\nBanned networks (§e{}§r):", ip_bans.len()).unwrap();
+        for (network, ban) in &ip_bans {
+            let expiry = describe_expiry(ban);
+            write!(&mut msg, "\n §e{network}§r: {} §7({expiry})", ban.reason).unwrap();
+        }
+
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WhitelistAction {
+    /// Only allow whitelisted players to join
+    On,
+    /// Allow every player who is not banned to join
+    Off,
+    /// Add an online player, or a player by UUID
+    Add {
+        player: String,
+    },
+    /// Remove a player by name or UUID
+    Remove {
+        player: String,
+    },
+    List,
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "whitelist")]
+#[command(about = "Manage the players who may join while the whitelist is on")]
+#[command_permission(group = "Admin")]
+pub struct WhitelistCommand {
+    #[command(subcommand)]
+    action: WhitelistAction,
+}
+
+impl MinecraftCommand for WhitelistCommand {
+    type State = ();
+
+    fn execute(self, world: &World, _state: &mut Self::State, caller: Entity) {
+        let storage = world.resource::<BanStorage>();
+
+        let result = match self.action {
+            WhitelistAction::On => storage
+                .set_whitelist_enabled(true)
+                .map(|()| "The whitelist is now §aon".to_string()),
+            WhitelistAction::Off => storage
+                .set_whitelist_enabled(false)
+                .map(|()| "The whitelist is now §coff".to_string()),
+            WhitelistAction::Add { player } => {
+                let Some(target) = find_target(world, caller, &player) else {
+                    return;
+                };
+
+                storage
+                    .add_to_whitelist(target.uuid, &target.name)
+                    .map(|()| format!("§b{}§r has been added to the whitelist", target.name))
+            }
+            WhitelistAction::Remove { player } => storage.whitelist().and_then(|players| {
+                let uuid = uuid::Uuid::parse_str(&player).ok().or_else(|| {
+                    players
+                        .iter()
+                        .find(|(_, name)| name.eq_ignore_ascii_case(&player))
+                        .map(|&(uuid, _)| uuid)
+                });
+
+                let removed = match uuid {
+                    Some(uuid) => storage.remove_from_whitelist(uuid)?,
+                    None => false,
+                };
+
+                Ok(if removed {
+                    format!("§b{player}§r has been removed from the whitelist")
+                } else {
+                    format!("§c{player} is not on the whitelist")
+                })
+            }),
+            WhitelistAction::List => storage.whitelist().and_then(|players| {
+                let enabled = if storage.whitelist_enabled()? {
+                    "§aon"
+                } else {
+                    "§coff"
+                };
+
+                let names = players
+                    .into_iter()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                Ok(format!(
+                    "The whitelist is {enabled}§r. Whitelisted players: {names}"
+                ))
+            }),
+        };
+
+        let msg = result.unwrap_or_else(|e| {
+            error!("whitelist command failed: {e}");
+            "§cFailed to update the whitelist".to_string()
+        });
+        reply(world, caller, msg);
+    }
+}
@@ -0,0 +1,238 @@
+//! Bans, IP bans and the whitelist.
+//!
+//! Players are checked while they log in, before they join the world. Banned players, players
+//! connecting from a banned network and, when the whitelist is enabled, players who are not
+//! whitelisted are disconnected with a message explaining why. Bans may be permanent or expire,
+//! and are stored in the [`LocalDb`] with the whitelist.
+
+use std::{
+    fmt::Write,
+    net::IpAddr,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use hyperion::{
+    ingress::{self, DenyLogin, offline_uuid},
+    net::{ClientAddress, Compose, ConnectionId, packets::DisconnectS2c},
+    simulation::packet,
+    storage::LocalDb,
+};
+use hyperion_clap::MinecraftCommand;
+use hyperion_text::Text;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+mod command;
+mod storage;
+
+pub use storage::BanStorage;
+
+/// A ban of a player or a network
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ban {
+    /// The name of the banned player when they were banned, if a player was banned
+    pub name: Option<String>,
+    pub reason: String,
+    /// Who created the ban
+    pub source: String,
+    pub created: SystemTime,
+    /// When the ban is lifted, or `None` if it is permanent
+    pub expires: Option<SystemTime>,
+}
+
+impl Ban {
+    /// Creates a ban starting now which lasts for `duration`, or forever if it is `None`. Returns
+    /// `None` if the ban would end too far in the future to represent.
+    #[must_use]
+    pub fn new(
+        reason: impl Into<String>,
+        source: impl Into<String>,
+        duration: Option<Duration>,
+    ) -> Option<Self> {
+        let created = SystemTime::now();
+        let expires = match duration {
+            Some(duration) => Some(created.checked_add(duration)?),
+            None => None,
+        };
+
+        Some(Self {
+            name: None,
+            reason: reason.into(),
+            source: source.into(),
+            created,
+            expires,
+        })
+    }
+
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    #[must_use]
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+
+    /// How long until the ban is lifted, rounded up to the minute, or `None` if it is permanent
+    #[must_use]
+    pub fn remaining(&self, now: SystemTime) -> Option<String> {
+        let left = self.expires?.duration_since(now).unwrap_or_default();
+        let minutes = left.as_secs().div_ceil(60).max(1);
+        let rounded = Duration::from_secs(minutes.saturating_mul(60));
+        Some(humantime::format_duration(rounded).to_string())
+    }
+
+    /// The message shown to the banned player when they are disconnected
+    #[must_use]
+    pub fn disconnect_message(&self, ip: bool) -> Text<'static> {
+        let mut msg = if ip {
+            "§cYour IP address is banned from this server".to_string()
+        } else {
+            "§cYou are banned from this server".to_string()
+        };
+
+        write!(&mut msg, "\n\n§7Reason: §f{}", self.reason).unwrap();
+
+        match self.remaining(SystemTime::now()) {
+            Some(remaining) => write!(&mut msg, "\n§7Expires in: §f{remaining}").unwrap(),
+            None => msg.push_str("\n§7This ban is permanent"),
+        }
+
+        Text::from_legacy(&msg, '§')
+    }
+}
+
+/// Disconnects a player who is in the world
+pub fn kick(compose: &Compose, connection_id: ConnectionId, reason: Text<'static>) {
+    let pkt = DisconnectS2c { reason };
+    if let Err(e) = compose.unicast(&pkt, connection_id) {
+        error!("failed to send disconnect message: {e}");
+    }
+    compose.io_buf().shutdown(connection_id);
+}
+
+/// Why a player may not join, or `None` if they may
+fn check_login(
+    storage: &BanStorage,
+    uuid: uuid::Uuid,
+    address: Option<IpAddr>,
+) -> anyhow::Result<Option<Text<'static>>> {
+    if let Some(ban) = storage.ban(uuid)? {
+        return Ok(Some(ban.disconnect_message(false)));
+    }
+
+    if let Some(address) = address
+        && let Some((_, ban)) = storage.ip_ban(address)?
+    {
+        return Ok(Some(ban.disconnect_message(true)));
+    }
+
+    if storage.whitelist_enabled()? && !storage.is_whitelisted(uuid)? {
+        return Ok(Some(Text::from_legacy(
+            "§cYou are not whitelisted on this server",
+            '§',
+        )));
+    }
+
+    Ok(None)
+}
+
+fn check_logins(
+    mut packets: EventReader<'_, '_, packet::login::LoginHello>,
+    addresses: Query<'_, '_, Option<&ClientAddress>>,
+    storage: Res<'_, BanStorage>,
+    mut denials: EventWriter<'_, DenyLogin>,
+) {
+    for packet in packets.read() {
+        let address = match addresses.get(packet.sender()) {
+            Ok(address) => address.map(|address| **address),
+            Err(e) => {
+                error!("failed to check login: query failed: {e}");
+                continue;
+            }
+        };
+
+        let uuid = packet
+            .profile_id
+            .unwrap_or_else(|| offline_uuid(&packet.username));
+
+        let reason = match check_login(&storage, uuid, address) {
+            Ok(Some(reason)) => reason,
+            Ok(None) => continue,
+            Err(e) => {
+                // Players are let in if the storage fails so that a broken database does not lock
+                // everyone out
+                error!("failed to check login of {}: {e}", packet.username);
+                continue;
+            }
+        };
+
+        info!(
+            "{} may not join: {}",
+            packet.username,
+            reason.to_legacy('§')
+        );
+        denials.write(DenyLogin {
+            entity: packet.sender(),
+            reason,
+        });
+    }
+}
+
+pub struct BanPlugin;
+
+impl Plugin for BanPlugin {
+    fn build(&self, app: &mut App) {
+        let storage = BanStorage::new(app.world().resource::<LocalDb>()).unwrap();
+        app.insert_resource(storage);
+        app.add_systems(
+            FixedUpdate,
+            check_logins
+                .after(ingress::decode::login)
+                .before(ingress::process_login_hello),
+        );
+    }
+
+    // Commands are registered once the command plugin has been added
+    fn finish(&self, app: &mut App) {
+        command::BanCommand::register(app.world_mut());
+        command::BanIpCommand::register(app.world_mut());
+        command::BanListCommand::register(app.world_mut());
+        command::UnbanCommand::register(app.world_mut());
+        command::UnbanIpCommand::register(app.world_mut());
+        command::WhitelistCommand::register(app.world_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::Ban;
+
+    #[test]
+    fn temporary_bans_expire() {
+        let ban = Ban::new("griefing", "console", Some(Duration::from_secs(90 * 60))).unwrap();
+        let now = ban.created;
+
+        assert!(ban.is_active(now));
+        assert_eq!(ban.remaining(now).as_deref(), Some("1h 30m"));
+        assert_eq!(
+            ban.remaining(now + Duration::from_secs(30)).as_deref(),
+            Some("1h 30m")
+        );
+        assert!(!ban.is_active(now + Duration::from_secs(90 * 60)));
+
+        let permanent = Ban::new("cheating", "console", None).unwrap();
+        assert!(permanent.is_active(SystemTime::now() + Duration::from_secs(u32::MAX.into())));
+        assert_eq!(permanent.remaining(SystemTime::now()), None);
+    }
+
+    #[test]
+    fn bans_too_long_to_represent_are_rejected() {
+        assert_eq!(Ban::new("griefing", "console", Some(Duration::MAX)), None);
+    }
+}
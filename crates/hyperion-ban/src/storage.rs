@@ -0,0 +1,220 @@
+use std::{net::IpAddr, time::SystemTime};
+
+use bevy::prelude::*;
+use heed::{Database, Env, byteorder::NativeEndian, types};
+use hyperion::storage::LocalDb;
+use ipnet::IpNet;
+
+use crate::Ban;
+
+/// Key in the settings database for whether the whitelist is enforced
+const WHITELIST_ENABLED: &str = "whitelist-enabled";
+
+/// Key in the settings database for whether players banned by earlier versions were migrated
+const LEGACY_BANS_MIGRATED: &str = "legacy-bans-migrated";
+
+/// The group earlier versions stored for banned players
+const LEGACY_BANNED: u8 = 0;
+
+/// Bans, IP bans and the whitelist
+#[derive(Resource)]
+pub struct BanStorage {
+    env: Env,
+    bans: Database<types::U128<NativeEndian>, types::SerdeJson<Ban>>,
+    /// IP bans keyed by the banned network, such as `10.0.0.0/8` or `1.2.3.4/32`
+    ip_bans: Database<types::Str, types::SerdeJson<Ban>>,
+    /// Whitelisted players and their name when they were added
+    whitelist: Database<types::U128<NativeEndian>, types::Str>,
+    settings: Database<types::Str, types::SerdeJson<bool>>,
+}
+
+impl BanStorage {
+    pub fn new(db: &LocalDb) -> anyhow::Result<Self> {
+        let mut wtxn = db.write_txn()?;
+        let bans = db.create_database(&mut wtxn, Some("uuid-to-ban"))?;
+        let ip_bans = db.create_database(&mut wtxn, Some("ip-bans"))?;
+        let whitelist = db.create_database(&mut wtxn, Some("whitelist"))?;
+        let settings = db.create_database(&mut wtxn, Some("ban-settings"))?;
+        let legacy: Option<Database<types::U128<NativeEndian>, types::U8>> =
+            db.open_database(&wtxn, Some("uuid-to-perms"))?;
+
+        // Earlier versions stored banned players as a permission group. They are migrated once so
+        // that players unbanned afterwards stay unbanned.
+        if let Some(legacy) = legacy
+            && !settings.get(&wtxn, LEGACY_BANS_MIGRATED)?.unwrap_or(false)
+        {
+            let banned = legacy
+                .iter(&wtxn)?
+                .filter_map(|entry| match entry {
+                    Ok((uuid, group)) => (group == LEGACY_BANNED).then_some(Ok(uuid)),
+                    Err(e) => Some(Err(e)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let ban = Ban {
+                name: None,
+                reason: "Banned before bans were moved to the ban list".to_string(),
+                source: "migration".to_string(),
+                created: SystemTime::now(),
+                expires: None,
+            };
+
+            for uuid in banned {
+                if bans.get(&wtxn, &uuid)?.is_none() {
+                    bans.put(&mut wtxn, &uuid, &ban)?;
+                }
+            }
+
+            settings.put(&mut wtxn, LEGACY_BANS_MIGRATED, &true)?;
+        }
+
+        wtxn.commit()?;
+
+        Ok(Self {
+            env: (**db).clone(),
+            bans,
+            ip_bans,
+            whitelist,
+            settings,
+        })
+    }
+
+    /// The ban of a player if it has not expired. Expired bans are removed.
+    pub fn ban(&self, uuid: uuid::Uuid) -> anyhow::Result<Option<Ban>> {
+        let rtxn = self.env.read_txn()?;
+        let Some(ban) = self.bans.get(&rtxn, &uuid.as_u128())? else {
+            return Ok(None);
+        };
+        drop(rtxn);
+
+        if ban.is_active(SystemTime::now()) {
+            return Ok(Some(ban));
+        }
+
+        self.remove_ban(uuid)?;
+        Ok(None)
+    }
+
+    pub fn set_ban(&self, uuid: uuid::Uuid, ban: &Ban) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.bans.put(&mut wtxn, &uuid.as_u128(), ban)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Removes the ban of a player, returning whether they were banned
+    pub fn remove_ban(&self, uuid: uuid::Uuid) -> anyhow::Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let removed = self.bans.delete(&mut wtxn, &uuid.as_u128())?;
+        wtxn.commit()?;
+        Ok(removed)
+    }
+
+    /// All bans which have not expired
+    pub fn bans(&self) -> anyhow::Result<Vec<(uuid::Uuid, Ban)>> {
+        let now = SystemTime::now();
+        let rtxn = self.env.read_txn()?;
+        let mut bans = Vec::new();
+
+        for entry in self.bans.iter(&rtxn)? {
+            let (uuid, ban) = entry?;
+            if ban.is_active(now) {
+                bans.push((uuid::Uuid::from_u128(uuid), ban));
+            }
+        }
+
+        Ok(bans)
+    }
+
+    /// The IP ban covering an address if it has not expired
+    pub fn ip_ban(&self, address: IpAddr) -> anyhow::Result<Option<(IpNet, Ban)>> {
+        let found = self
+            .ip_bans()?
+            .into_iter()
+            .filter(|(network, _)| network.contains(&address))
+            .max_by_key(|(network, _)| network.prefix_len());
+
+        Ok(found)
+    }
+
+    pub fn set_ip_ban(&self, network: IpNet, ban: &Ban) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.ip_bans
+            .put(&mut wtxn, &network.trunc().to_string(), ban)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Removes the ban of a network, returning whether it was banned
+    pub fn remove_ip_ban(&self, network: IpNet) -> anyhow::Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let removed = self
+            .ip_bans
+            .delete(&mut wtxn, &network.trunc().to_string())?;
+        wtxn.commit()?;
+        Ok(removed)
+    }
+
+    /// All IP bans which have not expired
+    pub fn ip_bans(&self) -> anyhow::Result<Vec<(IpNet, Ban)>> {
+        let now = SystemTime::now();
+        let rtxn = self.env.read_txn()?;
+        let mut bans = Vec::new();
+
+        for entry in self.ip_bans.iter(&rtxn)? {
+            let (network, ban) = entry?;
+            if ban.is_active(now) {
+                bans.push((network.parse()?, ban));
+            }
+        }
+
+        Ok(bans)
+    }
+
+    /// Whether only whitelisted players can join. The whitelist is disabled by default.
+    pub fn whitelist_enabled(&self) -> anyhow::Result<bool> {
+        let rtxn = self.env.read_txn()?;
+        let enabled = self.settings.get(&rtxn, WHITELIST_ENABLED)?;
+        Ok(enabled.unwrap_or(false))
+    }
+
+    pub fn set_whitelist_enabled(&self, enabled: bool) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.settings.put(&mut wtxn, WHITELIST_ENABLED, &enabled)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    pub fn is_whitelisted(&self, uuid: uuid::Uuid) -> anyhow::Result<bool> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.whitelist.get(&rtxn, &uuid.as_u128())?.is_some())
+    }
+
+    pub fn add_to_whitelist(&self, uuid: uuid::Uuid, name: &str) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.whitelist.put(&mut wtxn, &uuid.as_u128(), name)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Removes a player from the whitelist, returning whether they were on it
+    pub fn remove_from_whitelist(&self, uuid: uuid::Uuid) -> anyhow::Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let removed = self.whitelist.delete(&mut wtxn, &uuid.as_u128())?;
+        wtxn.commit()?;
+        Ok(removed)
+    }
+
+    /// The whitelisted players and their names
+    pub fn whitelist(&self) -> anyhow::Result<Vec<(uuid::Uuid, String)>> {
+        let rtxn = self.env.read_txn()?;
+        let mut players = Vec::new();
+
+        for entry in self.whitelist.iter(&rtxn)? {
+            let (uuid, name) = entry?;
+            players.push((uuid::Uuid::from_u128(uuid), name.to_string()));
+        }
+
+        Ok(players)
+    }
+}
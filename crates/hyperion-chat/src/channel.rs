@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use hyperion_permission::{PermissionGroups, PlayerPermissions};
+
+use crate::format::ChatFormat;
+
+/// Who receives messages sent in a channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelScope {
+    /// Every player
+    Global,
+    /// Players within `radius` blocks of the sender
+    Local { radius: f32 },
+    /// Players in the same scoreboard team as the sender. Players without a team only see their
+    /// own messages.
+    Team,
+}
+
+#[derive(Clone, Debug)]
+pub struct Channel {
+    pub scope: ChannelScope,
+    pub format: ChatFormat,
+    /// The permission node needed to read and write messages in the channel, or `None` if every
+    /// player can
+    pub permission: Option<String>,
+}
+
+impl Channel {
+    #[must_use]
+    pub fn new(scope: ChannelScope) -> Self {
+        Self {
+            scope,
+            format: ChatFormat::default(),
+            permission: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_format(mut self, format: ChatFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    #[must_use]
+    pub fn with_permission(mut self, permission: impl Into<String>) -> Self {
+        self.permission = Some(permission.into());
+        self
+    }
+
+    /// Whether a player can read and write messages in the channel
+    #[must_use]
+    pub fn allows(&self, groups: &PermissionGroups, player: Option<&PlayerPermissions>) -> bool {
+        self.permission
+            .as_deref()
+            .is_none_or(|permission| groups.check(player, permission))
+    }
+}
+
+/// The chat channels by name. Players talk in their [`ActiveChannel`], or the default channel if
+/// they have not chosen one.
+#[derive(Resource, Clone, Debug)]
+pub struct ChatChannels {
+    channels: BTreeMap<String, Channel>,
+    default: String,
+}
+
+impl ChatChannels {
+    /// Creates channels containing only `default`, which players talk in by default
+    #[must_use]
+    pub fn new(default: impl Into<String>, channel: Channel) -> Self {
+        let default = default.into();
+        Self {
+            channels: BTreeMap::from([(default.clone(), channel)]),
+            default,
+        }
+    }
+
+    /// Adds a channel, replacing any channel with the same name
+    pub fn insert(&mut self, name: impl Into<String>, channel: Channel) {
+        self.channels.insert(name.into(), channel);
+    }
+
+    /// Removes a channel. The default channel cannot be removed.
+    pub fn remove(&mut self, name: &str) -> Option<Channel> {
+        if name == self.default {
+            return None;
+        }
+
+        self.channels.remove(name)
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Channel> {
+        self.channels.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Channel> {
+        self.channels.get_mut(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Channel)> {
+        self.channels
+            .iter()
+            .map(|(name, channel)| (name.as_str(), channel))
+    }
+
+    #[must_use]
+    pub fn default_name(&self) -> &str {
+        &self.default
+    }
+
+    /// Changes the default channel. Returns `false` if there is no channel named `name`.
+    pub fn set_default(&mut self, name: &str) -> bool {
+        if !self.channels.contains_key(name) {
+            return false;
+        }
+
+        name.clone_into(&mut self.default);
+        true
+    }
+
+    /// The channel a player talks in, falling back to the default channel if their active channel
+    /// was removed
+    #[must_use]
+    pub fn active<'a>(&'a self, active: Option<&'a ActiveChannel>) -> (&'a str, &'a Channel) {
+        if let Some(active) = active
+            && let Some(channel) = self.channels.get(&active.0)
+        {
+            return (&active.0, channel);
+        }
+
+        (&self.default, &self.channels[&self.default])
+    }
+}
+
+impl Default for ChatChannels {
+    fn default() -> Self {
+        let mut channels = Self::new("global", Channel::new(ChannelScope::Global));
+        channels.insert(
+            "local",
+            Channel::new(ChannelScope::Local { radius: 64.0 })
+                .with_format(ChatFormat::new("§7[L] §8<§r{prefix}{name}§8>§r {message}")),
+        );
+        channels.insert(
+            "team",
+            Channel::new(ChannelScope::Team)
+                .with_format(ChatFormat::new("§9[T] §8<§r{prefix}{name}§8>§r {message}")),
+        );
+        channels.insert(
+            "staff",
+            Channel::new(ChannelScope::Global)
+                .with_format(ChatFormat::new("§c[S] §8<§r{prefix}{name}§8>§r {message}"))
+                .with_permission("chat.channel.staff"),
+        );
+        channels
+    }
+}
+
+/// The channel a player talks in
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct ActiveChannel(pub String);
+
+/// Shown before the name of a player in chat, such as a rank or team colour
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChatPrefix(pub String);
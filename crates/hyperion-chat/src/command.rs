@@ -0,0 +1,302 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+use clap::Parser;
+use hyperion::simulation::{IgnMap, Uuid};
+use hyperion_clap::{
+    CommandPermission, MinecraftCommand,
+    hyperion_command::{reply, require_player},
+};
+use hyperion_permission::{PermissionGroups, PlayerPermissions, expires_in};
+use tracing::error;
+
+use crate::{
+    ActiveChannel, ChatChannels, IgnoreList, Muted, PrivateMessageEvent, ReplyTarget,
+    storage::ChatStorage,
+};
+
+/// Finds an online player by name, telling the caller if they are not online
+fn find_player(world: &World, caller: Entity, player: &str) -> Option<Entity> {
+    let ign_map = world.resource::<IgnMap>();
+    if let Some(&entity) = ign_map.get(player) {
+        return Some(entity);
+    }
+
+    reply(world, caller, format!("§c{player} not found"));
+    None
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "msg")]
+#[command(about = "Send a private message to a player")]
+#[command_permission(group = "Normal")]
+pub struct MsgCommand {
+    player: String,
+
+    #[arg(trailing_var_arg = true, required = true)]
+    message: Vec<String>,
+}
+
+impl MinecraftCommand for MsgCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
+        let mut commands = state.get(world);
+
+        let Some(recipient) = find_player(world, caller, &self.player) else {
+            return;
+        };
+
+        commands.send_event(PrivateMessageEvent {
+            sender: caller,
+            recipient,
+            message: self.message.join(" "),
+        });
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "r")]
+#[command(about = "Reply to the last player you messaged")]
+#[command_permission(group = "Normal")]
+pub struct ReplyCommand {
+    #[arg(trailing_var_arg = true, required = true)]
+    message: Vec<String>,
+}
+
+impl MinecraftCommand for ReplyCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
+        let mut commands = state.get(world);
+
+        let Some(&ReplyTarget(recipient)) = world.entity(caller).get::<ReplyTarget>() else {
+            reply(world, caller, "§cYou have nobody to reply to");
+            return;
+        };
+
+        commands.send_event(PrivateMessageEvent {
+            sender: caller,
+            recipient,
+            message: self.message.join(" "),
+        });
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "ignore")]
+#[command(about = "Hide or show messages from a player")]
+#[command_permission(group = "Normal")]
+pub struct IgnoreCommand {
+    player: String,
+}
+
+impl MinecraftCommand for IgnoreCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
+        let mut commands = state.get(world);
+        let caller_ref = world.entity(caller);
+        let (Some(uuid), Some(ignored)) =
+            (caller_ref.get::<Uuid>(), caller_ref.get::<IgnoreList>())
+        else {
+            error!("ignore command failed: caller is missing chat components");
+            return;
+        };
+
+        let Some(target) = find_player(world, caller, &self.player) else {
+            return;
+        };
+
+        let Some(&Uuid(target_uuid)) = world.entity(target).get::<Uuid>() else {
+            error!("ignore command failed: target is missing Uuid component");
+            return;
+        };
+
+        if target == caller {
+            reply(world, caller, "§cYou cannot ignore yourself");
+            return;
+        }
+
+        let mut ignored = ignored.clone();
+        let msg = if ignored.0.remove(&target_uuid) {
+            format!("§aYou are no longer ignoring §b{}", self.player)
+        } else {
+            ignored.0.insert(target_uuid);
+            format!("§aYou are now ignoring §b{}", self.player)
+        };
+
+        if let Err(e) = world
+            .resource::<ChatStorage>()
+            .set_ignored(**uuid, &ignored.0)
+        {
+            error!("ignore command failed: failed to store ignore list: {e}");
+        }
+
+        commands.entity(caller).insert(ignored);
+
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "mute")]
+#[command(about = "Prevent a player from chatting")]
+#[command_permission(group = "Moderator")]
+pub struct MuteCommand {
+    player: String,
+
+    /// How long the mute lasts. The mute is permanent if this is not given.
+    minutes: Option<u64>,
+}
+
+impl MinecraftCommand for MuteCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        let mut commands = state.get(world);
+
+        let Some(target) = find_player(world, caller, &self.player) else {
+            return;
+        };
+
+        let Some(&uuid) = world.entity(target).get::<Uuid>() else {
+            error!("mute command failed: target is missing Uuid component");
+            return;
+        };
+
+        let until = match self.minutes {
+            Some(minutes) => {
+                let Some(until) = expires_in(minutes) else {
+                    reply(
+                        world,
+                        caller,
+                        format!("§c{minutes} minutes is too long for a mute"),
+                    );
+                    return;
+                };
+                Some(until)
+            }
+            None => None,
+        };
+
+        let muted = Muted { until };
+
+        if let Err(e) = world.resource::<ChatStorage>().set_mute(*uuid, muted) {
+            error!("mute command failed: failed to store mute: {e}");
+        }
+
+        commands.entity(target).insert(muted);
+
+        let msg = match self.minutes {
+            Some(minutes) => format!(
+                "§b{}§r has been muted for §e{minutes}§r minute(s)",
+                self.player
+            ),
+            None => format!("§b{}§r has been muted", self.player),
+        };
+        reply(world, caller, msg);
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "unmute")]
+#[command(about = "Allow a muted player to chat again")]
+#[command_permission(group = "Moderator")]
+pub struct UnmuteCommand {
+    player: String,
+}
+
+impl MinecraftCommand for UnmuteCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        let mut commands = state.get(world);
+
+        let Some(target) = find_player(world, caller, &self.player) else {
+            return;
+        };
+
+        let Some(&uuid) = world.entity(target).get::<Uuid>() else {
+            error!("unmute command failed: target is missing Uuid component");
+            return;
+        };
+
+        if let Err(e) = world.resource::<ChatStorage>().remove_mute(*uuid) {
+            error!("unmute command failed: failed to remove mute: {e}");
+        }
+
+        commands.entity(target).remove::<Muted>();
+
+        reply(
+            world,
+            caller,
+            format!("§b{}§r has been unmuted", self.player),
+        );
+    }
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "channel")]
+#[command(about = "Change the chat channel you talk in, or list the channels")]
+#[command_permission(group = "Normal")]
+pub struct ChannelCommand {
+    channel: Option<String>,
+}
+
+impl MinecraftCommand for ChannelCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
+        let mut commands = state.get(world);
+        let channels = world.resource::<ChatChannels>();
+        let caller_ref = world.entity(caller);
+
+        let groups = world.resource::<PermissionGroups>();
+        let permissions = caller_ref.get::<PlayerPermissions>();
+
+        let Some(name) = self.channel else {
+            let (active, _) = channels.active(caller_ref.get::<ActiveChannel>());
+            let available = channels
+                .iter()
+                .filter(|(_, channel)| channel.allows(groups, permissions))
+                .map(|(name, _)| {
+                    if name == active {
+                        format!("§a{name}")
+                    } else {
+                        format!("§7{name}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("§r, ");
+
+            reply(world, caller, format!("Channels: {available}"));
+            return;
+        };
+
+        let msg = match channels.get(&name) {
+            Some(channel) if channel.allows(groups, permissions) => {
+                let msg = format!("You are now talking in §a{name}");
+                commands.entity(caller).insert(ActiveChannel(name));
+                msg
+            }
+            _ => format!("§c{name} is not a channel you can talk in"),
+        };
+
+        reply(world, caller, msg);
+    }
+}
@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+
+/// What happens to a message containing a filtered word
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Filtered words are replaced with `*`
+    #[default]
+    Censor,
+    /// The message is not sent
+    Block,
+}
+
+/// Filters words from chat messages, ignoring ASCII case. Words also match inside other words.
+#[derive(Clone, Debug, Default)]
+pub struct WordFilter {
+    words: Vec<String>,
+    pub action: FilterAction,
+}
+
+impl WordFilter {
+    #[must_use]
+    pub fn new(words: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let words = words
+            .into_iter()
+            .map(|word| word.into().to_ascii_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        Self {
+            words,
+            action: FilterAction::default(),
+        }
+    }
+
+    #[must_use]
+    pub const fn with_action(mut self, action: FilterAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+
+    /// Returns the message with filtered words censored, or `None` if the message is blocked
+    #[must_use]
+    pub fn apply<'a>(&self, message: &'a str) -> Option<Cow<'a, str>> {
+        // ASCII lowercasing keeps byte offsets the same as the original message
+        let lower = message.to_ascii_lowercase();
+        let mut filtered = vec![false; message.len()];
+        let mut found = false;
+
+        for word in &self.words {
+            for (start, matched) in lower.match_indices(word.as_str()) {
+                filtered[start..start + matched.len()].fill(true);
+                found = true;
+            }
+        }
+
+        if !found {
+            return Some(Cow::Borrowed(message));
+        }
+
+        match self.action {
+            FilterAction::Block => None,
+            FilterAction::Censor => Some(Cow::Owned(
+                message
+                    .char_indices()
+                    .map(|(i, c)| if filtered[i] { '*' } else { c })
+                    .collect(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilterAction, WordFilter};
+
+    #[test]
+    fn censors_ignoring_case() {
+        let filter = WordFilter::new(["heck"]);
+
+        assert_eq!(filter.apply("what the HECK").unwrap(), "what the ****");
+        assert_eq!(filter.apply("fine").unwrap(), "fine");
+    }
+
+    #[test]
+    fn blocks() {
+        let filter = WordFilter::new(["heck"]).with_action(FilterAction::Block);
+
+        assert!(filter.apply("heckin").is_none());
+        assert!(filter.apply("hello").is_some());
+    }
+}
@@ -0,0 +1,92 @@
+/// A template for chat messages. `{channel}`, `{prefix}`, `{name}` and `{message}` are replaced
+/// when the message is sent. Everything else, including legacy `§` formatting codes, is kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChatFormat(String);
+
+/// The values substituted into a [`ChatFormat`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FormatArgs<'a> {
+    pub channel: &'a str,
+    pub prefix: &'a str,
+    pub name: &'a str,
+    pub message: &'a str,
+}
+
+impl ChatFormat {
+    #[must_use]
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+
+    #[must_use]
+    pub fn template(&self) -> &str {
+        &self.0
+    }
+
+    /// Fills in the template. Placeholders are only replaced in the template, so a message
+    /// containing `{name}` is sent as written.
+    #[must_use]
+    pub fn render(&self, args: FormatArgs<'_>) -> String {
+        let mut out = String::with_capacity(self.0.len() + args.name.len() + args.message.len());
+        let mut rest = self.0.as_str();
+
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+
+            let value = match &rest[1..end] {
+                "channel" => args.channel,
+                "prefix" => args.prefix,
+                "name" => args.name,
+                "message" => args.message,
+                _ => &rest[..=end],
+            };
+
+            out.push_str(value);
+            rest = &rest[end + 1..];
+        }
+
+        out.push_str(rest);
+        out
+    }
+}
+
+impl Default for ChatFormat {
+    fn default() -> Self {
+        Self::new("§8<§r{prefix}{name}§8>§r {message}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChatFormat, FormatArgs};
+
+    #[test]
+    fn replaces_placeholders() {
+        let format = ChatFormat::new("[{channel}] {prefix}{name}: {message}");
+        let args = FormatArgs {
+            channel: "staff",
+            prefix: "§c",
+            name: "Notch",
+            message: "hello",
+        };
+
+        assert_eq!(format.render(args), "[staff] §cNotch: hello");
+    }
+
+    #[test]
+    fn keeps_unknown_placeholders_and_message_contents() {
+        let format = ChatFormat::new("{unknown} {name}: {message} {");
+        let args = FormatArgs {
+            name: "Notch",
+            message: "{name}",
+            ..FormatArgs::default()
+        };
+
+        assert_eq!(format.render(args), "{unknown} Notch: {name} {");
+    }
+}
@@ -0,0 +1,526 @@
+//! Chat channels, formatting, moderation and private messages.
+//!
+//! Chat messages from players are checked against their [`Muted`] state, the
+//! [rate limit](ChatSettings::rate_limit) and the [word filter](ChatSettings::filter), and then
+//! become a [`ChatEvent`] in the player's [`ActiveChannel`]. Systems in [`ChatSet::Modify`] can
+//! change or [cancel](ChatEvent::cancel) messages through an [`EventMutator`] before they are
+//! formatted and sent to the channel in [`ChatSet::Deliver`].
+//!
+//! Mutes and ignore lists are stored in the [`LocalDb`] so they persist across restarts.
+
+use std::{borrow::Cow, collections::HashSet, time::SystemTime};
+
+use bevy::prelude::*;
+use hyperion::{
+    ingress,
+    net::{Compose, ConnectionId, agnostic},
+    simulation::{Position, Uuid, packet, packet_state},
+    storage::LocalDb,
+};
+use hyperion_clap::MinecraftCommand;
+use hyperion_permission::{PermissionGroups, PlayerPermissions};
+use hyperion_scoreboard::Scoreboard;
+use tracing::error;
+
+mod channel;
+mod command;
+mod filter;
+mod format;
+mod storage;
+
+pub use channel::{ActiveChannel, Channel, ChannelScope, ChatChannels, ChatPrefix};
+pub use filter::{FilterAction, WordFilter};
+pub use format::{ChatFormat, FormatArgs};
+use storage::ChatStorage;
+
+/// Ordering of the chat systems in [`FixedUpdate`]
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChatSet {
+    /// Chat packets are turned into [`ChatEvent`]s
+    Receive,
+    /// Games can modify or cancel [`ChatEvent`]s
+    Modify,
+    /// [`ChatEvent`]s and [`PrivateMessageEvent`]s are sent to their recipients
+    Deliver,
+}
+
+/// Limits how often a player can send messages. Players can send `burst` messages at once, and
+/// then one message every `interval_ticks`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub interval_ticks: i64,
+}
+
+impl RateLimit {
+    /// Records a message sent at `tick`, or returns the number of ticks until the player can send
+    /// another message
+    fn check(self, cooldown: &mut ChatCooldown, tick: i64) -> Result<(), i64> {
+        let next = cooldown.next.max(tick) + self.interval_ticks;
+        let wait = next - tick - i64::from(self.burst) * self.interval_ticks;
+
+        if wait > 0 {
+            return Err(wait);
+        }
+
+        cooldown.next = next;
+        Ok(())
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct ChatSettings {
+    /// Applies to both chat and private messages. `None` disables rate limiting.
+    pub rate_limit: Option<RateLimit>,
+    /// Applies to both chat and private messages
+    pub filter: WordFilter,
+    /// How a private message is shown to its sender. `{name}` is the recipient.
+    pub outgoing_format: ChatFormat,
+    /// How a private message is shown to its recipient. `{name}` is the sender.
+    pub incoming_format: ChatFormat,
+}
+
+impl Default for ChatSettings {
+    fn default() -> Self {
+        Self {
+            rate_limit: Some(RateLimit {
+                burst: 3,
+                interval_ticks: 20,
+            }),
+            filter: WordFilter::default(),
+            outgoing_format: ChatFormat::new("§7[me -> {name}] §r{message}"),
+            incoming_format: ChatFormat::new("§7[{name} -> me] §r{message}"),
+        }
+    }
+}
+
+/// A chat message sent in a channel
+#[derive(Event, Clone, Debug)]
+pub struct ChatEvent {
+    pub sender: Entity,
+    /// The name of the channel in [`ChatChannels`]
+    pub channel: String,
+    pub message: String,
+    cancelled: bool,
+}
+
+impl ChatEvent {
+    #[must_use]
+    pub fn new(sender: Entity, channel: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            sender,
+            channel: channel.into(),
+            message: message.into(),
+            cancelled: false,
+        }
+    }
+
+    /// Prevents the message from being sent
+    pub const fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    #[must_use]
+    pub const fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// A private message between two players. Sent by the `/msg` and `/r` commands.
+#[derive(Event, Clone, Debug)]
+pub struct PrivateMessageEvent {
+    pub sender: Entity,
+    pub recipient: Entity,
+    pub message: String,
+}
+
+/// Prevents a player from sending chat and private messages
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Muted {
+    /// When the mute expires, or `None` if it does not expire
+    pub until: Option<SystemTime>,
+}
+
+impl Muted {
+    #[must_use]
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+
+    fn message(&self, now: SystemTime) -> String {
+        let remaining = self
+            .until
+            .and_then(|until| until.duration_since(now).ok())
+            .map(|remaining| remaining.as_secs().div_ceil(60));
+
+        match remaining {
+            Some(minutes) => format!("§cYou are muted for {minutes} more minute(s)"),
+            None => "§cYou are muted".to_string(),
+        }
+    }
+}
+
+/// The players whose chat and private messages a player does not see
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IgnoreList(HashSet<uuid::Uuid>);
+
+impl IgnoreList {
+    #[must_use]
+    pub fn contains(&self, uuid: uuid::Uuid) -> bool {
+        self.0.contains(&uuid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = uuid::Uuid> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// The player `/r` replies to, which is the last player a private message was sent to or
+/// received from
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplyTarget(pub Entity);
+
+/// The rate limit state of a player
+#[derive(Component, Default, Copy, Clone, Debug)]
+struct ChatCooldown {
+    next: i64,
+}
+
+/// Checks whether a player can send a message. Returns the filtered message, or the reason the
+/// message cannot be sent.
+fn check_message<'a>(
+    message: &'a str,
+    muted: Option<&Muted>,
+    cooldown: &mut ChatCooldown,
+    settings: &ChatSettings,
+    tick: i64,
+) -> Result<Cow<'a, str>, String> {
+    let now = SystemTime::now();
+    if let Some(muted) = muted
+        && muted.is_active(now)
+    {
+        return Err(muted.message(now));
+    }
+
+    if let Some(rate_limit) = settings.rate_limit
+        && let Err(wait) = rate_limit.check(cooldown, tick)
+    {
+        let seconds = wait as f32 / 20.0;
+        return Err(format!(
+            "§cPlease wait {seconds:.1} seconds before sending another message"
+        ));
+    }
+
+    settings
+        .filter
+        .apply(message)
+        .ok_or_else(|| "§cYour message contains a blocked word".to_string())
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, Uuid>,
+    query: Query<'_, '_, &Uuid, With<ConnectionId>>,
+    storage: Res<'_, ChatStorage>,
+    mut commands: Commands<'_, '_>,
+) {
+    let Ok(uuid) = query.get(trigger.target()) else {
+        return;
+    };
+
+    let mut entity = commands.entity(trigger.target());
+    entity.insert(ChatCooldown::default());
+
+    match storage.ignored(**uuid) {
+        Ok(ignored) => {
+            entity.insert(IgnoreList(ignored));
+        }
+        Err(e) => {
+            error!("failed to load ignore list: {e}");
+            entity.insert(IgnoreList::default());
+        }
+    }
+
+    match storage.mute(**uuid) {
+        Ok(Some(muted)) if muted.is_active(SystemTime::now()) => {
+            entity.insert(muted);
+        }
+        Ok(Some(_)) => {
+            if let Err(e) = storage.remove_mute(**uuid) {
+                error!("failed to remove expired mute: {e}");
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("failed to load mute: {e}"),
+    }
+}
+
+fn receive_messages(
+    mut packets: EventReader<'_, '_, packet::play::ChatMessage>,
+    mut query: Query<
+        '_,
+        '_,
+        (
+            &mut ChatCooldown,
+            Option<&Muted>,
+            Option<&ActiveChannel>,
+            Option<&PlayerPermissions>,
+        ),
+    >,
+    settings: Res<'_, ChatSettings>,
+    channels: Res<'_, ChatChannels>,
+    groups: Res<'_, PermissionGroups>,
+    compose: Res<'_, Compose>,
+    mut events: EventWriter<'_, ChatEvent>,
+) {
+    let tick = compose.global().tick;
+
+    for packet in packets.read() {
+        let (mut cooldown, muted, active, permissions) = match query.get_mut(packet.sender()) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("could not process chat message: query failed: {e}");
+                continue;
+            }
+        };
+
+        let message = packet.message.to_string();
+        let message = match check_message(&message, muted, &mut cooldown, &settings, tick) {
+            Ok(message) => message,
+            Err(reason) => {
+                let chat = agnostic::chat(reason);
+                compose.unicast(&chat, packet.connection_id()).unwrap();
+                continue;
+            }
+        };
+
+        let (name, channel) = channels.active(active);
+        if !channel.allows(&groups, permissions) {
+            let chat = agnostic::chat(format!("§cYou cannot talk in {name}"));
+            compose.unicast(&chat, packet.connection_id()).unwrap();
+            continue;
+        }
+
+        events.write(ChatEvent::new(packet.sender(), name, message));
+    }
+}
+
+fn deliver_messages(
+    mut events: EventReader<'_, '_, ChatEvent>,
+    senders: Query<'_, '_, (&Name, &Uuid, &Position, Option<&ChatPrefix>)>,
+    recipients: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &ConnectionId,
+            &Name,
+            &Position,
+            Option<&PlayerPermissions>,
+            &IgnoreList,
+        ),
+        With<packet_state::Play>,
+    >,
+    channels: Res<'_, ChatChannels>,
+    groups: Res<'_, PermissionGroups>,
+    scoreboard: Option<Res<'_, Scoreboard>>,
+    compose: Res<'_, Compose>,
+) {
+    let scoreboard = scoreboard.as_deref();
+
+    for event in events.read() {
+        if event.is_cancelled() {
+            continue;
+        }
+
+        let (name, uuid, position, prefix) = match senders.get(event.sender) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to deliver chat message: query failed: {e}");
+                continue;
+            }
+        };
+
+        let Some(channel) = channels.get(&event.channel) else {
+            error!(
+                "failed to deliver chat message: channel {} does not exist",
+                event.channel
+            );
+            continue;
+        };
+
+        let chat = agnostic::chat(channel.format.render(FormatArgs {
+            channel: &event.channel,
+            prefix: prefix.map_or("", |prefix| prefix.0.as_str()),
+            name: name.as_str(),
+            message: &event.message,
+        }));
+
+        let team = scoreboard.and_then(|scoreboard| scoreboard.team_of(name.as_str()));
+
+        for (entity, &connection_id, recipient_name, recipient_position, permissions, ignored) in
+            &recipients
+        {
+            // Senders always see their own messages
+            if entity != event.sender {
+                if !channel.allows(&groups, permissions) || ignored.contains(**uuid) {
+                    continue;
+                }
+
+                let in_scope = match channel.scope {
+                    ChannelScope::Global => true,
+                    ChannelScope::Local { radius } => {
+                        position.distance_squared(**recipient_position) <= radius * radius
+                    }
+                    ChannelScope::Team => {
+                        team.is_some()
+                            && scoreboard
+                                .and_then(|scoreboard| scoreboard.team_of(recipient_name.as_str()))
+                                == team
+                    }
+                };
+
+                if !in_scope {
+                    continue;
+                }
+            }
+
+            compose.unicast(&chat, connection_id).unwrap();
+        }
+    }
+}
+
+fn deliver_private_messages(
+    mut events: EventReader<'_, '_, PrivateMessageEvent>,
+    mut senders: Query<
+        '_,
+        '_,
+        (
+            &ConnectionId,
+            &Name,
+            &Uuid,
+            Option<&Muted>,
+            &mut ChatCooldown,
+        ),
+    >,
+    recipients: Query<'_, '_, (&ConnectionId, &Name, &IgnoreList)>,
+    settings: Res<'_, ChatSettings>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    let tick = compose.global().tick;
+
+    for event in events.read() {
+        let (&sender_connection, sender_name, uuid, muted, mut cooldown) =
+            match senders.get_mut(event.sender) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("failed to deliver private message: query failed: {e}");
+                    continue;
+                }
+            };
+
+        let Ok((&recipient_connection, recipient_name, ignored)) = recipients.get(event.recipient)
+        else {
+            let chat = agnostic::chat("§cThat player is no longer online");
+            compose.unicast(&chat, sender_connection).unwrap();
+            continue;
+        };
+
+        let message = match check_message(&event.message, muted, &mut cooldown, &settings, tick) {
+            Ok(message) => message,
+            Err(reason) => {
+                let chat = agnostic::chat(reason);
+                compose.unicast(&chat, sender_connection).unwrap();
+                continue;
+            }
+        };
+
+        let outgoing = agnostic::chat(settings.outgoing_format.render(FormatArgs {
+            name: recipient_name.as_str(),
+            message: &message,
+            ..FormatArgs::default()
+        }));
+        compose.unicast(&outgoing, sender_connection).unwrap();
+
+        // Ignored players are not told that they are ignored
+        if ignored.contains(**uuid) {
+            continue;
+        }
+
+        let incoming = agnostic::chat(settings.incoming_format.render(FormatArgs {
+            name: sender_name.as_str(),
+            message: &message,
+            ..FormatArgs::default()
+        }));
+        compose.unicast(&incoming, recipient_connection).unwrap();
+
+        commands
+            .entity(event.sender)
+            .insert(ReplyTarget(event.recipient));
+        commands
+            .entity(event.recipient)
+            .insert(ReplyTarget(event.sender));
+    }
+}
+
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        let storage = ChatStorage::new(app.world().resource::<LocalDb>()).unwrap();
+        app.insert_resource(storage);
+        app.init_resource::<ChatSettings>();
+        app.init_resource::<ChatChannels>();
+        app.world_mut()
+            .get_resource_or_init::<PermissionGroups>()
+            .grant_default("moderator", "chat.channel.staff");
+        app.add_event::<ChatEvent>();
+        app.add_event::<PrivateMessageEvent>();
+
+        app.configure_sets(
+            FixedUpdate,
+            (ChatSet::Receive, ChatSet::Modify, ChatSet::Deliver)
+                .chain()
+                .after(ingress::decode::play),
+        );
+
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (
+                receive_messages.in_set(ChatSet::Receive),
+                (deliver_messages, deliver_private_messages).in_set(ChatSet::Deliver),
+            ),
+        );
+    }
+
+    // Commands are registered once the command plugin has been added
+    fn finish(&self, app: &mut App) {
+        command::ChannelCommand::register(app.world_mut());
+        command::IgnoreCommand::register(app.world_mut());
+        command::MsgCommand::register(app.world_mut());
+        command::MuteCommand::register(app.world_mut());
+        command::ReplyCommand::register(app.world_mut());
+        command::UnmuteCommand::register(app.world_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChatCooldown, RateLimit};
+
+    #[test]
+    fn rate_limit_allows_burst_then_interval() {
+        let limit = RateLimit {
+            burst: 2,
+            interval_ticks: 20,
+        };
+        let mut cooldown = ChatCooldown::default();
+
+        assert_eq!(limit.check(&mut cooldown, 100), Ok(()));
+        assert_eq!(limit.check(&mut cooldown, 100), Ok(()));
+        assert_eq!(limit.check(&mut cooldown, 105), Err(15));
+        assert_eq!(limit.check(&mut cooldown, 120), Ok(()));
+        assert_eq!(limit.check(&mut cooldown, 200), Ok(()));
+    }
+}
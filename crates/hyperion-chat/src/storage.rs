@@ -0,0 +1,95 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use heed::{Database, Env, byteorder::NativeEndian, types};
+use hyperion::storage::LocalDb;
+
+use crate::Muted;
+
+/// Stored instead of an expiry time for mutes which do not expire
+const PERMANENT: u64 = u64::MAX;
+
+/// Mutes and ignore lists of players
+#[derive(Resource)]
+pub struct ChatStorage {
+    env: Env,
+    mutes: Database<types::U128<NativeEndian>, types::U64<NativeEndian>>,
+    ignores: Database<types::U128<NativeEndian>, types::Bytes>,
+}
+
+impl ChatStorage {
+    pub fn new(db: &LocalDb) -> anyhow::Result<Self> {
+        let mut wtxn = db.write_txn()?;
+        let mutes = db.create_database(&mut wtxn, Some("uuid-to-mute"))?;
+        let ignores = db.create_database(&mut wtxn, Some("uuid-to-ignores"))?;
+        wtxn.commit()?;
+
+        Ok(Self {
+            env: (**db).clone(),
+            mutes,
+            ignores,
+        })
+    }
+
+    /// The mute of the player, which may have expired
+    pub fn mute(&self, uuid: uuid::Uuid) -> anyhow::Result<Option<Muted>> {
+        let rtxn = self.env.read_txn()?;
+        let Some(until) = self.mutes.get(&rtxn, &uuid.as_u128())? else {
+            return Ok(None);
+        };
+
+        let until =
+            (until != PERMANENT).then(|| SystemTime::UNIX_EPOCH + Duration::from_secs(until));
+        Ok(Some(Muted { until }))
+    }
+
+    pub fn set_mute(&self, uuid: uuid::Uuid, mute: Muted) -> anyhow::Result<()> {
+        let until = match mute.until {
+            Some(until) => until.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+            None => PERMANENT,
+        };
+
+        let mut wtxn = self.env.write_txn()?;
+        self.mutes.put(&mut wtxn, &uuid.as_u128(), &until)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_mute(&self, uuid: uuid::Uuid) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.mutes.delete(&mut wtxn, &uuid.as_u128())?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// The players whose messages the player does not see
+    pub fn ignored(&self, uuid: uuid::Uuid) -> anyhow::Result<HashSet<uuid::Uuid>> {
+        let rtxn = self.env.read_txn()?;
+        let Some(bytes) = self.ignores.get(&rtxn, &uuid.as_u128())? else {
+            return Ok(HashSet::new());
+        };
+
+        let ignored = bytes
+            .chunks_exact(16)
+            .map(uuid::Uuid::from_slice)
+            .collect::<Result<_, _>>()?;
+
+        Ok(ignored)
+    }
+
+    pub fn set_ignored(
+        &self,
+        uuid: uuid::Uuid,
+        ignored: &HashSet<uuid::Uuid>,
+    ) -> anyhow::Result<()> {
+        let bytes: Vec<u8> = ignored.iter().flat_map(|uuid| *uuid.as_bytes()).collect();
+
+        let mut wtxn = self.env.write_txn()?;
+        self.ignores.put(&mut wtxn, &uuid.as_u128(), &bytes)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{DeriveInput, Error, Ident, Lit, parse_macro_input};
+use syn::{DeriveInput, Error, Lit, parse_macro_input};
 
 #[proc_macro_derive(CommandPermission, attributes(command_permission))]
 pub fn derive_command_permission(input: TokenStream) -> TokenStream {
@@ -8,8 +8,11 @@ pub fn derive_command_permission(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident.clone(); // Clone the Ident to prevent moving
 
-    // Extract the group from the `#[command_permission(group = "Admin")]` attribute
+    // Extract the group and node from the
+    // `#[command_permission(group = "Admin", node = "command.example")]` attribute
     let mut group = None;
+    let mut node = None;
+    let mut command_name = None;
     for attr in &input.attrs {
         if attr.path().is_ident("command_permission") {
             if let Err(err) = attr.parse_nested_meta(|meta| {
@@ -17,6 +20,10 @@ pub fn derive_command_permission(input: TokenStream) -> TokenStream {
                     if let Ok(Lit::Str(lit)) = meta.value()?.parse::<Lit>() {
                         group = Some(lit);
                     }
+                } else if meta.path.is_ident("node") {
+                    if let Ok(Lit::Str(lit)) = meta.value()?.parse::<Lit>() {
+                        node = Some(lit.value());
+                    }
                 }
                 Ok(())
             }) {
@@ -24,11 +31,23 @@ pub fn derive_command_permission(input: TokenStream) -> TokenStream {
                     .to_compile_error()
                     .into();
             }
+        } else if attr.path().is_ident("command") {
+            // `#[command(about = "...")]` and others are not names, so errors are ignored
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    if let Ok(Lit::Str(lit)) = meta.value()?.parse::<Lit>() {
+                        command_name = Some(lit.value());
+                    }
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                }
+                Ok(())
+            });
         }
     }
 
-    let group_ident = match group {
-        Some(g) => Ident::new(&g.value(), g.span()),
+    let group = match group {
+        Some(g) => g.value().to_lowercase(),
         None => {
             return Error::new_spanned(
                 input,
@@ -39,19 +58,17 @@ pub fn derive_command_permission(input: TokenStream) -> TokenStream {
         }
     };
 
+    // Commands default to `command.<name>`, using the name from `#[command(name = "...")]`
+    let node = node.unwrap_or_else(|| {
+        let command_name = command_name.unwrap_or_else(|| name.to_string().to_lowercase());
+        format!("command.{command_name}")
+    });
+
     // Generate the trait implementation
     let expanded = quote! {
         impl CommandPermission for #name {
-            fn has_required_permission(user_group: ::hyperion_permission::Group) -> bool {
-                const REQUIRED_GROUP: ::hyperion_permission::Group = ::hyperion_permission::Group::#group_ident;
-
-                if REQUIRED_GROUP == ::hyperion_permission::Group::Banned {
-                    // When checking for the group "Banned" we don't want to check for higher groups.
-                    return REQUIRED_GROUP == user_group;
-                } else {
-                    return user_group as u32 >= REQUIRED_GROUP as u32
-                }
-            }
+            const NODE: &'static str = #node;
+            const DEFAULT_GROUP: &'static str = #group;
         }
     };
 
@@ -0,0 +1,216 @@
+//! Coordinates such as `10 64 -3`, `~ ~1 ~` or `^ ^ ^5`
+
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use hyperion::simulation::{Pitch, Position, Yaw};
+use thiserror::Error;
+
+/// Coordinate parsing and resolution error
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CoordinateError {
+    #[error("invalid coordinate {0:?}, expected a number, ~ or ^")]
+    Invalid(String),
+    #[error("cannot mix local coordinates (^) with world coordinates")]
+    MixedLocal,
+    #[error("coordinates are out of range")]
+    OutOfRange,
+}
+
+/// One coordinate of a position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coordinate {
+    /// A world coordinate such as `10`
+    Absolute(f32),
+    /// An offset from the caller along a world axis such as `~` or `~-2`
+    Relative(f32),
+    /// An offset from the caller along the direction they are looking such as `^` or `^5`.
+    /// Local coordinates are left, up and forward.
+    Local(f32),
+}
+
+impl FromStr for Coordinate {
+    type Err = CoordinateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CoordinateError::Invalid(s.to_string());
+
+        // `f32::from_str` also accepts `nan` and `inf`, which are not coordinates
+        let parse = |value: &str| match value.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(invalid()),
+        };
+        let parse_offset = |offset: &str| {
+            if offset.is_empty() {
+                Ok(0.0)
+            } else {
+                parse(offset)
+            }
+        };
+
+        if let Some(offset) = s.strip_prefix('~') {
+            parse_offset(offset).map(Self::Relative)
+        } else if let Some(offset) = s.strip_prefix('^') {
+            parse_offset(offset).map(Self::Local)
+        } else {
+            parse(s).map(Self::Absolute)
+        }
+    }
+}
+
+impl Coordinate {
+    const fn is_local(self) -> bool {
+        matches!(self, Self::Local(_))
+    }
+
+    fn resolve(self, origin: f32) -> f32 {
+        match self {
+            Self::Absolute(value) => value,
+            Self::Relative(offset) | Self::Local(offset) => origin + offset,
+        }
+    }
+}
+
+/// A position given as three coordinates, which can be flattened into a command
+///
+/// ```ignore
+/// #[derive(Parser, CommandPermission, Debug)]
+/// #[command(name = "tp")]
+/// #[command_permission(group = "Moderator")]
+/// pub struct TeleportCommand {
+///     #[command(flatten)]
+///     destination: Coordinates,
+/// }
+/// ```
+#[derive(clap::Args, Clone, Copy, Debug, PartialEq)]
+pub struct Coordinates {
+    #[arg(allow_hyphen_values = true)]
+    pub x: Coordinate,
+    #[arg(allow_hyphen_values = true)]
+    pub y: Coordinate,
+    #[arg(allow_hyphen_values = true)]
+    pub z: Coordinate,
+}
+
+impl Coordinates {
+    /// The position relative to an origin and the direction, in degrees, it is looking. Positions
+    /// too far away to represent are rejected.
+    pub fn resolve(&self, origin: Vec3, yaw: f32, pitch: f32) -> Result<Vec3, CoordinateError> {
+        let local = [self.x, self.y, self.z].map(Coordinate::is_local);
+
+        let position = if local.iter().all(|&local| local) {
+            Self::resolve_local(
+                origin,
+                yaw,
+                pitch,
+                Vec3::new(
+                    self.x.resolve(0.0),
+                    self.y.resolve(0.0),
+                    self.z.resolve(0.0),
+                ),
+            )
+        } else if local.contains(&true) {
+            return Err(CoordinateError::MixedLocal);
+        } else {
+            Vec3::new(
+                self.x.resolve(origin.x),
+                self.y.resolve(origin.y),
+                self.z.resolve(origin.z),
+            )
+        };
+
+        if !position.is_finite() {
+            return Err(CoordinateError::OutOfRange);
+        }
+
+        Ok(position)
+    }
+
+    /// The position relative to `caller`, which is the origin if it has no [`Position`]
+    pub fn resolve_for(&self, world: &World, caller: Entity) -> Result<Vec3, CoordinateError> {
+        let entity = world.entity(caller);
+        let origin = entity
+            .get::<Position>()
+            .map_or(Vec3::ZERO, |position| **position);
+        let yaw = entity.get::<Yaw>().map_or(0.0, |yaw| **yaw);
+        let pitch = entity.get::<Pitch>().map_or(0.0, |pitch| **pitch);
+
+        self.resolve(origin, yaw, pitch)
+    }
+
+    /// Moves `origin` by `offset` (left, up, forward) in the direction it is looking, matching
+    /// the Notchian server
+    fn resolve_local(origin: Vec3, yaw: f32, pitch: f32, offset: Vec3) -> Vec3 {
+        let (yaw_sin, yaw_cos) = (yaw + 90.0).to_radians().sin_cos();
+        let (pitch_sin, pitch_cos) = (-pitch).to_radians().sin_cos();
+        let (up_sin, up_cos) = (90.0 - pitch).to_radians().sin_cos();
+
+        let forward = Vec3::new(yaw_cos * pitch_cos, pitch_sin, yaw_sin * pitch_cos);
+        let up = Vec3::new(yaw_cos * up_cos, up_sin, yaw_sin * up_cos);
+        let left = -forward.cross(up);
+
+        origin + left * offset.x + up * offset.y + forward * offset.z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec3;
+
+    use super::{Coordinate, CoordinateError, Coordinates};
+
+    fn coordinates(x: &str, y: &str, z: &str) -> Coordinates {
+        Coordinates {
+            x: x.parse().unwrap(),
+            y: y.parse().unwrap(),
+            z: z.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn parse_coordinates() {
+        assert_eq!("-3.5".parse::<Coordinate>(), Ok(Coordinate::Absolute(-3.5)));
+        assert_eq!("~".parse::<Coordinate>(), Ok(Coordinate::Relative(0.0)));
+        assert_eq!("~-2".parse::<Coordinate>(), Ok(Coordinate::Relative(-2.0)));
+        assert_eq!("^5".parse::<Coordinate>(), Ok(Coordinate::Local(5.0)));
+        assert_eq!(
+            "~x".parse::<Coordinate>(),
+            Err(CoordinateError::Invalid("~x".to_string()))
+        );
+
+        for invalid in ["nan", "inf", "-infinity", "~NaN", "^inf"] {
+            assert_eq!(
+                invalid.parse::<Coordinate>(),
+                Err(CoordinateError::Invalid(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_relative() {
+        let origin = Vec3::new(10.0, 64.0, -5.0);
+        let position = coordinates("~", "~1", "3").resolve(origin, 0.0, 0.0);
+        assert_eq!(position, Ok(Vec3::new(10.0, 65.0, 3.0)));
+
+        let mixed = coordinates("^", "~", "^").resolve(origin, 0.0, 0.0);
+        assert_eq!(mixed, Err(CoordinateError::MixedLocal));
+
+        let far = coordinates("~3e38", "~", "~").resolve(Vec3::new(3e38, 0.0, 0.0), 0.0, 0.0);
+        assert_eq!(far, Err(CoordinateError::OutOfRange));
+    }
+
+    #[test]
+    fn resolve_local() {
+        // A yaw of 0 faces south (+z), so left is east (+x)
+        let position = coordinates("^1", "^2", "^3")
+            .resolve(Vec3::ZERO, 0.0, 0.0)
+            .unwrap();
+        assert!(position.abs_diff_eq(Vec3::new(1.0, 2.0, 3.0), 1e-5));
+
+        // A yaw of 90 faces west (-x)
+        let position = coordinates("^", "^", "^2")
+            .resolve(Vec3::ZERO, 90.0, 0.0)
+            .unwrap();
+        assert!(position.abs_diff_eq(Vec3::new(-2.0, 0.0, 0.0), 1e-5));
+    }
+}
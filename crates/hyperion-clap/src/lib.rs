@@ -1,24 +1,25 @@
-use std::iter::zip;
-
 use bevy::{ecs::system::SystemState, prelude::*};
-use clap::{Arg as ClapArg, Parser, ValueEnum, ValueHint, error::ErrorKind};
-use hyperion::{
-    net::{Compose, ConnectionId, DataBundle, agnostic},
-    simulation::{IgnMap, command::RootCommand, packet::play},
-};
+use clap::{Arg as ClapArg, Parser, ValueEnum, ValueHint, builder::ValueParser, error::ErrorKind};
+use hyperion::simulation::{PendingTeleportation, command::RootCommand};
 pub use hyperion_clap_macros::CommandPermission;
 pub use hyperion_command;
-use hyperion_command::{CommandHandler, CommandRegistry, ExecutableCommand};
-use hyperion_permission::Group;
+use hyperion_command::{
+    CommandHandler, CommandRegistry, Completions, ExecutableCommand, reply, require_player,
+};
+use hyperion_permission::{PermissionGroups, has_permission};
 use hyperion_utils::ApplyWorld;
-use tracing::error;
 use valence_bytes::Utf8Bytes;
-use valence_protocol::{
-    VarInt,
-    packets::play::{
-        command_suggestions_s2c::{CommandSuggestionsMatch, CommandSuggestionsS2c},
-        command_tree_s2c::StringArg,
-    },
+use valence_protocol::packets::play::command_tree_s2c::{Parser as ValenceParser, StringArg};
+
+mod coordinates;
+mod permission;
+mod selector;
+
+pub use coordinates::{Coordinate, CoordinateError, Coordinates};
+pub use permission::PermissionCommand;
+pub use selector::{
+    Condition, EntitySelector, PlayerSelector, Range, SelectorError, SelectorKind, SelectorOptions,
+    SelectorSort,
 };
 
 struct GenericExecutableCommand<Command: MinecraftCommand> {
@@ -26,27 +27,11 @@ struct GenericExecutableCommand<Command: MinecraftCommand> {
 }
 
 impl<Command: MinecraftCommand> ExecutableCommand for GenericExecutableCommand<Command> {
-    fn execute(&mut self, world: &World, packet: &play::CommandExecution) {
-        let compose = world.resource::<Compose>();
-        let input = packet.command.split_whitespace();
+    fn execute(&mut self, world: &World, sender: Entity, command: &str) {
+        let input = command.split_whitespace();
 
         match Command::try_parse_from(input) {
-            Ok(elem) => {
-                let Some(group) = world.entity(packet.sender()).get::<Group>() else {
-                    error!("failed to execute command: player is missing Group component");
-                    return;
-                };
-
-                if Command::has_required_permission(*group) {
-                    elem.execute(world, &mut self.state, packet.sender());
-                } else {
-                    let chat = agnostic::chat("§cYou do not have permission to use this command!");
-
-                    let mut bundle = DataBundle::new(compose);
-                    bundle.add_packet(&chat).unwrap();
-                    bundle.unicast(packet.connection_id()).unwrap();
-                }
-            }
+            Ok(elem) => elem.execute(world, &mut self.state, sender),
             Err(e) => {
                 // add red if not display help
                 let prefix = match e.kind() {
@@ -56,9 +41,7 @@ impl<Command: MinecraftCommand> ExecutableCommand for GenericExecutableCommand<C
 
                 // minecraft red
                 let msg = format!("{prefix}{e}");
-
-                let msg = agnostic::chat(msg);
-                compose.unicast(&msg, packet.connection_id()).unwrap();
+                reply(world, sender, msg);
 
                 tracing::warn!("could not parse command {e}");
             }
@@ -72,6 +55,46 @@ impl<Command: MinecraftCommand> ApplyWorld for GenericExecutableCommand<Command>
     }
 }
 
+/// The Minecraft argument type of a clap argument, found from the type it is parsed into
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArgumentKind {
+    Entity,
+    Player,
+    Coordinate,
+    Other,
+}
+
+impl ArgumentKind {
+    fn of(arg: &ClapArg) -> Self {
+        let type_id = arg.get_value_parser().type_id();
+        let is = |parser: ValueParser| parser.type_id() == type_id;
+
+        if is(clap::value_parser!(EntitySelector).into()) {
+            Self::Entity
+        } else if is(clap::value_parser!(PlayerSelector).into()) {
+            Self::Player
+        } else if is(clap::value_parser!(Coordinate).into()) {
+            Self::Coordinate
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Values suggested for an argument of this kind while it is typed
+    fn suggestions(self, world: &World, arg: &ClapArg) -> Vec<String> {
+        match self {
+            Self::Entity => selector::suggest(world, false),
+            Self::Player => selector::suggest(world, true),
+            Self::Coordinate => vec!["~".to_string(), "^".to_string()],
+            Self::Other => arg
+                .get_possible_values()
+                .iter()
+                .map(|possible| possible.get_name().to_string())
+                .collect(),
+        }
+    }
+}
+
 pub trait MinecraftCommand: Parser + CommandPermission + 'static {
     /// Command state passed to [`MinecraftCommand::execute`]. This can be any type, but it may be
     /// useful to store a [`bevy::ecs::system::SystemState`] to access types implementing
@@ -91,14 +114,12 @@ pub trait MinecraftCommand: Parser + CommandPermission + 'static {
         let cmd = Self::command();
         let name = Utf8Bytes::copy_from_str(cmd.get_name());
 
-        let has_permissions = |world: &World, caller: Entity| {
-            let Some(group) = world.entity(caller).get::<Group>() else {
-                error!("failed to check command permissions: client is missing Group component");
-                return false;
-            };
+        world
+            .get_resource_or_init::<PermissionGroups>()
+            .grant_default(Self::DEFAULT_GROUP, Self::NODE);
 
-            Self::has_required_permission(*group)
-        };
+        let has_permissions =
+            |world: &World, caller: Entity| has_permission(world, caller, Self::NODE);
 
         let node_to_register =
             hyperion::simulation::command::Command::literal(name.clone(), has_permissions);
@@ -107,130 +128,74 @@ pub trait MinecraftCommand: Parser + CommandPermission + 'static {
         let mut on = **root_command;
         on = world.spawn((node_to_register, ChildOf(on))).id();
 
-        for arg in cmd.get_arguments() {
-            use valence_protocol::packets::play::command_tree_s2c::Parser as ValenceParser;
+        let mut args = cmd.get_arguments().peekable();
+        while let Some(arg) = args.next() {
             let name = arg.get_value_names().unwrap().first().unwrap();
-            let name = name.to_ascii_lowercase();
-            let node_to_register = hyperion::simulation::command::Command::argument(
-                name,
-                ValenceParser::String(StringArg::SingleWord),
-            );
+            let mut name = name.to_ascii_lowercase();
+
+            let parser = match ArgumentKind::of(arg) {
+                ArgumentKind::Entity => ValenceParser::Entity {
+                    single: false,
+                    only_players: false,
+                },
+                ArgumentKind::Player => ValenceParser::Entity {
+                    single: false,
+                    only_players: true,
+                },
+                ArgumentKind::Coordinate => {
+                    // The client reads the three coordinates of a position as one argument
+                    for _ in 0..2 {
+                        args.next_if(|arg| ArgumentKind::of(arg) == ArgumentKind::Coordinate);
+                    }
+                    name = "position".to_string();
+                    ValenceParser::Vec3
+                }
+                ArgumentKind::Other => ValenceParser::String(StringArg::SingleWord),
+            };
+
+            let node_to_register = hyperion::simulation::command::Command::argument(name, parser);
 
             on = world.spawn((node_to_register, ChildOf(on))).id();
         }
 
         let executable = Box::new(GenericExecutableCommand::<Self> { state });
 
-        let tab_complete = |world: &World, completion: &play::RequestCommandCompletions| {
-            let compose = world.resource::<Compose>();
-            let full_query = &completion.text;
-            let id = completion.transaction_id;
-
-            let Some(query) = full_query.strip_prefix('/') else {
-                // todo: send error message to player
-                tracing::warn!("could not parse command {full_query}");
-                return;
-            };
+        let tab_complete = |world: &World, _sender: Entity, input: &str| {
+            let mut words = input.split_whitespace();
+            let _command_name = words.next()?;
+            let words: Vec<_> = words.collect();
 
-            let mut query = query.split_whitespace();
-            let _command_name = query.next().unwrap();
-
-            let command = Self::command();
-            let mut positionals = command.get_positionals();
-
-            'positionals: for (input_arg, cmd_arg) in zip(query, positionals.by_ref()) {
-                // see if anything matches
-                let possible_values = cmd_arg.get_possible_values();
-                for possible in &possible_values {
-                    if possible.matches(input_arg, true) {
-                        continue 'positionals;
-                    }
-                }
-
-                // nothing matches! let's see if a substring matches
-                let mut substring_matches = possible_values
-                    .iter()
-                    .filter(|possible| {
-                        // todo: this is inefficient
-                        possible
-                            .get_name()
-                            .to_lowercase()
-                            .starts_with(&input_arg.to_lowercase())
-                    })
-                    .peekable();
-
-                if substring_matches.peek().is_none() {
-                    // no matches
-                    return;
+            // The word being completed, which is empty after a space
+            let (index, word, start) = match words.last() {
+                Some(&last) if !input.ends_with(char::is_whitespace) => {
+                    let start = last.as_ptr() as usize - input.as_ptr() as usize;
+                    (words.len() - 1, last, start)
                 }
-
-                let matches = substring_matches
-                    .map(clap::builder::PossibleValue::get_name)
-                    .map(|name| CommandSuggestionsMatch {
-                        suggested_match: name.into(),
-                        tooltip: None,
-                    })
-                    .collect();
-
-                let start = input_arg.as_ptr() as usize - full_query.as_ptr() as usize;
-                let len = input_arg.len();
-
-                let start = i32::try_from(start).unwrap();
-                let len = i32::try_from(len).unwrap();
-
-                let packet = CommandSuggestionsS2c {
-                    id,
-                    start: VarInt(start),
-                    length: VarInt(len),
-                    matches,
-                };
-
-                compose
-                    .unicast(&packet, completion.connection_id())
-                    .unwrap();
-
-                // todo: send possible matches to player
-                return;
-            }
-
-            let Some(remaining_positional) = positionals.next() else {
-                // we are all done completing
-                return;
+                _ => (words.len(), "", input.len()),
             };
 
-            let possible_values = remaining_positional.get_possible_values();
-
-            let names = possible_values
-                .iter()
-                .map(clap::builder::PossibleValue::get_name);
+            let command = Self::command();
+            // we are all done completing if there are no more positionals
+            let arg = command.get_positionals().nth(index)?;
 
-            let matches = names
+            let word_lowercase = word.to_lowercase();
+            let matches = ArgumentKind::of(arg)
+                .suggestions(world, arg)
                 .into_iter()
-                .map(|name| CommandSuggestionsMatch {
-                    suggested_match: name.into(),
-                    tooltip: None,
-                })
+                .filter(|suggestion| suggestion.to_lowercase().starts_with(&word_lowercase))
                 .collect();
 
-            let start = full_query.len();
-            let start = i32::try_from(start).unwrap();
-
-            let packet = CommandSuggestionsS2c {
-                id,
-                start: VarInt(start),
-                length: VarInt(0),
+            Some(Completions {
+                start,
+                len: word.len(),
                 matches,
-            };
-
-            compose
-                .unicast(&packet, completion.connection_id())
-                .unwrap();
+            })
         };
 
         let handler = CommandHandler {
             executable,
             tab_complete,
-            has_permissions,
+            permission: Self::NODE,
         };
 
         tracing::info!("registering command {name}");
@@ -263,7 +228,10 @@ impl MinecraftArg for ClapArg {
 }
 
 pub trait CommandPermission {
-    fn has_required_permission(user_group: hyperion_permission::Group) -> bool;
+    /// The permission node needed to use the command, such as `command.fly`
+    const NODE: &'static str;
+    /// The group which is granted [`CommandPermission::NODE`] unless the server configured it
+    const DEFAULT_GROUP: &'static str;
 }
 
 #[derive(Clone, Debug, ValueEnum, PartialEq, Eq)]
@@ -274,73 +242,119 @@ pub enum GameMode {
     Spectator,
 }
 
-#[derive(clap::Parser, Debug)]
-pub struct SetCommand {
-    player: String,
-    group: Group,
+impl From<GameMode> for hyperion::simulation::game_mode::GameMode {
+    fn from(value: GameMode) -> Self {
+        match value {
+            GameMode::Survival => Self::Survival,
+            GameMode::Creative => Self::Creative,
+            GameMode::Adventure => Self::Adventure,
+            GameMode::Spectator => Self::Spectator,
+        }
+    }
 }
 
-#[derive(clap::Parser, Debug)]
-pub struct GetCommand {
-    player: String,
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "gamemode")]
+#[command(about = "Change the gamemode of a player")]
+#[command_permission(group = "Admin")]
+pub struct GameModeCommand {
+    /// The gamemode to set
+    #[arg(value_enum)]
+    mode: GameMode,
+
+    /// The players to change the gamemode of
+    target: Option<PlayerSelector>,
 }
 
-#[derive(Parser, CommandPermission, Debug)]
-#[command(name = "perms")]
-#[command_permission(group = "Normal")]
-pub enum PermissionCommand {
-    Set(SetCommand),
-    Get(GetCommand),
+/// Describes the players affected by a command, such as `Notch` or `3 players`
+fn describe_targets(world: &World, targets: &[Entity]) -> String {
+    match targets {
+        [target] => world
+            .get::<Name>(*target)
+            .map_or_else(|| "1 player".to_string(), |name| format!("§b{name}§r")),
+        targets => format!("§e{}§r players", targets.len()),
+    }
 }
 
-impl MinecraftCommand for PermissionCommand {
+impl MinecraftCommand for GameModeCommand {
     type State = SystemState<Commands<'static, 'static>>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
         let mut commands = state.get(world);
-        let compose = world.resource::<Compose>();
-        let ign_map = world.resource::<IgnMap>();
-        let Some(&connection_id) = world.entity(caller).get::<ConnectionId>() else {
-            error!("permission command failed: caller is missing ConnectionId component");
+
+        let targets = match &self.target {
+            Some(target) => target.resolve(world, caller),
+            None if require_player(world, caller) => vec![caller],
+            None => return,
+        };
+
+        if targets.is_empty() {
+            reply(world, caller, "§cNo player was found");
             return;
+        }
+
+        let mode = hyperion::simulation::game_mode::GameMode::from(self.mode);
+        for &target in &targets {
+            commands.entity(target).insert(mode);
+        }
+
+        let msg = if targets == [caller] {
+            format!("Your game mode has been set to §e{mode:?}")
+        } else {
+            let targets = describe_targets(world, &targets);
+            format!("The game mode of {targets} has been set to §e{mode:?}")
         };
-        match self {
-            Self::Set(cmd) => {
-                // Handle setting permissions
-                let Some(&entity) = ign_map.get(cmd.player.as_str()) else {
-                    let msg = format!("§c{} not found", cmd.player);
-                    let chat = hyperion::net::agnostic::chat(msg);
-                    compose.unicast(&chat, connection_id).unwrap();
-                    return;
-                };
+        reply(world, caller, msg);
+    }
+}
 
-                commands.entity(entity).insert(cmd.group);
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "tp")]
+#[command(about = "Teleport players to a position")]
+#[command_permission(group = "Moderator")]
+pub struct TeleportCommand {
+    /// The players to teleport
+    target: PlayerSelector,
+
+    /// Where to teleport the players. Relative (`~`) and local (`^`) coordinates are relative to
+    /// the caller.
+    #[command(flatten)]
+    destination: Coordinates,
+}
 
-                let msg = format!(
-                    "§b{}§r's group has been set to §e{:?}",
-                    cmd.player, cmd.group
-                );
-                let chat = hyperion::net::agnostic::chat(msg);
-                compose.unicast(&chat, connection_id).unwrap();
-            }
-            Self::Get(cmd) => {
-                let Some(&entity) = ign_map.get(cmd.player.as_str()) else {
-                    let msg = format!("§c{} not found", cmd.player);
-                    let chat = hyperion::net::agnostic::chat(msg);
-                    compose.unicast(&chat, connection_id).unwrap();
-                    return;
-                };
+impl MinecraftCommand for TeleportCommand {
+    type State = SystemState<Commands<'static, 'static>>;
 
-                let Some(group) = world.entity(entity).get::<Group>() else {
-                    error!("permission command failed: player is missing Group component");
-                    return;
-                };
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        let mut commands = state.get(world);
 
-                let msg = format!("§b{}§r's group is §e{:?}", cmd.player, group);
-                let chat = hyperion::net::agnostic::chat(msg);
-                compose.unicast(&chat, connection_id).unwrap();
+        let destination = match self.destination.resolve_for(world, caller) {
+            Ok(destination) => destination,
+            Err(e) => {
+                reply(world, caller, format!("§c{e}"));
+                return;
             }
+        };
+
+        let targets = self.target.resolve(world, caller);
+        if targets.is_empty() {
+            reply(world, caller, "§cNo player was found");
+            return;
         }
+
+        for &target in &targets {
+            commands
+                .entity(target)
+                .insert(PendingTeleportation::new(destination));
+        }
+
+        let targets = describe_targets(world, &targets);
+        let Vec3 { x, y, z } = destination;
+        reply(
+            world,
+            caller,
+            format!("Teleported {targets} to §e{x:.1} {y:.1} {z:.1}"),
+        );
     }
 }
 
@@ -350,5 +364,7 @@ impl Plugin for ClapCommandPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(hyperion_command::CommandPlugin);
         PermissionCommand::register(app.world_mut());
+        GameModeCommand::register(app.world_mut());
+        TeleportCommand::register(app.world_mut());
     }
 }
@@ -0,0 +1,340 @@
+use std::{fmt::Write, time::SystemTime};
+
+use bevy::{ecs::system::SystemState, prelude::*};
+use clap::{ArgAction, Parser, Subcommand};
+use hyperion::simulation::IgnMap;
+use hyperion_command::reply;
+use hyperion_permission::{
+    DEFAULT_GROUP, PermissionGroup, PermissionGroups, PlayerPermissions, expires_in, has_permission,
+};
+use tracing::error;
+
+use crate::{CommandPermission, MinecraftCommand};
+
+#[derive(clap::Parser, Debug)]
+pub struct InfoCommand {
+    player: String,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct CheckCommand {
+    player: String,
+    node: String,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct SetCommand {
+    player: String,
+    node: String,
+    #[arg(action = ArgAction::Set)]
+    value: bool,
+
+    /// How long the node is set. The node is set permanently if this is not given.
+    minutes: Option<u64>,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct UnsetCommand {
+    player: String,
+    node: String,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct AddGroupCommand {
+    player: String,
+    group: String,
+
+    /// How long the player is in the group. The player is added permanently if this is not given.
+    minutes: Option<u64>,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct RemoveGroupCommand {
+    player: String,
+    group: String,
+}
+
+/// Changes the permission groups
+#[derive(Subcommand, Debug)]
+pub enum GroupCommand {
+    /// List the groups and their nodes
+    List,
+    Create {
+        name: String,
+        #[arg(default_value_t = 0)]
+        priority: i32,
+    },
+    Delete {
+        name: String,
+    },
+    Set {
+        group: String,
+        node: String,
+        #[arg(action = ArgAction::Set)]
+        value: bool,
+    },
+    Unset {
+        group: String,
+        node: String,
+    },
+    /// Make a group inherit the nodes of another group
+    Parent {
+        group: String,
+        parent: String,
+    },
+}
+
+#[derive(Parser, CommandPermission, Debug)]
+#[command(name = "perms")]
+#[command(about = "View and change the permissions of players and groups")]
+#[command_permission(group = "Admin")]
+pub enum PermissionCommand {
+    Info(InfoCommand),
+    Check(CheckCommand),
+    Set(SetCommand),
+    Unset(UnsetCommand),
+    AddGroup(AddGroupCommand),
+    RemoveGroup(RemoveGroupCommand),
+    Group {
+        #[command(subcommand)]
+        command: GroupCommand,
+    },
+}
+
+/// When something given for `minutes` expires, or the message to show if it is too long
+fn expires_at(minutes: Option<u64>) -> Result<Option<SystemTime>, String> {
+    minutes
+        .map(|minutes| {
+            expires_in(minutes).ok_or_else(|| format!("§c{minutes} minutes is too long"))
+        })
+        .transpose()
+}
+
+fn describe_expiry(expires: Option<SystemTime>) -> String {
+    match expires.map(|expires| expires.duration_since(SystemTime::now())) {
+        None => String::new(),
+        Some(Ok(left)) => format!(" §7({} minute(s) left)", left.as_secs().div_ceil(60)),
+        Some(Err(_)) => " §7(expired)".to_string(),
+    }
+}
+
+impl PermissionCommand {
+    /// Changes a player's permissions, returning the message to show the caller
+    fn update_player(
+        world: &World,
+        commands: &mut Commands<'_, '_>,
+        player: &str,
+        update: impl FnOnce(&mut PlayerPermissions) -> String,
+    ) -> String {
+        let Some(&entity) = world.resource::<IgnMap>().get(player) else {
+            return format!("§c{player} not found");
+        };
+
+        let Some(permissions) = world.entity(entity).get::<PlayerPermissions>() else {
+            error!("permission command failed: player is missing PlayerPermissions component");
+            return format!("§c{player}'s permissions are not loaded yet");
+        };
+
+        let mut permissions = permissions.clone();
+        let msg = update(&mut permissions);
+
+        // Inserting the component again stores it and updates the player's command tree
+        commands.entity(entity).insert(permissions);
+        msg
+    }
+
+    fn info(world: &World, player: &str) -> String {
+        let Some(&entity) = world.resource::<IgnMap>().get(player) else {
+            return format!("§c{player} not found");
+        };
+
+        let Some(permissions) = world.entity(entity).get::<PlayerPermissions>() else {
+            return format!("§c{player}'s permissions are not loaded yet");
+        };
+
+        let mut msg = format!("§b{player}§r's groups:");
+        for (group, expires) in permissions.groups() {
+            write!(&mut msg, "\n §e{group}{}", describe_expiry(expires)).unwrap();
+        }
+
+        write!(&mut msg, "\n§b{player}§r's nodes:").unwrap();
+        for (node, grant) in permissions.nodes() {
+            let value = if grant.value { "§atrue" } else { "§cfalse" };
+            let expiry = describe_expiry(grant.expires);
+            write!(&mut msg, "\n §e{node}§r = {value}{expiry}").unwrap();
+        }
+
+        msg
+    }
+
+    fn group(world: &World, commands: &mut Commands<'_, '_>, command: GroupCommand) -> String {
+        let groups = world.resource::<PermissionGroups>();
+
+        let exists = |name: &str| groups.get(name).is_some();
+        let missing = |name: &str| format!("§c{name} is not a group");
+
+        match command {
+            GroupCommand::List => {
+                let mut msg = "Groups:".to_string();
+                for (name, group) in groups.iter() {
+                    let parents = group.parents.join(", ");
+                    write!(&mut msg, "\n §e{name}§r (priority {}", group.priority).unwrap();
+                    if !parents.is_empty() {
+                        write!(&mut msg, ", inherits {parents}").unwrap();
+                    }
+                    msg.push(')');
+
+                    for (node, value) in &group.nodes {
+                        let value = if *value { "§atrue" } else { "§cfalse" };
+                        write!(&mut msg, "\n  §7{node}§r = {value}").unwrap();
+                    }
+                    for (node, value) in groups.default_nodes(name) {
+                        let value = if value { "§atrue" } else { "§cfalse" };
+                        write!(&mut msg, "\n  §7{node}§r = {value} §7(default)").unwrap();
+                    }
+                }
+                msg
+            }
+            GroupCommand::Create { name, priority } => {
+                if exists(&name) {
+                    return format!("§c{name} already exists");
+                }
+
+                let msg = format!("Created group §e{name}§r with priority §e{priority}");
+                commands.queue(move |world: &mut World| {
+                    world
+                        .resource_mut::<PermissionGroups>()
+                        .insert(name, PermissionGroup::new(priority));
+                });
+                msg
+            }
+            GroupCommand::Delete { name } => {
+                if !exists(&name) {
+                    return missing(&name);
+                }
+                // Every player is in the default group, and keeping it means the stored groups are
+                // never empty, which would bring back the built-in groups
+                if name == DEFAULT_GROUP {
+                    return format!("§c{name} cannot be deleted");
+                }
+
+                let msg = format!("Deleted group §e{name}");
+                commands.queue(move |world: &mut World| {
+                    world.resource_mut::<PermissionGroups>().remove(&name);
+                });
+                msg
+            }
+            GroupCommand::Set { group, node, value } => {
+                if !exists(&group) {
+                    return missing(&group);
+                }
+
+                let msg = format!("Set §e{node}§r to §e{value}§r for group §e{group}");
+                commands.queue(move |world: &mut World| {
+                    if let Some(group) = world.resource_mut::<PermissionGroups>().get_mut(&group) {
+                        group.nodes.insert(node, value);
+                    }
+                });
+                msg
+            }
+            GroupCommand::Unset { group, node } => {
+                if !exists(&group) {
+                    return missing(&group);
+                }
+
+                let msg = format!("Unset §e{node}§r for group §e{group}");
+                commands.queue(move |world: &mut World| {
+                    if let Some(group) = world.resource_mut::<PermissionGroups>().get_mut(&group) {
+                        group.nodes.remove(&node);
+                    }
+                });
+                msg
+            }
+            GroupCommand::Parent { group, parent } => {
+                if !exists(&group) {
+                    return missing(&group);
+                }
+                if !exists(&parent) {
+                    return missing(&parent);
+                }
+
+                let msg = format!("Group §e{group}§r now inherits from §e{parent}");
+                commands.queue(move |world: &mut World| {
+                    if let Some(group) = world.resource_mut::<PermissionGroups>().get_mut(&group)
+                        && !group.parents.contains(&parent)
+                    {
+                        group.parents.push(parent);
+                    }
+                });
+                msg
+            }
+        }
+    }
+}
+
+impl MinecraftCommand for PermissionCommand {
+    type State = SystemState<Commands<'static, 'static>>;
+
+    fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        let mut commands = state.get(world);
+
+        let msg = match self {
+            Self::Info(cmd) => Self::info(world, &cmd.player),
+            Self::Check(cmd) => match world.resource::<IgnMap>().get(cmd.player.as_str()) {
+                Some(&entity) => {
+                    let value = has_permission(world, entity, &cmd.node);
+                    format!("§b{}§r has §e{}§r: §e{value}", cmd.player, cmd.node)
+                }
+                None => format!("§c{} not found", cmd.player),
+            },
+            Self::Set(cmd) => match expires_at(cmd.minutes) {
+                Ok(expires) => Self::update_player(world, &mut commands, &cmd.player, |perms| {
+                    perms.set(cmd.node.as_str(), cmd.value, expires);
+                    format!(
+                        "Set §e{}§r to §e{}§r for §b{}",
+                        cmd.node, cmd.value, cmd.player
+                    )
+                }),
+                Err(msg) => msg,
+            },
+            Self::Unset(cmd) => Self::update_player(world, &mut commands, &cmd.player, |perms| {
+                if perms.unset(&cmd.node) {
+                    format!("Unset §e{}§r for §b{}", cmd.node, cmd.player)
+                } else {
+                    format!("§c{} does not have {} set", cmd.player, cmd.node)
+                }
+            }),
+            Self::AddGroup(cmd) => {
+                if world
+                    .resource::<PermissionGroups>()
+                    .get(&cmd.group)
+                    .is_none()
+                {
+                    format!("§c{} is not a group", cmd.group)
+                } else {
+                    match expires_at(cmd.minutes) {
+                        Ok(expires) => {
+                            Self::update_player(world, &mut commands, &cmd.player, |perms| {
+                                perms.add_group(cmd.group.as_str(), expires);
+                                format!("Added §b{}§r to §e{}", cmd.player, cmd.group)
+                            })
+                        }
+                        Err(msg) => msg,
+                    }
+                }
+            }
+            Self::RemoveGroup(cmd) => {
+                Self::update_player(world, &mut commands, &cmd.player, |perms| {
+                    if perms.remove_group(&cmd.group) {
+                        format!("Removed §b{}§r from §e{}", cmd.player, cmd.group)
+                    } else {
+                        format!("§c{} is not in {}", cmd.player, cmd.group)
+                    }
+                })
+            }
+            Self::Group { command } => Self::group(world, &mut commands, command),
+        };
+
+        reply(world, caller, msg);
+    }
+}
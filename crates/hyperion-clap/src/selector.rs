@@ -0,0 +1,579 @@
+//! Entity selectors such as `Notch`, `@a` or `@p[distance=..10,gamemode=!spectator]`
+
+use std::{collections::HashMap, str::FromStr};
+
+use bevy::prelude::*;
+use clap::ValueEnum;
+use hyperion::simulation::{IgnMap, Position, Uuid, entity_kind::EntityKind};
+use thiserror::Error;
+
+use crate::GameMode;
+
+/// Entity selector parsing error
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    #[error("expected a player name, UUID or selector")]
+    Empty,
+    #[error("unknown selector {0:?}, expected @p, @r, @a, @e or @s")]
+    UnknownKind(String),
+    #[error("expected ] to close the selector options")]
+    Unclosed,
+    #[error("expected key=value, found {0:?}")]
+    InvalidOption(String),
+    #[error("unknown selector option {0:?}")]
+    UnknownOption(String),
+    #[error("invalid value {value:?} for selector option {key}")]
+    InvalidValue { key: String, value: String },
+    #[error("only players may be selected, but {0:?} can select other entities")]
+    NotPlayers(String),
+}
+
+/// Which entities a selector starts from, before its options are applied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectorKind {
+    /// `@p`
+    NearestPlayer,
+    /// `@r`
+    RandomPlayer,
+    /// `@a`
+    AllPlayers,
+    /// `@e`
+    AllEntities,
+    /// `@s`, the caller of the command
+    Caller,
+}
+
+impl SelectorKind {
+    const fn from_char(c: char) -> Option<Self> {
+        match c {
+            'p' => Some(Self::NearestPlayer),
+            'r' => Some(Self::RandomPlayer),
+            'a' => Some(Self::AllPlayers),
+            'e' => Some(Self::AllEntities),
+            's' => Some(Self::Caller),
+            _ => None,
+        }
+    }
+}
+
+/// The order of the selected entities, which decides which are kept by `limit`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectorSort {
+    Arbitrary,
+    Nearest,
+    Furthest,
+    Random,
+}
+
+/// A range of numbers such as `5`, `..10`, `5..` or `1.5..3`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Range {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl Range {
+    #[must_use]
+    pub fn contains(&self, value: f32) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+impl FromStr for Range {
+    type Err = std::num::ParseFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bound = |s: &str| (!s.is_empty()).then(|| s.parse()).transpose();
+
+        match s.split_once("..") {
+            Some((min, max)) => Ok(Self {
+                min: bound(min)?,
+                max: bound(max)?,
+            }),
+            None => {
+                let value = s.parse()?;
+                Ok(Self {
+                    min: Some(value),
+                    max: Some(value),
+                })
+            }
+        }
+    }
+}
+
+/// A selector option which matches when its value matches, or when it does not if it starts
+/// with `!`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition<T> {
+    pub value: T,
+    pub negated: bool,
+}
+
+impl<T> Condition<T> {
+    /// Parses the value after an optional `!`
+    fn parse<E>(s: &str, parse: impl FnOnce(&str) -> Result<T, E>) -> Result<Self, E> {
+        let (negated, s) = match s.strip_prefix('!') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+
+        Ok(Self {
+            value: parse(s)?,
+            negated,
+        })
+    }
+
+    const fn test(&self, matches: bool) -> bool {
+        matches != self.negated
+    }
+}
+
+/// The `[key=value,...]` options of a selector
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelectorOptions {
+    /// Distance from the caller
+    pub distance: Option<Range>,
+    pub limit: Option<usize>,
+    pub sort: Option<SelectorSort>,
+    pub name: Option<Condition<String>>,
+    pub gamemode: Option<Condition<GameMode>>,
+    /// The entity type, such as `player` or `minecraft:zombie`
+    pub kind: Option<Condition<String>>,
+}
+
+impl SelectorOptions {
+    fn parse(s: &str) -> Result<Self, SelectorError> {
+        let mut options = Self::default();
+
+        for option in s
+            .split(',')
+            .map(str::trim)
+            .filter(|option| !option.is_empty())
+        {
+            let Some((key, value)) = option.split_once('=') else {
+                return Err(SelectorError::InvalidOption(option.to_string()));
+            };
+
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || SelectorError::InvalidValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            };
+
+            match key {
+                "distance" => {
+                    let range: Range = value.parse().map_err(|_| invalid())?;
+                    if range.min.is_some_and(|min| min < 0.0) {
+                        return Err(invalid());
+                    }
+                    options.distance = Some(range);
+                }
+                "limit" => {
+                    let limit = value.parse().map_err(|_| invalid())?;
+                    if limit == 0 {
+                        return Err(invalid());
+                    }
+                    options.limit = Some(limit);
+                }
+                "sort" => {
+                    let sort = match value {
+                        "arbitrary" => SelectorSort::Arbitrary,
+                        "nearest" => SelectorSort::Nearest,
+                        "furthest" => SelectorSort::Furthest,
+                        "random" => SelectorSort::Random,
+                        _ => return Err(invalid()),
+                    };
+                    options.sort = Some(sort);
+                }
+                "name" => {
+                    let name = Condition::parse(value, |s| {
+                        if s.is_empty() {
+                            Err(())
+                        } else {
+                            Ok(s.to_string())
+                        }
+                    });
+                    options.name = Some(name.map_err(|()| invalid())?);
+                }
+                "gamemode" => {
+                    let gamemode =
+                        Condition::parse(value, |s| <GameMode as ValueEnum>::from_str(s, true));
+                    options.gamemode = Some(gamemode.map_err(|_| invalid())?);
+                }
+                "type" => {
+                    let kind = Condition::parse(value, |s| {
+                        let s = s.strip_prefix("minecraft:").unwrap_or(s);
+                        if s.is_empty() {
+                            Err(())
+                        } else {
+                            Ok(s.to_string())
+                        }
+                    });
+                    options.kind = Some(kind.map_err(|()| invalid())?);
+                }
+                _ => return Err(SelectorError::UnknownOption(key.to_string())),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Whether an entity kind has a name such as `armorstand`, which is the name without underscores
+fn kind_matches(kind: EntityKind, name: &str) -> bool {
+    format!("{kind:?}").eq_ignore_ascii_case(name)
+}
+
+/// A command argument naming one or more entities
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntitySelector {
+    /// An online player by name
+    Player(String),
+    /// Any entity by UUID
+    Uuid(uuid::Uuid),
+    /// A selector such as `@a[distance=..10]`
+    Selector {
+        kind: SelectorKind,
+        options: SelectorOptions,
+    },
+}
+
+impl FromStr for EntitySelector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(selector) = s.strip_prefix('@') else {
+            if s.is_empty() {
+                return Err(SelectorError::Empty);
+            }
+
+            if let Ok(uuid) = uuid::Uuid::parse_str(s) {
+                return Ok(Self::Uuid(uuid));
+            }
+
+            return Ok(Self::Player(s.to_string()));
+        };
+
+        let mut chars = selector.chars();
+        let kind = chars
+            .next()
+            .and_then(SelectorKind::from_char)
+            .ok_or_else(|| SelectorError::UnknownKind(s.to_string()))?;
+
+        let options = match chars.as_str() {
+            "" => SelectorOptions::default(),
+            rest => {
+                let Some(rest) = rest.strip_prefix('[') else {
+                    return Err(SelectorError::UnknownKind(s.to_string()));
+                };
+                let Some(options) = rest.strip_suffix(']') else {
+                    return Err(SelectorError::Unclosed);
+                };
+                SelectorOptions::parse(options)?
+            }
+        };
+
+        Ok(Self::Selector { kind, options })
+    }
+}
+
+impl EntitySelector {
+    /// Whether the selector can only select players
+    #[must_use]
+    pub fn only_players(&self) -> bool {
+        match self {
+            Self::Player(_) => true,
+            Self::Uuid(_) => false,
+            Self::Selector { kind, options } => {
+                *kind != SelectorKind::AllEntities
+                    || options
+                        .kind
+                        .as_ref()
+                        .is_some_and(|kind| !kind.negated && kind.value == "player")
+            }
+        }
+    }
+
+    /// Whether the selector selects at most one entity
+    #[must_use]
+    pub fn is_single(&self) -> bool {
+        match self {
+            Self::Player(_) | Self::Uuid(_) => true,
+            Self::Selector { kind, options } => {
+                matches!(
+                    kind,
+                    SelectorKind::NearestPlayer | SelectorKind::RandomPlayer | SelectorKind::Caller
+                ) || options.limit == Some(1)
+            }
+        }
+    }
+
+    /// The entities selected for `caller`. Distances are measured from the caller's position.
+    #[must_use]
+    pub fn resolve(&self, world: &World, caller: Entity) -> Vec<Entity> {
+        match self {
+            Self::Player(name) => world
+                .resource::<IgnMap>()
+                .get(name.as_str())
+                .copied()
+                .into_iter()
+                .collect(),
+            Self::Uuid(uuid) => {
+                let Some(mut query) = world.try_query::<(Entity, &Uuid)>() else {
+                    return Vec::new();
+                };
+
+                query
+                    .iter(world)
+                    .filter(|(_, entity_uuid)| ***entity_uuid == *uuid)
+                    .map(|(entity, _)| entity)
+                    .collect()
+            }
+            Self::Selector { kind, options } => Self::select(world, caller, *kind, options),
+        }
+    }
+
+    fn select(
+        world: &World,
+        caller: Entity,
+        kind: SelectorKind,
+        options: &SelectorOptions,
+    ) -> Vec<Entity> {
+        let origin = world.get::<Position>(caller).map(|position| **position);
+
+        // Names are compared once for every kind of entity rather than for every entity
+        let kind_name = options
+            .kind
+            .as_ref()
+            .map(|condition| condition.value.replace('_', ""));
+        let mut kinds = HashMap::new();
+
+        let Some(mut query) = world.try_query::<(
+            Entity,
+            &Position,
+            &EntityKind,
+            Option<&Name>,
+            Option<&hyperion::simulation::game_mode::GameMode>,
+        )>() else {
+            return Vec::new();
+        };
+
+        let mut selected: Vec<(Entity, f32)> = query
+            .iter(world)
+            .filter(|(entity, ..)| kind != SelectorKind::Caller || *entity == caller)
+            .filter(|&(_, _, &entity_kind, ..)| {
+                kind == SelectorKind::AllEntities || entity_kind == EntityKind::Player
+            })
+            .filter(|&(_, _, &entity_kind, ..)| {
+                options
+                    .kind
+                    .as_ref()
+                    .zip(kind_name.as_deref())
+                    .is_none_or(|(condition, name)| {
+                        let matches = *kinds
+                            .entry(entity_kind)
+                            .or_insert_with(|| kind_matches(entity_kind, name));
+                        condition.test(matches)
+                    })
+            })
+            .filter(|(.., name, _)| {
+                options.name.as_ref().is_none_or(|condition| {
+                    condition.test(name.is_some_and(|name| name.as_str() == condition.value))
+                })
+            })
+            .filter(|(.., gamemode)| {
+                options.gamemode.as_ref().is_none_or(|condition| {
+                    let expected =
+                        hyperion::simulation::game_mode::GameMode::from(condition.value.clone());
+                    condition.test(gamemode.is_some_and(|gamemode| *gamemode == expected))
+                })
+            })
+            .map(|(entity, position, ..)| {
+                let distance = origin.map_or(0.0, |origin| origin.distance(**position));
+                (entity, distance)
+            })
+            .filter(|(_, distance)| {
+                options
+                    .distance
+                    .is_none_or(|range| origin.is_some() && range.contains(*distance))
+            })
+            .collect();
+
+        let sort = options.sort.unwrap_or(match kind {
+            SelectorKind::NearestPlayer => SelectorSort::Nearest,
+            SelectorKind::RandomPlayer => SelectorSort::Random,
+            _ => SelectorSort::Arbitrary,
+        });
+
+        match sort {
+            SelectorSort::Arbitrary => {}
+            SelectorSort::Nearest => selected.sort_by(|(_, a), (_, b)| a.total_cmp(b)),
+            SelectorSort::Furthest => selected.sort_by(|(_, a), (_, b)| b.total_cmp(a)),
+            SelectorSort::Random => fastrand::shuffle(&mut selected),
+        }
+
+        let limit = options.limit.unwrap_or(match kind {
+            SelectorKind::NearestPlayer | SelectorKind::RandomPlayer => 1,
+            _ => usize::MAX,
+        });
+
+        selected
+            .into_iter()
+            .take(limit)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+}
+
+/// A command argument naming one or more players. Selectors which may select other entities,
+/// such as `@e`, are rejected unless they have `type=player`.
+#[derive(Clone, Debug, PartialEq, Deref)]
+pub struct PlayerSelector(EntitySelector);
+
+impl FromStr for PlayerSelector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let selector: EntitySelector = s.parse()?;
+
+        // Players are checked when the selector is resolved for names and UUIDs
+        if matches!(selector, EntitySelector::Selector { .. }) && !selector.only_players() {
+            return Err(SelectorError::NotPlayers(s.to_string()));
+        }
+        Ok(Self(selector))
+    }
+}
+
+impl PlayerSelector {
+    /// The players selected for `caller`
+    #[must_use]
+    pub fn resolve(&self, world: &World, caller: Entity) -> Vec<Entity> {
+        let mut players = self.0.resolve(world, caller);
+        players.retain(|&entity| world.get::<EntityKind>(entity) == Some(&EntityKind::Player));
+        players
+    }
+}
+
+/// Suggestions for a selector argument: the names of online players and the selector kinds
+pub fn suggest(world: &World, only_players: bool) -> Vec<String> {
+    let kinds = ["@p", "@r", "@a", "@s"]
+        .into_iter()
+        .chain((!only_players).then_some("@e"))
+        .map(str::to_string);
+
+    let mut names: Vec<_> = world.resource::<IgnMap>().keys().cloned().collect();
+    names.sort_unstable();
+
+    kinds.chain(names).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use hyperion::simulation::{IgnMap, Position, Uuid, entity_kind::EntityKind};
+
+    use super::{
+        Condition, EntitySelector, PlayerSelector, Range, SelectorError, SelectorKind,
+        SelectorOptions, SelectorSort,
+    };
+    use crate::GameMode;
+
+    #[test]
+    fn parse_selectors() {
+        assert_eq!(
+            "Notch".parse::<EntitySelector>(),
+            Ok(EntitySelector::Player("Notch".to_string()))
+        );
+
+        let selector: EntitySelector = "@p[distance=..10,gamemode=!spectator,sort=furthest]"
+            .parse()
+            .unwrap();
+
+        assert_eq!(selector, EntitySelector::Selector {
+            kind: SelectorKind::NearestPlayer,
+            options: SelectorOptions {
+                distance: Some(Range {
+                    min: None,
+                    max: Some(10.0)
+                }),
+                sort: Some(SelectorSort::Furthest),
+                gamemode: Some(Condition {
+                    value: GameMode::Spectator,
+                    negated: true
+                }),
+                ..SelectorOptions::default()
+            },
+        });
+        assert!(selector.is_single());
+
+        assert_eq!(
+            "@x".parse::<EntitySelector>(),
+            Err(SelectorError::UnknownKind("@x".to_string()))
+        );
+        assert_eq!(
+            "@a[limit=2".parse::<EntitySelector>(),
+            Err(SelectorError::Unclosed)
+        );
+        assert!("@a[limit=0]".parse::<EntitySelector>().is_err());
+    }
+
+    #[test]
+    fn player_selectors_reject_entities() {
+        assert!("@e".parse::<PlayerSelector>().is_err());
+        assert!("@e[type=player]".parse::<PlayerSelector>().is_ok());
+        assert!("@e[type=!player]".parse::<PlayerSelector>().is_err());
+        assert!("@a[type=zombie]".parse::<PlayerSelector>().is_ok());
+    }
+
+    #[test]
+    fn ranges() {
+        let range: Range = "1.5..3".parse().unwrap();
+        assert!(range.contains(1.5));
+        assert!(range.contains(3.0));
+        assert!(!range.contains(3.5));
+
+        let exact: Range = "5".parse().unwrap();
+        assert!(exact.contains(5.0));
+        assert!(!exact.contains(4.0));
+
+        let open: Range = "2..".parse().unwrap();
+        assert!(open.contains(1000.0));
+        assert!(!open.contains(1.0));
+    }
+
+    #[test]
+    fn resolve_logged_in_players() {
+        let mut world = World::new();
+        world.init_resource::<IgnMap>();
+
+        let player = world
+            .spawn((
+                Name::new("Notch"),
+                Uuid::from(uuid::Uuid::from_u128(1)),
+                Position::new(0.0, 64.0, 0.0),
+                EntityKind::Player,
+            ))
+            .id();
+        world
+            .resource_mut::<IgnMap>()
+            .insert("Notch".to_string(), player);
+
+        let zombie = world
+            .spawn((Position::new(1.0, 64.0, 0.0), EntityKind::Zombie))
+            .id();
+
+        for selector in ["@a", "@p", "@r", "@s", "Notch", "@e[type=player]"] {
+            let selector: PlayerSelector = selector.parse().unwrap();
+            assert_eq!(
+                selector.resolve(&world, player),
+                vec![player],
+                "{selector:?}"
+            );
+        }
+
+        let all: EntitySelector = "@e".parse().unwrap();
+        let mut selected = all.resolve(&world, player);
+        selected.sort();
+        assert_eq!(selected, vec![player, zombie]);
+    }
+}
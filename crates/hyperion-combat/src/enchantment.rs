@@ -0,0 +1,70 @@
+use hyperion_inventory::PlayerInventory;
+use valence_protocol::{
+    ItemStack,
+    nbt::{Value, list::List},
+};
+
+use crate::{DamageType, formula};
+
+pub const SHARPNESS: &str = "minecraft:sharpness";
+pub const SWEEPING: &str = "minecraft:sweeping";
+pub const KNOCKBACK: &str = "minecraft:knockback";
+pub const PROTECTION: &str = "minecraft:protection";
+pub const FIRE_PROTECTION: &str = "minecraft:fire_protection";
+pub const BLAST_PROTECTION: &str = "minecraft:blast_protection";
+pub const PROJECTILE_PROTECTION: &str = "minecraft:projectile_protection";
+pub const FEATHER_FALLING: &str = "minecraft:feather_falling";
+pub const RESPIRATION: &str = "minecraft:respiration";
+
+/// The highest enchantment level vanilla reads from NBT
+const MAX_LEVEL: i16 = 255;
+
+/// Returns the level of the enchantment on the item, or 0 if the item does not have it. Levels are
+/// read from NBT which creative clients can set, so they are clamped to `0..=255`.
+#[must_use]
+pub fn level(stack: &ItemStack, enchantment: &str) -> i16 {
+    let Some(Value::List(List::Compound(enchantments))) =
+        stack.nbt.as_ref().and_then(|nbt| nbt.get("Enchantments"))
+    else {
+        return 0;
+    };
+
+    enchantments
+        .iter()
+        .find(|entry| matches!(entry.get("id"), Some(Value::String(id)) if id == enchantment))
+        .and_then(|entry| match entry.get("lvl")? {
+            Value::Short(level) => Some(*level),
+            Value::Int(level) => i16::try_from((*level).clamp(0, MAX_LEVEL.into())).ok(),
+            Value::Byte(level) => Some(i16::from(*level)),
+            _ => None,
+        })
+        .map_or(0, |level| level.clamp(0, MAX_LEVEL))
+}
+
+/// The total enchantment protection factor of all worn armor against the damage type
+#[must_use]
+pub fn protection_factor(inventory: &PlayerInventory, source: DamageType) -> f32 {
+    let armor = [
+        inventory.get_helmet(),
+        inventory.get_chestplate(),
+        inventory.get_leggings(),
+        inventory.get_boots(),
+    ];
+
+    let total = armor
+        .into_iter()
+        .map(|slot| {
+            let stack = &slot.stack;
+            formula::protection_factor(
+                level(stack, PROTECTION),
+                level(stack, FIRE_PROTECTION),
+                level(stack, BLAST_PROTECTION),
+                level(stack, PROJECTILE_PROTECTION),
+                level(stack, FEATHER_FALLING),
+                source,
+            )
+        })
+        .fold(0, i16::saturating_add);
+
+    f32::from(total)
+}
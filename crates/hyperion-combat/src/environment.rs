@@ -0,0 +1,459 @@
+//! Damage from the world rather than from other entities: falling, fire, lava, drowning, the void,
+//! suffocation and harmful blocks.
+//!
+//! Entities are set on fire by inserting [`Burning`], which also shows the on-fire flag to
+//! clients. Fire damage is prevented by the fire resistance effect.
+
+use bevy::prelude::*;
+use hyperion::{
+    BlockKind, BlockState,
+    net::{Compose, agnostic},
+    simulation::{
+        EntitySize, Position, aabb,
+        blocks::{Blocks, chunk::START_Y},
+        effect::{ActiveEffects, EffectKind},
+        event::HitGroundEvent,
+        game_mode::GameMode,
+        metadata::{
+            entity::{AirSupply, EntityFlags},
+            living_entity::Health,
+        },
+        movement::is_water,
+    },
+};
+use hyperion_inventory::PlayerInventory;
+use hyperion_utils::Prev;
+use tracing::error;
+use valence_protocol::ident;
+
+use crate::{CombatSet, DamageEvent, DamageSource, DamageType, enchantment};
+
+/// Entities this far below the bottom of the world take void damage
+pub const VOID_DEPTH: f32 = 64.0;
+
+/// The most air an entity can have, in ticks
+pub const MAX_AIR: i32 = 300;
+
+/// Ticks between fire damage while burning
+const BURN_INTERVAL: u16 = 20;
+
+/// Ticks an entity burns for after touching fire
+const FIRE_IGNITE_TICKS: u16 = 160;
+
+/// Ticks an entity burns for after touching lava
+const LAVA_IGNITE_TICKS: u16 = 300;
+
+/// Sets an entity on fire for `ticks` more ticks
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Burning {
+    pub ticks: u16,
+}
+
+impl Burning {
+    #[must_use]
+    pub const fn new(ticks: u16) -> Self {
+        Self { ticks }
+    }
+
+    /// Burns for a tick, returning whether the entity takes fire damage this tick
+    const fn tick(&mut self) -> bool {
+        let damage = self.ticks % BURN_INTERVAL == 0;
+        self.ticks -= 1;
+        damage
+    }
+}
+
+/// The fall damage for falling `fall_distance` blocks
+fn fall_damage_amount(fall_distance: f32) -> f32 {
+    (fall_distance.floor() - 3.0).max(0.0)
+}
+
+/// The damage dealt by touching a block of the given kind and the ticks it sets entities on fire
+/// for. Sweet berry bushes only hurt entities which are `moving` through them.
+const fn contact_damage(kind: BlockKind, moving: bool) -> Option<(DamageType, f32, u16)> {
+    match kind {
+        BlockKind::Lava => Some((DamageType::Lava, 4.0, LAVA_IGNITE_TICKS)),
+        BlockKind::Fire => Some((DamageType::InFire, 1.0, FIRE_IGNITE_TICKS)),
+        BlockKind::SoulFire => Some((DamageType::InFire, 2.0, FIRE_IGNITE_TICKS)),
+        BlockKind::Cactus => Some((DamageType::Cactus, 1.0, 0)),
+        BlockKind::SweetBerryBush if moving => Some((DamageType::SweetBerryBush, 1.0, 0)),
+        _ => None,
+    }
+}
+
+/// Uses or refills a tick of air, returning whether the entity drowns this tick
+fn breathe(air: &mut i32, can_breathe: bool) -> bool {
+    if can_breathe {
+        if *air < MAX_AIR {
+            *air = (*air + 4).min(MAX_AIR);
+        }
+        return false;
+    }
+
+    *air -= 1;
+
+    if *air <= -20 {
+        *air = 0;
+        return true;
+    }
+
+    false
+}
+
+/// Where the eyes of an entity are
+fn eye_position(position: &Position, size: EntitySize) -> Vec3 {
+    **position + Vec3::new(0.0, size.height * 0.85, 0.0)
+}
+
+/// Calls `f` with every block overlapping the bounding box of an entity
+fn for_each_touching(
+    blocks: &Blocks,
+    position: &Position,
+    size: EntitySize,
+    mut f: impl FnMut(BlockState),
+) {
+    // Expanded slightly so that blocks such as cactus hurt entities standing next to them
+    let bounds = aabb(**position, size).expand(0.001);
+    let min = bounds.min.floor().as_ivec3();
+    let max = bounds.max.floor().as_ivec3();
+
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                if let Some(state) = blocks.get_block(IVec3::new(x, y, z)) {
+                    f(state);
+                }
+            }
+        }
+    }
+}
+
+fn set_on_fire_flag(
+    trigger: Trigger<'_, OnInsert, Burning>,
+    mut query: Query<'_, '_, &mut EntityFlags>,
+) {
+    let Ok(mut flags) = query.get_mut(trigger.target()) else {
+        return;
+    };
+
+    *flags |= EntityFlags::ON_FIRE;
+}
+
+fn clear_on_fire_flag(
+    trigger: Trigger<'_, OnRemove, Burning>,
+    mut query: Query<'_, '_, &mut EntityFlags>,
+) {
+    let Ok(mut flags) = query.get_mut(trigger.target()) else {
+        return;
+    };
+
+    *flags &= !EntityFlags::ON_FIRE;
+}
+
+fn fall_damage(
+    mut events: EventReader<'_, '_, HitGroundEvent>,
+    query: Query<'_, '_, (&Position, &GameMode)>,
+    compose: Res<'_, Compose>,
+    mut writer: EventWriter<'_, DamageEvent>,
+) {
+    for event in events.read() {
+        let damage = fall_damage_amount(event.fall_distance);
+
+        if damage <= 0.0 {
+            continue;
+        }
+
+        let (position, game_mode) = match query.get(event.client) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to apply fall damage: query failed: {e}");
+                continue;
+            }
+        };
+
+        if game_mode.is_invulnerable() {
+            continue;
+        }
+
+        writer.write(DamageEvent::new(
+            event.client,
+            DamageSource::new(DamageType::Fall),
+            damage,
+        ));
+
+        let sound = agnostic::sound(
+            if event.fall_distance > 7.0 {
+                ident!("minecraft:entity.player.big_fall")
+            } else {
+                ident!("minecraft:entity.player.small_fall")
+            },
+            **position,
+        )
+        .seed(fastrand::i64(..))
+        .build();
+
+        compose
+            .broadcast_local(&sound, position.to_chunk())
+            .send()
+            .unwrap();
+    }
+}
+
+fn burn(
+    mut query: Query<'_, '_, (Entity, &mut Burning, &Position, &EntitySize)>,
+    blocks: Res<'_, Blocks>,
+    mut writer: EventWriter<'_, DamageEvent>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, mut burning, position, &size) in &mut query {
+        let mut in_water = false;
+        for_each_touching(&blocks, position, size, |state| {
+            in_water |= is_water(state);
+        });
+
+        if in_water || burning.ticks == 0 {
+            commands.entity(entity).remove::<Burning>();
+            continue;
+        }
+
+        if burning.tick() {
+            writer.write(DamageEvent::new(
+                entity,
+                DamageSource::new(DamageType::OnFire),
+                1.0,
+            ));
+        }
+    }
+}
+
+fn block_contact(
+    query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &Position,
+            &EntitySize,
+            Option<&Prev<Position>>,
+            Option<&Burning>,
+            Option<&EntityFlags>,
+            Option<&GameMode>,
+        ),
+        With<Health>,
+    >,
+    blocks: Res<'_, Blocks>,
+    mut writer: EventWriter<'_, DamageEvent>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, position, &size, previous, burning, flags, game_mode) in &query {
+        if game_mode.is_some_and(GameMode::is_spectator) {
+            continue;
+        }
+
+        let moved = previous.is_some_and(|previous| {
+            let delta = ***previous - **position;
+            delta.x.abs() > 0.003 || delta.z.abs() > 0.003
+        });
+
+        let mut damage: Option<(DamageType, f32)> = None;
+        let mut ignite = 0;
+
+        for_each_touching(&blocks, position, size, |state| {
+            let Some((kind, amount, ignite_ticks)) = contact_damage(state.to_kind(), moved) else {
+                return;
+            };
+
+            ignite = ignite.max(ignite_ticks);
+
+            // Only the most damaging block hurts the entity
+            if damage.is_none_or(|(_, current)| amount > current) {
+                damage = Some((kind, amount));
+            }
+        });
+
+        // Magma blocks hurt entities standing on them unless they are sneaking
+        let sneaking =
+            flags.is_some_and(|&flags| (flags & EntityFlags::CROUCHING) == EntityFlags::CROUCHING);
+        let below = (**position - Vec3::new(0.0, 0.001, 0.0)).floor().as_ivec3();
+        if !sneaking
+            && damage.is_none()
+            && blocks
+                .get_block(below)
+                .is_some_and(|state| state.to_kind() == BlockKind::MagmaBlock)
+        {
+            damage = Some((DamageType::HotFloor, 1.0));
+        }
+
+        if let Some((kind, amount)) = damage {
+            writer.write(DamageEvent::new(entity, DamageSource::new(kind), amount));
+        }
+
+        let invulnerable = game_mode.is_some_and(|game_mode| game_mode.is_invulnerable());
+        if ignite > 0 && !invulnerable && burning.is_none_or(|burning| burning.ticks < ignite) {
+            commands.entity(entity).insert(Burning::new(ignite));
+        }
+    }
+}
+
+fn drown(
+    mut query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &Position,
+            &EntitySize,
+            &mut AirSupply,
+            Option<&ActiveEffects>,
+            Option<&PlayerInventory>,
+            Option<&GameMode>,
+        ),
+        With<Health>,
+    >,
+    blocks: Res<'_, Blocks>,
+    mut writer: EventWriter<'_, DamageEvent>,
+) {
+    for (entity, position, &size, mut air, effects, inventory, game_mode) in &mut query {
+        let eye = eye_position(position, size).floor().as_ivec3();
+        let underwater = blocks.get_block(eye).is_some_and(is_water);
+
+        let can_breathe = !underwater
+            || game_mode.is_some_and(|game_mode| game_mode.is_invulnerable())
+            || effects.is_some_and(|effects| {
+                effects.contains(EffectKind::WaterBreathing)
+                    || effects.contains(EffectKind::ConduitPower)
+            });
+
+        // Respiration has a chance of not using air each tick
+        let respiration = inventory.map_or(0, |inventory| {
+            enchantment::level(&inventory.get_helmet().stack, enchantment::RESPIRATION)
+        });
+        if !can_breathe && respiration > 0 && fastrand::i16(0..=respiration) > 0 {
+            continue;
+        }
+
+        if breathe(&mut air.0, can_breathe) {
+            writer.write(DamageEvent::new(
+                entity,
+                DamageSource::new(DamageType::Drown),
+                2.0,
+            ));
+        }
+    }
+}
+
+fn void_damage(
+    query: Query<'_, '_, (Entity, &Position), With<Health>>,
+    mut writer: EventWriter<'_, DamageEvent>,
+) {
+    for (entity, position) in &query {
+        if position.y < f32::from(START_Y) - VOID_DEPTH {
+            writer.write(DamageEvent::new(
+                entity,
+                DamageSource::new(DamageType::OutOfWorld),
+                4.0,
+            ));
+        }
+    }
+}
+
+fn suffocate(
+    query: Query<'_, '_, (Entity, &Position, &EntitySize, Option<&GameMode>), With<Health>>,
+    blocks: Res<'_, Blocks>,
+    mut writer: EventWriter<'_, DamageEvent>,
+) {
+    for (entity, position, &size, game_mode) in &query {
+        // Spectators can fly through blocks
+        if game_mode.is_some_and(GameMode::is_spectator) {
+            continue;
+        }
+
+        let eye = eye_position(position, size).floor().as_ivec3();
+        let inside_block = blocks
+            .get_block(eye)
+            .is_some_and(|state| state.is_opaque() && state.collision_shapes().next().is_some());
+
+        if inside_block {
+            writer.write(DamageEvent::new(
+                entity,
+                DamageSource::new(DamageType::InWall),
+                1.0,
+            ));
+        }
+    }
+}
+
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(set_on_fire_flag);
+        app.add_observer(clear_on_fire_flag);
+        app.add_systems(
+            FixedUpdate,
+            (
+                fall_damage,
+                burn,
+                block_contact,
+                drown,
+                void_damage,
+                suffocate,
+            )
+                .in_set(CombatSet::Attack),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fall_damage_after_three_blocks() {
+        assert!(fall_damage_amount(3.9).abs() < f32::EPSILON);
+        assert!((fall_damage_amount(4.0) - 1.0).abs() < f32::EPSILON);
+        assert!((fall_damage_amount(23.5) - 20.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn fire_damages_every_second() {
+        let mut burning = Burning::new(FIRE_IGNITE_TICKS);
+
+        let damage_ticks = (0..FIRE_IGNITE_TICKS).filter(|_| burning.tick()).count();
+
+        assert_eq!(damage_ticks, usize::from(FIRE_IGNITE_TICKS / BURN_INTERVAL));
+        assert_eq!(burning.ticks, 0);
+    }
+
+    #[test]
+    fn fire_and_lava_ignite() {
+        assert_eq!(
+            contact_damage(BlockKind::Lava, false),
+            Some((DamageType::Lava, 4.0, LAVA_IGNITE_TICKS))
+        );
+        assert_eq!(
+            contact_damage(BlockKind::Fire, false),
+            Some((DamageType::InFire, 1.0, FIRE_IGNITE_TICKS))
+        );
+        assert_eq!(contact_damage(BlockKind::SweetBerryBush, false), None);
+        assert_eq!(contact_damage(BlockKind::Stone, true), None);
+    }
+
+    #[test]
+    fn drowning_after_running_out_of_air() {
+        let mut air = MAX_AIR;
+
+        // Air runs out, then the entity drowns every 20 ticks
+        let first = (1..).find(|_| breathe(&mut air, false)).unwrap();
+        assert_eq!(first, MAX_AIR + 20);
+        assert_eq!(air, 0);
+        assert!(!(1..20).any(|_| breathe(&mut air, false)));
+        assert!(breathe(&mut air, false));
+
+        // Air comes back four times faster than it is used
+        for _ in 0..MAX_AIR / 4 {
+            assert!(!breathe(&mut air, true));
+        }
+        assert_eq!(air, MAX_AIR);
+    }
+}
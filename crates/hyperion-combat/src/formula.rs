@@ -0,0 +1,198 @@
+//! Vanilla damage formulas. All of these are pure functions so games can reuse them for custom
+//! damage.
+
+use hyperion::glam::{Vec2, Vec3};
+
+/// The maximum enchantment protection factor which is taken into account
+pub const MAX_PROTECTION: f32 = 20.0;
+
+/// Damage multiplier for critical hits
+pub const CRITICAL_MULTIPLIER: f32 = 1.5;
+
+/// The horizontal strength of the knockback every attack applies
+pub const BASE_KNOCKBACK: f32 = 0.4;
+
+/// Applies armor and armor toughness to the damage
+#[must_use]
+pub fn damage_after_armor(damage: f32, armor: f32, toughness: f32) -> f32 {
+    let toughness_factor = 2.0 + toughness / 4.0;
+    let effective_armor = (armor - damage / toughness_factor).clamp(armor * 0.2, 20.0);
+    damage * (1.0 - effective_armor / 25.0)
+}
+
+/// Applies the total enchantment protection factor (EPF) to the damage
+#[must_use]
+pub fn damage_after_protection(damage: f32, protection: f32) -> f32 {
+    let protection = protection.clamp(0.0, MAX_PROTECTION);
+    damage * (1.0 - protection / 25.0)
+}
+
+/// Applies the resistance effect with the given amplifier to the damage
+#[must_use]
+pub fn damage_after_resistance(damage: f32, amplifier: u8) -> f32 {
+    let reduction = (f32::from(amplifier) + 1.0) * 5.0;
+    (damage * (25.0 - reduction) / 25.0).max(0.0)
+}
+
+/// The number of ticks until an attack is fully charged
+#[must_use]
+pub fn attack_cooldown_ticks(attack_speed: f64) -> f32 {
+    #[expect(clippy::cast_possible_truncation)]
+    let ticks = (20.0 / attack_speed) as f32;
+    ticks
+}
+
+/// How charged an attack is, from 0 to 1, given the ticks since the last attack
+#[must_use]
+pub fn attack_strength(ticks_since_attack: f32, attack_speed: f64) -> f32 {
+    ((ticks_since_attack + 0.5) / attack_cooldown_ticks(attack_speed)).clamp(0.0, 1.0)
+}
+
+/// Scales the base damage of an attack by how charged it is
+#[must_use]
+pub fn scale_base_damage(damage: f32, strength: f32) -> f32 {
+    damage * strength.mul_add(strength * 0.8, 0.2)
+}
+
+/// Scales the enchantment damage of an attack by how charged it is
+#[must_use]
+pub fn scale_enchantment_damage(damage: f32, strength: f32) -> f32 {
+    damage * strength
+}
+
+/// The extra damage dealt by a sharpness enchantment of the given level
+#[must_use]
+pub fn sharpness_damage(level: i16) -> f32 {
+    if level <= 0 {
+        return 0.0;
+    }
+
+    f32::from(level).mul_add(0.5, 0.5)
+}
+
+/// The damage dealt to entities caught in a sweep attack
+#[must_use]
+pub fn sweep_damage(damage: f32, sweeping_level: i16) -> f32 {
+    let level = f32::from(sweeping_level.max(0));
+    let ratio = level / (level + 1.0);
+    ratio.mul_add(damage, 1.0)
+}
+
+/// The protection factor a single armor piece gives against damage
+#[must_use]
+pub const fn protection_factor(
+    protection: i16,
+    fire_protection: i16,
+    blast_protection: i16,
+    projectile_protection: i16,
+    feather_falling: i16,
+    source: crate::DamageType,
+) -> i16 {
+    let mut factor = protection;
+
+    // Levels come from item NBT, which the client controls in creative mode
+    if source.is_fire() {
+        factor = factor.saturating_add(fire_protection.saturating_mul(2));
+    }
+    if source.is_explosion() {
+        factor = factor.saturating_add(blast_protection.saturating_mul(2));
+    }
+    if source.is_projectile() {
+        factor = factor.saturating_add(projectile_protection.saturating_mul(2));
+    }
+    if source.is_fall() {
+        factor = factor.saturating_add(feather_falling.saturating_mul(3));
+    }
+
+    factor
+}
+
+/// The velocity of an entity after knockback, given its current velocity and the horizontal
+/// direction from the attacker to the target. Like vanilla, the current velocity is halved before
+/// the knockback is added.
+///
+/// Returns [`None`] if the knockback was fully resisted.
+#[must_use]
+pub fn knockback(
+    velocity: Vec3,
+    strength: f32,
+    direction_x: f32,
+    direction_z: f32,
+    knockback_resistance: f32,
+    on_ground: bool,
+) -> Option<Vec3> {
+    let strength = strength * (1.0 - knockback_resistance);
+    if strength <= 0.0 {
+        return None;
+    }
+
+    let horizontal = Vec2::new(direction_x, direction_z).normalize_or_zero() * strength;
+    let vertical = if on_ground {
+        velocity.y.mul_add(0.5, strength).min(0.4)
+    } else {
+        velocity.y
+    };
+
+    Some(Vec3::new(
+        velocity.x.mul_add(0.5, horizontal.x),
+        vertical,
+        velocity.z.mul_add(0.5, horizontal.y),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armor_reduces_damage() {
+        // 20 armor caps at 80% reduction
+        assert!((damage_after_armor(10.0, 20.0, 0.0) - 2.0).abs() < 1e-5);
+
+        // strong hits pierce armor without toughness
+        assert!(damage_after_armor(20.0, 20.0, 0.0) > damage_after_armor(20.0, 20.0, 8.0));
+    }
+
+    #[test]
+    fn protection_is_capped() {
+        assert!((damage_after_protection(10.0, 100.0) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn cooldown_scales_damage() {
+        assert!((scale_base_damage(10.0, 1.0) - 10.0).abs() < 1e-5);
+        assert!((scale_base_damage(10.0, 0.0) - 2.0).abs() < 1e-5);
+
+        // A sword has an attack speed of 1.6, so it takes 12.5 ticks to charge
+        assert!(attack_strength(5.0, 1.6) < 1.0);
+        assert!((attack_strength(12.0, 1.6) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn knockback_resistance() {
+        assert!(knockback(Vec3::ZERO, 0.4, 1.0, 0.0, 1.0, true).is_none());
+
+        let velocity = knockback(Vec3::ZERO, 0.4, 1.0, 0.0, 0.5, true).unwrap();
+        assert!((velocity.x - 0.2).abs() < 1e-5);
+        assert!((velocity.y - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn knockback_halves_velocity() {
+        let velocity = Vec3::new(-0.6, 0.2, 0.4);
+        let knocked = knockback(velocity, 0.4, 1.0, 0.0, 0.0, true).unwrap();
+
+        assert!((knocked.x - 0.1).abs() < 1e-5);
+        assert!((knocked.y - 0.4).abs() < 1e-5);
+        assert!((knocked.z - 0.2).abs() < 1e-5);
+
+        let airborne = knockback(velocity, 0.4, 1.0, 0.0, 0.0, false).unwrap();
+        assert!((airborne.y - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn protection_factor_saturates() {
+        let factor = protection_factor(i16::MAX, 0, 0, 0, i16::MAX, crate::DamageType::Fall);
+        assert_eq!(factor, i16::MAX);
+    }
+}
@@ -0,0 +1,664 @@
+//! Vanilla combat: melee attacks, damage mitigation, knockback and invulnerability frames.
+//!
+//! Anything that wants to hurt an entity writes a [`DamageEvent`]. The [`CombatPlugin`] applies
+//! armor, protection enchantments and the resistance effect, updates [`Health`], applies
+//! knockback, and writes a [`DeathEvent`] if the entity died.
+//!
+//! Damage events should be written before [`CombatSet::Modify`]. Systems in
+//! [`CombatSet::Modify`] can change or [cancel](DamageEvent::cancel) damage through an
+//! [`EventMutator`] before it is applied in [`CombatSet::Apply`].
+
+use bevy::{ecs::entity::Entities, prelude::*};
+use hyperion::{
+    ingress,
+    net::{Compose, ConnectionId, agnostic},
+    simulation::{
+        EntitySize, Flight, MovementTracking, Position, Velocity, Yaw, aabb,
+        attribute::{AttributeKind, Attributes},
+        effect::{ActiveEffects, EffectKind},
+        event::UpdateSelectedSlotEvent,
+        game_mode::GameMode,
+        metadata::living_entity::Health,
+        movement::MovementState,
+        packet::play,
+        packet_state,
+    },
+};
+use hyperion_inventory::PlayerInventory;
+use hyperion_utils::{EntityExt, Prev};
+use tracing::error;
+use valence_protocol::{
+    Ident, ItemKind, Particle, VarInt, ident,
+    math::{DVec3, Vec3},
+    packets::play::{
+        DamageTiltS2c, EntityDamageS2c, player_interact_entity_c2s::EntityInteraction,
+    },
+};
+
+pub mod enchantment;
+pub mod environment;
+pub mod formula;
+mod source;
+
+pub use source::{DamageSource, DamageType};
+
+/// The number of ticks after being hurt during which an entity only takes damage that is higher
+/// than the damage which hurt it
+pub const INVULNERABILITY_TICKS: i64 = 10;
+
+/// Ordering of the combat systems in [`FixedUpdate`]
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CombatSet {
+    /// Melee attack packets are turned into [`DamageEvent`]s
+    Attack,
+    /// Games can modify or cancel [`DamageEvent`]s
+    Modify,
+    /// [`DamageEvent`]s are applied
+    Apply,
+}
+
+/// Invulnerability frames of an entity after it was hurt
+#[derive(Component, Default, Copy, Clone, Debug)]
+pub struct Invulnerability {
+    until: i64,
+    last_damage: f32,
+}
+
+impl Invulnerability {
+    #[must_use]
+    pub const fn is_invulnerable(&self, tick: i64) -> bool {
+        tick < self.until
+    }
+}
+
+/// Tracks how charged the next melee attack of a player is
+#[derive(Component, Default, Copy, Clone, Debug)]
+pub struct AttackCooldown {
+    last_reset: i64,
+}
+
+impl AttackCooldown {
+    /// How charged an attack is, from 0 to 1
+    #[must_use]
+    pub fn strength(&self, tick: i64, attack_speed: f64) -> f32 {
+        let ticks = (tick - self.last_reset) as f32;
+        formula::attack_strength(ticks, attack_speed)
+    }
+
+    pub const fn reset(&mut self, tick: i64) {
+        self.last_reset = tick;
+    }
+}
+
+/// The last damage an entity took after mitigation. This can be used for death messages.
+#[derive(Component, Default, Copy, Clone, Debug)]
+pub struct LastDamage {
+    pub source: Option<DamageSource>,
+    pub amount: f32,
+    pub tick: i64,
+}
+
+/// The kind of melee hit, which decides the sound and particles shown when the damage is applied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeleeHit {
+    /// An attack which was not fully charged
+    Weak,
+    Strong,
+    Critical,
+    /// A fully charged attack while sprinting
+    Knockback,
+    /// An entity caught in a sweep attack
+    Sweep,
+}
+
+impl MeleeHit {
+    #[must_use]
+    pub fn sound(self) -> Ident {
+        match self {
+            Self::Weak => ident!("minecraft:entity.player.attack.weak"),
+            Self::Strong => ident!("minecraft:entity.player.attack.strong"),
+            Self::Critical => ident!("minecraft:entity.player.attack.crit"),
+            Self::Knockback => ident!("minecraft:entity.player.attack.knockback"),
+            Self::Sweep => ident!("minecraft:entity.player.attack.sweep"),
+        }
+    }
+}
+
+/// Requests damage to be dealt to an entity. The amount is before armor and other mitigation.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: DamageSource,
+    pub amount: f32,
+    /// Knockback strength added to the base knockback of [`formula::BASE_KNOCKBACK`]
+    pub knockback: f32,
+    pub melee: Option<MeleeHit>,
+    cancelled: bool,
+}
+
+impl DamageEvent {
+    #[must_use]
+    pub const fn new(target: Entity, source: DamageSource, amount: f32) -> Self {
+        Self {
+            target,
+            source,
+            amount,
+            knockback: 0.0,
+            melee: None,
+            cancelled: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_knockback(mut self, knockback: f32) -> Self {
+        self.knockback = knockback;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_melee(mut self, melee: MeleeHit) -> Self {
+        self.melee = Some(melee);
+        self
+    }
+
+    /// Prevents the damage from being applied
+    pub const fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    #[must_use]
+    pub const fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// Sent when an entity dies from a [`DamageEvent`]
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub source: DamageSource,
+}
+
+const fn is_sword(item: ItemKind) -> bool {
+    matches!(
+        item,
+        ItemKind::WoodenSword
+            | ItemKind::StoneSword
+            | ItemKind::IronSword
+            | ItemKind::GoldenSword
+            | ItemKind::DiamondSword
+            | ItemKind::NetheriteSword
+    )
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    commands.entity(trigger.target()).insert((
+        Invulnerability::default(),
+        AttackCooldown::default(),
+        LastDamage::default(),
+    ));
+}
+
+fn reset_cooldown_on_slot_change(
+    mut events: EventReader<'_, '_, UpdateSelectedSlotEvent>,
+    mut query: Query<'_, '_, &mut AttackCooldown>,
+    compose: Res<'_, Compose>,
+) {
+    let tick = compose.global().tick;
+
+    for event in events.read() {
+        if let Ok(mut cooldown) = query.get_mut(event.client) {
+            cooldown.reset(tick);
+        }
+    }
+}
+
+#[expect(clippy::too_many_lines)]
+fn handle_melee_attacks(
+    mut packets: EventReader<'_, '_, play::PlayerInteractEntity>,
+    entities: &Entities,
+    compose: Res<'_, Compose>,
+    mut attacker_query: Query<
+        '_,
+        '_,
+        (
+            &Position,
+            &Prev<Position>,
+            &Attributes,
+            &PlayerInventory,
+            &MovementTracking,
+            &MovementState,
+            &Flight,
+            &GameMode,
+            &mut AttackCooldown,
+        ),
+    >,
+    target_query: Query<'_, '_, (Entity, &Position, &EntitySize), With<Health>>,
+    mut writer: EventWriter<'_, DamageEvent>,
+) {
+    let tick = compose.global().tick;
+
+    for packet in packets.read() {
+        if packet.interact != EntityInteraction::Attack {
+            continue;
+        }
+
+        let attacker = packet.sender();
+
+        let id = u32::from_ne_bytes(packet.entity_id.0.to_ne_bytes());
+        let Some(target) = entities.resolve_from_id(id) else {
+            error!("handle melee attack failed: target id is invalid");
+            continue;
+        };
+
+        if target == attacker {
+            continue;
+        }
+
+        let (
+            &attacker_pos,
+            &attacker_prev_pos,
+            attributes,
+            inventory,
+            tracking,
+            movement,
+            flight,
+            game_mode,
+            mut cooldown,
+        ) = match attacker_query.get_mut(attacker) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("handle melee attack failed: query failed: {e}");
+                continue;
+            }
+        };
+
+        if !game_mode.can_interact() {
+            continue;
+        }
+
+        let (_, &target_pos, &target_size) = match target_query.get(target) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("handle melee attack failed: query failed: {e}");
+                continue;
+            }
+        };
+
+        let strength = cooldown.strength(tick, attributes.value(AttributeKind::AttackSpeed));
+        cooldown.reset(tick);
+
+        let weapon = &inventory.get_cursor().stack;
+
+        #[expect(clippy::cast_possible_truncation)]
+        let base_damage = formula::scale_base_damage(
+            attributes.value(AttributeKind::AttackDamage) as f32,
+            strength,
+        );
+        let enchantment_damage = formula::scale_enchantment_damage(
+            formula::sharpness_damage(enchantment::level(weapon, enchantment::SHARPNESS)),
+            strength,
+        );
+
+        if base_damage <= 0.0 && enchantment_damage <= 0.0 {
+            continue;
+        }
+
+        let charged = strength > 0.9;
+        let sprint_knockback = charged && tracking.sprinting;
+
+        let critical = charged
+            && !tracking.sprinting
+            && !tracking.was_on_ground
+            && !flight.is_flying
+            && !movement.climbing
+            && !movement.in_water
+            && attacker_pos.y < attacker_prev_pos.y;
+
+        let mut damage = base_damage;
+        if critical {
+            damage *= formula::CRITICAL_MULTIPLIER;
+        }
+        damage += enchantment_damage;
+
+        #[expect(clippy::cast_possible_truncation)]
+        let knockback_level = f32::from(enchantment::level(weapon, enchantment::KNOCKBACK))
+            + f32::from(u8::from(sprint_knockback))
+            + attributes.value(AttributeKind::AttackKnockback) as f32;
+
+        let hit = if critical {
+            MeleeHit::Critical
+        } else if sprint_knockback {
+            MeleeHit::Knockback
+        } else if charged {
+            MeleeHit::Strong
+        } else {
+            MeleeHit::Weak
+        };
+
+        let source = DamageSource::player_attack(attacker);
+
+        writer.write(
+            DamageEvent::new(target, source, damage)
+                .with_knockback(knockback_level * 0.5)
+                .with_melee(hit),
+        );
+
+        #[expect(clippy::cast_possible_truncation)]
+        let movement_speed = attributes.value(AttributeKind::MovementSpeed) as f32;
+        let moved = (*attacker_pos - **attacker_prev_pos).with_y(0.0).length();
+
+        let sweep = charged
+            && !critical
+            && !sprint_knockback
+            && tracking.was_on_ground
+            && moved < movement_speed
+            && is_sword(weapon.item);
+
+        if !sweep {
+            continue;
+        }
+
+        let sweep_damage =
+            formula::sweep_damage(damage, enchantment::level(weapon, enchantment::SWEEPING));
+
+        let target_aabb = aabb(*target_pos, target_size);
+        let sweep_area = geometry::aabb::Aabb::new(
+            target_aabb.min - Vec3::new(1.0, 0.25, 1.0),
+            target_aabb.max + Vec3::new(1.0, 0.25, 1.0),
+        );
+
+        for (entity, position, &size) in &target_query {
+            if entity == attacker || entity == target {
+                continue;
+            }
+
+            if position.distance_squared(*attacker_pos) >= 9.0 {
+                continue;
+            }
+
+            if !aabb(**position, size).collides(&sweep_area) {
+                continue;
+            }
+
+            writer.write(
+                DamageEvent::new(entity, source, sweep_damage)
+                    .with_knockback(0.0)
+                    .with_melee(MeleeHit::Sweep),
+            );
+        }
+    }
+}
+
+#[expect(clippy::too_many_lines)]
+fn apply_damage(
+    mut events: EventReader<'_, '_, DamageEvent>,
+    mut target_query: Query<
+        '_,
+        '_,
+        (
+            &mut Health,
+            &Position,
+            Option<&Yaw>,
+            Option<&Attributes>,
+            Option<&PlayerInventory>,
+            Option<&ActiveEffects>,
+            Option<&MovementTracking>,
+            Option<&GameMode>,
+            Option<&ConnectionId>,
+            Option<&mut Invulnerability>,
+            Option<&mut LastDamage>,
+            Option<&mut Velocity>,
+        ),
+    >,
+    position_query: Query<'_, '_, &Position>,
+    compose: Res<'_, Compose>,
+    mut death_writer: EventWriter<'_, DeathEvent>,
+) {
+    let tick = compose.global().tick;
+
+    for event in events.read() {
+        if event.is_cancelled() || event.amount <= 0.0 {
+            continue;
+        }
+
+        let (
+            mut health,
+            &target_pos,
+            yaw,
+            attributes,
+            inventory,
+            effects,
+            tracking,
+            game_mode,
+            connection,
+            invulnerability,
+            last_damage,
+            velocity,
+        ) = match target_query.get_mut(event.target) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to apply damage: query failed: {e}");
+                continue;
+            }
+        };
+
+        if health.is_dead() {
+            continue;
+        }
+
+        let kind = event.source.kind;
+        let mut amount = event.amount;
+
+        if game_mode.is_some_and(|game_mode| game_mode.is_invulnerable())
+            && !kind.bypasses_invulnerability()
+        {
+            continue;
+        }
+
+        if kind.is_fire()
+            && effects.is_some_and(|effects| effects.contains(EffectKind::FireResistance))
+        {
+            continue;
+        }
+
+        // During invulnerability frames, only the damage exceeding the previous hit is dealt and
+        // the entity is not hurt again visually
+        let mut hurt = true;
+        if let Some(mut invulnerability) = invulnerability {
+            if invulnerability.is_invulnerable(tick) && !kind.bypasses_invulnerability() {
+                if amount <= invulnerability.last_damage {
+                    continue;
+                }
+
+                amount -= invulnerability.last_damage;
+                invulnerability.last_damage = event.amount;
+                hurt = false;
+            } else {
+                invulnerability.until = tick + INVULNERABILITY_TICKS;
+                invulnerability.last_damage = event.amount;
+            }
+        }
+
+        if !kind.bypasses_armor()
+            && let Some(attributes) = attributes
+        {
+            #[expect(clippy::cast_possible_truncation)]
+            {
+                amount = formula::damage_after_armor(
+                    amount,
+                    attributes.value(AttributeKind::Armor) as f32,
+                    attributes.value(AttributeKind::ArmorToughness) as f32,
+                );
+            }
+        }
+
+        if !kind.bypasses_enchantments() {
+            if let Some(amplifier) =
+                effects.and_then(|effects| effects.amplifier(EffectKind::Resistance))
+            {
+                amount = formula::damage_after_resistance(amount, amplifier);
+            }
+
+            if let Some(inventory) = inventory {
+                amount = formula::damage_after_protection(
+                    amount,
+                    enchantment::protection_factor(inventory, kind),
+                );
+            }
+        }
+
+        if amount <= 0.0 {
+            continue;
+        }
+
+        health.damage(amount);
+
+        if let Some(mut last_damage) = last_damage {
+            *last_damage = LastDamage {
+                source: Some(event.source),
+                amount,
+                tick,
+            };
+        }
+
+        let source_pos = event
+            .source
+            .direct
+            .or(event.source.attacker)
+            .and_then(|entity| position_query.get(entity).ok())
+            .map(|position| **position)
+            .or(event.source.position);
+
+        if hurt {
+            let entity_id = VarInt(event.target.minecraft_id());
+            let chunk = target_pos.to_chunk();
+
+            // The entity ids are optional VarInts, where 0 is none
+            let pkt_damage = EntityDamageS2c {
+                entity_id,
+                source_cause_id: VarInt(
+                    event
+                        .source
+                        .attacker
+                        .map_or(0, |entity| entity.minecraft_id() + 1),
+                ),
+                source_direct_id: VarInt(
+                    event
+                        .source
+                        .direct
+                        .map_or(0, |entity| entity.minecraft_id() + 1),
+                ),
+                source_type_id: VarInt(kind.protocol_id()),
+                source_pos: event
+                    .source
+                    .direct
+                    .is_none()
+                    .then_some(event.source.position)
+                    .flatten()
+                    .map(|position| position.as_dvec3()),
+            };
+            compose.broadcast_local(&pkt_damage, chunk).send().unwrap();
+
+            if let Some(source_pos) = source_pos {
+                let direction = *target_pos - source_pos;
+
+                if let (Some(&connection), Some(yaw)) = (connection, yaw) {
+                    #[expect(clippy::cast_possible_truncation)]
+                    let pkt_tilt = DamageTiltS2c {
+                        entity_id,
+                        yaw: (f64::from(direction.z)
+                            .atan2(f64::from(direction.x))
+                            .to_degrees()
+                            - f64::from(**yaw)) as f32,
+                    };
+                    compose.unicast(&pkt_tilt, connection).unwrap();
+                }
+
+                #[expect(clippy::cast_possible_truncation)]
+                let resistance = attributes.map_or(0.0, |attributes| {
+                    attributes.value(AttributeKind::KnockbackResistance) as f32
+                });
+                let on_ground = tracking.is_none_or(|tracking| tracking.was_on_ground);
+
+                if let Some(mut velocity) = velocity
+                    && let Some(knocked) = formula::knockback(
+                        velocity.0,
+                        formula::BASE_KNOCKBACK + event.knockback,
+                        direction.x,
+                        direction.z,
+                        resistance,
+                        on_ground,
+                    )
+                {
+                    velocity.0 = knocked;
+                }
+            }
+
+            if let Some(melee) = event.melee {
+                let sound = agnostic::sound(melee.sound(), *target_pos)
+                    .seed(fastrand::i64(..))
+                    .build();
+                compose.broadcast_local(&sound, chunk).send().unwrap();
+
+                let particle = match melee {
+                    MeleeHit::Critical => Some(Particle::Crit),
+                    MeleeHit::Sweep => Some(Particle::SweepAttack),
+                    _ => None,
+                };
+
+                if let Some(particle) = particle {
+                    let particle = agnostic::particle(
+                        particle,
+                        target_pos.as_dvec3() + DVec3::new(0.0, 1.0, 0.0),
+                    )
+                    .offset(Vec3::new(0.5, 0.5, 0.5))
+                    .speed(0.5)
+                    .count(20)
+                    .long_distance(true)
+                    .build();
+                    compose.broadcast_local(&particle, chunk).send().unwrap();
+                }
+            }
+        }
+
+        if health.is_dead() {
+            death_writer.write(DeathEvent {
+                entity: event.target,
+                source: event.source,
+            });
+        }
+    }
+}
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>();
+        app.add_event::<DeathEvent>();
+
+        app.configure_sets(
+            FixedUpdate,
+            (CombatSet::Attack, CombatSet::Modify, CombatSet::Apply)
+                .chain()
+                .after(ingress::decode::play),
+        );
+
+        app.add_plugins(environment::EnvironmentPlugin);
+
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (
+                (reset_cooldown_on_slot_change, handle_melee_attacks)
+                    .chain()
+                    .in_set(CombatSet::Attack),
+                apply_damage.in_set(CombatSet::Apply),
+            ),
+        );
+    }
+}
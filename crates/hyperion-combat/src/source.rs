@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use hyperion::glam::Vec3;
+
+/// The type of damage. This decides which protections apply and which death message and effects
+/// the client shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Arrow,
+    Cactus,
+    Drown,
+    Explosion,
+    Fall,
+    Fireball,
+    Generic,
+    GenericKill,
+    HotFloor,
+    InFire,
+    InWall,
+    Lava,
+    Magic,
+    MobAttack,
+    OnFire,
+    OutOfWorld,
+    PlayerAttack,
+    PlayerExplosion,
+    Starve,
+    SweetBerryBush,
+    Thrown,
+    Trident,
+    Wither,
+}
+
+impl DamageType {
+    /// The id of this damage type in the `minecraft:damage_type` registry sent to the client
+    #[must_use]
+    pub const fn protocol_id(self) -> i32 {
+        match self {
+            Self::Arrow => 0,
+            Self::Cactus => 2,
+            Self::Drown => 5,
+            Self::Explosion => 7,
+            Self::Fall => 8,
+            Self::Fireball => 12,
+            Self::Generic => 16,
+            Self::GenericKill => 17,
+            Self::HotFloor => 18,
+            Self::InFire => 19,
+            Self::InWall => 20,
+            Self::Lava => 22,
+            Self::Magic => 24,
+            Self::MobAttack => 25,
+            Self::OnFire => 28,
+            Self::OutOfWorld => 29,
+            Self::PlayerAttack => 31,
+            Self::PlayerExplosion => 32,
+            Self::Starve => 35,
+            Self::SweetBerryBush => 37,
+            Self::Thrown => 39,
+            Self::Trident => 40,
+            Self::Wither => 42,
+        }
+    }
+
+    /// Whether armor does not reduce this damage
+    #[must_use]
+    pub const fn bypasses_armor(self) -> bool {
+        matches!(
+            self,
+            Self::Drown
+                | Self::Fall
+                | Self::Generic
+                | Self::GenericKill
+                | Self::InWall
+                | Self::Magic
+                | Self::OnFire
+                | Self::OutOfWorld
+                | Self::Starve
+                | Self::Wither
+        )
+    }
+
+    /// Whether protection enchantments and the resistance effect do not reduce this damage
+    #[must_use]
+    pub const fn bypasses_enchantments(self) -> bool {
+        matches!(self, Self::GenericKill | Self::OutOfWorld | Self::Starve)
+    }
+
+    /// Whether this damage is dealt during invulnerability frames
+    #[must_use]
+    pub const fn bypasses_invulnerability(self) -> bool {
+        matches!(self, Self::GenericKill | Self::OutOfWorld)
+    }
+
+    #[must_use]
+    pub const fn is_fire(self) -> bool {
+        matches!(
+            self,
+            Self::Fireball | Self::HotFloor | Self::InFire | Self::Lava | Self::OnFire
+        )
+    }
+
+    #[must_use]
+    pub const fn is_explosion(self) -> bool {
+        matches!(self, Self::Explosion | Self::PlayerExplosion)
+    }
+
+    #[must_use]
+    pub const fn is_projectile(self) -> bool {
+        matches!(
+            self,
+            Self::Arrow | Self::Fireball | Self::Thrown | Self::Trident
+        )
+    }
+
+    #[must_use]
+    pub const fn is_fall(self) -> bool {
+        matches!(self, Self::Fall)
+    }
+}
+
+/// Where damage came from
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[must_use]
+pub struct DamageSource {
+    pub kind: DamageType,
+    /// The entity responsible for the damage, such as the player who shot an arrow
+    pub attacker: Option<Entity>,
+    /// The entity which directly dealt the damage, such as the arrow itself. This is the same as
+    /// `attacker` for melee attacks.
+    pub direct: Option<Entity>,
+    /// The position the damage came from, used when there is no entity, such as an explosion
+    pub position: Option<Vec3>,
+}
+
+impl DamageSource {
+    /// Damage without any responsible entity or position, such as fall damage
+    pub const fn new(kind: DamageType) -> Self {
+        Self {
+            kind,
+            attacker: None,
+            direct: None,
+            position: None,
+        }
+    }
+
+    /// Melee damage dealt by a player
+    pub const fn player_attack(attacker: Entity) -> Self {
+        Self {
+            kind: DamageType::PlayerAttack,
+            attacker: Some(attacker),
+            direct: Some(attacker),
+            position: None,
+        }
+    }
+
+    /// Damage dealt by a projectile fired by `owner`
+    pub const fn projectile(kind: DamageType, projectile: Entity, owner: Option<Entity>) -> Self {
+        Self {
+            kind,
+            attacker: owner,
+            direct: Some(projectile),
+            position: None,
+        }
+    }
+
+    /// Damage from a position without an entity, such as an explosion
+    pub const fn at(kind: DamageType, position: Vec3) -> Self {
+        Self {
+            kind,
+            attacker: None,
+            direct: None,
+            position: Some(position),
+        }
+    }
+
+    pub const fn with_attacker(mut self, attacker: Entity) -> Self {
+        self.attacker = Some(attacker);
+        self
+    }
+}
@@ -1,20 +1,34 @@
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard, TryLockError};
 
 use bevy::prelude::*;
 use derive_more::{Deref, DerefMut};
-use hyperion::simulation::packet::play;
+use hyperion_permission::has_permission;
 use hyperion_utils::ApplyWorld;
 use indexmap::IndexMap;
+use tracing::warn;
 
 pub trait ExecutableCommand: ApplyWorld {
-    /// Executes a command triggered by a player
-    fn execute(&mut self, world: &World, execution: &play::CommandExecution);
+    /// Executes a command run by `sender`, which is a player or an entity with a
+    /// [`CommandSender`](crate::CommandSender). `command` does not start with `/`.
+    fn execute(&mut self, world: &World, sender: Entity, command: &str);
+}
+
+/// Suggestions for the word being typed in a command
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Completions {
+    /// Byte offset of the word in the input
+    pub start: usize,
+    /// Length of the word in bytes, which is replaced by the chosen suggestion
+    pub len: usize,
+    pub matches: Vec<String>,
 }
 
 pub struct CommandHandler {
     pub executable: Box<dyn ExecutableCommand + Send + Sync + 'static>,
-    pub tab_complete: fn(&World, &play::RequestCommandCompletions),
-    pub has_permissions: fn(&World, Entity) -> bool,
+    /// Suggests how to complete a partially typed command, which does not start with `/`
+    pub tab_complete: fn(world: &World, sender: Entity, input: &str) -> Option<Completions>,
+    /// The permission node needed to see and execute the command, such as `command.fly`
+    pub permission: &'static str,
 }
 
 pub struct CommandRegistryInner {
@@ -37,7 +51,7 @@ impl CommandRegistryInner {
         self.commands
             .iter()
             .filter_map(move |(cmd_name, handler)| {
-                if (handler.has_permissions)(world, caller) {
+                if has_permission(world, caller, handler.permission) {
                     Some(cmd_name)
                 } else {
                     None
@@ -45,6 +59,33 @@ impl CommandRegistryInner {
             })
             .map(String::as_str)
     }
+
+    /// Suggests how to complete a partially typed command, which does not start with `/`. Command
+    /// names are suggested until the first space.
+    #[must_use]
+    pub fn complete(&self, world: &World, sender: Entity, input: &str) -> Option<Completions> {
+        let Some((name, _)) = input.split_once(char::is_whitespace) else {
+            let matches = self
+                .get_permitted(world, sender)
+                .filter(|name| name.starts_with(input))
+                .map(str::to_string)
+                .collect();
+
+            return Some(Completions {
+                start: 0,
+                len: input.len(),
+                matches,
+            });
+        };
+
+        let handler = self.commands.get(name)?;
+
+        if !has_permission(world, sender, handler.permission) {
+            return None;
+        }
+
+        (handler.tab_complete)(world, sender, input)
+    }
 }
 
 /// Registry storing a list of commands.
@@ -54,6 +95,24 @@ impl CommandRegistryInner {
 #[derive(Resource, Deref, DerefMut)]
 pub struct CommandRegistry(Mutex<CommandRegistryInner>);
 
+impl CommandRegistry {
+    /// Locks the registry from a system which accepts `&World`, warning if the lock is contested
+    pub fn lock_uncontested(&self, system: &str) -> MutexGuard<'_, CommandRegistryInner> {
+        match self.try_lock() {
+            Ok(registry) => registry,
+            Err(TryLockError::WouldBlock) => {
+                warn!(
+                    "{system}: CommandRegistry lock is contested - this should ideally not occur"
+                );
+                self.lock().unwrap()
+            }
+            Err(poison) => {
+                panic!("command registry lock is poisoned: {poison}");
+            }
+        }
+    }
+}
+
 pub struct CommandComponentPlugin;
 
 impl Plugin for CommandComponentPlugin {
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use derive_more::Deref;
+use hyperion_permission::AllPermissions;
+use rustyline::{
+    Context, Editor, Helper, completion::Completer, error::ReadlineError, highlight::Highlighter,
+    hint::Hinter, history::DefaultHistory, validate::Validator,
+};
+use tracing::{debug, error, info};
+
+use crate::{
+    component::{CommandRegistry, Completions},
+    sender::{CommandOutput, CommandSender, RunCommand},
+    system::{apply_deferred_changes, execute_commands},
+};
+
+/// How long the console waits for the server to answer a tab completion. Completions are answered
+/// once per tick, so this only runs out if the server is overloaded.
+const COMPLETION_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The entity which runs the commands typed in the console
+#[derive(Resource, Deref, Copy, Clone, Debug)]
+pub struct Console(Entity);
+
+enum ConsoleInput {
+    Command(String),
+    /// Ctrl-C was pressed
+    Interrupt,
+}
+
+struct CompletionRequest {
+    input: String,
+    reply: flume::Sender<Option<Completions>>,
+}
+
+#[derive(Resource)]
+struct ConsoleChannels {
+    input: flume::Receiver<ConsoleInput>,
+    completions: flume::Receiver<CompletionRequest>,
+}
+
+/// Completes commands in the console by asking the server
+struct ConsoleHelper {
+    completions: flume::Sender<CompletionRequest>,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let input = line[..pos].trim_start_matches('/');
+        let offset = pos - input.len();
+
+        let (reply, response) = flume::bounded(1);
+        let request = CompletionRequest {
+            input: input.to_string(),
+            reply,
+        };
+
+        if self.completions.send(request).is_err() {
+            return Ok((pos, Vec::new()));
+        }
+
+        match response.recv_timeout(COMPLETION_TIMEOUT) {
+            Ok(Some(completions)) => Ok((offset + completions.start, completions.matches)),
+            Ok(None) | Err(_) => Ok((pos, Vec::new())),
+        }
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+/// Reads lines from stdin until it is closed
+fn read_console(
+    input: flume::Sender<ConsoleInput>,
+    completions: flume::Sender<CompletionRequest>,
+) -> rustyline::Result<()> {
+    let mut editor = Editor::<ConsoleHelper, DefaultHistory>::new()?;
+    editor.set_helper(Some(ConsoleHelper { completions }));
+
+    loop {
+        let message = match editor.readline("> ") {
+            Ok(line) => {
+                let command = line.trim().trim_start_matches('/');
+                if command.is_empty() {
+                    continue;
+                }
+
+                editor.add_history_entry(command)?;
+                ConsoleInput::Command(command.to_string())
+            }
+            // The console reads Ctrl-C instead of the terminal, so the server is stopped here
+            Err(ReadlineError::Interrupted) => ConsoleInput::Interrupt,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let interrupted = matches!(message, ConsoleInput::Interrupt);
+        if input.send(message).is_err() || interrupted {
+            return Ok(());
+        }
+    }
+}
+
+fn run_console_commands(
+    console: Res<'_, Console>,
+    channels: Res<'_, ConsoleChannels>,
+    mut runs: EventWriter<'_, RunCommand>,
+    mut exit: EventWriter<'_, AppExit>,
+) {
+    for input in channels.input.try_iter() {
+        match input {
+            ConsoleInput::Command(command) => {
+                info!("console issued command: {command}");
+                runs.write(RunCommand {
+                    sender: **console,
+                    command,
+                });
+            }
+            ConsoleInput::Interrupt => {
+                info!("stopping the server");
+                exit.write(AppExit::Success);
+            }
+        }
+    }
+}
+
+#[expect(
+    clippy::significant_drop_tightening,
+    reason = "the mutex should not be contended and the lock guard lifetime cannot be tightened"
+)]
+fn complete_console_commands(
+    console: Res<'_, Console>,
+    channels: Res<'_, ConsoleChannels>,
+    registry: Res<'_, CommandRegistry>,
+    world: &World,
+) {
+    let mut requests = channels.completions.try_iter().peekable();
+    if requests.peek().is_none() {
+        return;
+    }
+
+    let registry = registry.lock_uncontested("complete_console_commands");
+
+    for request in requests {
+        let completions = registry.complete(world, **console, &request.input);
+        if request.reply.send(completions).is_err() {
+            debug!("console stopped waiting for completions");
+        }
+    }
+}
+
+/// Reads commands from stdin, with history and tab completion. The commands are run by the
+/// [`Console`] entity, which has every permission and logs the output of commands.
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let console = app
+            .world_mut()
+            .spawn((
+                Name::new("Console"),
+                CommandSender::new(CommandOutput::Log),
+                AllPermissions,
+            ))
+            .id();
+
+        let (input_tx, input_rx) = flume::unbounded();
+        let (completions_tx, completions_rx) = flume::unbounded();
+
+        let spawned = std::thread::Builder::new()
+            .name("console".to_string())
+            .spawn(move || {
+                if let Err(e) = read_console(input_tx, completions_tx) {
+                    error!("console stopped: {e}");
+                }
+            });
+
+        if let Err(e) = spawned {
+            error!("failed to start console: {e}");
+        }
+
+        app.insert_resource(Console(console));
+        app.insert_resource(ConsoleChannels {
+            input: input_rx,
+            completions: completions_rx,
+        });
+        app.add_systems(
+            FixedUpdate,
+            (
+                run_console_commands.before(execute_commands),
+                complete_console_commands.after(apply_deferred_changes),
+            ),
+        );
+    }
+}
@@ -3,9 +3,16 @@
 use bevy::prelude::*;
 
 mod component;
+mod console;
+mod sender;
 mod system;
 
-pub use component::{CommandHandler, CommandRegistry, ExecutableCommand};
+pub use component::{CommandHandler, CommandRegistry, Completions, ExecutableCommand};
+pub use console::{Console, ConsolePlugin};
+pub use sender::{
+    CommandOutput, CommandSender, RunCommand, reply, require_player, strip_formatting,
+};
+pub use system::{apply_deferred_changes, execute_commands};
 
 pub struct CommandPlugin;
 
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use hyperion::{
+    net::{Compose, ConnectionId, agnostic},
+    simulation::Position,
+};
+use tracing::{debug, error, info};
+
+/// Where the output of commands run by a [`CommandSender`] goes
+#[derive(Clone, Debug)]
+pub enum CommandOutput {
+    /// Output is logged with formatting codes removed. The console uses this.
+    Log,
+    /// Output is sent through a channel, for example to the plugin which ran the command
+    Channel(flume::Sender<String>),
+    /// Output is dropped
+    Silent,
+}
+
+/// An entity which runs commands but is not a player, such as the console, a scheduled task or
+/// another plugin. Give it a [`Name`] to name it in the output of commands, and
+/// [`AllPermissions`](hyperion_permission::AllPermissions) or other permissions so it may run
+/// them.
+///
+/// Output of commands run by players is sent to their chat instead.
+#[derive(Component, Clone, Debug)]
+pub struct CommandSender {
+    pub output: CommandOutput,
+}
+
+impl CommandSender {
+    #[must_use]
+    pub const fn new(output: CommandOutput) -> Self {
+        Self { output }
+    }
+
+    /// Creates a sender whose output is read from the returned receiver
+    #[must_use]
+    pub fn channel() -> (Self, flume::Receiver<String>) {
+        let (tx, rx) = flume::unbounded();
+        (Self::new(CommandOutput::Channel(tx)), rx)
+    }
+
+    fn send(&self, name: Option<&Name>, msg: String) {
+        match &self.output {
+            CommandOutput::Log => {
+                let name = name.map_or("command sender", Name::as_str);
+                info!("[{name}] {}", strip_formatting(&msg));
+            }
+            CommandOutput::Channel(tx) => {
+                // The receiver may have stopped listening, which is not an error
+                if tx.send(msg).is_err() {
+                    debug!("dropped command output: receiver is disconnected");
+                }
+            }
+            CommandOutput::Silent => {}
+        }
+    }
+}
+
+/// Runs a command as if `sender` typed it. Plugins and scheduled tasks send this event to run
+/// registered commands, usually with a [`CommandSender`] entity as the sender.
+#[derive(Event, Clone, Debug)]
+pub struct RunCommand {
+    pub sender: Entity,
+    /// The command and its arguments, such as `gamemode creative Notch`. A leading `/` is
+    /// ignored.
+    pub command: String,
+}
+
+/// Sends a message to whoever ran a command: to the chat of a player, or to the
+/// [`CommandOutput`] of a [`CommandSender`]
+pub fn reply(world: &World, sender: Entity, msg: impl Into<String>) {
+    let Ok(entity) = world.get_entity(sender) else {
+        error!("failed to reply to command: sender {sender} does not exist");
+        return;
+    };
+
+    if let Some(command_sender) = entity.get::<CommandSender>() {
+        command_sender.send(entity.get::<Name>(), msg.into());
+        return;
+    }
+
+    let Some(&connection_id) = entity.get::<ConnectionId>() else {
+        error!("failed to reply to command: sender is missing ConnectionId component");
+        return;
+    };
+
+    let chat = agnostic::chat(msg);
+    world
+        .resource::<Compose>()
+        .unicast(&chat, connection_id)
+        .unwrap();
+}
+
+/// Whether `caller` is a player, telling other senders such as the console that the command can
+/// only be run by players otherwise. Commands which act on their caller check this first.
+#[must_use]
+pub fn require_player(world: &World, caller: Entity) -> bool {
+    let is_player = world
+        .get_entity(caller)
+        .is_ok_and(|entity| entity.contains::<ConnectionId>() && entity.contains::<Position>());
+
+    if !is_player {
+        reply(world, caller, "§cThis command can only be run by players");
+    }
+
+    is_player
+}
+
+/// Removes `§` formatting codes from a message
+#[must_use]
+pub fn strip_formatting(msg: &str) -> String {
+    let mut stripped = String::with_capacity(msg.len());
+    let mut chars = msg.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{CommandSender, require_player, strip_formatting};
+
+    #[test]
+    fn strip_formatting_codes() {
+        assert_eq!(strip_formatting("§cNo §lplayer§r found"), "No player found");
+        assert_eq!(strip_formatting("trailing §"), "trailing ");
+    }
+
+    #[test]
+    fn non_players_are_rejected() {
+        let mut world = World::new();
+        let (sender, output) = CommandSender::channel();
+        let console = world.spawn(sender).id();
+
+        assert!(!require_player(&world, console));
+        assert_eq!(
+            output.try_recv().unwrap(),
+            "§cThis command can only be run by players"
+        );
+    }
+}
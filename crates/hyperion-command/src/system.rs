@@ -1,16 +1,20 @@
-use std::{fmt::Write, sync::TryLockError};
+use std::fmt::Write;
 
 use bevy::prelude::*;
-use hyperion::{
-    ingress,
-    net::{Compose, agnostic},
-    simulation::packet::play,
-};
+use hyperion::{ingress, net::Compose, simulation::packet::play};
+use hyperion_permission::has_permission;
 use tracing::{debug, warn};
+use valence_protocol::{
+    VarInt,
+    packets::play::command_suggestions_s2c::{CommandSuggestionsMatch, CommandSuggestionsS2c},
+};
 
-use crate::component::CommandRegistry;
+use crate::{
+    component::CommandRegistry,
+    sender::{RunCommand, reply},
+};
 
-/// Executes commands sent by the client.
+/// Executes commands sent by the client and commands run with [`RunCommand`].
 ///
 /// This system is the reason that [`CommandRegistry`] must be locked by a mutex. Storing
 /// [`bevy::ecs::system::SystemState`] as command state is common. However, to use it, a `&World`
@@ -22,28 +26,24 @@ use crate::component::CommandRegistry;
     clippy::significant_drop_tightening,
     reason = "the mutex should not be contended and the lock guard lifetime cannot be tightened"
 )]
-fn execute_commands(
+pub fn execute_commands(
     mut packets: EventReader<'_, '_, play::CommandExecution>,
+    mut runs: EventReader<'_, '_, RunCommand>,
     registry: Res<'_, CommandRegistry>,
-    compose: Res<'_, Compose>,
     world: &World,
 ) {
-    let mut registry = match registry.try_lock() {
-        Ok(registry) => registry,
-        Err(TryLockError::WouldBlock) => {
-            warn!(
-                "execute_commands: CommandRegistry lock is contested - this should ideally not \
-                 occur"
-            );
-            registry.lock().unwrap()
-        }
-        Err(poison) => {
-            panic!("command registry lock is poisoned: {poison}");
-        }
-    };
-
-    for packet in packets.read() {
-        let Some(first_word) = packet.command.split_whitespace().next() else {
+    let mut registry = registry.lock_uncontested("execute_commands");
+
+    let packets = packets
+        .read()
+        .map(|packet| (packet.sender(), packet.command.as_str()));
+    let runs = runs.read().map(|run| {
+        let command = run.command.strip_prefix('/').unwrap_or(&run.command);
+        (run.sender, command)
+    });
+
+    for (sender, input) in packets.chain(runs) {
+        let Some(first_word) = input.split_whitespace().next() else {
             warn!("command is empty");
             continue;
         };
@@ -54,29 +54,33 @@ fn execute_commands(
             let mut msg = String::new();
             write!(&mut msg, "§cAvailable commands: §r[").unwrap();
 
-            for w in registry
-                .get_permitted(world, packet.sender())
-                .intersperse(", ")
-            {
+            for w in registry.get_permitted(world, sender).intersperse(", ") {
                 write!(&mut msg, "{w}").unwrap();
             }
 
             write!(&mut msg, "]").unwrap();
 
-            let chat = agnostic::chat(msg);
-
-            compose.unicast(&chat, packet.connection_id()).unwrap();
+            reply(world, sender, msg);
 
             continue;
         };
 
+        if !has_permission(world, sender, command.permission) {
+            reply(
+                world,
+                sender,
+                "§cYou do not have permission to use this command!",
+            );
+            continue;
+        }
+
         debug!("executing command {first_word}");
 
-        command.executable.execute(world, packet);
+        command.executable.execute(world, sender, input);
     }
 }
 
-fn apply_deferred_changes(world: &mut World) {
+pub fn apply_deferred_changes(world: &mut World) {
     let mut registry = world.resource_mut::<CommandRegistry>();
 
     // TODO: There should be some sort of error if the apply callback tries to access the
@@ -101,43 +105,52 @@ fn apply_deferred_changes(world: &mut World) {
 fn complete_commands(
     mut packets: EventReader<'_, '_, play::RequestCommandCompletions>,
     registry: Res<'_, CommandRegistry>,
+    compose: Res<'_, Compose>,
     world: &World,
 ) {
     // TODO: This lock could be removed by separating the tab_complete callback from the execute
     // callback
-    let registry = match registry.try_lock() {
-        Ok(registry) => registry,
-        Err(TryLockError::WouldBlock) => {
-            warn!(
-                "complete_commands: CommandRegistry lock is contested - this should ideally not \
-                 occur"
-            );
-            registry.lock().unwrap()
-        }
-        Err(poison) => {
-            panic!("command registry lock is poisoned: {poison}");
-        }
-    };
+    let registry = registry.lock_uncontested("complete_commands");
 
     for packet in packets.read() {
         // should be in form "/{command}"
-        let command = packet
-            .text
-            .strip_prefix("/")
-            .unwrap_or(&packet.text)
-            .split_whitespace()
-            .next()
-            .unwrap_or("");
-
-        if command.is_empty() {
+        let input = packet.text.strip_prefix('/').unwrap_or(&packet.text);
+
+        // The client completes command names itself
+        if !input.contains(char::is_whitespace) {
             continue;
         }
 
-        let Some(cmd) = registry.commands.get(command) else {
+        let Some(completions) = registry.complete(world, packet.sender(), input) else {
             continue;
         };
 
-        (cmd.tab_complete)(world, packet);
+        if completions.matches.is_empty() {
+            continue;
+        }
+
+        // Offsets sent to the client include the `/`
+        let start = completions.start + packet.text.len() - input.len();
+        let start = i32::try_from(start).unwrap();
+        let len = i32::try_from(completions.len).unwrap();
+
+        let matches = completions
+            .matches
+            .iter()
+            .map(|suggestion| CommandSuggestionsMatch {
+                suggested_match: suggestion.as_str().into(),
+                tooltip: None,
+            })
+            .collect();
+
+        let pkt = CommandSuggestionsS2c {
+            id: packet.transaction_id,
+            start: VarInt(start),
+            length: VarInt(len),
+            matches,
+        };
+
+        compose.unicast(&pkt, packet.connection_id()).unwrap();
     }
 }
 
@@ -145,6 +158,8 @@ pub struct CommandSystemPlugin;
 
 impl Plugin for CommandSystemPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<RunCommand>();
+
         // The ordering constraint between execute_command and complete_commands isn't necessary,
         // but they avoid lock contention on the CommandRegistry.
         app.add_systems(
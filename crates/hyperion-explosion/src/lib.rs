@@ -0,0 +1,912 @@
+//! Explosions.
+//!
+//! Writing an [`Explosion`] event blows up the world at a position. Rays are cast outward from the
+//! center and lose strength based on the [blast resistance](ExplosionSettings::resistance) of the
+//! blocks they pass through, and every block a ray reaches with strength left is destroyed.
+//! Entities in range take damage and knockback based on how exposed they are to the explosion.
+//!
+//! Destroyed blocks are removed over the next ticks, at most [`BLOCKS_PER_TICK`] per tick. TNT
+//! caught in an explosion is primed with a short fuse, and other blocks drop as items with a chance
+//! of one over the explosion power. Explosions without a positive power do nothing.
+//!
+//! Entities with a [`Fuse`] explode once the fuse runs out. Writing a [`PrimeTnt`] event spawns
+//! primed TNT, and players prime TNT blocks by using flint and steel on them. Creepers prime
+//! themselves when a player who can be hurt comes within [`CREEPER_RANGE`] blocks, and stop once
+//! no such player is within [`CREEPER_CANCEL_RANGE`] blocks.
+
+use std::collections::{HashSet, VecDeque};
+
+use bevy::prelude::*;
+use geometry::{aabb::Aabb, ray::Ray};
+use hyperion::{
+    BlockKind, BlockState, ItemKind, ItemStack, ingress,
+    net::{Compose, ConnectionId},
+    simulation::{
+        EntitySize, Pitch, Position, SpawnEvent, Uuid, Velocity, Yaw, aabb,
+        blocks::Blocks,
+        can_reach_block,
+        entity_kind::EntityKind,
+        event::ItemDropEvent,
+        game_mode::GameMode,
+        metadata::{
+            creeper::{Charged, CreeperState},
+            living_entity::Health,
+            tnt::FuseTicks,
+        },
+        packet::play,
+        packet_state,
+    },
+    spatial::SpatialIndex,
+};
+use hyperion_combat::{CombatSet, DamageEvent, DamageSource, DamageType, formula};
+use hyperion_inventory::PlayerInventory;
+use hyperion_item::dropped::DroppedItemPlugin;
+use hyperion_utils::EntityExt;
+use tracing::error;
+use valence_protocol::{
+    Hand, VarInt,
+    packets::play::{EntitiesDestroyS2c, EntityVelocityUpdateS2c, ExplosionS2c},
+};
+
+/// The most destroyed blocks removed from the world each tick
+pub const BLOCKS_PER_TICK: usize = 4096;
+
+/// The power of a TNT explosion
+pub const TNT_POWER: f32 = 4.0;
+
+/// The number of ticks before primed TNT explodes
+pub const TNT_FUSE: u16 = 80;
+
+/// The power of a creeper explosion. Charged creepers explode with twice this power.
+pub const CREEPER_POWER: f32 = 3.0;
+
+/// The number of ticks before a creeper explodes
+pub const CREEPER_FUSE: u16 = 30;
+
+/// Creepers prime themselves when a player is within this many blocks
+pub const CREEPER_RANGE: f32 = 3.0;
+
+/// Primed creepers stop their fuse when no player is within this many blocks
+pub const CREEPER_CANCEL_RANGE: f32 = 7.0;
+
+/// The number of rays cast along each edge of the cube around an explosion
+const RAYS_PER_EDGE: u8 = 16;
+
+/// The distance rays travel each step
+const RAY_STEP: f32 = 0.3;
+
+/// Configuration for explosions
+#[derive(Resource, Clone, Debug)]
+pub struct ExplosionSettings {
+    /// The blast resistance of a block. Blocks with a resistance of [`f32::INFINITY`] are never
+    /// destroyed.
+    pub resistance: fn(BlockState) -> f32,
+}
+
+impl Default for ExplosionSettings {
+    fn default() -> Self {
+        Self {
+            resistance: blast_resistance,
+        }
+    }
+}
+
+/// Blows up the world at `position`
+#[derive(Event, Copy, Clone, Debug)]
+#[must_use]
+pub struct Explosion {
+    pub position: Vec3,
+    pub power: f32,
+    /// Whether blocks are destroyed
+    pub destroy_blocks: bool,
+    /// The entity responsible for the explosion, such as the player who lit the TNT
+    pub igniter: Option<Entity>,
+    /// The entity which exploded, such as the TNT itself. It is not hurt by the explosion.
+    pub exploder: Option<Entity>,
+}
+
+impl Explosion {
+    pub const fn new(position: Vec3, power: f32) -> Self {
+        Self {
+            position,
+            power,
+            destroy_blocks: true,
+            igniter: None,
+            exploder: None,
+        }
+    }
+
+    pub const fn with_destroy_blocks(mut self, destroy_blocks: bool) -> Self {
+        self.destroy_blocks = destroy_blocks;
+        self
+    }
+
+    pub const fn with_igniter(mut self, igniter: Entity) -> Self {
+        self.igniter = Some(igniter);
+        self
+    }
+
+    pub const fn with_exploder(mut self, exploder: Entity) -> Self {
+        self.exploder = Some(exploder);
+        self
+    }
+
+    /// The radius in which entities are affected
+    #[must_use]
+    pub const fn radius(&self) -> f32 {
+        self.power * 2.0
+    }
+
+    /// Whether the explosion has a positive, finite power. Other explosions do nothing.
+    #[must_use]
+    pub const fn has_power(&self) -> bool {
+        self.power > 0.0 && self.power.is_finite()
+    }
+
+    /// How strongly an entity at `position` is hit, which is its `exposure` scaled by how close
+    /// it is to the center. Entities outside the [radius](Self::radius) are not hit.
+    #[must_use]
+    pub fn impact(&self, position: Vec3, exposure: f32) -> Option<f32> {
+        if !self.has_power() {
+            return None;
+        }
+
+        let distance = position.distance(self.position) / self.radius();
+        if distance > 1.0 {
+            return None;
+        }
+
+        Some((1.0 - distance) * exposure)
+    }
+
+    /// The knockback of an entity whose eyes are at `eye` and which is hit with `impact`
+    #[must_use]
+    pub fn knockback(&self, eye: Vec3, impact: f32) -> Vec3 {
+        (eye - self.position).normalize_or_zero() * impact
+    }
+}
+
+/// Makes an entity explode and despawn once `ticks` reaches zero
+#[derive(Component, Copy, Clone, Debug)]
+pub struct Fuse {
+    pub ticks: u16,
+    pub power: f32,
+    /// The entity responsible for the explosion
+    pub igniter: Option<Entity>,
+}
+
+impl Fuse {
+    #[must_use]
+    pub const fn new(ticks: u16, power: f32) -> Self {
+        Self {
+            ticks,
+            power,
+            igniter: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn tnt() -> Self {
+        Self::new(TNT_FUSE, TNT_POWER)
+    }
+
+    #[must_use]
+    pub const fn creeper(charged: bool) -> Self {
+        let power = if charged {
+            CREEPER_POWER * 2.0
+        } else {
+            CREEPER_POWER
+        };
+
+        Self::new(CREEPER_FUSE, power)
+    }
+
+    #[must_use]
+    pub const fn with_igniter(mut self, igniter: Entity) -> Self {
+        self.igniter = Some(igniter);
+        self
+    }
+}
+
+/// Spawns primed TNT at `position`, which explodes after `fuse` ticks
+#[derive(Event, Copy, Clone, Debug)]
+pub struct PrimeTnt {
+    pub position: Vec3,
+    pub fuse: u16,
+    pub igniter: Option<Entity>,
+}
+
+struct BlockRemoval {
+    position: IVec3,
+    drop_chance: f32,
+    igniter: Option<Entity>,
+}
+
+/// Blocks destroyed by explosions which have not been removed yet
+#[derive(Resource, Default)]
+struct PendingRemovals(VecDeque<BlockRemoval>);
+
+/// The vanilla blast resistance of a block
+#[must_use]
+pub fn blast_resistance(state: BlockState) -> f32 {
+    let kind = state.to_kind();
+
+    match kind {
+        BlockKind::Air
+        | BlockKind::CaveAir
+        | BlockKind::VoidAir
+        | BlockKind::Tnt
+        | BlockKind::SlimeBlock
+        | BlockKind::HoneyBlock => 0.0,
+        BlockKind::Bedrock
+        | BlockKind::Barrier
+        | BlockKind::Light
+        | BlockKind::CommandBlock
+        | BlockKind::ChainCommandBlock
+        | BlockKind::RepeatingCommandBlock
+        | BlockKind::StructureBlock
+        | BlockKind::Jigsaw
+        | BlockKind::EndPortal
+        | BlockKind::EndPortalFrame
+        | BlockKind::EndGateway => f32::INFINITY,
+        BlockKind::Obsidian
+        | BlockKind::CryingObsidian
+        | BlockKind::RespawnAnchor
+        | BlockKind::NetheriteBlock
+        | BlockKind::AncientDebris
+        | BlockKind::ReinforcedDeepslate
+        | BlockKind::Anvil
+        | BlockKind::ChippedAnvil
+        | BlockKind::DamagedAnvil => 1200.0,
+        BlockKind::EnderChest => 600.0,
+        BlockKind::Water | BlockKind::Lava => 100.0,
+        BlockKind::EndStone | BlockKind::EndStoneBricks => 9.0,
+        // Blocks whose names would otherwise match a material with a different resistance
+        BlockKind::GoldBlock
+        | BlockKind::DiamondBlock
+        | BlockKind::EmeraldBlock
+        | BlockKind::CoalBlock
+        | BlockKind::RedstoneBlock
+        | BlockKind::RawIronBlock
+        | BlockKind::RawGoldBlock
+        | BlockKind::RawCopperBlock => 6.0,
+        BlockKind::LapisBlock => 3.0,
+        BlockKind::DriedKelpBlock => 2.5,
+        BlockKind::BoneBlock => 2.0,
+        BlockKind::NetherWartBlock | BlockKind::WarpedWartBlock => 1.0,
+        BlockKind::NoteBlock | BlockKind::QuartzBlock => 0.8,
+        BlockKind::GrassBlock | BlockKind::Farmland => 0.6,
+        BlockKind::HayBlock | BlockKind::MagmaBlock | BlockKind::Podzol | BlockKind::Mycelium => {
+            0.5
+        }
+        BlockKind::Netherrack => 0.4,
+        BlockKind::Glowstone | BlockKind::SeaLantern | BlockKind::RedstoneLamp => 0.3,
+        BlockKind::SnowBlock
+        | BlockKind::BrownMushroomBlock
+        | BlockKind::RedMushroomBlock
+        | BlockKind::MushroomStem => 0.2,
+        _ => {
+            let name = kind.to_str();
+
+            // Most blocks share a resistance with the other blocks of their material
+            if state.collision_shapes().next().is_none() {
+                // Plants, torches and other blocks without collision
+                0.0
+            } else if name.contains("glass") {
+                0.3
+            } else if name.contains("leaves") || name.ends_with("_bed") {
+                0.2
+            } else if name.contains("wool") || name.contains("carpet") {
+                0.8
+            } else if name.contains("deepslate") || name.contains("iron") {
+                6.0
+            } else if name.contains("ore") {
+                3.0
+            } else if name.contains("terracotta") {
+                4.2
+            } else if name.contains("concrete_powder") {
+                0.5
+            } else if name.contains("concrete") {
+                1.8
+            } else if name.contains("sandstone") {
+                // Only smooth sandstone and sandstone slabs are as resistant as stone
+                if name.starts_with("smooth") || name.ends_with("_slab") {
+                    6.0
+                } else {
+                    0.8
+                }
+            } else if name.contains("stone")
+                || name.contains("brick")
+                || name.contains("andesite")
+                || name.contains("diorite")
+                || name.contains("granite")
+                || name.contains("tuff")
+                || name.contains("copper")
+            {
+                6.0
+            } else if name.contains("planks")
+                || name.contains("fence")
+                || name.contains("door")
+                || name.contains("log")
+                || name.contains("wood")
+                || name.contains("stem")
+                || name.contains("hyphae")
+            {
+                3.0
+            } else if name.contains("dirt")
+                || name.contains("sand")
+                || name.contains("gravel")
+                || name.contains("mud")
+                || name.contains("clay")
+            {
+                0.5
+            } else {
+                1.0
+            }
+        }
+    }
+}
+
+/// The blocks destroyed by an explosion, given the block at each position and the blast
+/// resistance of each block. Positions without a block are in unloaded chunks.
+fn destroyed_blocks(
+    explosion: &Explosion,
+    get_block: impl Fn(IVec3) -> Option<BlockState>,
+    resistance: fn(BlockState) -> f32,
+) -> HashSet<IVec3> {
+    const LAST: u8 = RAYS_PER_EDGE - 1;
+
+    let mut destroyed = HashSet::new();
+
+    for x in 0..RAYS_PER_EDGE {
+        for y in 0..RAYS_PER_EDGE {
+            for z in 0..RAYS_PER_EDGE {
+                // Rays are only cast from the surface of the cube
+                let on_surface = [x, y, z].iter().any(|&i| i == 0 || i == LAST);
+                if !on_surface {
+                    continue;
+                }
+
+                let direction =
+                    (Vec3::new(f32::from(x), f32::from(y), f32::from(z)) / f32::from(LAST) * 2.0
+                        - Vec3::ONE)
+                        .normalize();
+
+                let mut strength = explosion.power * fastrand::f32().mul_add(0.6, 0.7);
+                let mut position = explosion.position;
+
+                while strength > 0.0 {
+                    let block = position.floor().as_ivec3();
+
+                    let Some(state) = get_block(block) else {
+                        // The explosion does not reach into unloaded chunks
+                        break;
+                    };
+
+                    if !state.is_air() {
+                        strength -= (resistance(state) + RAY_STEP) * RAY_STEP;
+
+                        if strength > 0.0 {
+                            destroyed.insert(block);
+                        }
+                    }
+
+                    position += direction * RAY_STEP;
+                    strength -= RAY_STEP * 0.75;
+                }
+            }
+        }
+    }
+
+    destroyed
+}
+
+/// The fraction of points in `bounds` which have a clear line to `center`
+fn exposure(center: Vec3, bounds: Aabb, blocks: &Blocks) -> f32 {
+    let step = Vec3::ONE / (bounds.lens() * 2.0 + Vec3::ONE);
+    let steps = (Vec3::ONE / step).floor();
+    // Center the samples horizontally
+    let offset = (Vec3::ONE - steps * step) / 2.0;
+
+    let counts = steps.as_uvec3() + UVec3::ONE;
+
+    let mut visible = 0_u32;
+    let mut total = 0_u32;
+
+    for x in 0..counts.x {
+        for y in 0..counts.y {
+            for z in 0..counts.z {
+                let t = Vec3::new(x as f32, y as f32, z as f32) * step;
+                let sample = bounds.min.lerp(bounds.max, t) + Vec3::new(offset.x, 0.0, offset.z);
+
+                let ray = Ray::new(sample, center - sample);
+                if blocks.first_collision_in_segment(ray).is_none() {
+                    visible += 1;
+                }
+
+                total += 1;
+            }
+        }
+    }
+
+    if total == 0 {
+        return 0.0;
+    }
+
+    visible as f32 / total as f32
+}
+
+/// The damage dealt to an entity with the given impact, which is its exposure scaled by how close
+/// it is to the center
+#[must_use]
+pub const fn explosion_damage(impact: f32, radius: f32) -> f32 {
+    (impact * impact + impact) / 2.0 * 7.0 * radius + 1.0
+}
+
+fn ignite_tnt(
+    mut packets: EventReader<'_, '_, play::PlayerInteractBlock>,
+    query: Query<'_, '_, (&PlayerInventory, &GameMode, &Position, &EntitySize)>,
+    mut blocks: ResMut<'_, Blocks>,
+    mut writer: EventWriter<'_, PrimeTnt>,
+) {
+    for packet in packets.read() {
+        let (inventory, game_mode, player_position, &size) = match query.get(packet.sender()) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to ignite tnt: query failed: {e}");
+                continue;
+            }
+        };
+
+        if packet.hand != Hand::Main || !game_mode.can_interact() {
+            continue;
+        }
+
+        if inventory.get_cursor().stack.item != ItemKind::FlintAndSteel {
+            continue;
+        }
+
+        let position = IVec3::new(packet.position.x, packet.position.y, packet.position.z);
+
+        if !can_reach_block(**player_position, size, position) {
+            continue;
+        }
+
+        if blocks.get_block(position) != Some(BlockState::TNT) {
+            continue;
+        }
+
+        if blocks.set_block(position, BlockState::AIR).is_err() {
+            continue;
+        }
+
+        writer.write(PrimeTnt {
+            position: position.as_vec3() + Vec3::new(0.5, 0.0, 0.5),
+            fuse: TNT_FUSE,
+            igniter: Some(packet.sender()),
+        });
+    }
+}
+
+fn prime_tnt(
+    mut events: EventReader<'_, '_, PrimeTnt>,
+    mut spawn_writer: EventWriter<'_, SpawnEvent>,
+    mut commands: Commands<'_, '_>,
+) {
+    for event in events.read() {
+        let angle = fastrand::f32() * std::f32::consts::TAU;
+
+        let id = commands
+            .spawn((
+                Uuid::new_v4(),
+                Position::from(event.position),
+                Velocity::new(-angle.sin() * 0.02, 0.2, -angle.cos() * 0.02),
+                Pitch::new(0.0),
+                Yaw::new(0.0),
+                EntityKind::Tnt,
+                Fuse {
+                    ticks: event.fuse,
+                    power: TNT_POWER,
+                    igniter: event.igniter,
+                },
+            ))
+            .id();
+
+        spawn_writer.write(SpawnEvent(id));
+    }
+}
+
+/// Moves primed TNT. Clients simulate its movement themselves, so no packets are sent.
+fn move_tnt(
+    mut query: Query<'_, '_, (&EntityKind, &mut Position, &mut Velocity), With<Fuse>>,
+    blocks: Res<'_, Blocks>,
+) {
+    for (&kind, mut position, mut velocity) in &mut query {
+        if kind != EntityKind::Tnt {
+            continue;
+        }
+
+        velocity.0.y -= 0.04;
+        **position += velocity.0;
+        velocity.0 *= 0.98;
+
+        // Check slightly below the TNT so that TNT resting on a block stays on the ground
+        if let Some(ground) = blocks.ground_height(**position - Vec3::new(0.0, 0.001, 0.0))
+            && position.y < ground
+        {
+            position.y = ground;
+            velocity.0 *= Vec3::new(0.7, -0.5, 0.7);
+        }
+    }
+}
+
+/// Shows the fuse of primed TNT. Clients count the fuse down themselves, so it is only sent once.
+fn show_fuses(mut query: Query<'_, '_, (&Fuse, &mut FuseTicks), Added<FuseTicks>>) {
+    for (fuse, mut ticks) in &mut query {
+        **ticks = VarInt(i32::from(fuse.ticks));
+    }
+}
+
+fn prime_creepers(
+    mut creepers: Query<'_, '_, (Entity, &Position, &Charged, &mut CreeperState, Has<Fuse>)>,
+    players: Query<'_, '_, (&Position, &GameMode, &Health), With<packet_state::Play>>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, position, charged, mut state, primed) in &mut creepers {
+        let nearest = players
+            .iter()
+            .filter(|(_, game_mode, health)| !game_mode.is_invulnerable() && !health.is_dead())
+            .map(|(player_position, ..)| player_position.distance(**position))
+            .reduce(f32::min)
+            .unwrap_or(f32::INFINITY);
+
+        if !primed && nearest < CREEPER_RANGE {
+            commands.entity(entity).insert(Fuse::creeper(**charged));
+            **state = VarInt(1);
+        } else if primed && nearest > CREEPER_CANCEL_RANGE {
+            commands.entity(entity).remove::<Fuse>();
+            **state = VarInt(-1);
+        }
+    }
+}
+
+fn tick_fuses(
+    mut query: Query<'_, '_, (Entity, &mut Fuse, &Position)>,
+    compose: Res<'_, Compose>,
+    mut writer: EventWriter<'_, Explosion>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, mut fuse, position) in &mut query {
+        if fuse.ticks > 0 {
+            fuse.ticks -= 1;
+            continue;
+        }
+
+        let mut explosion = Explosion::new(**position, fuse.power).with_exploder(entity);
+        if let Some(igniter) = fuse.igniter {
+            explosion = explosion.with_igniter(igniter);
+        }
+        writer.write(explosion);
+
+        let pkt = EntitiesDestroyS2c {
+            entity_ids: vec![VarInt(entity.minecraft_id())].into(),
+        };
+        compose
+            .broadcast_local(&pkt, position.to_chunk())
+            .send()
+            .unwrap();
+
+        commands.entity(entity).despawn();
+    }
+}
+
+fn explode(
+    mut events: EventReader<'_, '_, Explosion>,
+    bounds_query: Query<'_, '_, (&Position, &EntitySize)>,
+    mut targets: Query<
+        '_,
+        '_,
+        (
+            Option<&mut Velocity>,
+            Option<&ConnectionId>,
+            Option<&GameMode>,
+        ),
+    >,
+    index: Res<'_, SpatialIndex>,
+    blocks: Res<'_, Blocks>,
+    settings: Res<'_, ExplosionSettings>,
+    compose: Res<'_, Compose>,
+    mut removals: ResMut<'_, PendingRemovals>,
+    mut damage_writer: EventWriter<'_, DamageEvent>,
+) {
+    for explosion in events.read() {
+        if !explosion.has_power() {
+            continue;
+        }
+
+        let center = explosion.position;
+        let origin = center.floor().as_ivec3();
+
+        let mut records = Vec::new();
+
+        if explosion.destroy_blocks {
+            let get_block = |position| blocks.get_block(position);
+            for position in destroyed_blocks(explosion, get_block, settings.resistance) {
+                // The packet stores blocks as offsets from the center
+                let offset = (position - origin).to_array().map(i8::try_from);
+                if let [Ok(x), Ok(y), Ok(z)] = offset {
+                    records.push([x, y, z]);
+                }
+
+                removals.0.push_back(BlockRemoval {
+                    position,
+                    drop_chance: 1.0 / explosion.power,
+                    igniter: explosion.igniter,
+                });
+            }
+        }
+
+        let radius = explosion.radius();
+        let range = Aabb::new(
+            center - Vec3::splat(radius + 1.0),
+            center + Vec3::splat(radius + 1.0),
+        );
+
+        let kind = if explosion.igniter.is_some() {
+            DamageType::PlayerExplosion
+        } else {
+            DamageType::Explosion
+        };
+
+        let mut source = DamageSource::at(kind, center);
+        source.direct = explosion.exploder;
+        if let Some(igniter) = explosion.igniter {
+            source = source.with_attacker(igniter);
+        }
+
+        for entity in index.get_collisions(range, bounds_query) {
+            if Some(entity) == explosion.exploder {
+                continue;
+            }
+
+            let Ok((position, size)) = bounds_query.get(entity) else {
+                continue;
+            };
+
+            let Some(closeness) = explosion.impact(**position, 1.0) else {
+                continue;
+            };
+
+            let (velocity, connection, game_mode) = match targets.get_mut(entity) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("failed to apply explosion: query failed: {e}");
+                    continue;
+                }
+            };
+
+            if game_mode.is_some_and(GameMode::is_spectator) {
+                continue;
+            }
+
+            // Exposure is only computed for entities in range, as it casts many rays
+            let impact = closeness * exposure(center, aabb(**position, *size), &blocks);
+
+            // The explosion applies its own knockback below
+            damage_writer.write(
+                DamageEvent::new(entity, source, explosion_damage(impact, radius))
+                    .with_knockback(-formula::BASE_KNOCKBACK),
+            );
+
+            let Some(mut velocity) = velocity else {
+                continue;
+            };
+
+            let eye = **position + Vec3::new(0.0, size.height * 0.85, 0.0);
+            velocity.0 += explosion.knockback(eye, impact);
+
+            // Player velocity is sent when players are synced
+            if connection.is_none() {
+                let pkt = EntityVelocityUpdateS2c {
+                    entity_id: VarInt(entity.minecraft_id()),
+                    velocity: velocity.to_packet_units(),
+                };
+                compose
+                    .broadcast_local(&pkt, position.to_chunk())
+                    .send()
+                    .unwrap();
+            }
+        }
+
+        // The client shows the particles and plays the sound of the explosion
+        let pkt = ExplosionS2c {
+            window_x: f64::from(center.x),
+            window_y: f64::from(center.y),
+            window_z: f64::from(center.z),
+            size: explosion.power,
+            affected_blocks: records.into(),
+            player_motion: Vec3::ZERO,
+        };
+        compose
+            .broadcast_local(&pkt, Position::from(center).to_chunk())
+            .send()
+            .unwrap();
+    }
+}
+
+fn remove_blocks(
+    mut removals: ResMut<'_, PendingRemovals>,
+    mut blocks: ResMut<'_, Blocks>,
+    mut tnt_writer: EventWriter<'_, PrimeTnt>,
+    mut drop_writer: EventWriter<'_, ItemDropEvent>,
+) {
+    let count = removals.0.len().min(BLOCKS_PER_TICK);
+
+    for removal in removals.0.drain(..count) {
+        let Ok(state) = blocks.set_block(removal.position, BlockState::AIR) else {
+            continue;
+        };
+
+        let location = removal.position.as_vec3() + Vec3::splat(0.5);
+
+        if state == BlockState::TNT {
+            // TNT caught in an explosion explodes sooner than usual
+            tnt_writer.write(PrimeTnt {
+                position: location - Vec3::new(0.0, 0.5, 0.0),
+                fuse: fastrand::u16(TNT_FUSE / 8..TNT_FUSE / 8 * 3),
+                igniter: removal.igniter,
+            });
+            continue;
+        }
+
+        if state.is_air() || fastrand::f32() >= removal.drop_chance {
+            continue;
+        }
+
+        let item = state.to_kind().to_item_kind();
+        if item == ItemKind::Air {
+            continue;
+        }
+
+        drop_writer.write(ItemDropEvent {
+            item: ItemStack::new(item, 1, None),
+            location,
+        });
+    }
+}
+
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExplosionSettings>();
+        app.init_resource::<PendingRemovals>();
+        app.add_event::<Explosion>();
+        app.add_event::<PrimeTnt>();
+
+        // Destroyed blocks drop as items
+        if !app.is_plugin_added::<DroppedItemPlugin>() {
+            app.add_plugins(DroppedItemPlugin);
+        }
+
+        app.add_systems(
+            FixedUpdate,
+            (
+                ignite_tnt,
+                prime_tnt,
+                show_fuses,
+                move_tnt,
+                prime_creepers,
+                tick_fuses,
+                explode,
+                remove_blocks,
+            )
+                .chain()
+                .after(ingress::decode::play)
+                .before(CombatSet::Modify),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_scales_with_impact() {
+        assert!((explosion_damage(0.0, 8.0) - 1.0).abs() < f32::EPSILON);
+        // Standing at the center of a TNT explosion
+        assert!((explosion_damage(1.0, TNT_POWER * 2.0) - 57.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn explosions_destroy_nearby_blocks() {
+        let explosion = Explosion::new(Vec3::new(0.5, 64.0, 0.5), TNT_POWER);
+        let ground = |position: IVec3| {
+            Some(if position.y < 64 {
+                BlockState::STONE
+            } else {
+                BlockState::AIR
+            })
+        };
+
+        let destroyed = destroyed_blocks(&explosion, ground, blast_resistance);
+
+        assert!(destroyed.contains(&IVec3::new(0, 63, 0)));
+        assert!(destroyed.iter().all(|block| block.y < 64));
+        assert!(
+            destroyed
+                .iter()
+                .all(|block| block.as_vec3().distance(explosion.position) < explosion.radius())
+        );
+    }
+
+    #[test]
+    fn explosions_stop_at_resistant_and_unloaded_blocks() {
+        let explosion = Explosion::new(Vec3::new(0.5, 64.5, 0.5), TNT_POWER);
+
+        let obsidian = |_: IVec3| Some(BlockState::OBSIDIAN);
+        assert!(destroyed_blocks(&explosion, obsidian, blast_resistance).is_empty());
+
+        let unloaded = |_: IVec3| None::<BlockState>;
+        assert!(destroyed_blocks(&explosion, unloaded, blast_resistance).is_empty());
+    }
+
+    #[test]
+    fn impact_falls_off_with_distance() {
+        let explosion = Explosion::new(Vec3::ZERO, TNT_POWER);
+        let radius = explosion.radius();
+
+        let near = explosion.impact(Vec3::new(1.0, 0.0, 0.0), 1.0).unwrap();
+        let far = explosion.impact(Vec3::new(6.0, 0.0, 0.0), 1.0).unwrap();
+
+        assert!(near > far);
+        assert!(explosion_damage(near, radius) > explosion_damage(far, radius));
+        assert_eq!(explosion.impact(Vec3::new(1.0, 0.0, 0.0), 0.0), Some(0.0));
+        assert_eq!(
+            explosion.impact(Vec3::new(radius + 1.0, 0.0, 0.0), 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn knockback_pushes_away_from_center() {
+        let explosion = Explosion::new(Vec3::ZERO, TNT_POWER);
+
+        let knockback = explosion.knockback(Vec3::new(0.0, 0.0, 2.0), 0.5);
+        assert_eq!(knockback, Vec3::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn explosions_without_power_do_nothing() {
+        for power in [0.0, -1.0, f32::NAN] {
+            let explosion = Explosion::new(Vec3::ZERO, power);
+
+            assert!(!explosion.has_power());
+            assert_eq!(explosion.impact(Vec3::ZERO, 1.0), None);
+        }
+    }
+
+    #[test]
+    fn resistance() {
+        assert!(blast_resistance(BlockState::OBSIDIAN) > TNT_POWER * 100.0);
+        assert!(blast_resistance(BlockState::BEDROCK).is_infinite());
+        assert!(blast_resistance(BlockState::STONE) > blast_resistance(BlockState::DIRT));
+        assert!(blast_resistance(BlockState::GLASS) < 1.0);
+    }
+
+    #[test]
+    fn resistance_of_blocks_named_like_other_materials() {
+        let resists = |state: BlockState, expected: f32| {
+            (blast_resistance(state) - expected).abs() < f32::EPSILON
+        };
+
+        assert!(resists(BlockState::GRASS_BLOCK, 0.6));
+        assert!(resists(BlockState::SANDSTONE, 0.8));
+        assert!(resists(BlockState::SMOOTH_SANDSTONE, 6.0));
+        assert!(resists(BlockState::GLOWSTONE, 0.3));
+        assert!(resists(BlockState::HAY_BLOCK, 0.5));
+        assert!(resists(BlockState::DIAMOND_BLOCK, 6.0));
+        assert!(resists(BlockState::END_STONE, 9.0));
+    }
+}
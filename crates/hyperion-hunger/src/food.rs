@@ -0,0 +1,141 @@
+use hyperion::simulation::effect::{Effect, EffectKind};
+use valence_protocol::ItemKind;
+
+/// The number of ticks it takes to eat most food
+pub const EAT_TICKS: i64 = 32;
+
+const RAW_CHICKEN_EFFECTS: &[(Effect, f32)] = &[(Effect::new(EffectKind::Hunger, 600), 0.3)];
+
+const ENCHANTED_GOLDEN_APPLE_EFFECTS: &[(Effect, f32)] = &[
+    (Effect::new(EffectKind::Regeneration, 400).amplifier(1), 1.0),
+    (Effect::new(EffectKind::Resistance, 6000), 1.0),
+    (Effect::new(EffectKind::FireResistance, 6000), 1.0),
+    (Effect::new(EffectKind::Absorption, 2400).amplifier(3), 1.0),
+];
+
+const GOLDEN_APPLE_EFFECTS: &[(Effect, f32)] = &[
+    (Effect::new(EffectKind::Regeneration, 100).amplifier(1), 1.0),
+    (Effect::new(EffectKind::Absorption, 2400), 1.0),
+];
+
+const POISONOUS_POTATO_EFFECTS: &[(Effect, f32)] = &[(Effect::new(EffectKind::Poison, 100), 0.6)];
+
+const PUFFERFISH_EFFECTS: &[(Effect, f32)] = &[
+    (Effect::new(EffectKind::Poison, 1200).amplifier(1), 1.0),
+    (Effect::new(EffectKind::Hunger, 300).amplifier(2), 1.0),
+    (Effect::new(EffectKind::Nausea, 300), 1.0),
+];
+
+const ROTTEN_FLESH_EFFECTS: &[(Effect, f32)] = &[(Effect::new(EffectKind::Hunger, 600), 0.8)];
+
+const SPIDER_EYE_EFFECTS: &[(Effect, f32)] = &[(Effect::new(EffectKind::Poison, 100), 1.0)];
+
+/// Properties of an item which can be eaten
+#[derive(Clone, Copy, Debug)]
+pub struct Food {
+    /// Food points restored
+    pub nutrition: u8,
+    /// Saturation restored is `nutrition * saturation_modifier * 2`
+    pub saturation_modifier: f32,
+    /// Whether the item can be eaten with a full food bar
+    pub always_edible: bool,
+    /// Ticks until the item is eaten
+    pub eat_ticks: i64,
+    /// Effects applied when eaten, with the chance of each being applied
+    pub effects: &'static [(Effect, f32)],
+    /// The item left behind after eating, such as a bowl
+    pub remainder: Option<ItemKind>,
+}
+
+impl Food {
+    const fn new(nutrition: u8, saturation_modifier: f32) -> Self {
+        Self {
+            nutrition,
+            saturation_modifier,
+            always_edible: false,
+            eat_ticks: EAT_TICKS,
+            effects: &[],
+            remainder: None,
+        }
+    }
+
+    const fn always_edible(mut self) -> Self {
+        self.always_edible = true;
+        self
+    }
+
+    const fn fast(mut self) -> Self {
+        self.eat_ticks = EAT_TICKS / 2;
+        self
+    }
+
+    const fn effects(mut self, effects: &'static [(Effect, f32)]) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    const fn remainder(mut self, remainder: ItemKind) -> Self {
+        self.remainder = Some(remainder);
+        self
+    }
+
+    /// Saturation restored when eaten
+    #[must_use]
+    pub fn saturation(&self) -> f32 {
+        f32::from(self.nutrition) * self.saturation_modifier * 2.0
+    }
+}
+
+/// Returns the food properties of an item, or [`None`] if it cannot be eaten
+#[must_use]
+pub const fn food(item: ItemKind) -> Option<Food> {
+    let food = match item {
+        ItemKind::Apple => Food::new(4, 0.3),
+        ItemKind::BakedPotato => Food::new(5, 0.6),
+        ItemKind::Beef => Food::new(3, 0.3),
+        ItemKind::Beetroot => Food::new(1, 0.6),
+        ItemKind::BeetrootSoup => Food::new(6, 0.6).remainder(ItemKind::Bowl),
+        ItemKind::Bread => Food::new(5, 0.6),
+        ItemKind::Carrot => Food::new(3, 0.6),
+        ItemKind::Chicken => Food::new(2, 0.3).effects(RAW_CHICKEN_EFFECTS),
+        ItemKind::ChorusFruit => Food::new(4, 0.3).always_edible(),
+        ItemKind::Cod => Food::new(2, 0.1),
+        ItemKind::CookedBeef => Food::new(8, 0.8),
+        ItemKind::CookedChicken => Food::new(6, 0.6),
+        ItemKind::CookedCod => Food::new(5, 0.6),
+        ItemKind::CookedMutton => Food::new(6, 0.8),
+        ItemKind::CookedPorkchop => Food::new(8, 0.8),
+        ItemKind::CookedRabbit => Food::new(5, 0.6),
+        ItemKind::CookedSalmon => Food::new(6, 0.8),
+        ItemKind::Cookie => Food::new(2, 0.1),
+        ItemKind::DriedKelp => Food::new(1, 0.3).fast(),
+        ItemKind::EnchantedGoldenApple => Food::new(4, 1.2)
+            .always_edible()
+            .effects(ENCHANTED_GOLDEN_APPLE_EFFECTS),
+        ItemKind::GlowBerries => Food::new(2, 0.1),
+        ItemKind::GoldenApple => Food::new(4, 1.2)
+            .always_edible()
+            .effects(GOLDEN_APPLE_EFFECTS),
+        ItemKind::GoldenCarrot => Food::new(6, 1.2),
+        ItemKind::HoneyBottle => Food::new(6, 0.1).remainder(ItemKind::GlassBottle),
+        ItemKind::MelonSlice => Food::new(2, 0.3),
+        ItemKind::MushroomStew => Food::new(6, 0.6).remainder(ItemKind::Bowl),
+        ItemKind::Mutton => Food::new(2, 0.3),
+        ItemKind::PoisonousPotato => Food::new(2, 0.3).effects(POISONOUS_POTATO_EFFECTS),
+        ItemKind::Porkchop => Food::new(3, 0.3),
+        ItemKind::Potato => Food::new(1, 0.3),
+        ItemKind::Pufferfish => Food::new(1, 0.1).effects(PUFFERFISH_EFFECTS),
+        ItemKind::PumpkinPie => Food::new(8, 0.3),
+        ItemKind::Rabbit => Food::new(3, 0.3),
+        ItemKind::RabbitStew => Food::new(10, 0.6).remainder(ItemKind::Bowl),
+        ItemKind::RottenFlesh => Food::new(4, 0.1).effects(ROTTEN_FLESH_EFFECTS),
+        ItemKind::Salmon => Food::new(2, 0.1),
+        ItemKind::SpiderEye => Food::new(2, 0.8).effects(SPIDER_EYE_EFFECTS),
+        ItemKind::SuspiciousStew => Food::new(6, 0.6).always_edible().remainder(ItemKind::Bowl),
+        ItemKind::SweetBerries => Food::new(2, 0.1),
+        ItemKind::TropicalFish => Food::new(1, 0.1),
+        _ => return None,
+    };
+
+    Some(food)
+}
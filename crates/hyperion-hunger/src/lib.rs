@@ -0,0 +1,630 @@
+//! Vanilla hunger: food level, saturation and exhaustion.
+//!
+//! Actions such as sprinting, jumping, attacking and taking damage add exhaustion. Every
+//! [`EXHAUSTION_PER_POINT`] exhaustion removes a point of saturation, or a point of food once
+//! saturation is depleted. A full food bar regenerates health, and an empty food bar deals
+//! [`DamageType::Starve`] damage.
+//!
+//! Health and hunger are sent to the client with [`HealthUpdateS2c`] whenever they change.
+
+use bevy::prelude::*;
+use hyperion::{
+    ingress,
+    net::{Compose, ConnectionId, agnostic},
+    simulation::{
+        Flight, MovementTracking, Position,
+        attribute::{self, Attributes},
+        effect::{ActiveEffects, EffectKind},
+        event::{ItemInteract, JumpEvent, ReleaseUseItem},
+        game_mode::GameMode,
+        metadata::living_entity::{HandStates, Health},
+        packet_state,
+    },
+};
+use hyperion_combat::{CombatSet, DamageEvent, DamageSource, DamageType, MeleeHit};
+use hyperion_inventory::PlayerInventory;
+use hyperion_utils::{EntityExt, Prev};
+use tracing::error;
+use valence_protocol::{
+    ItemKind, ItemStack, VarInt, ident,
+    packets::play::{EntityStatusS2c, HealthUpdateS2c},
+};
+
+pub mod food;
+
+/// The maximum food level
+pub const MAX_FOOD: u8 = 20;
+
+/// Exhaustion is capped at this value
+pub const MAX_EXHAUSTION: f32 = 40.0;
+
+/// The exhaustion which removes a point of saturation or food
+pub const EXHAUSTION_PER_POINT: f32 = 4.0;
+
+/// Exhaustion added per block sprinted
+pub const SPRINT_EXHAUSTION: f32 = 0.1;
+
+/// Exhaustion added per jump
+pub const JUMP_EXHAUSTION: f32 = 0.05;
+
+/// Exhaustion added per jump while sprinting
+pub const SPRINT_JUMP_EXHAUSTION: f32 = 0.2;
+
+/// Exhaustion added per melee attack
+pub const ATTACK_EXHAUSTION: f32 = 0.1;
+
+/// Exhaustion added when taking damage which armor protects against
+pub const DAMAGE_EXHAUSTION: f32 = 0.1;
+
+/// Exhaustion added for each point of health regenerated from food
+pub const REGENERATION_EXHAUSTION: f32 = 6.0;
+
+/// Exhaustion added per tick for each level of the hunger effect
+pub const HUNGER_EFFECT_EXHAUSTION: f32 = 0.005;
+
+/// Food level needed to regenerate health when saturation is depleted
+const REGENERATION_FOOD: u8 = 18;
+
+/// Ticks between regenerating health when both food and saturation are full
+const SATURATED_REGENERATION_TICKS: u8 = 10;
+
+/// Ticks between regenerating health or starving otherwise
+const REGENERATION_TICKS: u8 = 80;
+
+/// Configuration for hunger
+#[derive(Resource, Copy, Clone, Debug)]
+pub struct HungerSettings {
+    /// Whether a high food level regenerates health. Games with their own regeneration should
+    /// disable this.
+    pub natural_regeneration: bool,
+}
+
+impl Default for HungerSettings {
+    fn default() -> Self {
+        Self {
+            natural_regeneration: true,
+        }
+    }
+}
+
+/// What happened to an entity's health during a hunger tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HungerTick {
+    None,
+    /// The entity should be healed by this amount
+    Heal(f32),
+    /// The entity should take starvation damage
+    Starve,
+}
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Hunger {
+    food: u8,
+    saturation: f32,
+    exhaustion: f32,
+    timer: u8,
+}
+
+impl Default for Hunger {
+    fn default() -> Self {
+        Self {
+            food: MAX_FOOD,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            timer: 0,
+        }
+    }
+}
+
+impl Hunger {
+    #[must_use]
+    pub const fn food(&self) -> u8 {
+        self.food
+    }
+
+    #[must_use]
+    pub const fn saturation(&self) -> f32 {
+        self.saturation
+    }
+
+    #[must_use]
+    pub const fn exhaustion(&self) -> f32 {
+        self.exhaustion
+    }
+
+    #[must_use]
+    pub const fn is_full(&self) -> bool {
+        self.food >= MAX_FOOD
+    }
+
+    pub fn set_food(&mut self, food: u8) {
+        self.food = food.min(MAX_FOOD);
+        self.saturation = self.saturation.min(f32::from(self.food));
+    }
+
+    /// Sets the saturation, which cannot exceed the food level
+    pub fn set_saturation(&mut self, saturation: f32) {
+        self.saturation = saturation.clamp(0.0, f32::from(self.food));
+    }
+
+    pub fn add_exhaustion(&mut self, exhaustion: f32) {
+        self.exhaustion = (self.exhaustion + exhaustion).min(MAX_EXHAUSTION);
+    }
+
+    /// Restores food and saturation, such as when eating
+    pub fn eat(&mut self, nutrition: u8, saturation: f32) {
+        self.food = self.food.saturating_add(nutrition).min(MAX_FOOD);
+        self.saturation = (self.saturation + saturation).min(f32::from(self.food));
+    }
+
+    /// Advances hunger by a tick, returning what should happen to the entity's health
+    pub fn tick(&mut self, health: f32, max_health: f32, natural_regeneration: bool) -> HungerTick {
+        if self.exhaustion > EXHAUSTION_PER_POINT {
+            self.exhaustion -= EXHAUSTION_PER_POINT;
+
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.food = self.food.saturating_sub(1);
+            }
+        }
+
+        let can_regenerate = natural_regeneration && health > 0.0 && health < max_health;
+
+        if can_regenerate && self.saturation > 0.0 && self.food >= MAX_FOOD {
+            self.timer += 1;
+            if self.timer >= SATURATED_REGENERATION_TICKS {
+                let amount = self.saturation.min(REGENERATION_EXHAUSTION);
+                self.add_exhaustion(amount);
+                self.timer = 0;
+                return HungerTick::Heal(amount / REGENERATION_EXHAUSTION);
+            }
+        } else if can_regenerate && self.food >= REGENERATION_FOOD {
+            self.timer += 1;
+            if self.timer >= REGENERATION_TICKS {
+                self.add_exhaustion(REGENERATION_EXHAUSTION);
+                self.timer = 0;
+                return HungerTick::Heal(1.0);
+            }
+        } else if self.food == 0 {
+            self.timer += 1;
+            if self.timer >= REGENERATION_TICKS {
+                self.timer = 0;
+
+                // Starvation does not kill on normal difficulty
+                if health > 1.0 {
+                    return HungerTick::Starve;
+                }
+            }
+        } else {
+            self.timer = 0;
+        }
+
+        HungerTick::None
+    }
+}
+
+/// A player who is eating the item in their main hand
+#[derive(Component, Copy, Clone, Debug)]
+pub struct Eating {
+    item: ItemKind,
+    slot: u16,
+    started: i64,
+    duration: i64,
+}
+
+/// The last [`HealthUpdateS2c`] sent to the client
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq)]
+struct SentHealth(Option<(f32, u8, f32)>);
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    commands
+        .entity(trigger.target())
+        .insert((Hunger::default(), SentHealth::default()));
+}
+
+fn exhaust_on_movement(
+    query: Query<
+        '_,
+        '_,
+        (
+            &mut Hunger,
+            &Position,
+            &Prev<Position>,
+            &MovementTracking,
+            &Flight,
+            &GameMode,
+        ),
+    >,
+) {
+    for (mut hunger, position, prev_position, tracking, flight, game_mode) in query {
+        // Players who cannot be hurt do not get hungry
+        if game_mode.is_invulnerable() {
+            continue;
+        }
+
+        if tracking.sprinting && !flight.is_flying {
+            let distance = (**position - ***prev_position).with_y(0.0).length();
+            if distance > 0.0 {
+                hunger.add_exhaustion(distance * SPRINT_EXHAUSTION);
+            }
+        }
+    }
+}
+
+/// Applies the hunger and saturation status effects every tick
+fn apply_hunger_effects(query: Query<'_, '_, (&mut Hunger, &ActiveEffects, &GameMode)>) {
+    for (mut hunger, effects, game_mode) in query {
+        // Like other exhaustion, the hunger effect does not affect players who cannot be hurt
+        if let Some(amplifier) = effects.amplifier(EffectKind::Hunger)
+            && !game_mode.is_invulnerable()
+        {
+            hunger.add_exhaustion(HUNGER_EFFECT_EXHAUSTION * (f32::from(amplifier) + 1.0));
+        }
+
+        if let Some(amplifier) = effects.amplifier(EffectKind::Saturation) {
+            let level = amplifier.saturating_add(1);
+            hunger.eat(level, f32::from(level) * 2.0);
+        }
+    }
+}
+
+fn exhaust_on_jump(
+    mut events: EventReader<'_, '_, JumpEvent>,
+    mut query: Query<'_, '_, (&mut Hunger, &GameMode)>,
+) {
+    for event in events.read() {
+        let Ok((mut hunger, game_mode)) = query.get_mut(event.client) else {
+            continue;
+        };
+
+        if game_mode.is_invulnerable() {
+            continue;
+        }
+
+        hunger.add_exhaustion(if event.sprinting {
+            SPRINT_JUMP_EXHAUSTION
+        } else {
+            JUMP_EXHAUSTION
+        });
+    }
+}
+
+fn exhaust_on_damage(
+    mut events: EventReader<'_, '_, DamageEvent>,
+    mut query: Query<'_, '_, (&mut Hunger, &GameMode)>,
+) {
+    for event in events.read() {
+        if event.is_cancelled() {
+            continue;
+        }
+
+        if !event.source.kind.bypasses_armor()
+            && let Ok((mut hunger, game_mode)) = query.get_mut(event.target)
+            && !game_mode.is_invulnerable()
+        {
+            hunger.add_exhaustion(DAMAGE_EXHAUSTION);
+        }
+
+        // Entities caught in a sweep are part of the same attack
+        if matches!(event.melee, Some(hit) if hit != MeleeHit::Sweep)
+            && let Some(attacker) = event.source.attacker
+            && let Ok((mut hunger, game_mode)) = query.get_mut(attacker)
+            && !game_mode.is_invulnerable()
+        {
+            hunger.add_exhaustion(ATTACK_EXHAUSTION);
+        }
+    }
+}
+
+fn tick_hunger(
+    query: Query<'_, '_, (Entity, &mut Hunger, &mut Health, Option<&Attributes>)>,
+    settings: Res<'_, HungerSettings>,
+    mut writer: EventWriter<'_, DamageEvent>,
+) {
+    for (entity, mut hunger, mut health, attributes) in query {
+        let max_health = attribute::max_health(attributes);
+
+        match hunger.tick(**health, max_health, settings.natural_regeneration) {
+            HungerTick::None => {}
+            HungerTick::Heal(amount) => health.heal(amount, max_health),
+            HungerTick::Starve => {
+                writer.write(DamageEvent::new(
+                    entity,
+                    DamageSource::new(DamageType::Starve),
+                    1.0,
+                ));
+            }
+        }
+    }
+}
+
+fn start_eating(
+    mut events: EventReader<'_, '_, ItemInteract>,
+    query: Query<'_, '_, (&PlayerInventory, &Hunger)>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for event in events.read() {
+        let (inventory, hunger) = match query.get(event.entity) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to start eating: query failed: {e}");
+                continue;
+            }
+        };
+
+        let item = inventory.get_cursor().stack.item;
+        let Some(food) = food::food(item) else {
+            continue;
+        };
+
+        if hunger.is_full() && !food.always_edible {
+            continue;
+        }
+
+        commands.entity(event.entity).insert((
+            Eating {
+                item,
+                slot: inventory.get_cursor_index(),
+                started: compose.global().tick,
+                duration: food.eat_ticks,
+            },
+            HandStates::new(1),
+        ));
+    }
+}
+
+fn stop_eating(
+    mut events: EventReader<'_, '_, ReleaseUseItem>,
+    query: Query<'_, '_, (), With<Eating>>,
+    mut commands: Commands<'_, '_>,
+) {
+    for event in events.read() {
+        if !query.contains(event.from) {
+            continue;
+        }
+
+        commands
+            .entity(event.from)
+            .remove::<Eating>()
+            .insert(HandStates::new(0));
+    }
+}
+
+fn tick_eating(
+    query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &Eating,
+            &mut Hunger,
+            &mut PlayerInventory,
+            &mut ActiveEffects,
+            &Position,
+            &ConnectionId,
+            &GameMode,
+        ),
+    >,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    let tick = compose.global().tick;
+
+    for (
+        entity,
+        eating,
+        mut hunger,
+        mut inventory,
+        mut effects,
+        position,
+        &connection,
+        game_mode,
+    ) in query
+    {
+        // Switching items cancels eating
+        if inventory.get_cursor_index() != eating.slot
+            || inventory.get_cursor().stack.item != eating.item
+        {
+            commands
+                .entity(entity)
+                .remove::<Eating>()
+                .insert(HandStates::new(0));
+            continue;
+        }
+
+        let Some(food) = food::food(eating.item) else {
+            continue;
+        };
+
+        let elapsed = tick - eating.started;
+        let chunk = position.to_chunk();
+
+        let eat_sound = if eating.item == ItemKind::HoneyBottle {
+            ident!("minecraft:entity.generic.drink")
+        } else {
+            ident!("minecraft:entity.generic.eat")
+        };
+
+        if elapsed < eating.duration {
+            if elapsed >= 7 && elapsed % 4 == 0 {
+                let sound = agnostic::sound(eat_sound, **position)
+                    .volume(fastrand::f32().mul_add(0.5, 0.5))
+                    .pitch((fastrand::f32() - fastrand::f32()).mul_add(0.2, 1.0))
+                    .build();
+                compose.broadcast_local(&sound, chunk).send().unwrap();
+            }
+            continue;
+        }
+
+        hunger.eat(food.nutrition, food.saturation());
+
+        for &(effect, chance) in food.effects {
+            if fastrand::f32() < chance {
+                effects.add(effect);
+            }
+        }
+
+        // Food is not used up in creative
+        if !game_mode.is_creative() {
+            let slot = match inventory.get_mut(eating.slot) {
+                Ok(slot) => slot,
+                Err(e) => {
+                    error!("failed to finish eating: {e}");
+                    continue;
+                }
+            };
+
+            slot.stack.count -= 1;
+            if slot.stack.count <= 0 {
+                slot.stack = ItemStack::EMPTY;
+            }
+
+            if let Some(remainder) = food.remainder {
+                let remainder = ItemStack::new(remainder, 1, None);
+                if slot.stack.is_empty() {
+                    slot.stack = remainder;
+                } else {
+                    inventory.try_add_item(remainder);
+                }
+            }
+        }
+
+        // Tells the client that it has finished using the item
+        let pkt_finish = EntityStatusS2c {
+            entity_id: entity.minecraft_id(),
+            entity_status: 9,
+        };
+        compose.unicast(&pkt_finish, connection).unwrap();
+
+        let sound = agnostic::sound(ident!("minecraft:entity.player.burp"), **position)
+            .volume(0.5)
+            .pitch(fastrand::f32().mul_add(0.1, 0.9))
+            .build();
+        compose.broadcast_local(&sound, chunk).send().unwrap();
+
+        commands
+            .entity(entity)
+            .remove::<Eating>()
+            .insert(HandStates::new(0));
+    }
+}
+
+fn sync_health(
+    query: Query<'_, '_, (&Health, &Hunger, &ConnectionId, &mut SentHealth)>,
+    compose: Res<'_, Compose>,
+) {
+    for (health, hunger, &connection, mut sent) in query {
+        let current = Some((**health, hunger.food(), hunger.saturation()));
+        if sent.0 == current {
+            continue;
+        }
+
+        sent.0 = current;
+
+        let pkt = HealthUpdateS2c {
+            health: **health,
+            food: VarInt(i32::from(hunger.food())),
+            food_saturation: hunger.saturation(),
+        };
+
+        compose.unicast(&pkt, connection).unwrap();
+    }
+}
+
+pub struct HungerPlugin;
+
+impl Plugin for HungerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HungerSettings>();
+
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (
+                (
+                    exhaust_on_movement,
+                    exhaust_on_jump,
+                    apply_hunger_effects,
+                    tick_hunger,
+                )
+                    .chain()
+                    .after(ingress::decode::play)
+                    .before(CombatSet::Modify),
+                exhaust_on_damage.after(CombatSet::Apply),
+                (start_eating, stop_eating, tick_eating)
+                    .chain()
+                    .after(ingress::decode::play),
+            ),
+        );
+        app.add_systems(FixedPostUpdate, sync_health);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exhaustion_drains_saturation_before_food() {
+        let mut hunger = Hunger::default();
+        hunger.add_exhaustion(EXHAUSTION_PER_POINT + 0.1);
+        hunger.tick(20.0, 20.0, true);
+
+        assert_eq!(hunger.food(), MAX_FOOD);
+        assert!((hunger.saturation() - 4.0).abs() < f32::EPSILON);
+
+        hunger.set_saturation(0.0);
+        hunger.add_exhaustion(EXHAUSTION_PER_POINT);
+        hunger.tick(20.0, 20.0, true);
+
+        assert_eq!(hunger.food(), MAX_FOOD - 1);
+    }
+
+    #[test]
+    fn saturation_is_capped_by_food() {
+        let mut hunger = Hunger::default();
+        hunger.set_food(2);
+        hunger.set_saturation(0.0);
+        hunger.eat(4, 100.0);
+
+        assert_eq!(hunger.food(), 6);
+        assert!((hunger.saturation() - 6.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn regeneration_requires_food() {
+        let mut hunger = Hunger::default();
+        let heals = (0..SATURATED_REGENERATION_TICKS)
+            .filter(|_| matches!(hunger.tick(10.0, 20.0, true), HungerTick::Heal(_)))
+            .count();
+        assert_eq!(heals, 1);
+
+        let mut hunger = Hunger::default();
+        hunger.set_food(REGENERATION_FOOD - 1);
+        let heals = (0..REGENERATION_TICKS)
+            .filter(|_| matches!(hunger.tick(10.0, 20.0, true), HungerTick::Heal(_)))
+            .count();
+        assert_eq!(heals, 0);
+    }
+
+    #[test]
+    fn starvation() {
+        let mut hunger = Hunger::default();
+        hunger.set_food(0);
+
+        let ticks: Vec<_> = (0..REGENERATION_TICKS)
+            .map(|_| hunger.tick(10.0, 20.0, true))
+            .collect();
+        assert_eq!(ticks.last(), Some(&HungerTick::Starve));
+
+        let ticks: Vec<_> = (0..REGENERATION_TICKS)
+            .map(|_| hunger.tick(1.0, 20.0, true))
+            .collect();
+        assert!(ticks.iter().all(|tick| *tick == HungerTick::None));
+    }
+}
@@ -0,0 +1,337 @@
+//! Dropped items.
+//!
+//! Writing an [`ItemDropEvent`] spawns the stack as an item entity on the ground. Dropped items
+//! fall, are picked up by players who touch them once [`PICKUP_DELAY`] has passed, and despawn
+//! after [`ITEM_LIFETIME`] ticks.
+
+use bevy::prelude::*;
+use hyperion::{
+    net::{Compose, DataBundle},
+    simulation::{
+        EntitySize, Pitch, Position, Uuid, Velocity, Yaw, aabb, blocks::Blocks,
+        entity_kind::EntityKind, event::ItemDropEvent, game_mode::GameMode,
+        metadata::living_entity::Health, packet_state,
+    },
+};
+use hyperion_inventory::PlayerInventory;
+use hyperion_utils::EntityExt;
+use valence_protocol::{
+    ByteAngle, Encode, ItemStack, RawBytes, VarInt,
+    packets::play::{
+        EntitiesDestroyS2c, EntityPositionS2c, EntitySpawnS2c, EntityTrackerUpdateS2c,
+        ItemPickupAnimationS2c, MoveRelativeS2c,
+    },
+};
+
+/// Ticks before a dropped item can be picked up
+pub const PICKUP_DELAY: u16 = 10;
+
+/// Dropped items despawn after this many ticks
+pub const ITEM_LIFETIME: u16 = 6000;
+
+/// The metadata index of the stack shown by an item entity
+const ITEM_INDEX: u8 = 8;
+
+/// The metadata type of an item stack
+const SLOT_TYPE: i32 = 7;
+
+/// An item entity lying on the ground
+#[derive(Component, Clone, Debug)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    age: u16,
+    pickup_delay: u16,
+}
+
+impl DroppedItem {
+    #[must_use]
+    pub const fn new(stack: ItemStack) -> Self {
+        Self {
+            stack,
+            age: 0,
+            pickup_delay: PICKUP_DELAY,
+        }
+    }
+}
+
+/// Metadata packet showing `stack` as the item of the item entity `entity`
+fn item_metadata(entity: Entity, stack: &ItemStack) -> EntityTrackerUpdateS2c<'static> {
+    let mut tracked_values = vec![ITEM_INDEX];
+    VarInt(SLOT_TYPE).encode(&mut tracked_values).unwrap();
+    stack.encode(&mut tracked_values).unwrap();
+    tracked_values.push(0xff);
+
+    EntityTrackerUpdateS2c {
+        entity_id: VarInt(entity.minecraft_id()),
+        tracked_values: RawBytes(tracked_values.into()),
+    }
+}
+
+fn destroy_item(compose: &Compose, commands: &mut Commands<'_, '_>, item: Entity, position: Vec3) {
+    let pkt = EntitiesDestroyS2c {
+        entity_ids: vec![VarInt(item.minecraft_id())].into(),
+    };
+    compose
+        .broadcast_local(&pkt, Position::from(position).to_chunk())
+        .send()
+        .unwrap();
+
+    commands.entity(item).despawn();
+}
+
+fn spawn_dropped_items(
+    mut events: EventReader<'_, '_, ItemDropEvent>,
+    mut commands: Commands<'_, '_>,
+) {
+    for event in events.read() {
+        if event.item.is_empty() {
+            continue;
+        }
+
+        let velocity = Vec3::new(
+            fastrand::f32() * 0.2 - 0.1,
+            0.2,
+            fastrand::f32() * 0.2 - 0.1,
+        );
+
+        commands.spawn((
+            Uuid::new_v4(),
+            EntityKind::Item,
+            Position::from(event.location),
+            Velocity(velocity),
+            Pitch::new(0.0),
+            Yaw::new(fastrand::f32() * 360.0),
+            DroppedItem::new(event.item.clone()),
+        ));
+    }
+}
+
+fn send_dropped_items(
+    items: Query<
+        '_,
+        '_,
+        (Entity, &DroppedItem, &Uuid, &Position, &Velocity, &Yaw),
+        Added<DroppedItem>,
+    >,
+    compose: Res<'_, Compose>,
+) {
+    for (entity, item, uuid, position, &velocity, yaw) in &items {
+        let mut bundle = DataBundle::new(&compose);
+
+        let pkt = EntitySpawnS2c {
+            entity_id: VarInt(entity.minecraft_id()),
+            object_uuid: uuid.0,
+            kind: VarInt(EntityKind::Item as i32),
+            position: position.as_dvec3(),
+            pitch: ByteAngle(0),
+            yaw: ByteAngle::from_degrees(**yaw),
+            head_yaw: ByteAngle(0),
+            data: VarInt::default(),
+            velocity: velocity.to_packet_units(),
+        };
+        bundle.add_packet(&pkt).unwrap();
+        bundle
+            .add_packet(&item_metadata(entity, &item.stack))
+            .unwrap();
+
+        bundle.broadcast_local(position.to_chunk()).unwrap();
+    }
+}
+
+fn tick_dropped_items(
+    mut items: Query<'_, '_, (Entity, &mut DroppedItem, &mut Position, &mut Velocity)>,
+    blocks: Res<'_, Blocks>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, mut item, mut position, mut velocity) in &mut items {
+        item.age += 1;
+        item.pickup_delay = item.pickup_delay.saturating_sub(1);
+
+        if item.age >= ITEM_LIFETIME {
+            destroy_item(&compose, &mut commands, entity, **position);
+            continue;
+        }
+
+        let previous = **position;
+
+        velocity.0.y -= 0.04;
+        **position += velocity.0;
+
+        let mut on_ground = false;
+        // Check slightly below the item so that items resting on a block stay on the ground
+        if let Some(ground) = blocks.ground_height(**position - Vec3::new(0.0, 0.001, 0.0))
+            && position.y < ground
+        {
+            position.y = ground;
+            velocity.0.y = 0.0;
+            on_ground = true;
+        }
+
+        let friction = if on_ground { 0.98 * 0.6 } else { 0.98 };
+        velocity.0 *= Vec3::new(friction, 0.98, friction);
+
+        let delta = **position - previous;
+        if (delta * 4096.0).abs().max_element() < 1.0 {
+            continue;
+        }
+
+        let entity_id = VarInt(entity.minecraft_id());
+        let chunk = position.to_chunk();
+
+        if delta.abs().max_element() >= 8.0 {
+            let pkt = EntityPositionS2c {
+                entity_id,
+                position: position.as_dvec3(),
+                yaw: ByteAngle(0),
+                pitch: ByteAngle(0),
+                on_ground,
+            };
+            compose.broadcast_local(&pkt, chunk).send().unwrap();
+        } else {
+            let pkt = MoveRelativeS2c {
+                entity_id,
+                #[expect(clippy::cast_possible_truncation)]
+                delta: (delta * 4096.0).to_array().map(|x| x as i16),
+                on_ground,
+            };
+            compose.broadcast_local(&pkt, chunk).send().unwrap();
+        }
+    }
+}
+
+fn pick_up_items(
+    mut players: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &Position,
+            &EntitySize,
+            &GameMode,
+            &Health,
+            &mut PlayerInventory,
+        ),
+        With<packet_state::Play>,
+    >,
+    mut items: Query<'_, '_, (Entity, &mut DroppedItem, &Position)>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (player, position, size, game_mode, health, mut inventory) in &mut players {
+        if game_mode.is_spectator() || health.is_dead() {
+            continue;
+        }
+
+        // Players pick up items within a block horizontally and half a block vertically
+        let reach = aabb(**position - Vec3::new(0.0, 0.5, 0.0), EntitySize {
+            half_width: size.half_width + 1.0,
+            height: size.height + 1.0,
+        });
+
+        for (entity, mut item, item_position) in &mut items {
+            if item.pickup_delay > 0
+                || item.stack.is_empty()
+                || !reach.contains_point(**item_position)
+            {
+                continue;
+            }
+
+            let remaining = inventory.try_add_item(item.stack.clone()).remaining;
+            let remaining_count = remaining.as_ref().map_or(0, |stack| stack.count);
+            let picked_up = item.stack.count - remaining_count;
+
+            if picked_up == 0 {
+                continue;
+            }
+
+            // The client plays the pickup sound when it receives this packet
+            let pkt = ItemPickupAnimationS2c {
+                collected_entity_id: VarInt(entity.minecraft_id()),
+                collector_entity_id: VarInt(player.minecraft_id()),
+                pickup_item_count: VarInt(i32::from(picked_up)),
+            };
+            compose
+                .broadcast_local(&pkt, item_position.to_chunk())
+                .send()
+                .unwrap();
+
+            match remaining {
+                Some(stack) => {
+                    compose
+                        .broadcast_local(&item_metadata(entity, &stack), item_position.to_chunk())
+                        .send()
+                        .unwrap();
+                    item.stack = stack;
+                }
+                None => {
+                    item.stack = ItemStack::EMPTY;
+                    destroy_item(&compose, &mut commands, entity, **item_position);
+                }
+            }
+        }
+    }
+}
+
+pub struct DroppedItemPlugin;
+
+impl Plugin for DroppedItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                spawn_dropped_items,
+                (send_dropped_items, tick_dropped_items, pick_up_items).chain(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::ItemKind;
+
+    use super::*;
+
+    #[test]
+    fn drops_become_item_entities() {
+        let mut app = App::new();
+        app.add_event::<ItemDropEvent>();
+        app.add_systems(Update, spawn_dropped_items);
+
+        let location = Vec3::new(1.0, 64.0, -3.0);
+        let stacks = [
+            ItemStack::new(ItemKind::Diamond, 3, None),
+            ItemStack::EMPTY,
+            ItemStack::new(ItemKind::Stone, 64, None),
+        ];
+
+        // Like the drops of a player who died with these stacks in their inventory
+        for item in stacks {
+            app.world_mut().send_event(ItemDropEvent { item, location });
+        }
+
+        app.update();
+
+        let world = app.world_mut();
+        let mut dropped: Vec<_> = world
+            .query::<(&DroppedItem, &Position, &EntityKind)>()
+            .iter(world)
+            .map(|(item, position, &kind)| (item.stack.clone(), **position, kind))
+            .collect();
+        dropped.sort_by_key(|(stack, ..)| stack.count);
+
+        assert_eq!(dropped, [
+            (
+                ItemStack::new(ItemKind::Diamond, 3, None),
+                location,
+                EntityKind::Item
+            ),
+            (
+                ItemStack::new(ItemKind::Stone, 64, None),
+                location,
+                EntityKind::Item
+            ),
+        ]);
+    }
+}
@@ -6,6 +6,7 @@ use tracing::error;
 use valence_protocol::nbt;
 
 pub mod builder;
+pub mod dropped;
 
 pub struct ItemPlugin;
 
@@ -71,5 +72,10 @@ impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<NbtInteractEvent>();
         app.add_systems(FixedUpdate, handle_interact.after(ingress::decode::play));
+
+        // Other plugins which drop items may have added it already
+        if !app.is_plugin_added::<dropped::DroppedItemPlugin>() {
+            app.add_plugins(dropped::DroppedItemPlugin);
+        }
     }
 }
@@ -0,0 +1,245 @@
+use std::{borrow::Cow, collections::HashMap, fs, path::Path};
+
+use anyhow::Context;
+use bevy::prelude::*;
+use hyperion_text::Text;
+use tracing::warn;
+
+use crate::DEFAULT_LOCALE;
+
+/// A message which is translated for each player who receives it. The key is looked up in the
+/// [`Catalog`], and `{0}`, `{1}` and so on in the translation are replaced by the arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    key: Cow<'static, str>,
+    args: Vec<Text<'static>>,
+}
+
+impl Message {
+    #[must_use]
+    pub fn new(key: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            key: key.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Adds an argument. Arguments are inserted as text, so markup in them is not parsed.
+    #[must_use]
+    pub fn arg(mut self, arg: impl Into<Text<'static>>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+/// Creates a [`Message`] from a key and its arguments
+#[macro_export]
+macro_rules! message {
+    ($key:expr $(, $arg:expr)* $(,)?) => {
+        $crate::Message::new($key)$(.arg($arg))*
+    };
+}
+
+/// Translations for each locale, such as `en_us` or `de_de`. Translations are written in
+/// [markup](Text::from_markup).
+#[derive(Resource, Debug)]
+pub struct Catalog {
+    fallback: String,
+    translations: HashMap<String, HashMap<String, String>>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE)
+    }
+}
+
+impl Catalog {
+    /// Creates an empty catalog. Keys which are missing from a player's locale are looked up in
+    /// the `fallback` locale.
+    #[must_use]
+    pub fn new(fallback: impl Into<String>) -> Self {
+        Self {
+            fallback: fallback.into(),
+            translations: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn fallback(&self) -> &str {
+        &self.fallback
+    }
+
+    pub fn set_fallback(&mut self, fallback: impl Into<String>) {
+        self.fallback = fallback.into();
+    }
+
+    /// The locales which have at least one translation
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.translations.keys().map(String::as_str)
+    }
+
+    /// Adds a translation, replacing any existing translation of the key
+    pub fn insert(&mut self, locale: &str, key: impl Into<String>, translation: impl Into<String>) {
+        self.translations
+            .entry(locale.to_ascii_lowercase())
+            .or_default()
+            .insert(key.into(), translation.into());
+    }
+
+    /// Adds a translation unless the key is already translated. Plugins use this for their
+    /// built-in messages so that servers can replace them regardless of plugin order.
+    pub fn insert_default(&mut self, locale: &str, key: &str, translation: &str) {
+        self.translations
+            .entry(locale.to_ascii_lowercase())
+            .or_default()
+            .entry(key.to_string())
+            .or_insert_with(|| translation.to_string());
+    }
+
+    /// Adds the translations from a JSON object which maps keys to translations, in the same
+    /// format as the client's language files
+    pub fn load_json(&mut self, locale: &str, json: &str) -> anyhow::Result<()> {
+        let translations: HashMap<String, String> =
+            serde_json::from_str(json).with_context(|| format!("invalid {locale} translations"))?;
+
+        for (key, translation) in translations {
+            self.insert(locale, key, translation);
+        }
+
+        Ok(())
+    }
+
+    /// Loads every `<locale>.json` file in a directory with [`Catalog::load_json`]
+    pub fn load_dir(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let entries = fs::read_dir(path)
+            .with_context(|| format!("failed to read locale directory {}", path.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let json = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            self.load_json(locale, &json)?;
+        }
+
+        Ok(())
+    }
+
+    /// The translation of `key` in `locale`, or in the fallback locale if it is missing
+    #[must_use]
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        [locale, self.fallback.as_str()]
+            .into_iter()
+            .find_map(|locale| self.translations.get(locale)?.get(key))
+            .map(String::as_str)
+    }
+
+    /// Translates a message into `locale`. Messages without a translation show their key, like
+    /// missing translations on the client.
+    #[must_use]
+    pub fn render(&self, locale: &str, message: &Message) -> Text<'static> {
+        let translation = self.get(locale, &message.key).unwrap_or(&message.key);
+        let markup = fill_args(translation, &message.args);
+
+        match Text::from_markup(&markup) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("translation of {} in {locale} is invalid: {e}", message.key);
+                Text::from(markup)
+            }
+        }
+    }
+}
+
+/// Replaces `{0}`, `{1}` and so on with the arguments as markup. Placeholders without an argument
+/// are kept as written.
+fn fill_args(translation: &str, args: &[Text<'static>]) -> String {
+    let mut out = String::with_capacity(translation.len());
+    let mut rest = translation;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let arg = rest
+            .find('}')
+            .and_then(|end| Some((rest[1..end].parse::<usize>().ok()?, end)))
+            .and_then(|(index, end)| Some((args.get(index)?, end)));
+
+        match arg {
+            Some((arg, end)) => {
+                out.push_str(&arg.to_markup());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use hyperion_text::{Color, Text, TextContent};
+
+    use super::Catalog;
+
+    fn plain(text: &Text<'_>) -> String {
+        let mut out = match &text.content {
+            TextContent::Text { text } => text.to_string(),
+            _ => String::new(),
+        };
+        for child in &text.extra {
+            out.push_str(&plain(child));
+        }
+        out
+    }
+
+    #[test]
+    fn falls_back_to_default_locale() {
+        let mut catalog = Catalog::default();
+        catalog.insert("en_us", "greeting", "Hello");
+        catalog.insert("en_us", "farewell", "Bye");
+        catalog.insert("de_de", "greeting", "Hallo");
+
+        assert_eq!(catalog.get("de_de", "greeting"), Some("Hallo"));
+        assert_eq!(catalog.get("de_de", "farewell"), Some("Bye"));
+        assert_eq!(catalog.get("fr_fr", "greeting"), Some("Hello"));
+        assert_eq!(catalog.get("de_de", "missing"), None);
+    }
+
+    #[test]
+    fn renders_arguments_as_text() {
+        let mut catalog = Catalog::default();
+        catalog
+            .load_json("en_us", r#"{ "kill": "<red>{0}</red> killed {1} {2}" }"#)
+            .unwrap();
+
+        let text = catalog.render("en_us", &message!("kill", "Steve", "<bold>Alex"));
+
+        assert_eq!(plain(&text), "Steve killed <bold>Alex {2}");
+        assert_eq!(text.extra[0].color, Some(Color::RED));
+        assert_eq!(
+            plain(&catalog.render("en_us", &message!("missing"))),
+            "missing"
+        );
+    }
+}
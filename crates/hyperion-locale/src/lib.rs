@@ -0,0 +1,122 @@
+//! Server-side translation of messages into each player's language.
+//!
+//! Each player's locale is copied from the [`ClientSettings`] their client sends into their
+//! [`Locale`] component, which the [`Localizer`] reads. Messages are written as a [`Message`] key
+//! with arguments, and the [`Localizer`] renders them with the [`Catalog`] separately for each
+//! recipient, so one broadcast is shown in every player's language.
+
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use hyperion::{
+    net::{Compose, ConnectionId, agnostic},
+    simulation::{client_settings::ClientSettings, packet_state},
+};
+use hyperion_text::Text;
+use tracing::error;
+
+mod catalog;
+
+pub use catalog::{Catalog, Message};
+
+/// The locale used for players whose client has not sent its settings yet
+pub const DEFAULT_LOCALE: &str = "en_us";
+
+/// The language of a player's client, such as `en_us`
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct Locale(String);
+
+impl Locale {
+    #[must_use]
+    pub fn new(locale: &str) -> Self {
+        Self(locale.to_ascii_lowercase())
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Translates and sends [`Message`]s to players in their own locale
+#[derive(SystemParam)]
+pub struct Localizer<'w, 's> {
+    catalog: Res<'w, Catalog>,
+    compose: Res<'w, Compose>,
+    players:
+        Query<'w, 's, (&'static ConnectionId, Option<&'static Locale>), With<packet_state::Play>>,
+}
+
+impl Localizer<'_, '_> {
+    /// The locale of a player, or the fallback locale if it is not known yet
+    #[must_use]
+    pub fn locale(&self, player: Entity) -> &str {
+        self.players
+            .get(player)
+            .ok()
+            .and_then(|(_, locale)| locale)
+            .map_or(self.catalog.fallback(), Locale::as_str)
+    }
+
+    /// Translates a message for a player
+    #[must_use]
+    pub fn text(&self, player: Entity, message: &Message) -> Text<'static> {
+        self.catalog.render(self.locale(player), message)
+    }
+
+    /// Sends a message to a player in their locale
+    pub fn chat(&self, player: Entity, message: &Message) {
+        let (&connection_id, _) = match self.players.get(player) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to send translated chat: query failed: {e}");
+                return;
+            }
+        };
+
+        let chat = agnostic::chat_text(self.text(player, message));
+        if let Err(e) = self.compose.unicast(&chat, connection_id) {
+            error!("failed to send translated chat: {e}");
+        }
+    }
+
+    /// Sends a message to every player. The message is translated once for each locale.
+    pub fn broadcast_chat(&self, message: &Message) {
+        let mut rendered = HashMap::new();
+
+        for (&connection_id, locale) in &self.players {
+            let locale = locale.map_or(self.catalog.fallback(), Locale::as_str);
+            let chat = rendered
+                .entry(locale)
+                .or_insert_with(|| agnostic::chat_text(self.catalog.render(locale, message)));
+
+            if let Err(e) = self.compose.unicast(&*chat, connection_id) {
+                error!("failed to broadcast translated chat: {e}");
+            }
+        }
+    }
+}
+
+/// Copies the locale of changed [`ClientSettings`] into the player's [`Locale`]
+pub fn update_locale(
+    query: Query<'_, '_, (Entity, &ClientSettings, Option<&Locale>), Changed<ClientSettings>>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, settings, current) in &query {
+        let locale = Locale::new(&settings.locale);
+        if current == Some(&locale) {
+            continue;
+        }
+
+        commands.entity(entity).insert(locale);
+    }
+}
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Catalog>();
+        app.add_systems(FixedPostUpdate, update_locale);
+    }
+}
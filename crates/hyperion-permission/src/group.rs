@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::SystemTime,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{PlayerPermissions, node};
+
+/// The group every player is in, whether or not they were added to it
+pub const DEFAULT_GROUP: &str = "normal";
+
+/// A named set of permission nodes which players can be added to
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionGroup {
+    /// Groups with a higher priority are checked first when a player is in several groups
+    pub priority: i32,
+    /// Groups whose nodes are inherited. Nodes set in this group override inherited nodes.
+    pub parents: Vec<String>,
+    /// Permission nodes such as `command.fly` or `command.*` and whether they are granted
+    pub nodes: BTreeMap<String, bool>,
+}
+
+impl PermissionGroup {
+    #[must_use]
+    pub const fn new(priority: i32) -> Self {
+        Self {
+            priority,
+            parents: Vec::new(),
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_parent(mut self, parent: impl Into<String>) -> Self {
+        self.parents.push(parent.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_node(mut self, node: impl Into<String>, value: bool) -> Self {
+        self.nodes.insert(node.into(), value);
+        self
+    }
+}
+
+/// All permission groups by name. By default there are `normal`, `moderator` and `admin` groups,
+/// where each inherits from the previous one and `admin` is granted every node.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct PermissionGroups {
+    groups: BTreeMap<String, PermissionGroup>,
+    /// Nodes granted by plugins, by group. They are not stored, and nodes set on the group
+    /// override them.
+    defaults: BTreeMap<String, BTreeMap<String, bool>>,
+}
+
+impl Default for PermissionGroups {
+    fn default() -> Self {
+        let groups = BTreeMap::from([
+            (DEFAULT_GROUP.to_string(), PermissionGroup::new(0)),
+            (
+                "moderator".to_string(),
+                PermissionGroup::new(10).with_parent(DEFAULT_GROUP),
+            ),
+            (
+                "admin".to_string(),
+                PermissionGroup::new(20)
+                    .with_parent("moderator")
+                    .with_node("*", true),
+            ),
+        ]);
+
+        Self {
+            groups,
+            defaults: BTreeMap::new(),
+        }
+    }
+}
+
+impl PermissionGroups {
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&PermissionGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut PermissionGroup> {
+        self.groups.get_mut(name)
+    }
+
+    /// Adds a group, returning the group it replaced
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        group: PermissionGroup,
+    ) -> Option<PermissionGroup> {
+        self.groups.insert(name.into(), group)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PermissionGroup> {
+        self.groups.remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PermissionGroup)> {
+        self.groups
+            .iter()
+            .map(|(name, group)| (name.as_str(), group))
+    }
+
+    /// Grants a node to a group by default. Plugins use this for the permissions of their
+    /// commands so that servers can deny them by setting the node to `false` on the group.
+    /// Default nodes are not stored and only apply while the group exists.
+    pub fn grant_default(&mut self, group: &str, node: &str) {
+        self.defaults
+            .entry(group.to_string())
+            .or_default()
+            .entry(node.to_string())
+            .or_insert(true);
+    }
+
+    /// The nodes granted to a group by default which the group does not set itself
+    pub fn default_nodes<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (&'a str, bool)> {
+        let group = self.groups.get(name);
+        self.defaults
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter(move |(node, _)| group.is_none_or(|group| !group.nodes.contains_key(*node)))
+            .map(|(node, &value)| (node.as_str(), value))
+    }
+
+    /// Replaces the groups with those loaded from storage. The built-in groups are only kept if
+    /// no groups were stored yet, so groups which were deleted stay deleted.
+    pub fn load(&mut self, groups: impl IntoIterator<Item = (String, PermissionGroup)>) {
+        let stored: BTreeMap<_, _> = groups.into_iter().collect();
+        if !stored.is_empty() {
+            self.groups = stored;
+        }
+    }
+
+    /// Whether a player is granted a node. Nodes set on the player are checked first, then the
+    /// groups of the player from the highest to the lowest priority. Nodes which are not set
+    /// anywhere are denied.
+    #[must_use]
+    pub fn check(&self, player: Option<&PlayerPermissions>, node: &str) -> bool {
+        let now = SystemTime::now();
+
+        if let Some(player) = player
+            && let Some(value) = node::resolve(player.active_nodes(now), node)
+        {
+            return value;
+        }
+
+        let groups = player
+            .into_iter()
+            .flat_map(|player| player.active_groups(now))
+            .chain([DEFAULT_GROUP]);
+
+        let mut visited = HashSet::new();
+        self.by_priority(groups)
+            .into_iter()
+            .find_map(|name| self.resolve(name, node, &mut visited))
+            .unwrap_or(false)
+    }
+
+    /// The names of the existing groups among `names`, from the highest to the lowest priority
+    fn by_priority<'a>(&'a self, names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+        let mut groups: Vec<_> = names
+            .into_iter()
+            .filter_map(|name| self.groups.get_key_value(name))
+            .collect();
+
+        groups.sort_by_key(|(_, group)| std::cmp::Reverse(group.priority));
+        groups.into_iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    fn resolve<'a>(
+        &'a self,
+        name: &'a str,
+        node: &str,
+        visited: &mut HashSet<&'a str>,
+    ) -> Option<bool> {
+        // Parents may form a cycle
+        if !visited.insert(name) {
+            return None;
+        }
+
+        let group = self.groups.get(name)?;
+        let nodes = group
+            .nodes
+            .iter()
+            .map(|(node, &value)| (node.as_str(), value));
+
+        node::resolve(nodes, node)
+            .or_else(|| node::resolve(self.default_nodes(name), node))
+            .or_else(|| {
+                self.by_priority(group.parents.iter().map(String::as_str))
+                    .into_iter()
+                    .find_map(|parent| self.resolve(parent, node, visited))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{PermissionGroup, PermissionGroups};
+    use crate::PlayerPermissions;
+
+    #[test]
+    fn inherits_from_parents() {
+        let mut groups = PermissionGroups::default();
+        groups.grant_default("normal", "command.spawn");
+        groups.grant_default("moderator", "command.fly");
+
+        let mut moderator = PlayerPermissions::default();
+        moderator.add_group("moderator", None);
+        let mut admin = PlayerPermissions::default();
+        admin.add_group("admin", None);
+
+        assert!(groups.check(None, "command.spawn"));
+        assert!(!groups.check(None, "command.fly"));
+        assert!(groups.check(Some(&moderator), "command.spawn"));
+        assert!(groups.check(Some(&moderator), "command.fly"));
+        assert!(!groups.check(Some(&moderator), "command.gamemode"));
+        assert!(groups.check(Some(&admin), "command.gamemode"));
+    }
+
+    #[test]
+    fn higher_priority_and_player_nodes_win() {
+        let mut groups = PermissionGroups::default();
+        groups.insert("muted", PermissionGroup::new(30).with_node("chat.*", false));
+        groups.grant_default("normal", "chat.send");
+
+        let mut player = PlayerPermissions::default();
+        player.add_group("admin", None);
+        player.add_group("muted", None);
+
+        assert!(!groups.check(Some(&player), "chat.send"));
+        assert!(groups.check(Some(&player), "command.fly"));
+
+        player.set("chat.send", true, None);
+        assert!(groups.check(Some(&player), "chat.send"));
+
+        let expired = SystemTime::now() - Duration::from_secs(1);
+        player.set("command.fly", false, Some(expired));
+        assert!(groups.check(Some(&player), "command.fly"));
+    }
+
+    #[test]
+    fn parent_cycles_terminate() {
+        let mut groups = PermissionGroups::default();
+        groups.insert("a", PermissionGroup::new(1).with_parent("b"));
+        groups.insert("b", PermissionGroup::new(1).with_parent("a"));
+
+        let mut player = PlayerPermissions::default();
+        player.add_group("a", None);
+
+        assert!(!groups.check(Some(&player), "command.fly"));
+    }
+
+    #[test]
+    fn group_nodes_override_defaults() {
+        let mut groups = PermissionGroups::default();
+        groups.grant_default("normal", "command.spawn");
+        groups.grant_default("normal", "command.fly");
+        groups
+            .get_mut("normal")
+            .unwrap()
+            .nodes
+            .insert("command.fly".to_string(), false);
+
+        assert!(groups.check(None, "command.spawn"));
+        assert!(!groups.check(None, "command.fly"));
+        assert_eq!(groups.default_nodes("normal").collect::<Vec<_>>(), [(
+            "command.spawn",
+            true
+        )]);
+    }
+
+    #[test]
+    fn defaults_do_not_create_groups() {
+        let mut groups = PermissionGroups::default();
+        groups.grant_default("builder", "command.fill");
+        assert!(groups.get("builder").is_none());
+
+        groups.insert("builder", PermissionGroup::new(5));
+        let mut player = PlayerPermissions::default();
+        player.add_group("builder", None);
+        assert!(groups.check(Some(&player), "command.fill"));
+    }
+
+    #[test]
+    fn loading_keeps_deleted_groups_deleted() {
+        let mut groups = PermissionGroups::default();
+        groups.grant_default("moderator", "command.fly");
+        groups.load([("normal".to_string(), PermissionGroup::new(0))]);
+
+        assert!(groups.get("normal").is_some());
+        assert!(groups.get("moderator").is_none());
+        assert!(groups.get("admin").is_none());
+
+        let mut player = PlayerPermissions::default();
+        player.add_group("moderator", None);
+        assert!(!groups.check(Some(&player), "command.fly"));
+
+        let mut fresh = PermissionGroups::default();
+        fresh.load(Vec::new());
+        assert_eq!(fresh, PermissionGroups::default());
+    }
+}
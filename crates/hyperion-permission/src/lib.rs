@@ -1,61 +1,110 @@
+//! Permission nodes, groups and per-player grants.
+//!
+//! A permission node is a dotted name such as `command.fly`. Groups and players set nodes, or
+//! patterns such as `command.*`, to granted or denied, and the most specific pattern wins. Players
+//! are always in the [`DEFAULT_GROUP`] and may be added to other groups, temporarily or
+//! permanently. Check permissions with [`has_permission`] or the [`Permissions`] system param.
+
+mod group;
+pub mod node;
+mod player;
 mod storage;
 
-use bevy::{ecs::world::OnDespawn, prelude::*};
-use clap::ValueEnum;
+use std::time::{Duration, SystemTime};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+pub use group::{DEFAULT_GROUP, PermissionGroup, PermissionGroups};
 use hyperion::{
     net::{Compose, ConnectionId},
     simulation::{Uuid, command::get_command_packet},
     storage::LocalDb,
 };
-use num_derive::{FromPrimitive, ToPrimitive};
+pub use player::{Grant, PlayerPermissions};
 use storage::PermissionStorage;
 use tracing::error;
 
 pub struct PermissionPlugin;
 
-#[derive(
-    Default,
-    Component,
-    FromPrimitive,
-    ToPrimitive,
-    Copy,
-    Clone,
-    Debug,
-    PartialEq,
-    ValueEnum,
-    Eq
-)]
-#[repr(C)]
-pub enum Group {
-    Banned,
-    #[default]
-    Normal,
-    Moderator,
-    Admin,
+/// Grants every permission node to an entity which is not a player, such as the console
+#[derive(Component, Copy, Clone, Debug, Default)]
+pub struct AllPermissions;
+
+/// The time `minutes` from now, or `None` if it is too far away to represent. This is used for
+/// grants and other things which expire after a number of minutes.
+#[must_use]
+pub fn expires_in(minutes: u64) -> Option<SystemTime> {
+    let seconds = minutes.checked_mul(60)?;
+    SystemTime::now().checked_add(Duration::from_secs(seconds))
+}
+
+/// Whether an entity is granted a permission node. Entities without [`PlayerPermissions`] only
+/// have the nodes of the [`DEFAULT_GROUP`].
+#[must_use]
+pub fn has_permission(world: &World, entity: Entity, node: &str) -> bool {
+    if world.get::<AllPermissions>(entity).is_some() {
+        return true;
+    }
+
+    let Some(groups) = world.get_resource::<PermissionGroups>() else {
+        return false;
+    };
+
+    groups.check(world.get::<PlayerPermissions>(entity), node)
 }
 
-// todo:
+/// Checks permission nodes from systems
+#[derive(SystemParam)]
+pub struct Permissions<'w, 's> {
+    groups: Res<'w, PermissionGroups>,
+    players: Query<'w, 's, (Option<&'static PlayerPermissions>, Has<AllPermissions>)>,
+}
+
+impl Permissions<'_, '_> {
+    /// Whether an entity is granted a permission node
+    #[must_use]
+    pub fn has(&self, entity: Entity, node: &str) -> bool {
+        match self.players.get(entity) {
+            Ok((_, true)) => true,
+            Ok((permissions, false)) => self.groups.check(permissions, node),
+            Err(_) => self.groups.check(None, node),
+        }
+    }
+
+    #[must_use]
+    pub fn groups(&self) -> &PermissionGroups {
+        &self.groups
+    }
+}
 
 fn load_permissions(
     trigger: Trigger<'_, OnAdd, Uuid>,
     query: Query<'_, '_, &Uuid, With<ConnectionId>>,
-    permissions: Res<'_, PermissionStorage>,
+    storage: Res<'_, PermissionStorage>,
     mut commands: Commands<'_, '_>,
 ) {
     let Ok(uuid) = query.get(trigger.target()) else {
         return;
     };
 
-    let group = permissions.get(**uuid);
-    commands.entity(trigger.target()).insert(group);
+    let permissions = match storage.player(**uuid) {
+        Ok(permissions) => permissions,
+        Err(e) => {
+            error!("failed to load permissions: {e}");
+            PlayerPermissions::default()
+        }
+    };
+
+    commands.entity(trigger.target()).insert(permissions);
 }
 
+/// Stores the permissions of a player whenever they are inserted, so changes are not lost if the
+/// server stops
 fn store_permissions(
-    trigger: Trigger<'_, OnDespawn, Group>,
-    query: Query<'_, '_, (&Uuid, &Group)>,
-    permissions: Res<'_, PermissionStorage>,
+    trigger: Trigger<'_, OnInsert, PlayerPermissions>,
+    query: Query<'_, '_, (&Uuid, &PlayerPermissions)>,
+    storage: Res<'_, PermissionStorage>,
 ) {
-    let (uuid, group) = match query.get(trigger.target()) {
+    let (uuid, permissions) = match query.get(trigger.target()) {
         Ok(data) => data,
         Err(e) => {
             error!("failed to store permissions: query failed: {e}");
@@ -63,11 +112,13 @@ fn store_permissions(
         }
     };
 
-    permissions.set(**uuid, *group).unwrap();
+    if let Err(e) = storage.set_player(**uuid, permissions) {
+        error!("failed to store permissions: {e}");
+    }
 }
 
 fn initialize_commands(
-    trigger: Trigger<'_, OnInsert, Group>,
+    trigger: Trigger<'_, OnInsert, PlayerPermissions>,
     query: Query<'_, '_, &ConnectionId>,
     compose: Res<'_, Compose>,
     world: &World,
@@ -80,12 +131,75 @@ fn initialize_commands(
     compose.unicast(&cmd_pkt, connection_id).unwrap();
 }
 
+/// Removes expired grants. The component is inserted again so the command tree is updated.
+fn remove_expired(
+    query: Query<'_, '_, (Entity, &PlayerPermissions)>,
+    mut commands: Commands<'_, '_>,
+) {
+    let now = SystemTime::now();
+
+    for (entity, permissions) in &query {
+        if !permissions.has_expired(now) {
+            continue;
+        }
+
+        let mut permissions = permissions.clone();
+        permissions.remove_expired(now);
+        commands.entity(entity).insert(permissions);
+    }
+}
+
+fn store_groups(groups: Res<'_, PermissionGroups>, storage: Res<'_, PermissionStorage>) {
+    if let Err(e) = storage.set_groups(&groups) {
+        error!("failed to store permission groups: {e}");
+    }
+}
+
+/// Sends every player their command tree again after the groups changed
+fn update_commands(
+    query: Query<'_, '_, (Entity, &ConnectionId), With<PlayerPermissions>>,
+    compose: Res<'_, Compose>,
+    world: &World,
+) {
+    for (entity, &connection_id) in &query {
+        let cmd_pkt = get_command_packet(world, Some(entity));
+        if let Err(e) = compose.unicast(&cmd_pkt, connection_id) {
+            error!("failed to update commands: {e}");
+        }
+    }
+}
+
 impl Plugin for PermissionPlugin {
     fn build(&self, app: &mut App) {
-        let storage = storage::PermissionStorage::new(app.world().resource::<LocalDb>()).unwrap();
+        let storage = PermissionStorage::new(app.world().resource::<LocalDb>()).unwrap();
+        let stored = storage.groups().unwrap();
+
+        app.world_mut()
+            .get_resource_or_init::<PermissionGroups>()
+            .load(stored);
+
         app.insert_resource(storage);
         app.add_observer(load_permissions);
         app.add_observer(store_permissions);
         app.add_observer(initialize_commands);
+        app.add_systems(
+            FixedUpdate,
+            (
+                remove_expired,
+                (store_groups, update_commands).run_if(resource_changed::<PermissionGroups>),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expires_in;
+
+    #[test]
+    fn expiries_too_far_away_are_rejected() {
+        assert!(expires_in(10).is_some());
+        assert_eq!(expires_in(u64::MAX), None);
+        assert_eq!(expires_in(u64::MAX / 60), None);
     }
 }
@@ -0,0 +1,59 @@
+//! Matching permission nodes such as `command.fly` against patterns such as `command.*`.
+
+/// How closely `pattern` matches `node`, or `None` if it does not match. More specific patterns
+/// have a higher value, so an exact match overrides `command.*`, which overrides `*`.
+#[must_use]
+pub fn specificity(pattern: &str, node: &str) -> Option<usize> {
+    if pattern == node {
+        return Some(usize::MAX);
+    }
+
+    if pattern == "*" {
+        return Some(0);
+    }
+
+    // `command.*` matches `command` and everything below it
+    let prefix = pattern.strip_suffix('*')?.strip_suffix('.')?;
+    let below = node
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'));
+
+    below.then_some(prefix.len() + 1)
+}
+
+/// The value of the most specific pattern matching `node`
+pub fn resolve<'a>(
+    patterns: impl IntoIterator<Item = (&'a str, bool)>,
+    node: &str,
+) -> Option<bool> {
+    patterns
+        .into_iter()
+        .filter_map(|(pattern, value)| Some((specificity(pattern, node)?, value)))
+        .max_by_key(|&(specificity, _)| specificity)
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, specificity};
+
+    #[test]
+    fn matches_wildcards() {
+        assert_eq!(specificity("command.fly", "command.fly"), Some(usize::MAX));
+        assert_eq!(specificity("*", "command.fly"), Some(0));
+        assert!(specificity("command.*", "command.fly").is_some());
+        assert!(specificity("command.*", "command").is_some());
+        assert_eq!(specificity("command.*", "commander.fly"), None);
+        assert_eq!(specificity("command.fly", "command.flying"), None);
+    }
+
+    #[test]
+    fn most_specific_wins() {
+        let patterns = [("*", true), ("command.*", false), ("command.fly", true)];
+
+        assert_eq!(resolve(patterns, "command.fly"), Some(true));
+        assert_eq!(resolve(patterns, "command.speed"), Some(false));
+        assert_eq!(resolve(patterns, "chat.staff"), Some(true));
+        assert_eq!(resolve([("command.*", true)], "chat.staff"), None);
+    }
+}
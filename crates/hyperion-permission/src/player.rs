@@ -0,0 +1,104 @@
+use std::{collections::BTreeMap, time::SystemTime};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A permission node set on a single player
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grant {
+    pub value: bool,
+    /// When the grant is removed, or `None` if it is permanent
+    pub expires: Option<SystemTime>,
+}
+
+impl Grant {
+    #[must_use]
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+/// The groups a player is in and the nodes set on them. Both can expire. Modify this component by
+/// inserting it again so that it is stored and the player's command tree is updated.
+#[derive(
+    Component,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize
+)]
+pub struct PlayerPermissions {
+    groups: BTreeMap<String, Option<SystemTime>>,
+    nodes: BTreeMap<String, Grant>,
+}
+
+impl PlayerPermissions {
+    /// The groups of the player and when their membership expires
+    pub fn groups(&self) -> impl Iterator<Item = (&str, Option<SystemTime>)> {
+        self.groups
+            .iter()
+            .map(|(name, &expires)| (name.as_str(), expires))
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (&str, Grant)> {
+        self.nodes
+            .iter()
+            .map(|(node, &grant)| (node.as_str(), grant))
+    }
+
+    /// The groups which have not expired
+    pub fn active_groups(&self, now: SystemTime) -> impl Iterator<Item = &str> {
+        self.groups
+            .iter()
+            .filter(move |(_, expires)| expires.is_none_or(|expires| expires > now))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The nodes which have not expired and whether they are granted
+    pub fn active_nodes(&self, now: SystemTime) -> impl Iterator<Item = (&str, bool)> {
+        self.nodes
+            .iter()
+            .filter(move |(_, grant)| grant.is_active(now))
+            .map(|(node, grant)| (node.as_str(), grant.value))
+    }
+
+    /// Adds the player to a group until `expires`, or permanently if it is `None`
+    pub fn add_group(&mut self, group: impl Into<String>, expires: Option<SystemTime>) {
+        self.groups.insert(group.into(), expires);
+    }
+
+    /// Removes the player from a group, returning whether they were in it
+    pub fn remove_group(&mut self, group: &str) -> bool {
+        self.groups.remove(group).is_some()
+    }
+
+    /// Sets a node until `expires`, or permanently if it is `None`
+    pub fn set(&mut self, node: impl Into<String>, value: bool, expires: Option<SystemTime>) {
+        self.nodes.insert(node.into(), Grant { value, expires });
+    }
+
+    /// Removes a node, returning whether it was set
+    pub fn unset(&mut self, node: &str) -> bool {
+        self.nodes.remove(node).is_some()
+    }
+
+    /// Whether any group membership or node has expired
+    #[must_use]
+    pub fn has_expired(&self, now: SystemTime) -> bool {
+        self.groups
+            .values()
+            .flatten()
+            .any(|&expires| expires <= now)
+            || self.nodes.values().any(|grant| !grant.is_active(now))
+    }
+
+    /// Removes the group memberships and nodes which have expired
+    pub fn remove_expired(&mut self, now: SystemTime) {
+        self.groups
+            .retain(|_, expires| expires.is_none_or(|expires| expires > now));
+        self.nodes.retain(|_, grant| grant.is_active(now));
+    }
+}
@@ -1,51 +1,90 @@
 use bevy::prelude::*;
 use heed::{Database, Env, byteorder::NativeEndian, types};
 use hyperion::storage::LocalDb;
-use num_traits::{FromPrimitive, ToPrimitive};
+use tracing::warn;
 
-use crate::Group;
+use crate::{PermissionGroup, PermissionGroups, PlayerPermissions};
 
+/// Permissions of players and the permission groups
 #[derive(Resource)]
 pub struct PermissionStorage {
     env: Env,
-    perms: Database<types::U128<NativeEndian>, types::U8>,
+    players: Database<types::U128<NativeEndian>, types::SerdeJson<PlayerPermissions>>,
+    groups: Database<types::Str, types::SerdeJson<PermissionGroup>>,
+    /// Groups stored by earlier versions as a number, which are migrated when a player joins
+    legacy: Option<Database<types::U128<NativeEndian>, types::U8>>,
 }
 
 impl PermissionStorage {
     pub fn new(db: &LocalDb) -> anyhow::Result<Self> {
-        // We open the default unnamed database
-        let perms = {
-            let mut wtxn = db.write_txn()?;
-            let db = db.create_database(&mut wtxn, Some("uuid-to-perms"))?;
-            wtxn.commit()?;
-            db
-        };
+        let mut wtxn = db.write_txn()?;
+        let players = db.create_database(&mut wtxn, Some("uuid-to-permissions"))?;
+        let groups = db.create_database(&mut wtxn, Some("permission-groups"))?;
+        let legacy = db.open_database(&wtxn, Some("uuid-to-perms"))?;
+        wtxn.commit()?;
 
         Ok(Self {
             env: (**db).clone(),
-            perms,
+            players,
+            groups,
+            legacy,
         })
     }
 
-    pub fn get(&self, uuid: uuid::Uuid) -> Group {
+    pub fn player(&self, uuid: uuid::Uuid) -> anyhow::Result<PlayerPermissions> {
         let uuid = uuid.as_u128();
-        let rtxn = self.env.read_txn().unwrap();
-        let Some(perms) = self.perms.get(&rtxn, &uuid).unwrap() else {
-            return Group::default();
-        };
+        let rtxn = self.env.read_txn()?;
+
+        if let Some(permissions) = self.players.get(&rtxn, &uuid)? {
+            return Ok(permissions);
+        }
 
-        let Some(group) = Group::from_u8(perms) else {
-            tracing::error!("invalid group {perms:?}");
-            return Group::default();
+        let mut permissions = PlayerPermissions::default();
+
+        let Some(legacy) = self.legacy else {
+            return Ok(permissions);
         };
 
-        group
+        match legacy.get(&rtxn, &uuid)? {
+            // Banned players are migrated to the ban list by hyperion-ban
+            None | Some(0 | 1) => {}
+            Some(2) => permissions.add_group("moderator", None),
+            Some(3) => permissions.add_group("admin", None),
+            Some(group) => warn!("player {uuid} has unsupported legacy group {group}"),
+        }
+
+        Ok(permissions)
     }
 
-    pub fn set(&self, uuid: uuid::Uuid, group: Group) -> anyhow::Result<()> {
-        let uuid = uuid.as_u128();
+    pub fn set_player(
+        &self,
+        uuid: uuid::Uuid,
+        permissions: &PlayerPermissions,
+    ) -> anyhow::Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        self.players.put(&mut wtxn, &uuid.as_u128(), permissions)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    pub fn groups(&self) -> anyhow::Result<Vec<(String, PermissionGroup)>> {
+        let rtxn = self.env.read_txn()?;
+        let groups = self
+            .groups
+            .iter(&rtxn)?
+            .map(|entry| entry.map(|(name, group)| (name.to_string(), group)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(groups)
+    }
+
+    /// Replaces the stored groups
+    pub fn set_groups(&self, groups: &PermissionGroups) -> anyhow::Result<()> {
         let mut wtxn = self.env.write_txn()?;
-        self.perms.put(&mut wtxn, &uuid, &group.to_u8().unwrap())?;
+        self.groups.clear(&mut wtxn)?;
+        for (name, group) in groups.iter() {
+            self.groups.put(&mut wtxn, name, group)?;
+        }
         wtxn.commit()?;
         Ok(())
     }
@@ -1,3 +1,5 @@
+use std::net::IpAddr;
+
 use rkyv::{Archive, Deserialize, Serialize, with::InlineAsBox};
 
 #[derive(Archive, Deserialize, Serialize, Clone, PartialEq, Debug)]
@@ -11,6 +13,8 @@ pub struct PlayerPackets<'a> {
 #[derive(Archive, Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
 pub struct PlayerConnect {
     pub stream: u64,
+    /// The IP address of the client, or `None` if it did not connect over IP
+    pub address: Option<IpAddr>,
 }
 
 #[derive(Archive, Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
@@ -16,7 +16,7 @@
     clippy::future_not_send
 )]
 
-use std::fmt::Debug;
+use std::{fmt::Debug, net::IpAddr};
 
 use anyhow::Context;
 use colored::Colorize;
@@ -191,13 +191,13 @@ async fn connect_to_server_and_run_proxy(
 
     loop {
         let mut shutdown_rx = shutdown_rx.clone();
-        let socket = tokio::select! {
+        let (socket, address) = tokio::select! {
             _ = shutdown_rx.wait_for(Option::is_some) => {
                 return Ok(())
             }
             Ok((socket, addr)) = listener.accept() => {
                 info!("New client connection from {addr:?}");
-                socket
+                (socket, addr.client_ip())
             }
         };
 
@@ -214,6 +214,7 @@ async fn connect_to_server_and_run_proxy(
             socket,
             shutdown_rx.clone(),
             player_id_on,
+            address,
             rx,
             server_sender.clone(),
             player_registry,
@@ -290,6 +291,24 @@ impl IngressHandler {
     }
 }
 
-trait HyperionListener: Listener<Io: Send, Addr: Debug> + 'static {}
+trait HyperionListener: Listener<Io: Send, Addr: Debug + ClientAddr> + 'static {}
 
-impl<L: Listener<Io: Send, Addr: Debug> + 'static> HyperionListener for L {}
+impl<L: Listener<Io: Send, Addr: Debug + ClientAddr> + 'static> HyperionListener for L {}
+
+/// The address a client connected from, which is sent to the server so it can enforce IP bans
+trait ClientAddr {
+    fn client_ip(&self) -> Option<IpAddr>;
+}
+
+impl ClientAddr for std::net::SocketAddr {
+    fn client_ip(&self) -> Option<IpAddr> {
+        Some(self.ip())
+    }
+}
+
+#[cfg(unix)]
+impl ClientAddr for tokio::net::unix::SocketAddr {
+    fn client_ip(&self) -> Option<IpAddr> {
+        None
+    }
+}
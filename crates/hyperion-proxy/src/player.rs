@@ -1,6 +1,6 @@
 //! Player connection handling and packet processing.
 
-use std::io::IoSlice;
+use std::{io::IoSlice, net::IpAddr};
 
 use hyperion_proto::{
     ChunkPosition, PlayerConnect, PlayerDisconnect, PlayerDisconnectReason, PlayerPackets,
@@ -37,6 +37,7 @@ pub fn initiate_player_connection(
     socket: impl tokio::io::AsyncRead + AsyncWrite + Send + 'static,
     mut shutdown_signal: tokio::sync::watch::Receiver<Option<ShutdownType>>,
     player_id: u64,
+    address: Option<IpAddr>,
     incoming_packet_receiver: kanal::AsyncReceiver<OrderedBytes>,
     server_sender: ServerSender,
     player_registry: &'static papaya::HashMap<u64, PlayerHandle, FxBuildHasher>,
@@ -61,6 +62,7 @@ pub fn initiate_player_connection(
             let connect = rkyv::to_bytes::<rkyv::rancor::Error>(
                 &ProxyToServerMessage::PlayerConnect(PlayerConnect {
                     stream: player_stream_id,
+                    address,
                 }),
             )
             .unwrap();
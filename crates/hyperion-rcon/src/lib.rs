@@ -0,0 +1,129 @@
+//! Remote administration with the [RCON protocol](https://minecraft.wiki/w/RCON).
+//!
+//! RCON clients log in with a password and then run registered commands as if they were typed in
+//! the console. Each command is run by its own entity with every permission, and its output is
+//! sent back to the client with formatting codes removed. Addresses which send too many wrong
+//! passwords are locked out for a minute. The server is started by triggering [`StartRcon`].
+
+use std::{net::SocketAddr, sync::Arc};
+
+use bevy::prelude::*;
+use hyperion::runtime::AsyncRuntime;
+use hyperion_command::{CommandSender, RunCommand, apply_deferred_changes, execute_commands};
+use hyperion_permission::AllPermissions;
+use tokio::net::TcpListener;
+use tracing::{debug, error, info};
+
+use crate::server::RconRequest;
+
+mod packet;
+mod server;
+
+/// Starts listening for RCON clients
+#[derive(Event, Clone, Debug)]
+pub struct StartRcon {
+    /// The address to listen on. RCON clients can run any command, so this should be a loopback
+    /// address unless the port is protected by a firewall.
+    pub address: SocketAddr,
+    /// The password clients log in with. RCON is not started if it is empty.
+    pub password: String,
+}
+
+#[derive(Resource)]
+struct RconRequests {
+    tx: flume::Sender<RconRequest>,
+    rx: flume::Receiver<RconRequest>,
+}
+
+/// A command run by an RCON client, whose output has not been sent yet
+#[derive(Component)]
+struct PendingRcon {
+    output: flume::Receiver<String>,
+    reply: flume::Sender<String>,
+}
+
+fn start_rcon(
+    trigger: Trigger<'_, StartRcon>,
+    runtime: Res<'_, AsyncRuntime>,
+    requests: Res<'_, RconRequests>,
+) {
+    let StartRcon { address, password } = trigger.event().clone();
+
+    if password.is_empty() {
+        error!("failed to start rcon: the password is empty");
+        return;
+    }
+
+    let requests = requests.tx.clone();
+
+    runtime.spawn(async move {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("failed to start rcon: could not listen on {address}: {e}");
+                return;
+            }
+        };
+
+        info!("rcon listening on {address}");
+
+        server::run(listener, Arc::from(password), requests).await;
+    });
+}
+
+fn run_rcon_commands(
+    requests: Res<'_, RconRequests>,
+    mut commands: Commands<'_, '_>,
+    mut runs: EventWriter<'_, RunCommand>,
+) {
+    for RconRequest { command, reply } in requests.rx.try_iter() {
+        let (sender, output) = CommandSender::channel();
+
+        let sender = commands
+            .spawn((Name::new("Rcon"), sender, AllPermissions, PendingRcon {
+                output,
+                reply,
+            }))
+            .id();
+
+        runs.write(RunCommand { sender, command });
+    }
+}
+
+fn reply_to_rcon_commands(
+    pending: Query<'_, '_, (Entity, &PendingRcon)>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, pending) in &pending {
+        let output: Vec<_> = pending
+            .output
+            .try_iter()
+            .map(|msg| hyperion_command::strip_formatting(&msg))
+            .collect();
+
+        if pending.reply.send(output.join("\n")).is_err() {
+            debug!("rcon client disconnected before the command finished");
+        }
+
+        commands.entity(entity).despawn();
+    }
+}
+
+pub struct RconPlugin;
+
+impl Plugin for RconPlugin {
+    fn build(&self, app: &mut App) {
+        let (tx, rx) = flume::unbounded();
+
+        app.insert_resource(RconRequests { tx, rx });
+        app.add_event::<StartRcon>();
+        app.add_observer(start_rcon);
+        app.add_systems(
+            FixedUpdate,
+            (
+                run_rcon_commands.before(execute_commands),
+                reply_to_rcon_commands.after(apply_deferred_changes),
+            ),
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Packets of the [RCON protocol](https://minecraft.wiki/w/RCON)
+
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Sent by the client to log in, with the password as the body
+pub const LOGIN: i32 = 3;
+/// Sent by the client to run the command in the body
+pub const COMMAND: i32 = 2;
+/// Sent by the server in response to [`LOGIN`]
+pub const LOGIN_RESPONSE: i32 = 2;
+/// Sent by the server in response to [`COMMAND`], with the output of the command as the body
+pub const COMMAND_RESPONSE: i32 = 0;
+
+/// The request id of a [`LOGIN_RESPONSE`] when the password is wrong
+pub const LOGIN_FAILED: i32 = -1;
+
+/// The id and type fields followed by the two null bytes which end the body
+const MIN_LENGTH: usize = 10;
+/// The longest request the Notchian server accepts
+const MAX_REQUEST_LENGTH: usize = 1460;
+/// Longer responses are split into several packets
+const MAX_RESPONSE_BODY: usize = 4096;
+
+/// RCON protocol error
+#[derive(Debug, Error)]
+pub enum PacketError {
+    #[error("packet length {0} is out of range")]
+    Length(i32),
+    #[error("packet body is not terminated by two null bytes")]
+    Unterminated,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
+    /// Chosen by the client and copied into the response
+    pub id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+impl Packet {
+    #[must_use]
+    pub const fn new(id: i32, kind: i32, body: String) -> Self {
+        Self { id, kind, body }
+    }
+
+    /// Decodes a packet without its length prefix
+    pub fn decode(bytes: &[u8]) -> Result<Self, PacketError> {
+        let Some((&id, rest)) = bytes.split_first_chunk::<4>() else {
+            return Err(PacketError::Unterminated);
+        };
+
+        let Some((&kind, rest)) = rest.split_first_chunk::<4>() else {
+            return Err(PacketError::Unterminated);
+        };
+
+        let Some(body) = rest.strip_suffix(&[0, 0]) else {
+            return Err(PacketError::Unterminated);
+        };
+
+        Ok(Self {
+            id: i32::from_le_bytes(id),
+            kind: i32::from_le_bytes(kind),
+            body: String::from_utf8_lossy(body).into_owned(),
+        })
+    }
+
+    /// Encodes the packet with its length prefix
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let length = MIN_LENGTH + self.body.len();
+        let mut bytes = Vec::with_capacity(4 + length);
+
+        bytes.extend_from_slice(&i32::try_from(length).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&self.kind.to_le_bytes());
+        bytes.extend_from_slice(self.body.as_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+
+        bytes
+    }
+
+    /// Reads a packet. Returns `None` if the connection was closed before the next packet.
+    pub async fn read(reader: &mut (impl AsyncRead + Unpin)) -> Result<Option<Self>, PacketError> {
+        let length = match reader.read_i32_le().await {
+            Ok(length) => length,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let Some(len) = usize::try_from(length)
+            .ok()
+            .filter(|len| (MIN_LENGTH..=MAX_REQUEST_LENGTH).contains(len))
+        else {
+            return Err(PacketError::Length(length));
+        };
+
+        let mut bytes = vec![0; len];
+        reader.read_exact(&mut bytes).await?;
+
+        Self::decode(&bytes).map(Some)
+    }
+
+    pub async fn write(&self, writer: &mut (impl AsyncWrite + Unpin)) -> Result<(), PacketError> {
+        writer.write_all(&self.encode()).await?;
+        Ok(())
+    }
+
+    /// The [`COMMAND_RESPONSE`] packets answering request `id`. Output longer than one packet is
+    /// split between characters.
+    #[must_use]
+    pub fn responses(id: i32, output: &str) -> Vec<Self> {
+        let mut responses = Vec::new();
+        let mut rest = output;
+
+        loop {
+            let mut end = rest.len().min(MAX_RESPONSE_BODY);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let (body, remaining) = rest.split_at(end);
+            responses.push(Self::new(id, COMMAND_RESPONSE, body.to_string()));
+            rest = remaining;
+
+            if rest.is_empty() {
+                return responses;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{COMMAND, COMMAND_RESPONSE, MAX_RESPONSE_BODY, Packet, PacketError};
+
+    #[test]
+    fn encode_and_decode() {
+        let packet = Packet::new(7, COMMAND, "list".to_string());
+        let bytes = packet.encode();
+
+        assert_eq!(bytes[..4], 14_i32.to_le_bytes());
+        assert_eq!(Packet::decode(&bytes[4..]).unwrap(), packet);
+        assert!(matches!(
+            Packet::decode(&bytes[4..bytes.len() - 1]),
+            Err(PacketError::Unterminated)
+        ));
+    }
+
+    #[test]
+    fn split_long_responses() {
+        let empty = Packet::new(1, COMMAND_RESPONSE, String::new());
+        assert_eq!(Packet::responses(1, ""), vec![empty]);
+
+        let output = "é".repeat(MAX_RESPONSE_BODY);
+        let responses = Packet::responses(1, &output);
+
+        assert_eq!(responses.len(), 2);
+        assert!(
+            responses
+                .iter()
+                .all(|packet| packet.body.len() <= MAX_RESPONSE_BODY)
+        );
+        assert_eq!(
+            responses
+                .iter()
+                .map(|packet| packet.body.as_str())
+                .collect::<String>(),
+            output
+        );
+    }
+}
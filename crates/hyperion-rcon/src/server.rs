@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use subtle::ConstantTimeEq;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::packet::{COMMAND, LOGIN, LOGIN_FAILED, LOGIN_RESPONSE, Packet, PacketError};
+
+/// How many wrong passwords an address can send before it is locked out
+const MAX_FAILED_LOGINS: u32 = 3;
+/// How long an address stays locked out after its last wrong password
+const LOCKOUT: Duration = Duration::from_secs(60);
+
+/// A command sent by an RCON client, answered with the output of the command
+pub struct RconRequest {
+    pub command: String,
+    pub reply: flume::Sender<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FailedLogins {
+    count: u32,
+    last: Instant,
+}
+
+/// Tracks wrong passwords by address so passwords cannot be guessed quickly
+#[derive(Default, Debug)]
+struct LoginLimiter {
+    failed: Mutex<HashMap<IpAddr, FailedLogins>>,
+}
+
+impl LoginLimiter {
+    fn is_locked_out(&self, ip: IpAddr, now: Instant) -> bool {
+        let failed = self.failed.lock().unwrap();
+        failed.get(&ip).is_some_and(|failed| {
+            failed.count >= MAX_FAILED_LOGINS && now.duration_since(failed.last) < LOCKOUT
+        })
+    }
+
+    fn record_failure(&self, ip: IpAddr, now: Instant) {
+        let mut failed = self.failed.lock().unwrap();
+
+        // Forget addresses which have not failed recently so the map does not grow forever
+        failed.retain(|_, failed| now.duration_since(failed.last) < LOCKOUT);
+
+        let failed = failed.entry(ip).or_insert(FailedLogins {
+            count: 0,
+            last: now,
+        });
+        failed.count = failed.count.saturating_add(1);
+        failed.last = now;
+    }
+
+    fn record_success(&self, ip: IpAddr) {
+        self.failed.lock().unwrap().remove(&ip);
+    }
+}
+
+/// Accepts RCON connections until the server stops
+pub async fn run(listener: TcpListener, password: Arc<str>, requests: flume::Sender<RconRequest>) {
+    let limiter = Arc::new(LoginLimiter::default());
+
+    loop {
+        let (stream, address) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("failed to accept rcon connection: {e}");
+                continue;
+            }
+        };
+
+        let password = password.clone();
+        let requests = requests.clone();
+        let limiter = limiter.clone();
+
+        tokio::spawn(async move {
+            match handle_connection(stream, address, &password, &requests, &limiter).await {
+                Ok(()) => debug!("rcon connection from {address} closed"),
+                Err(e) => warn!("rcon connection from {address} failed: {e}"),
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    address: SocketAddr,
+    password: &str,
+    requests: &flume::Sender<RconRequest>,
+    limiter: &LoginLimiter,
+) -> Result<(), PacketError> {
+    let mut logged_in = false;
+
+    while let Some(packet) = Packet::read(&mut stream).await? {
+        match packet.kind {
+            LOGIN => {
+                let ip = address.ip();
+
+                if limiter.is_locked_out(ip, Instant::now()) {
+                    warn!("rcon login from {address} refused: too many wrong passwords");
+                    let response = Packet::new(LOGIN_FAILED, LOGIN_RESPONSE, String::new());
+                    return response.write(&mut stream).await;
+                }
+
+                // Compared in constant time so the password cannot be guessed from timing
+                let correct: bool = packet.body.as_bytes().ct_eq(password.as_bytes()).into();
+
+                if !correct {
+                    warn!("rcon login from {address} failed: wrong password");
+                    limiter.record_failure(ip, Instant::now());
+                    let response = Packet::new(LOGIN_FAILED, LOGIN_RESPONSE, String::new());
+                    // The connection is closed so the password cannot be guessed quickly
+                    return response.write(&mut stream).await;
+                }
+
+                info!("rcon client {address} logged in");
+                limiter.record_success(ip);
+                logged_in = true;
+
+                let response = Packet::new(packet.id, LOGIN_RESPONSE, String::new());
+                response.write(&mut stream).await?;
+            }
+            COMMAND if logged_in => {
+                info!("rcon client {address} issued command: {}", packet.body);
+
+                let (reply, output) = flume::bounded(1);
+                let request = RconRequest {
+                    command: packet.body,
+                    reply,
+                };
+
+                // Either fails only if the server is stopping
+                if requests.send_async(request).await.is_err() {
+                    return Ok(());
+                }
+
+                let Ok(output) = output.recv_async().await else {
+                    return Ok(());
+                };
+
+                for response in Packet::responses(packet.id, &output) {
+                    response.write(&mut stream).await?;
+                }
+            }
+            COMMAND => {
+                let response = Packet::new(LOGIN_FAILED, LOGIN_RESPONSE, String::new());
+                response.write(&mut stream).await?;
+            }
+            kind => {
+                let output = format!("Unknown request {kind:x}");
+                for response in Packet::responses(packet.id, &output) {
+                    response.write(&mut stream).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::packet::COMMAND_RESPONSE;
+
+    const PASSWORD: &str = "hunter2";
+
+    async fn read(stream: &mut TcpStream) -> Packet {
+        Packet::read(stream).await.unwrap().unwrap()
+    }
+
+    #[test]
+    fn login_and_run_command() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let (requests, rx) = flume::unbounded::<RconRequest>();
+
+            tokio::spawn(run(listener, Arc::from(PASSWORD), requests));
+            tokio::spawn(async move {
+                while let Ok(RconRequest { command, reply }) = rx.recv_async().await {
+                    reply.send(format!("ran {command}")).unwrap();
+                }
+            });
+
+            let mut stream = TcpStream::connect(address).await.unwrap();
+
+            Packet::new(1, COMMAND, "list".to_string())
+                .write(&mut stream)
+                .await
+                .unwrap();
+            assert_eq!(
+                read(&mut stream).await,
+                Packet::new(LOGIN_FAILED, LOGIN_RESPONSE, String::new())
+            );
+
+            Packet::new(2, LOGIN, PASSWORD.to_string())
+                .write(&mut stream)
+                .await
+                .unwrap();
+            assert_eq!(
+                read(&mut stream).await,
+                Packet::new(2, LOGIN_RESPONSE, String::new())
+            );
+
+            Packet::new(3, COMMAND, "list".to_string())
+                .write(&mut stream)
+                .await
+                .unwrap();
+            assert_eq!(
+                read(&mut stream).await,
+                Packet::new(3, COMMAND_RESPONSE, "ran list".to_string())
+            );
+
+            let mut stream = TcpStream::connect(address).await.unwrap();
+
+            Packet::new(4, LOGIN, "wrong".to_string())
+                .write(&mut stream)
+                .await
+                .unwrap();
+            assert_eq!(
+                read(&mut stream).await,
+                Packet::new(LOGIN_FAILED, LOGIN_RESPONSE, String::new())
+            );
+            assert_eq!(Packet::read(&mut stream).await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn lock_out_after_failed_logins() {
+        let limiter = LoginLimiter::default();
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let start = Instant::now();
+
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert!(!limiter.is_locked_out(ip, start));
+            limiter.record_failure(ip, start);
+        }
+
+        assert!(limiter.is_locked_out(ip, start));
+        assert!(!limiter.is_locked_out(other, start));
+        assert!(!limiter.is_locked_out(ip, start + LOCKOUT));
+
+        limiter.record_success(ip);
+        assert!(!limiter.is_locked_out(ip, start));
+    }
+}
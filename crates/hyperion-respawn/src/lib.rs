@@ -1,79 +1,412 @@
+//! Player death and respawning.
+//!
+//! When a player dies from a [`DeathEvent`], they drop their inventory and experience (unless
+//! [`RespawnSettings`] keeps them), the death screen is shown, and a [`PlayerDeath`] event is
+//! sent. Once the player clicks respawn and [`RespawnSettings::delay`] has passed, they are
+//! respawned at the first valid location out of:
+//!
+//! 1. their own [`SpawnPoint`], such as a bed or respawn anchor
+//! 2. the [`SpawnPoint`] of the entity referenced by their [`TeamSpawnPoint`]
+//! 3. [`RespawnSettings::world_spawn`]
+//! 4. the position they died at
+//!
+//! and a [`PlayerRespawn`] event is sent.
+
 use bevy::prelude::*;
 use hyperion::{
     ingress,
-    net::{Compose, DataBundle},
+    net::{Compose, ConnectionId, DataBundle},
     simulation::{
+        EntitySize, Flight, FlyingSpeed, PendingTeleportation, Pitch, Position, Uuid, Xp, Yaw,
+        attribute::{self, Attributes},
+        blocks::Blocks,
+        can_reach_block,
+        effect::ActiveEffects,
+        event::ItemDropEvent,
+        game_mode::GameMode,
         metadata::{entity::Pose, living_entity::Health},
         packet::play,
-        Flight, FlyingSpeed, Pitch, Position, Uuid, Xp, Yaw,
+        packet_state,
     },
 };
+use hyperion_combat::{CombatSet, DamageSource, DamageType, DeathEvent};
+use hyperion_hunger::Hunger;
+use hyperion_inventory::PlayerInventory;
+use hyperion_locale::{Catalog, DEFAULT_LOCALE, Localizer, Message};
+use hyperion_utils::EntityExt;
 use tracing::error;
 use valence_protocol::{
+    BlockPos, ByteAngle, GlobalPos, VarInt,
+    block::{BlockKind, BlockState, PropName, PropValue},
     game_mode::OptGameMode,
     packets::play::{
-        player_abilities_s2c::{PlayerAbilitiesFlags, PlayerAbilitiesS2c},
-        ClientStatusC2s, ExperienceBarUpdateS2c, HealthUpdateS2c, PlayerRespawnS2c, PlayerSpawnS2c,
+        ClientStatusC2s, DeathMessageS2c, ExperienceBarUpdateS2c, GameStateChangeS2c,
+        HealthUpdateS2c, PlayerRespawnS2c, PlayerSpawnS2c, game_state_change_s2c::GameEventKind,
+        player_abilities_s2c::PlayerAbilitiesS2c,
     },
-    BlockPos, ByteAngle, GlobalPos, VarInt,
+    text::IntoText,
 };
-use valence_server::{ident, GameMode};
+use valence_server::ident;
 
-fn handle_respawn(
+/// The most experience a player can drop on death
+const MAX_DROPPED_XP: u16 = 100;
+
+/// Configuration for death and respawning
+#[derive(Resource, Clone, Debug)]
+pub struct RespawnSettings {
+    /// Where players without a spawn point respawn. If this is `None`, players respawn where they
+    /// died.
+    pub world_spawn: Option<Vec3>,
+    /// The minimum number of ticks between dying and respawning
+    pub delay: i64,
+    /// Whether players keep their inventory on death. Otherwise, an [`ItemDropEvent`] is sent for
+    /// every item.
+    pub keep_inventory: bool,
+    /// Whether players keep their experience on death
+    pub keep_xp: bool,
+    /// Whether the client shows the death screen. Otherwise, the client requests to respawn
+    /// immediately.
+    pub death_screen: bool,
+    /// The message shown on the death screen, given the cause of death and the name of the
+    /// attacker
+    pub death_message: fn(&DamageSource, Option<&str>) -> String,
+}
+
+impl Default for RespawnSettings {
+    fn default() -> Self {
+        Self {
+            world_spawn: None,
+            delay: 0,
+            keep_inventory: false,
+            keep_xp: false,
+            death_screen: true,
+            death_message: default_death_message,
+        }
+    }
+}
+
+/// The default death screen message
+#[must_use]
+pub fn default_death_message(source: &DamageSource, attacker: Option<&str>) -> String {
+    match (source.kind, attacker) {
+        (DamageType::Arrow, Some(attacker)) => format!("You were shot by {attacker}"),
+        (DamageType::PlayerAttack | DamageType::MobAttack, Some(attacker)) => {
+            format!("You were slain by {attacker}")
+        }
+        (DamageType::Fall, _) => "You hit the ground too hard".to_string(),
+        (DamageType::Starve, _) => "You starved to death".to_string(),
+        (DamageType::Drown, _) => "You drowned".to_string(),
+        (DamageType::Lava, _) => "You tried to swim in lava".to_string(),
+        (DamageType::InFire | DamageType::OnFire, _) => "You burned to death".to_string(),
+        (DamageType::Explosion | DamageType::PlayerExplosion, _) => "You blew up".to_string(),
+        (DamageType::OutOfWorld, _) => "You fell out of the world".to_string(),
+        (_, Some(attacker)) => format!("You were killed by {attacker}"),
+        (_, None) => "You died".to_string(),
+    }
+}
+
+/// Where an entity respawns
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum SpawnPoint {
+    /// A fixed position, such as one chosen by a game
+    Position(Vec3),
+    /// A bed or respawn anchor. The spawn point is lost once the block is destroyed or the respawn
+    /// anchor runs out of charges.
+    Block(IVec3),
+}
+
+impl SpawnPoint {
+    /// Returns where to respawn, or `None` if the spawn point is no longer valid. Respawning at a
+    /// respawn anchor uses up one of its charges.
+    fn use_point(self, blocks: &mut Blocks) -> Option<Vec3> {
+        let position = match self {
+            Self::Position(position) => return Some(position),
+            Self::Block(position) => position,
+        };
+
+        let state = blocks.get_block(position)?;
+
+        if is_bed(state.to_kind()) {
+            return Some(position.as_vec3() + Vec3::new(0.5, 0.5625, 0.5));
+        }
+
+        let charges = anchor_charges(state).filter(|&charges| charges > 0)?;
+        let value = PropValue::from_u16(charges - 1)?;
+
+        if let Err(e) = blocks.set_block(position, state.set(PropName::Charges, value)) {
+            error!("failed to use respawn anchor charge: {e}");
+        }
+
+        Some(position.as_vec3() + Vec3::new(0.5, 1.0, 0.5))
+    }
+}
+
+/// Respawns the player at the [`SpawnPoint`] of another entity, such as an entity representing
+/// their team. The player's own [`SpawnPoint`] takes priority.
+#[derive(Component, Clone, Copy, Debug, Deref)]
+pub struct TeamSpawnPoint(pub Entity);
+
+/// A player who is dead and has not respawned yet
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Dead {
+    since: i64,
+    position: Vec3,
+    respawn_requested: bool,
+}
+
+impl Dead {
+    /// The tick the player died on
+    #[must_use]
+    pub const fn since(&self) -> i64 {
+        self.since
+    }
+
+    /// Where the player died
+    #[must_use]
+    pub const fn position(&self) -> Vec3 {
+        self.position
+    }
+}
+
+/// Sent when a player dies
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDeath {
+    pub entity: Entity,
+    pub source: DamageSource,
+    pub position: Vec3,
+    /// The experience the player lost, which should be dropped as experience orbs
+    pub dropped_xp: u16,
+}
+
+/// Sent when a player respawns
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerRespawn {
+    pub entity: Entity,
+    pub position: Vec3,
+}
+
+fn is_bed(kind: BlockKind) -> bool {
+    kind.to_str().ends_with("_bed")
+}
+
+fn anchor_charges(state: BlockState) -> Option<u16> {
+    if state.to_kind() != BlockKind::RespawnAnchor {
+        return None;
+    }
+
+    state.get(PropName::Charges)?.to_u16()
+}
+
+/// The experience dropped by a player with the given level
+fn dropped_xp(level: u8) -> u16 {
+    (u16::from(level) * 7).min(MAX_DROPPED_XP)
+}
+
+fn handle_deaths(
+    mut events: EventReader<'_, '_, DeathEvent>,
+    mut query: Query<
+        '_,
+        '_,
+        (&ConnectionId, &Position, &mut PlayerInventory, &mut Xp),
+        With<packet_state::Play>,
+    >,
+    name_query: Query<'_, '_, &Name>,
+    settings: Res<'_, RespawnSettings>,
+    compose: Res<'_, Compose>,
+    mut drop_writer: EventWriter<'_, ItemDropEvent>,
+    mut death_writer: EventWriter<'_, PlayerDeath>,
+    mut commands: Commands<'_, '_>,
+) {
+    let tick = compose.global().tick;
+
+    for event in events.read() {
+        // Only players respawn
+        let Ok((&connection, &position, mut inventory, mut xp)) = query.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        if !settings.keep_inventory {
+            for (_, item) in inventory.items() {
+                drop_writer.write(ItemDropEvent {
+                    item: item.clone(),
+                    location: *position,
+                });
+            }
+            inventory.clear();
+        }
+
+        let dropped_xp = if settings.keep_xp {
+            0
+        } else {
+            let dropped = dropped_xp(xp.get_visual().level);
+            xp.amount = 0;
+            dropped
+        };
+
+        commands.entity(event.entity).insert(Dead {
+            since: tick,
+            position: *position,
+            respawn_requested: false,
+        });
+
+        let attacker = event
+            .source
+            .attacker
+            .filter(|&attacker| attacker != event.entity)
+            .and_then(|attacker| name_query.get(attacker).ok());
+        let message = (settings.death_message)(&event.source, attacker.map(Name::as_str));
+
+        let pkt_screen = GameStateChangeS2c {
+            kind: GameEventKind::EnableRespawnScreen,
+            value: if settings.death_screen { 0.0 } else { 1.0 },
+        };
+
+        // Even if the death screen is disabled, the client needs this to send ClientStatusC2s and
+        // initiate its respawn
+        let pkt_death = DeathMessageS2c {
+            player_id: VarInt(event.entity.minecraft_id()),
+            message: message.into_cow_text(),
+        };
+
+        let mut bundle = DataBundle::new(&compose);
+        bundle.add_packet(&pkt_screen).unwrap();
+        bundle.add_packet(&pkt_death).unwrap();
+        bundle.unicast(connection).unwrap();
+
+        death_writer.write(PlayerDeath {
+            entity: event.entity,
+            source: event.source,
+            position: *position,
+            dropped_xp,
+        });
+    }
+}
+
+fn request_respawn(
     mut packets: EventReader<'_, '_, play::ClientStatus>,
+    mut query: Query<'_, '_, (Option<&mut Dead>, &Health, &Position)>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for packet in packets.read() {
+        if !matches!(**packet, ClientStatusC2s::PerformRespawn) {
+            continue;
+        }
+
+        let (dead, health, position) = match query.get_mut(packet.sender()) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to request respawn: query failed: {e}");
+                continue;
+            }
+        };
+
+        match dead {
+            Some(mut dead) => dead.respawn_requested = true,
+            None if health.is_dead() => {
+                // The player died without a DeathEvent, such as by setting their health directly
+                commands.entity(packet.sender()).insert(Dead {
+                    since: compose.global().tick,
+                    position: **position,
+                    respawn_requested: true,
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+fn handle_respawn(
     mut query: Query<
         '_,
         '_,
         (
+            Entity,
+            &Dead,
+            &ConnectionId,
             &mut Health,
             &mut Pose,
-            &Uuid,
-            &Position,
-            &Yaw,
-            &Pitch,
-            &Xp,
-            &Flight,
-            &FlyingSpeed,
+            (&Uuid, &Yaw, &Pitch),
+            (&Xp, &Flight, &FlyingSpeed),
+            &mut ActiveEffects,
+            &mut Hunger,
+            &GameMode,
+            Option<&Attributes>,
+            Option<&TeamSpawnPoint>,
         ),
     >,
+    spawn_points: Query<'_, '_, &SpawnPoint>,
+    settings: Res<'_, RespawnSettings>,
+    mut blocks: ResMut<'_, Blocks>,
     compose: Res<'_, Compose>,
+    mut writer: EventWriter<'_, PlayerRespawn>,
+    mut commands: Commands<'_, '_>,
 ) {
-    for packet in packets.read() {
-        if !matches!(**packet, ClientStatusC2s::PerformRespawn) {
+    let tick = compose.global().tick;
+
+    for (
+        entity,
+        dead,
+        &connection,
+        mut health,
+        mut pose,
+        (uuid, yaw, pitch),
+        (xp, flight, flying_speed),
+        mut effects,
+        mut hunger,
+        &game_mode,
+        attributes,
+        team_spawn_point,
+    ) in &mut query
+    {
+        if !dead.respawn_requested || tick - dead.since < settings.delay {
             continue;
         }
 
-        let (mut health, mut pose, uuid, position, yaw, pitch, xp, flight, flying_speed) =
-            match query.get_mut(packet.sender()) {
-                Ok(data) => data,
-                Err(e) => {
-                    error!("failed to handle respawn: query failed: {e}");
-                    continue;
-                }
-            };
+        let mut spawn_point_lost = false;
+
+        let destination = spawn_points
+            .get(entity)
+            .ok()
+            .and_then(|point| {
+                let destination = point.use_point(&mut blocks);
+                spawn_point_lost = destination.is_none();
+                destination
+            })
+            .or_else(|| {
+                let point = spawn_points.get(**team_spawn_point?).ok()?;
+                point.use_point(&mut blocks)
+            })
+            .or(settings.world_spawn);
+
+        let position = destination.unwrap_or(dead.position);
 
-        health.heal(20.);
+        let max_health = attribute::max_health(attributes);
+        health.heal(max_health, max_health);
+        effects.clear();
+        *hunger = Hunger::default();
 
         *pose = Pose::Standing;
 
         let pkt_health = HealthUpdateS2c {
             health: health.abs(),
-            food: VarInt(20),
-            food_saturation: 5.0,
+            food: VarInt(i32::from(hunger.food())),
+            food_saturation: hunger.saturation(),
         };
 
         let pkt_respawn = PlayerRespawnS2c {
             dimension_type_name: ident!("minecraft:overworld"),
             dimension_name: ident!("minecraft:overworld"),
             hashed_seed: 0,
-            game_mode: GameMode::Survival,
+            game_mode: game_mode.into(),
             previous_game_mode: OptGameMode::default(),
             is_debug: false,
             is_flat: false,
             copy_metadata: false,
             last_death_location: Option::from(GlobalPos {
                 dimension_name: ident!("minecraft:overworld"),
-                position: BlockPos::from(position.as_dvec3()),
+                position: BlockPos::from(dead.position.as_dvec3()),
             }),
             portal_cooldown: VarInt::default(),
         };
@@ -85,9 +418,7 @@ fn handle_respawn(
         };
 
         let pkt_abilities = PlayerAbilitiesS2c {
-            flags: PlayerAbilitiesFlags::default()
-                .with_flying(flight.is_flying)
-                .with_allow_flying(flight.allow),
+            flags: game_mode.abilities(flight),
             flying_speed: flying_speed.speed,
             fov_modifier: 0.0,
         };
@@ -97,21 +428,90 @@ fn handle_respawn(
         bundle.add_packet(&pkt_respawn).unwrap();
         bundle.add_packet(&pkt_xp).unwrap();
         bundle.add_packet(&pkt_abilities).unwrap();
-        bundle.unicast(packet.connection_id()).unwrap();
-
-        let pkt_add_player = PlayerSpawnS2c {
-            entity_id: VarInt(packet.minecraft_id()),
-            player_uuid: uuid.0,
-            position: position.as_dvec3(),
-            yaw: ByteAngle::from_degrees(**yaw),
-            pitch: ByteAngle::from_degrees(**pitch),
+
+        if spawn_point_lost {
+            let pkt = GameStateChangeS2c {
+                kind: GameEventKind::NoRespawnBlockAvailable,
+                value: 0.0,
+            };
+            bundle.add_packet(&pkt).unwrap();
+            commands.entity(entity).remove::<SpawnPoint>();
+        }
+
+        bundle.unicast(connection).unwrap();
+
+        // Spectators are only visible to other spectators
+        if !game_mode.is_spectator() {
+            let pkt_add_player = PlayerSpawnS2c {
+                entity_id: VarInt(entity.minecraft_id()),
+                player_uuid: uuid.0,
+                position: position.as_dvec3(),
+                yaw: ByteAngle::from_degrees(**yaw),
+                pitch: ByteAngle::from_degrees(**pitch),
+            };
+
+            compose
+                .broadcast(&pkt_add_player)
+                .exclude(connection)
+                .send()
+                .unwrap();
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<Dead>();
+
+        if destination.is_some() {
+            entity_commands.insert(PendingTeleportation::new(position));
+        }
+
+        writer.write(PlayerRespawn { entity, position });
+    }
+}
+
+fn set_spawn_point(
+    mut packets: EventReader<'_, '_, play::PlayerInteractBlock>,
+    query: Query<'_, '_, (&GameMode, &Position, &EntitySize, Option<&SpawnPoint>)>,
+    blocks: Res<'_, Blocks>,
+    localizer: Localizer<'_, '_>,
+    mut commands: Commands<'_, '_>,
+) {
+    for packet in packets.read() {
+        let (game_mode, player_position, &size, current) = match query.get(packet.sender()) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("failed to set spawn point: query failed: {e}");
+                continue;
+            }
         };
 
-        compose
-            .broadcast(&pkt_add_player)
-            .exclude(packet.connection_id())
-            .send()
-            .unwrap();
+        if !game_mode.can_interact() {
+            continue;
+        }
+
+        let position = IVec3::new(packet.position.x, packet.position.y, packet.position.z);
+
+        // The position is sent by the client, which could otherwise pick any bed in the world
+        if !can_reach_block(**player_position, size, position) {
+            continue;
+        }
+
+        let Some(state) = blocks.get_block(position) else {
+            continue;
+        };
+
+        let valid = is_bed(state.to_kind()) || anchor_charges(state).is_some_and(|c| c > 0);
+        if !valid {
+            continue;
+        }
+
+        let point = SpawnPoint::Block(position);
+        if current == Some(&point) {
+            continue;
+        }
+
+        commands.entity(packet.sender()).insert(point);
+
+        localizer.chat(packet.sender(), &Message::new("respawn.point_set"));
     }
 }
 
@@ -119,6 +519,47 @@ pub struct RespawnPlugin;
 
 impl Plugin for RespawnPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedUpdate, handle_respawn.after(ingress::decode::play));
+        app.init_resource::<RespawnSettings>();
+        app.init_resource::<Catalog>();
+        app.world_mut().resource_mut::<Catalog>().insert_default(
+            DEFAULT_LOCALE,
+            "respawn.point_set",
+            "Respawn point set",
+        );
+        app.add_event::<PlayerDeath>();
+        app.add_event::<PlayerRespawn>();
+
+        app.add_systems(
+            FixedUpdate,
+            (
+                handle_deaths.after(CombatSet::Apply),
+                set_spawn_point.after(ingress::decode::play),
+                (request_respawn, handle_respawn)
+                    .chain()
+                    .after(ingress::decode::play),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_xp_is_capped() {
+        assert_eq!(dropped_xp(0), 0);
+        assert_eq!(dropped_xp(5), 35);
+        assert_eq!(dropped_xp(30), MAX_DROPPED_XP);
+    }
+
+    #[test]
+    fn death_message_names_attacker() {
+        let source = DamageSource::new(DamageType::Arrow);
+        assert_eq!(
+            default_death_message(&source, Some("Steve")),
+            "You were shot by Steve"
+        );
+        assert_eq!(default_death_message(&source, None), "You died");
     }
 }
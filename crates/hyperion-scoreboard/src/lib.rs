@@ -0,0 +1,743 @@
+//! Scoreboards: objectives shown in the sidebar, player list or below names, and teams.
+//!
+//! The [`Scoreboard`] resource is shown to every player. A [`PlayerScoreboard`] overrides scores
+//! for a single player, such as a sidebar line showing their own kills.
+//!
+//! Only the changes since the last tick are sent, so updating a sidebar does not make it flicker.
+//! Changes to [`Scoreboard`] are encoded once and broadcast to every player.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+};
+
+use bevy::prelude::*;
+use hyperion::{
+    net::{Compose, ConnectionId, DataBundle},
+    simulation::packet_state,
+};
+use tracing::error;
+use valence_bytes::CowUtf8Bytes;
+pub use valence_protocol::packets::play::{
+    scoreboard_objective_update_s2c::ObjectiveRenderType,
+    team_s2c::{CollisionRule, NameTagVisibility, TeamColor},
+};
+use valence_protocol::{
+    Text, VarInt,
+    packets::play::{
+        self, scoreboard_display_s2c::ScoreboardPosition,
+        scoreboard_objective_update_s2c::ObjectiveMode,
+        scoreboard_player_update_s2c::ScoreboardPlayerUpdateAction, team_s2c::Mode,
+    },
+};
+
+mod team;
+pub use team::Team;
+
+/// Where an objective is displayed
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DisplaySlot {
+    /// Next to player names in the player list
+    List,
+    Sidebar,
+    /// Below the name tags of players
+    BelowName,
+}
+
+impl DisplaySlot {
+    const ALL: [Self; 3] = [Self::List, Self::Sidebar, Self::BelowName];
+
+    const fn position(self) -> ScoreboardPosition {
+        match self {
+            Self::List => ScoreboardPosition::List,
+            Self::Sidebar => ScoreboardPosition::Sidebar,
+            Self::BelowName => ScoreboardPosition::BelowName,
+        }
+    }
+}
+
+/// A set of scores which can be displayed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Objective {
+    pub display_name: Text,
+    pub render_type: ObjectiveRenderType,
+}
+
+impl Objective {
+    #[must_use]
+    pub fn new(display_name: impl Into<Text>) -> Self {
+        Self {
+            display_name: display_name.into(),
+            render_type: ObjectiveRenderType::Integer,
+        }
+    }
+
+    /// Shows scores as hearts instead of numbers. This only affects the player list.
+    #[must_use]
+    pub const fn with_hearts(mut self) -> Self {
+        self.render_type = ObjectiveRenderType::Hearts;
+        self
+    }
+}
+
+/// Scores by objective and then by score holder
+type Scores = BTreeMap<String, BTreeMap<String, i32>>;
+
+fn get_score(scores: &Scores, objective: &str, holder: &str) -> Option<i32> {
+    scores.get(objective)?.get(holder).copied()
+}
+
+fn remove_score(scores: &mut Scores, objective: &str, holder: &str) -> Option<i32> {
+    let holders = scores.get_mut(objective)?;
+    let score = holders.remove(holder);
+
+    if holders.is_empty() {
+        scores.remove(objective);
+    }
+
+    score
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct State {
+    objectives: BTreeMap<String, Objective>,
+    displays: BTreeMap<DisplaySlot, String>,
+    scores: Scores,
+    teams: BTreeMap<String, Team>,
+}
+
+impl State {
+    /// The objective shown in `slot`, if it exists
+    fn display(&self, slot: DisplaySlot) -> Option<&str> {
+        self.displays
+            .get(&slot)
+            .filter(|objective| self.objectives.contains_key(*objective))
+            .map(String::as_str)
+    }
+
+    fn team_of(&self, member: &str) -> Option<&str> {
+        self.teams
+            .iter()
+            .find(|(_, team)| team.contains(member))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// A score which differs between two states
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct ScoreChange<'a> {
+    objective: &'a str,
+    holder: &'a str,
+    score: Option<i32>,
+}
+
+/// The scores which differ between `old` and `new`. Scores of removed objectives are skipped
+/// because removing an objective removes its scores on the client.
+fn score_changes<'a>(old: &'a State, new: &'a State) -> Vec<ScoreChange<'a>> {
+    let mut changes = Vec::new();
+
+    for objective in new.objectives.keys() {
+        let current = new.scores.get(objective);
+        let previous = old
+            .objectives
+            .contains_key(objective)
+            .then(|| old.scores.get(objective))
+            .flatten();
+
+        for (holder, &score) in current.into_iter().flatten() {
+            if previous.and_then(|previous| previous.get(holder)) != Some(&score) {
+                changes.push(ScoreChange {
+                    objective,
+                    holder,
+                    score: Some(score),
+                });
+            }
+        }
+
+        for holder in previous.into_iter().flat_map(BTreeMap::keys) {
+            if current.is_none_or(|current| !current.contains_key(holder)) {
+                changes.push(ScoreChange {
+                    objective,
+                    holder,
+                    score: None,
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+fn score_packet<'a>(
+    objective: &'a str,
+    holder: &'a str,
+    score: Option<i32>,
+) -> play::ScoreboardPlayerUpdateS2c<'a> {
+    let objective_name = CowUtf8Bytes::Borrowed(objective);
+
+    play::ScoreboardPlayerUpdateS2c {
+        entity_name: CowUtf8Bytes::Borrowed(holder),
+        action: match score {
+            Some(score) => ScoreboardPlayerUpdateAction::Update {
+                objective_name,
+                objective_score: VarInt(score),
+            },
+            None => ScoreboardPlayerUpdateAction::Remove { objective_name },
+        },
+    }
+}
+
+fn team_packet<'a>(name: &'a str, mode: Mode<'a>) -> play::TeamS2c<'a> {
+    play::TeamS2c {
+        team_name: CowUtf8Bytes::Borrowed(name),
+        mode,
+    }
+}
+
+fn member_names<'a>(members: impl IntoIterator<Item = &'a String>) -> Vec<CowUtf8Bytes<'a>> {
+    members
+        .into_iter()
+        .map(|member| CowUtf8Bytes::Borrowed(member.as_str()))
+        .collect()
+}
+
+/// The team packets which change the teams of a client from `old` to `new`. Members are removed
+/// from their old team before they are added to a new one, since the client rejects joining a
+/// team while still in another.
+fn team_changes<'a>(old: &'a State, new: &'a State) -> Vec<(&'a str, Mode<'a>)> {
+    let mut changes = Vec::new();
+
+    for (name, previous) in &old.teams {
+        let Some(team) = new.teams.get(name) else {
+            changes.push((name.as_str(), Mode::RemoveTeam));
+            continue;
+        };
+
+        let removed = member_names(previous.members.difference(&team.members));
+        if !removed.is_empty() {
+            changes.push((name.as_str(), Mode::RemoveEntities { entities: removed }));
+        }
+    }
+
+    for (name, team) in &new.teams {
+        let Some(previous) = old.teams.get(name) else {
+            changes.push((name.as_str(), Mode::CreateTeam {
+                team_display_name: Cow::Borrowed(&team.display_name),
+                friendly_flags: team.flags(),
+                name_tag_visibility: team.name_tag_visibility,
+                collision_rule: team.collision_rule,
+                team_color: team.color,
+                team_prefix: Cow::Borrowed(&team.prefix),
+                team_suffix: Cow::Borrowed(&team.suffix),
+                entities: member_names(&team.members),
+            }));
+            continue;
+        };
+
+        if !team.same_info(previous) {
+            changes.push((name.as_str(), Mode::UpdateTeamInfo {
+                team_display_name: Cow::Borrowed(&team.display_name),
+                friendly_flags: team.flags(),
+                name_tag_visibility: team.name_tag_visibility,
+                collision_rule: team.collision_rule,
+                team_color: team.color,
+                team_prefix: Cow::Borrowed(&team.prefix),
+                team_suffix: Cow::Borrowed(&team.suffix),
+            }));
+        }
+
+        let added = member_names(team.members.difference(&previous.members));
+        if !added.is_empty() {
+            changes.push((name.as_str(), Mode::AddEntities { entities: added }));
+        }
+    }
+
+    changes
+}
+
+/// Adds the packets which change the scoreboard of a client from `old` to `new`
+fn write_diff(old: &State, new: &State, bundle: &mut DataBundle<'_>) -> anyhow::Result<()> {
+    for name in old.objectives.keys() {
+        if !new.objectives.contains_key(name) {
+            bundle.add_packet(&play::ScoreboardObjectiveUpdateS2c {
+                objective_name: CowUtf8Bytes::Borrowed(name),
+                mode: ObjectiveMode::Remove,
+            })?;
+        }
+    }
+
+    for (name, objective) in &new.objectives {
+        let previous = old.objectives.get(name);
+        if previous == Some(objective) {
+            continue;
+        }
+
+        let objective_display_name = Cow::Borrowed(&objective.display_name);
+        let render_type = objective.render_type;
+
+        bundle.add_packet(&play::ScoreboardObjectiveUpdateS2c {
+            objective_name: CowUtf8Bytes::Borrowed(name),
+            mode: if previous.is_some() {
+                ObjectiveMode::Update {
+                    objective_display_name,
+                    render_type,
+                }
+            } else {
+                ObjectiveMode::Create {
+                    objective_display_name,
+                    render_type,
+                }
+            },
+        })?;
+    }
+
+    for slot in DisplaySlot::ALL {
+        let objective = new.display(slot);
+        let created = objective.is_some_and(|objective| !old.objectives.contains_key(objective));
+
+        // Displaying an empty name clears the slot
+        if objective != old.display(slot) || created {
+            bundle.add_packet(&play::ScoreboardDisplayS2c {
+                position: slot.position(),
+                score_name: CowUtf8Bytes::Borrowed(objective.unwrap_or_default()),
+            })?;
+        }
+    }
+
+    for change in score_changes(old, new) {
+        bundle.add_packet(&score_packet(change.objective, change.holder, change.score))?;
+    }
+
+    for (name, mode) in team_changes(old, new) {
+        bundle.add_packet(&team_packet(name, mode))?;
+    }
+
+    Ok(())
+}
+
+/// The scoreboard shown to every player
+#[derive(Resource, Default, Debug)]
+pub struct Scoreboard {
+    state: State,
+    /// The state last sent to clients
+    sent: State,
+    dirty: bool,
+    /// Objectives created by the last sync
+    created: HashSet<String>,
+    /// Scores changed by the last sync, by objective and holder
+    overwritten: HashSet<(String, String)>,
+}
+
+impl Scoreboard {
+    /// Adds an objective, replacing any objective with the same name
+    pub fn add_objective(&mut self, name: impl Into<String>, objective: Objective) {
+        self.state.objectives.insert(name.into(), objective);
+        self.dirty = true;
+    }
+
+    /// Removes an objective and all of its scores
+    pub fn remove_objective(&mut self, name: &str) -> Option<Objective> {
+        let objective = self.state.objectives.remove(name)?;
+        self.state.scores.remove(name);
+        self.state.displays.retain(|_, displayed| displayed != name);
+        self.dirty = true;
+        Some(objective)
+    }
+
+    #[must_use]
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.state.objectives.get(name)
+    }
+
+    pub fn objective_mut(&mut self, name: &str) -> Option<&mut Objective> {
+        self.dirty = true;
+        self.state.objectives.get_mut(name)
+    }
+
+    /// Shows an objective in `slot`, or clears the slot if `objective` is `None`
+    pub fn set_display(&mut self, slot: DisplaySlot, objective: Option<&str>) {
+        match objective {
+            Some(objective) => self.state.displays.insert(slot, objective.to_owned()),
+            None => self.state.displays.remove(&slot),
+        };
+        self.dirty = true;
+    }
+
+    #[must_use]
+    pub fn display(&self, slot: DisplaySlot) -> Option<&str> {
+        self.state.displays.get(&slot).map(String::as_str)
+    }
+
+    /// Sets the score of `holder`, which can be a player name or any other text such as a sidebar
+    /// line. Scores of objectives which do not exist yet are sent once the objective is added.
+    pub fn set_score(&mut self, objective: &str, holder: impl Into<String>, score: i32) {
+        self.state
+            .scores
+            .entry(objective.to_owned())
+            .or_default()
+            .insert(holder.into(), score);
+        self.dirty = true;
+    }
+
+    /// Adds `amount` to the score of `holder`, which starts at 0, and returns the new score
+    pub fn add_score(&mut self, objective: &str, holder: impl Into<String>, amount: i32) -> i32 {
+        let score = self
+            .state
+            .scores
+            .entry(objective.to_owned())
+            .or_default()
+            .entry(holder.into())
+            .or_default();
+        *score = score.saturating_add(amount);
+        self.dirty = true;
+        *score
+    }
+
+    #[must_use]
+    pub fn score(&self, objective: &str, holder: &str) -> Option<i32> {
+        get_score(&self.state.scores, objective, holder)
+    }
+
+    pub fn remove_score(&mut self, objective: &str, holder: &str) -> Option<i32> {
+        let score = remove_score(&mut self.state.scores, objective, holder)?;
+        self.dirty = true;
+        Some(score)
+    }
+
+    /// Removes every score of `holder`
+    pub fn reset_scores(&mut self, holder: &str) {
+        for holders in self.state.scores.values_mut() {
+            self.dirty |= holders.remove(holder).is_some();
+        }
+        self.state.scores.retain(|_, holders| !holders.is_empty());
+    }
+
+    /// Adds a team, replacing any team with the same name
+    pub fn add_team(&mut self, name: impl Into<String>, team: Team) {
+        let name = name.into();
+
+        // A name can only be in one team
+        for member in &team.members {
+            self.leave_team(member);
+        }
+
+        self.state.teams.insert(name, team);
+        self.dirty = true;
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Option<Team> {
+        let team = self.state.teams.remove(name)?;
+        self.dirty = true;
+        Some(team)
+    }
+
+    #[must_use]
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.state.teams.get(name)
+    }
+
+    pub fn team_mut(&mut self, name: &str) -> Option<&mut Team> {
+        self.dirty = true;
+        self.state.teams.get_mut(name)
+    }
+
+    /// The team `member` is in
+    #[must_use]
+    pub fn team_of(&self, member: &str) -> Option<&str> {
+        self.state.team_of(member)
+    }
+
+    /// Adds `member`, which is usually a player name, to a team and removes it from its previous
+    /// team. Returns `false` if the team does not exist.
+    pub fn join_team(&mut self, team: &str, member: impl Into<String>) -> bool {
+        if !self.state.teams.contains_key(team) {
+            return false;
+        }
+
+        let member = member.into();
+        self.leave_team(&member);
+
+        if let Some(team) = self.state.teams.get_mut(team) {
+            team.members.insert(member);
+        }
+
+        self.dirty = true;
+        true
+    }
+
+    /// Removes `member` from its team
+    pub fn leave_team(&mut self, member: &str) {
+        for team in self.state.teams.values_mut() {
+            self.dirty |= team.members.remove(member);
+        }
+    }
+}
+
+/// Scores shown to a single player which override the scores in [`Scoreboard`]
+#[derive(Component, Default, Debug)]
+pub struct PlayerScoreboard {
+    scores: Scores,
+    /// The scores last sent to the client
+    sent: Scores,
+    dirty: bool,
+    /// Whether the client has been sent the scoreboard
+    initialized: bool,
+}
+
+impl PlayerScoreboard {
+    pub fn set_score(&mut self, objective: &str, holder: impl Into<String>, score: i32) {
+        self.scores
+            .entry(objective.to_owned())
+            .or_default()
+            .insert(holder.into(), score);
+        self.dirty = true;
+    }
+
+    #[must_use]
+    pub fn score(&self, objective: &str, holder: &str) -> Option<i32> {
+        get_score(&self.scores, objective, holder)
+    }
+
+    /// Removes the score override, showing the score in [`Scoreboard`] instead
+    pub fn remove_score(&mut self, objective: &str, holder: &str) -> Option<i32> {
+        let score = remove_score(&mut self.scores, objective, holder)?;
+        self.dirty = true;
+        Some(score)
+    }
+
+    /// Removes every score override
+    pub fn clear(&mut self) {
+        self.dirty |= !self.scores.is_empty();
+        self.scores.clear();
+    }
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    commands
+        .entity(trigger.target())
+        .insert(PlayerScoreboard::default());
+}
+
+fn sync_scoreboard(mut scoreboard: ResMut<'_, Scoreboard>, compose: Res<'_, Compose>) {
+    let scoreboard = &mut *scoreboard;
+
+    scoreboard.created.clear();
+    scoreboard.overwritten.clear();
+
+    if !scoreboard.dirty {
+        return;
+    }
+
+    scoreboard.dirty = false;
+
+    if scoreboard.state == scoreboard.sent {
+        return;
+    }
+
+    let mut bundle = DataBundle::new(&compose);
+    if let Err(e) = write_diff(&scoreboard.sent, &scoreboard.state, &mut bundle) {
+        error!("failed to sync scoreboard: {e}");
+        return;
+    }
+    bundle.broadcast().unwrap();
+
+    scoreboard.created.extend(
+        scoreboard
+            .state
+            .objectives
+            .keys()
+            .filter(|name| !scoreboard.sent.objectives.contains_key(*name))
+            .cloned(),
+    );
+    scoreboard.overwritten.extend(
+        score_changes(&scoreboard.sent, &scoreboard.state)
+            .into_iter()
+            .map(|change| (change.objective.to_owned(), change.holder.to_owned())),
+    );
+
+    scoreboard.sent = scoreboard.state.clone();
+}
+
+/// Sends the scoreboard as it was last broadcast to players who just joined. This runs before
+/// [`sync_scoreboard`], so the changes broadcast this tick are applied on top of it rather than
+/// being sent twice.
+fn initialize_players(
+    scoreboard: Res<'_, Scoreboard>,
+    query: Query<'_, '_, (&ConnectionId, &mut PlayerScoreboard)>,
+    compose: Res<'_, Compose>,
+) {
+    for (&connection, mut player) in query {
+        if player.initialized {
+            continue;
+        }
+
+        let player = &mut *player;
+
+        let mut state = scoreboard.sent.clone();
+        for (objective, holders) in &player.scores {
+            state.scores.entry(objective.clone()).or_default().extend(
+                holders
+                    .iter()
+                    .map(|(holder, &score)| (holder.clone(), score)),
+            );
+        }
+
+        let mut bundle = DataBundle::new(&compose);
+        if let Err(e) = write_diff(&State::default(), &state, &mut bundle) {
+            error!("failed to send scoreboard: {e}");
+            continue;
+        }
+        bundle.unicast(connection).unwrap();
+
+        player.sent.clone_from(&player.scores);
+        player.dirty = false;
+        player.initialized = true;
+    }
+}
+
+fn sync_players(
+    scoreboard: Res<'_, Scoreboard>,
+    query: Query<'_, '_, (&ConnectionId, &mut PlayerScoreboard)>,
+    compose: Res<'_, Compose>,
+) {
+    let global = &scoreboard.sent;
+
+    for (&connection, mut player) in query {
+        let player = &mut *player;
+
+        let changed =
+            player.dirty || !scoreboard.created.is_empty() || !scoreboard.overwritten.is_empty();
+        if !player.initialized || !changed {
+            continue;
+        }
+
+        let mut bundle = DataBundle::new(&compose);
+
+        let keys = player
+            .scores
+            .iter()
+            .chain(&player.sent)
+            .flat_map(|(objective, holders)| holders.keys().map(move |holder| (objective, holder)))
+            .collect::<HashSet<_>>();
+
+        for (objective, holder) in keys {
+            // Removing an objective removes its scores
+            if !global.objectives.contains_key(objective) {
+                continue;
+            }
+
+            let global_score = get_score(&global.scores, objective, holder);
+
+            // Scores broadcast this tick replaced the score the client had
+            let overwritten = scoreboard.created.contains(objective)
+                || scoreboard
+                    .overwritten
+                    .contains(&(objective.clone(), holder.clone()));
+
+            let previous = if overwritten {
+                global_score
+            } else {
+                get_score(&player.sent, objective, holder).or(global_score)
+            };
+            let desired = get_score(&player.scores, objective, holder).or(global_score);
+
+            if previous == desired {
+                continue;
+            }
+
+            if let Err(e) = bundle.add_packet(&score_packet(objective, holder, desired)) {
+                error!("failed to sync player scores: {e}");
+            }
+        }
+
+        bundle.unicast(connection).unwrap();
+
+        player.sent.clone_from(&player.scores);
+        player.dirty = false;
+    }
+}
+
+pub struct ScoreboardPlugin;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Scoreboard>();
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedPostUpdate,
+            (initialize_players, sync_scoreboard, sync_players).chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_changes_skip_unchanged_scores() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.add_objective("kills", Objective::new("Kills"));
+        scoreboard.set_score("kills", "alice", 1);
+        scoreboard.set_score("kills", "bob", 2);
+        scoreboard.sent = scoreboard.state.clone();
+
+        scoreboard.set_score("kills", "bob", 3);
+        scoreboard.remove_score("kills", "alice");
+
+        let changes = score_changes(&scoreboard.sent, &scoreboard.state);
+        assert_eq!(changes, [
+            ScoreChange {
+                objective: "kills",
+                holder: "bob",
+                score: Some(3),
+            },
+            ScoreChange {
+                objective: "kills",
+                holder: "alice",
+                score: None,
+            },
+        ]);
+
+        // Removing the objective removes its scores on the client
+        scoreboard.remove_objective("kills");
+        assert!(score_changes(&scoreboard.sent, &scoreboard.state).is_empty());
+    }
+
+    #[test]
+    fn members_are_in_one_team() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.add_team("red", Team::new("Red"));
+        scoreboard.add_team("blue", Team::new("Blue"));
+
+        assert!(scoreboard.join_team("red", "alice"));
+        assert!(scoreboard.join_team("blue", "alice"));
+        assert!(!scoreboard.join_team("green", "alice"));
+
+        assert_eq!(scoreboard.team_of("alice"), Some("blue"));
+        assert!(!scoreboard.team("red").unwrap().contains("alice"));
+    }
+
+    #[test]
+    fn members_leave_their_old_team_first() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.add_team("blue", Team::new("Blue"));
+        scoreboard.add_team("red", Team::new("Red"));
+        assert!(scoreboard.join_team("red", "alice"));
+        scoreboard.sent = scoreboard.state.clone();
+
+        assert!(scoreboard.join_team("blue", "alice"));
+        scoreboard.add_team("green", Team::new("Green"));
+        assert!(scoreboard.join_team("green", "bob"));
+
+        let changes = team_changes(&scoreboard.sent, &scoreboard.state);
+        assert!(matches!(changes.as_slice(), [
+            ("red", Mode::RemoveEntities { entities: removed }),
+            ("blue", Mode::AddEntities { entities: added }),
+            ("green", Mode::CreateTeam { .. }),
+        ] if removed.len() == 1 && added.len() == 1));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeSet;
+
+use valence_protocol::{
+    Text,
+    packets::play::team_s2c::{CollisionRule, NameTagVisibility, TeamColor, TeamFlags},
+};
+
+/// A team of players or entities. Members are added with [`crate::Scoreboard::join_team`] because
+/// a name can only be in one team at a time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Team {
+    pub display_name: Text,
+    /// Shown before the name of each member
+    pub prefix: Text,
+    /// Shown after the name of each member
+    pub suffix: Text,
+    /// The colour of the names of members
+    pub color: TeamColor,
+    pub collision_rule: CollisionRule,
+    pub name_tag_visibility: NameTagVisibility,
+    /// Whether members can attack each other
+    pub friendly_fire: bool,
+    /// Whether members can see invisible members
+    pub see_invisible_teammates: bool,
+    pub(crate) members: BTreeSet<String>,
+}
+
+impl Team {
+    #[must_use]
+    pub fn new(display_name: impl Into<Text>) -> Self {
+        Self {
+            display_name: display_name.into(),
+            prefix: Text::default(),
+            suffix: Text::default(),
+            color: TeamColor::White,
+            collision_rule: CollisionRule::Always,
+            name_tag_visibility: NameTagVisibility::Always,
+            friendly_fire: true,
+            see_invisible_teammates: false,
+            members: BTreeSet::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_prefix(mut self, prefix: impl Into<Text>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_suffix(mut self, suffix: impl Into<Text>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, color: TeamColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_collision_rule(mut self, collision_rule: CollisionRule) -> Self {
+        self.collision_rule = collision_rule;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_name_tag_visibility(
+        mut self,
+        name_tag_visibility: NameTagVisibility,
+    ) -> Self {
+        self.name_tag_visibility = name_tag_visibility;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_friendly_fire(mut self, friendly_fire: bool) -> Self {
+        self.friendly_fire = friendly_fire;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_see_invisible_teammates(mut self, see_invisible_teammates: bool) -> Self {
+        self.see_invisible_teammates = see_invisible_teammates;
+        self
+    }
+
+    /// The names of the members of the team
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn contains(&self, member: &str) -> bool {
+        self.members.contains(member)
+    }
+
+    pub(crate) const fn flags(&self) -> TeamFlags {
+        TeamFlags::new()
+            .with_friendly_fire(self.friendly_fire)
+            .with_see_invisible(self.see_invisible_teammates)
+    }
+
+    /// Whether the two teams look the same, ignoring their members
+    pub(crate) fn same_info(&self, other: &Self) -> bool {
+        self.display_name == other.display_name
+            && self.prefix == other.prefix
+            && self.suffix == other.suffix
+            && self.color == other.color
+            && self.collision_rule == other.collision_rule
+            && self.name_tag_visibility == other.name_tag_visibility
+            && self.friendly_fire == other.friendly_fire
+            && self.see_invisible_teammates == other.see_invisible_teammates
+    }
+}
@@ -103,6 +103,20 @@ impl RgbColor {
         Self { r, g, b }
     }
 
+    /// Blends between this color at `t = 0` and `other` at `t = 1`.
+    #[must_use]
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| f32::from(a).mul_add(1.0 - t, f32::from(b) * t).round() as u8;
+
+        Self::new(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+        )
+    }
+
     /// Converts the RGB color to the closest [`NamedColor`] equivalent (lossy).
     pub fn to_named_lossy(self) -> NamedColor {
         // calculates the squared distance between 2 colors
@@ -142,6 +156,29 @@ impl NamedColor {
         b"0123456789abcdef"[self as usize] as char
     }
 
+    /// Returns the color with the given hex digit, ignoring case.
+    pub const fn from_hex_digit(digit: char) -> Option<Self> {
+        Some(match digit.to_ascii_lowercase() {
+            '0' => Self::Black,
+            '1' => Self::DarkBlue,
+            '2' => Self::DarkGreen,
+            '3' => Self::DarkAqua,
+            '4' => Self::DarkRed,
+            '5' => Self::DarkPurple,
+            '6' => Self::Gold,
+            '7' => Self::Gray,
+            '8' => Self::DarkGray,
+            '9' => Self::Blue,
+            'a' => Self::Green,
+            'b' => Self::Aqua,
+            'c' => Self::Red,
+            'd' => Self::LightPurple,
+            'e' => Self::Yellow,
+            'f' => Self::White,
+            _ => return None,
+        })
+    }
+
     /// Returns the identifier of the color.
     pub const fn name(self) -> &'static str {
         [
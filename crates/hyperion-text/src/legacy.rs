@@ -0,0 +1,293 @@
+//! Legacy formatting codes such as `§c` or `&c`.
+
+use crate::{
+    Text, TextContent,
+    color::{Color, NamedColor, RgbColor},
+};
+
+/// The formatting which applies to a piece of text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+    obfuscated: bool,
+}
+
+impl Style {
+    /// The style of `text` when it is inside text with this style
+    fn inherit(self, text: &Text<'_>) -> Self {
+        Self {
+            color: match text.color {
+                Some(Color::Reset) => None,
+                Some(color) => Some(color),
+                None => self.color,
+            },
+            bold: text.bold.unwrap_or(self.bold),
+            italic: text.italic.unwrap_or(self.italic),
+            underlined: text.underlined.unwrap_or(self.underlined),
+            strikethrough: text.strikethrough.unwrap_or(self.strikethrough),
+            obfuscated: text.obfuscated.unwrap_or(self.obfuscated),
+        }
+    }
+
+    fn to_text(self, content: String) -> Text<'static> {
+        let flag = |enabled: bool| enabled.then_some(true);
+
+        Text {
+            color: self.color,
+            bold: flag(self.bold),
+            italic: flag(self.italic),
+            underlined: flag(self.underlined),
+            strikethrough: flag(self.strikethrough),
+            obfuscated: flag(self.obfuscated),
+            ..Text::from(content)
+        }
+    }
+
+    /// The formatting flags and their codes
+    const fn flags(self) -> [(bool, char); 5] {
+        [
+            (self.obfuscated, 'k'),
+            (self.bold, 'l'),
+            (self.strikethrough, 'm'),
+            (self.underlined, 'n'),
+            (self.italic, 'o'),
+        ]
+    }
+
+    /// Writes the codes which change the formatting from `previous` to this style. Colour codes
+    /// reset the formatting, so flags can only be added without writing the colour again.
+    fn write_codes(self, previous: Self, code: char, out: &mut String) {
+        let adds_flags = self.color == previous.color
+            && self
+                .flags()
+                .into_iter()
+                .zip(previous.flags())
+                .all(|((enabled, _), (was_enabled, _))| enabled || !was_enabled);
+
+        let previous = if adds_flags {
+            previous
+        } else {
+            match self.color {
+                None | Some(Color::Reset) => {
+                    out.push(code);
+                    out.push('r');
+                }
+                Some(Color::Named(named)) => {
+                    out.push(code);
+                    out.push(named.hex_digit());
+                }
+                Some(Color::Rgb(rgb)) => {
+                    out.push(code);
+                    out.push('x');
+                    for digit in rgb.to_string().chars().skip(1) {
+                        out.push(code);
+                        out.push(digit);
+                    }
+                }
+            }
+
+            Self {
+                color: self.color,
+                ..Self::default()
+            }
+        };
+
+        for ((enabled, flag), (was_enabled, _)) in self.flags().into_iter().zip(previous.flags()) {
+            if enabled && !was_enabled {
+                out.push(code);
+                out.push(flag);
+            }
+        }
+    }
+}
+
+/// Reads the six hex digits of an RGB code in the form `§x§r§r§g§g§b§b`, after the `x`
+fn parse_rgb(chars: &[char], code: char) -> Option<RgbColor> {
+    let mut hex = String::with_capacity(7);
+    hex.push('#');
+
+    for pair in chars.get(..12)?.chunks_exact(2) {
+        if pair[0] != code || !pair[1].is_ascii_hexdigit() {
+            return None;
+        }
+        hex.push(pair[1]);
+    }
+
+    RgbColor::try_from(hex.as_str()).ok()
+}
+
+impl Text<'static> {
+    /// Parses text containing legacy formatting codes which start with `code`, usually `§` or
+    /// `&`. RGB colours are written as `§x§r§r§g§g§b§b`. Unknown codes are kept as written.
+    #[must_use]
+    pub fn from_legacy(input: &str, code: char) -> Self {
+        let chars: Vec<char> = input.chars().collect();
+        let mut root = Text::new("");
+        let mut style = Style::default();
+        let mut buffer = String::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).map(char::to_ascii_lowercase);
+
+            let Some(next) = next.filter(|_| c == code) else {
+                buffer.push(c);
+                i += 1;
+                continue;
+            };
+
+            let mut new_style = style;
+            let mut consumed = 2;
+
+            if let Some(named) = NamedColor::from_hex_digit(next) {
+                new_style = Style {
+                    color: Some(Color::Named(named)),
+                    ..Style::default()
+                };
+            } else {
+                match next {
+                    'k' => new_style.obfuscated = true,
+                    'l' => new_style.bold = true,
+                    'm' => new_style.strikethrough = true,
+                    'n' => new_style.underlined = true,
+                    'o' => new_style.italic = true,
+                    'r' => new_style = Style::default(),
+                    'x' => match parse_rgb(&chars[i + 2..], code) {
+                        Some(rgb) => {
+                            new_style = Style {
+                                color: Some(Color::Rgb(rgb)),
+                                ..Style::default()
+                            };
+                            consumed += 12;
+                        }
+                        None => consumed = 0,
+                    },
+                    _ => consumed = 0,
+                }
+            }
+
+            if consumed == 0 {
+                buffer.push(c);
+                i += 1;
+                continue;
+            }
+
+            if !buffer.is_empty() {
+                root.extra.push(style.to_text(std::mem::take(&mut buffer)));
+            }
+
+            style = new_style;
+            i += consumed;
+        }
+
+        if !buffer.is_empty() {
+            root.extra.push(style.to_text(buffer));
+        }
+
+        root
+    }
+}
+
+impl Text<'_> {
+    /// Converts the text to legacy formatting codes starting with `code`. Only plain text content
+    /// is kept, and click and hover events are lost.
+    #[must_use]
+    pub fn to_legacy(&self, code: char) -> String {
+        fn write(
+            text: &Text<'_>,
+            parent: Style,
+            code: char,
+            current: &mut Style,
+            out: &mut String,
+        ) {
+            let style = parent.inherit(text);
+
+            if let TextContent::Text { text: content } = &text.content
+                && !content.is_empty()
+            {
+                if style != *current {
+                    style.write_codes(*current, code, out);
+                    *current = style;
+                }
+                out.push_str(content);
+            }
+
+            for child in &text.extra {
+                write(child, style, code, current, out);
+            }
+        }
+
+        let mut out = String::new();
+        write(
+            self,
+            Style::default(),
+            code,
+            &mut Style::default(),
+            &mut out,
+        );
+        out
+    }
+}
+
+/// Replaces `&` codes with `§` codes, so that `&` can be used in config files
+#[must_use]
+pub fn translate_alternate_codes(input: &str, code: char) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == code
+            && let Some(&next) = chars.peek()
+            && "0123456789abcdefklmnorxABCDEFKLMNORX".contains(next)
+        {
+            out.push('§');
+            continue;
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::translate_alternate_codes;
+    use crate::{Text, color::Color};
+
+    #[test]
+    fn parses_colors_and_formatting() {
+        let text = Text::from_legacy("§cred §lbold§r plain", '§');
+
+        assert_eq!(text.extra.len(), 3);
+        assert_eq!(text.extra[0].color, Some(Color::RED));
+        assert_eq!(text.extra[1].color, Some(Color::RED));
+        assert_eq!(text.extra[1].bold, Some(true));
+        assert_eq!(text.extra[2].color, None);
+        assert_eq!(text.extra[2].bold, None);
+    }
+
+    #[test]
+    fn parses_rgb_and_keeps_unknown_codes() {
+        let text = Text::from_legacy("&x&1&2&3&4&5&6hi &zok", '&');
+
+        assert_eq!(text.extra.len(), 1);
+        assert_eq!(text.extra[0].color, Some(Color::rgb(0x12, 0x34, 0x56)));
+        assert_eq!(text.to_legacy('&'), "&x&1&2&3&4&5&6hi &zok");
+    }
+
+    #[test]
+    fn round_trips() {
+        let input = "§6gold §lbold§7 gray §r§nplain";
+        assert_eq!(Text::from_legacy(input, '§').to_legacy('§'), input);
+    }
+
+    #[test]
+    fn translates_alternate_codes() {
+        assert_eq!(translate_alternate_codes("&cHi & bye", '&'), "§cHi & bye");
+    }
+}
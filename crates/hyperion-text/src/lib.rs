@@ -3,17 +3,21 @@ use std::{borrow::Cow, io::Write};
 use serde::{Deserialize, Serialize};
 use valence_protocol::{Bounded, Encode, anyhow, anyhow::Context};
 
-use crate::{
-    color::Color,
+use crate::scoreboard::ScoreboardValueContent;
+pub use crate::{
+    color::{Color, ColorError, NamedColor, RgbColor},
     event::{ClickEvent, HoverEvent},
     font::Font,
-    scoreboard::ScoreboardValueContent,
+    legacy::translate_alternate_codes,
+    markup::MarkupError,
 };
 
 mod color;
 mod event;
 mod font;
 mod helper;
+mod legacy;
+mod markup;
 mod scoreboard;
 
 /// Text data and formatting.
@@ -0,0 +1,727 @@
+//! MiniMessage-style markup such as `<red>Hello <bold>world</bold></red>`.
+//!
+//! Supported tags:
+//! - colours: `<red>`, `<#ff5555>`, `<color:red>`
+//! - decorations: `<bold>`, `<italic>`, `<underlined>`, `<strikethrough>` and `<obfuscated>`, their
+//!   short forms `<b>`, `<i>`, `<u>`, `<st>` and `<obf>`, and negations such as `<!bold>`
+//! - `<click:action:value>` where the action is `open_url`, `run_command`, `suggest_command`,
+//!   `copy_to_clipboard` or `change_page`
+//! - `<hover:show_text:text>` where the text is markup
+//! - `<insert:text>`
+//! - `<gradient:color:color...>`, which replaces the colours of the text inside it
+//! - `<lang:key:args...>` and `<key:keybind>`
+//! - `<newline>` and `<reset>`, which closes every open tag
+//!
+//! Tags are closed with `</name>`, or `</>` for the last open tag. Unclosed tags are closed at the
+//! end. Arguments can be quoted with `'` or `"` to contain `:` or `>`, and `\` escapes the next
+//! character inside quotes. Outside of tags, `\<` is a literal `<`. Unknown tags, including unknown
+//! closing tags, are kept as text.
+
+use std::{borrow::Cow, mem};
+
+use thiserror::Error;
+
+use crate::{
+    Text, TextContent,
+    color::{Color, RgbColor},
+    event::{ClickEvent, HoverEvent},
+};
+
+/// Markup parsing error
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    #[error("invalid color {0:?}")]
+    InvalidColor(String),
+    #[error("<{0}> is missing an argument")]
+    MissingArgument(String),
+    #[error("invalid argument {argument:?} for <{tag}>")]
+    InvalidArgument { tag: String, argument: String },
+    #[error("</{0}> does not close an open tag")]
+    UnmatchedClose(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Decoration {
+    Bold,
+    Italic,
+    Underlined,
+    Strikethrough,
+    Obfuscated,
+}
+
+impl Decoration {
+    const ALL: [Self; 5] = [
+        Self::Bold,
+        Self::Italic,
+        Self::Underlined,
+        Self::Strikethrough,
+        Self::Obfuscated,
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bold" | "b" => Some(Self::Bold),
+            "italic" | "i" | "em" => Some(Self::Italic),
+            "underlined" | "u" => Some(Self::Underlined),
+            "strikethrough" | "st" => Some(Self::Strikethrough),
+            "obfuscated" | "obf" => Some(Self::Obfuscated),
+            _ => None,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Bold => "bold",
+            Self::Italic => "italic",
+            Self::Underlined => "underlined",
+            Self::Strikethrough => "strikethrough",
+            Self::Obfuscated => "obfuscated",
+        }
+    }
+
+    const fn get(self, text: &Text<'_>) -> Option<bool> {
+        match self {
+            Self::Bold => text.bold,
+            Self::Italic => text.italic,
+            Self::Underlined => text.underlined,
+            Self::Strikethrough => text.strikethrough,
+            Self::Obfuscated => text.obfuscated,
+        }
+    }
+
+    const fn get_mut<'t>(self, text: &'t mut Text<'_>) -> &'t mut Option<bool> {
+        match self {
+            Self::Bold => &mut text.bold,
+            Self::Italic => &mut text.italic,
+            Self::Underlined => &mut text.underlined,
+            Self::Strikethrough => &mut text.strikethrough,
+            Self::Obfuscated => &mut text.obfuscated,
+        }
+    }
+}
+
+/// Finds the `>` ending a tag whose contents start at `start`, skipping quoted arguments
+fn find_tag_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut quote = None;
+    let mut i = start;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match quote {
+            Some(_) if c == '\\' => i += 1,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '>' => return Some(i),
+                '<' => return None,
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+
+    None
+}
+
+/// Splits the contents of a tag into its name and arguments, removing quotes
+fn split_tag(contents: &[char]) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut chars = contents.iter().copied();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(_) if c == '\\' => current.extend(chars.next()),
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                ':' => parts.push(mem::take(&mut current)),
+                _ => current.push(c),
+            },
+        }
+    }
+
+    parts.push(current);
+    parts
+}
+
+fn escape_text(text: &str, out: &mut String) {
+    for c in text.chars() {
+        if matches!(c, '<' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+fn quote_argument(argument: &str, out: &mut String) {
+    out.push('\'');
+    for c in argument.chars() {
+        if matches!(c, '\'' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('\'');
+}
+
+/// Whether `name` is the name of a tag which can be opened, such as `bold` or `!bold`
+fn is_tag(name: &str) -> bool {
+    if let Some(base) = name.strip_prefix('!') {
+        return Decoration::from_name(base).is_some();
+    }
+
+    Decoration::from_name(name).is_some()
+        || matches!(
+            name,
+            "reset"
+                | "newline"
+                | "br"
+                | "color"
+                | "colour"
+                | "c"
+                | "click"
+                | "hover"
+                | "insert"
+                | "insertion"
+                | "gradient"
+                | "lang"
+                | "tr"
+                | "translate"
+                | "key"
+        )
+        || Color::try_from(name).is_ok()
+}
+
+fn parse_color(argument: &str) -> Result<Color, MarkupError> {
+    Color::try_from(argument.to_ascii_lowercase().as_str())
+        .map_err(|_| MarkupError::InvalidColor(argument.to_string()))
+}
+
+fn colored(color: Color) -> Text<'static> {
+    Text {
+        color: Some(color),
+        ..Text::new("")
+    }
+}
+
+fn count_chars(text: &Text<'_>) -> usize {
+    let own = match &text.content {
+        TextContent::Text { text } => text.chars().count(),
+        _ => 0,
+    };
+
+    own + text.extra.iter().map(count_chars).sum::<usize>()
+}
+
+fn gradient_color(colors: &[RgbColor], index: usize, total: usize) -> RgbColor {
+    if total <= 1 {
+        return colors[0];
+    }
+
+    let position = index as f32 / (total - 1) as f32 * (colors.len() - 1) as f32;
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let segment = (position.floor() as usize).min(colors.len() - 2);
+
+    colors[segment].lerp(colors[segment + 1], position - segment as f32)
+}
+
+/// Splits the text into one component per character, coloured along the gradient
+fn apply_gradient(text: &mut Text<'static>, colors: &[RgbColor], index: &mut usize, total: usize) {
+    let mut characters = Vec::new();
+    if let TextContent::Text { text: content } = &text.content {
+        for c in content.chars() {
+            let color = gradient_color(colors, *index, total);
+            characters.push(Text {
+                color: Some(Color::Rgb(color)),
+                ..Text::from(c.to_string())
+            });
+            *index += 1;
+        }
+    }
+
+    let inserted = characters.len();
+    if inserted > 0 {
+        text.content = TextContent::Text {
+            text: Cow::Borrowed(""),
+        };
+        text.extra.splice(0..0, characters);
+    }
+
+    for child in &mut text.extra[inserted..] {
+        child.color = None;
+        apply_gradient(child, colors, index, total);
+    }
+}
+
+struct Frame {
+    /// The name the tag was opened with
+    name: String,
+    /// The name of the tag without aliases, which also closes it
+    canonical: &'static str,
+    text: Text<'static>,
+    gradient: Option<Vec<RgbColor>>,
+}
+
+struct Parser {
+    stack: Vec<Frame>,
+    buffer: String,
+}
+
+impl Parser {
+    fn top(&mut self) -> &mut Text<'static> {
+        &mut self.stack.last_mut().unwrap().text
+    }
+
+    fn flush(&mut self) {
+        if !self.buffer.is_empty() {
+            let text = Text::from(mem::take(&mut self.buffer));
+            self.top().extra.push(text);
+        }
+    }
+
+    fn open(&mut self, name: String, canonical: &'static str, text: Text<'static>) {
+        self.flush();
+        self.stack.push(Frame {
+            name,
+            canonical,
+            text,
+            gradient: None,
+        });
+    }
+
+    fn push(&mut self, text: Text<'static>) {
+        self.flush();
+        self.top().extra.push(text);
+    }
+
+    fn close_top(&mut self) {
+        let frame = self.stack.pop().unwrap();
+        let mut text = frame.text;
+
+        if let Some(colors) = frame.gradient {
+            let total = count_chars(&text);
+            apply_gradient(&mut text, &colors, &mut 0, total);
+        }
+
+        self.top().extra.push(text);
+    }
+
+    /// Closes the tag `name`, returning `false` if it is unknown
+    fn close(&mut self, name: &str) -> Result<bool, MarkupError> {
+        let index = if name.is_empty() {
+            self.stack.len() - 1
+        } else {
+            self.stack
+                .iter()
+                .rposition(|frame| frame.name == name || frame.canonical == name)
+                .unwrap_or(0)
+        };
+
+        // The root frame cannot be closed
+        if index == 0 {
+            if !name.is_empty() && !is_tag(name) {
+                return Ok(false);
+            }
+            return Err(MarkupError::UnmatchedClose(name.to_string()));
+        }
+
+        self.flush();
+        while self.stack.len() > index {
+            self.close_top();
+        }
+
+        Ok(true)
+    }
+
+    fn close_all(&mut self) {
+        self.flush();
+        while self.stack.len() > 1 {
+            self.close_top();
+        }
+    }
+
+    /// Handles a tag, returning `false` if it is unknown
+    fn handle_tag(&mut self, parts: Vec<String>) -> Result<bool, MarkupError> {
+        let mut parts = parts.into_iter();
+        let name = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let args: Vec<String> = parts.collect();
+
+        let argument = |index: usize| {
+            args.get(index)
+                .map(String::as_str)
+                .ok_or_else(|| MarkupError::MissingArgument(name.clone()))
+        };
+        let invalid = |argument: &str| MarkupError::InvalidArgument {
+            tag: name.clone(),
+            argument: argument.to_string(),
+        };
+
+        if let Some(close) = name.strip_prefix('/') {
+            return self.close(close);
+        }
+
+        let (negated, base) = match name.strip_prefix('!') {
+            Some(base) => (true, base),
+            None => (false, name.as_str()),
+        };
+
+        if let Some(decoration) = Decoration::from_name(base) {
+            let mut text = Text::new("");
+            *decoration.get_mut(&mut text) = Some(!negated);
+            self.open(name.clone(), decoration.name(), text);
+            return Ok(true);
+        }
+
+        if negated {
+            return Ok(false);
+        }
+
+        match base {
+            "reset" => self.close_all(),
+            "newline" | "br" => self.buffer.push('\n'),
+            "color" | "colour" | "c" => {
+                let color = parse_color(argument(0)?)?;
+                self.open(name.clone(), "color", colored(color));
+            }
+            "click" => {
+                let action = argument(0)?;
+                let value = argument(1)?.to_string();
+                let event = match action.to_ascii_lowercase().as_str() {
+                    "open_url" => ClickEvent::OpenUrl(Cow::Owned(value)),
+                    "run_command" => ClickEvent::RunCommand(Cow::Owned(value)),
+                    "suggest_command" => ClickEvent::SuggestCommand(Cow::Owned(value)),
+                    "copy_to_clipboard" => ClickEvent::CopyToClipboard(Cow::Owned(value)),
+                    "change_page" => {
+                        ClickEvent::ChangePage(value.parse().map_err(|_| invalid(&value))?)
+                    }
+                    _ => return Err(invalid(action)),
+                };
+
+                let text = Text {
+                    click_event: Some(Box::new(event)),
+                    ..Text::new("")
+                };
+                self.open(name.clone(), "click", text);
+            }
+            "hover" => {
+                let action = argument(0)?;
+                if !action.eq_ignore_ascii_case("show_text") {
+                    return Err(invalid(action));
+                }
+
+                let shown = Text::from_markup(argument(1)?)?;
+                let text = Text {
+                    hover_event: Some(Box::new(HoverEvent::ShowText(shown))),
+                    ..Text::new("")
+                };
+                self.open(name.clone(), "hover", text);
+            }
+            "insert" | "insertion" => {
+                let text = Text {
+                    insertion: Some(Cow::Owned(argument(0)?.to_string())),
+                    ..Text::new("")
+                };
+                self.open(name.clone(), "insert", text);
+            }
+            "gradient" => {
+                if args.len() < 2 {
+                    return Err(invalid(&args.join(":")));
+                }
+
+                let colors = args
+                    .iter()
+                    .map(|argument| match parse_color(argument)? {
+                        Color::Rgb(rgb) => Ok(rgb),
+                        Color::Named(named) => Ok(named.into()),
+                        Color::Reset => Err(MarkupError::InvalidColor(argument.clone())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.open(name.clone(), "gradient", Text::new(""));
+                self.stack.last_mut().unwrap().gradient = Some(colors);
+            }
+            "lang" | "tr" | "translate" => {
+                let translate = Cow::Owned(argument(0)?.to_string());
+                let with = args[1..]
+                    .iter()
+                    .map(|argument| Text::from_markup(argument))
+                    .collect::<Result<_, _>>()?;
+
+                self.push(Text {
+                    content: TextContent::Translate { translate, with },
+                    ..Text::new("")
+                });
+            }
+            "key" => {
+                let keybind = Cow::Owned(argument(0)?.to_string());
+                self.push(Text {
+                    content: TextContent::Keybind { keybind },
+                    ..Text::new("")
+                });
+            }
+            _ => {
+                let Ok(color) = Color::try_from(base) else {
+                    return Ok(false);
+                };
+                self.open(name.clone(), "color", colored(color));
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+fn write_markup(text: &Text<'_>, out: &mut String) {
+    let mut closing = Vec::new();
+
+    match text.color {
+        Some(Color::Named(named)) => {
+            out.push('<');
+            out.push_str(named.name());
+            out.push('>');
+            closing.push(format!("</{}>", named.name()));
+        }
+        Some(Color::Rgb(rgb)) => {
+            out.push_str(&format!("<{rgb}>"));
+            closing.push("</color>".to_string());
+        }
+        // Resetting the colour has no equivalent tag
+        Some(Color::Reset) | None => {}
+    }
+
+    for decoration in Decoration::ALL {
+        let name = decoration.name();
+        match decoration.get(text) {
+            Some(true) => {
+                out.push_str(&format!("<{name}>"));
+                closing.push(format!("</{name}>"));
+            }
+            Some(false) => {
+                out.push_str(&format!("<!{name}>"));
+                closing.push(format!("</!{name}>"));
+            }
+            None => {}
+        }
+    }
+
+    if let Some(insertion) = &text.insertion {
+        out.push_str("<insert:");
+        quote_argument(insertion, out);
+        out.push('>');
+        closing.push("</insert>".to_string());
+    }
+
+    if let Some(click) = &text.click_event {
+        let action = match &**click {
+            ClickEvent::OpenUrl(value) => Some(("open_url", value.to_string())),
+            ClickEvent::RunCommand(value) => Some(("run_command", value.to_string())),
+            ClickEvent::SuggestCommand(value) => Some(("suggest_command", value.to_string())),
+            ClickEvent::CopyToClipboard(value) => Some(("copy_to_clipboard", value.to_string())),
+            ClickEvent::ChangePage(page) => Some(("change_page", page.to_string())),
+            ClickEvent::OpenFile(_) => None,
+        };
+
+        if let Some((action, value)) = action {
+            out.push_str("<click:");
+            out.push_str(action);
+            out.push(':');
+            quote_argument(&value, out);
+            out.push('>');
+            closing.push("</click>".to_string());
+        }
+    }
+
+    if let Some(hover) = &text.hover_event
+        && let HoverEvent::ShowText(shown) = &**hover
+    {
+        out.push_str("<hover:show_text:");
+        quote_argument(&shown.to_markup(), out);
+        out.push('>');
+        closing.push("</hover>".to_string());
+    }
+
+    match &text.content {
+        TextContent::Text { text } => escape_text(text, out),
+        TextContent::Translate { translate, with } => {
+            out.push_str("<lang:");
+            quote_argument(translate, out);
+            for argument in with {
+                out.push(':');
+                quote_argument(&argument.to_markup(), out);
+            }
+            out.push('>');
+        }
+        TextContent::Keybind { keybind } => {
+            out.push_str("<key:");
+            quote_argument(keybind, out);
+            out.push('>');
+        }
+        // Other content has no equivalent tag
+        _ => {}
+    }
+
+    for child in &text.extra {
+        write_markup(child, out);
+    }
+
+    for tag in closing.iter().rev() {
+        out.push_str(tag);
+    }
+}
+
+impl Text<'static> {
+    /// Parses MiniMessage-style markup. See the [module documentation](self) for the supported
+    /// tags.
+    pub fn from_markup(input: &str) -> Result<Self, MarkupError> {
+        let chars: Vec<char> = input.chars().collect();
+        let mut parser = Parser {
+            stack: vec![Frame {
+                name: String::new(),
+                canonical: "",
+                text: Text::new(""),
+                gradient: None,
+            }],
+            buffer: String::new(),
+        };
+
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if matches!(chars.get(i + 1), Some('<' | '\\')) => {
+                    parser.buffer.push(chars[i + 1]);
+                    i += 2;
+                }
+                '<' => {
+                    if let Some(end) = find_tag_end(&chars, i + 1)
+                        && parser.handle_tag(split_tag(&chars[i + 1..end]))?
+                    {
+                        i = end + 1;
+                    } else {
+                        parser.buffer.push('<');
+                        i += 1;
+                    }
+                }
+                c => {
+                    parser.buffer.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        parser.close_all();
+        Ok(parser.stack.pop().unwrap().text)
+    }
+}
+
+impl Text<'_> {
+    /// Converts the text to markup which [`Text::from_markup`] parses back. Content other than
+    /// plain text, translations and keybinds, and hover events other than text are lost.
+    #[must_use]
+    pub fn to_markup(&self) -> String {
+        let mut out = String::new();
+        write_markup(self, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::MarkupError;
+    use crate::{
+        Text, TextContent,
+        color::Color,
+        event::{ClickEvent, HoverEvent},
+    };
+
+    fn plain<'a>(text: &'a Text<'_>) -> &'a str {
+        match &text.content {
+            TextContent::Text { text } => text,
+            _ => panic!("expected plain text"),
+        }
+    }
+
+    #[test]
+    fn nests_tags() {
+        let text = Text::from_markup("<red>Hello <bold>world</bold>!</red> bye").unwrap();
+
+        let red = &text.extra[0];
+        assert_eq!(red.color, Some(Color::RED));
+        assert_eq!(plain(&red.extra[0]), "Hello ");
+        assert_eq!(red.extra[1].bold, Some(true));
+        assert_eq!(plain(&red.extra[1].extra[0]), "world");
+        assert_eq!(plain(&red.extra[2]), "!");
+        assert_eq!(plain(&text.extra[1]), " bye");
+    }
+
+    #[test]
+    fn parses_events() {
+        let text =
+            Text::from_markup("<click:run_command:'/spawn'><hover:show_text:'<green>Go'>Spawn")
+                .unwrap();
+
+        let click = &text.extra[0];
+        assert_eq!(
+            click.click_event.as_deref(),
+            Some(&ClickEvent::RunCommand(Cow::Borrowed("/spawn")))
+        );
+
+        let hover = &click.extra[0];
+        let Some(HoverEvent::ShowText(shown)) = hover.hover_event.as_deref() else {
+            panic!("expected hover text");
+        };
+        assert_eq!(shown.extra[0].color, Some(Color::GREEN));
+        assert_eq!(plain(&hover.extra[0]), "Spawn");
+    }
+
+    #[test]
+    fn applies_gradients() {
+        let text = Text::from_markup("<gradient:#000000:#ffffff>abc</gradient>").unwrap();
+
+        let gradient = &text.extra[0];
+        assert_eq!(gradient.extra[0].color, None);
+
+        let colors: Vec<_> = gradient.extra[0].extra.iter().map(|c| c.color).collect();
+        assert_eq!(colors, [
+            Some(Color::rgb(0, 0, 0)),
+            Some(Color::rgb(128, 128, 128)),
+            Some(Color::rgb(255, 255, 255)),
+        ]);
+    }
+
+    #[test]
+    fn keeps_unknown_tags_and_escapes() {
+        let text = Text::from_markup(r"<unknown> \<red> 1 < 2").unwrap();
+        assert_eq!(plain(&text.extra[0]), "<unknown> <red> 1 < 2");
+
+        let text = Text::from_markup("a</foo> b</!foo>").unwrap();
+        assert_eq!(plain(&text.extra[0]), "a</foo> b</!foo>");
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            Text::from_markup("<color:blurple>hi"),
+            Err(MarkupError::InvalidColor("blurple".to_string()))
+        );
+        assert_eq!(
+            Text::from_markup("hi</red>"),
+            Err(MarkupError::UnmatchedClose("red".to_string()))
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let input = "<red>a <!italic>b</!italic></red><click:suggest_command:'/msg \
+                     '><hover:show_text:'<bold>it\\'s'>c</hover></click><lang:'chat.type.text'> \
+                     \\<";
+        let text = Text::from_markup(input).unwrap();
+
+        assert_eq!(Text::from_markup(&text.to_markup()).unwrap(), text);
+    }
+}
@@ -0,0 +1,505 @@
+//! Experience orbs.
+//!
+//! Writing a [`SpawnXp`] event drops experience as orbs, which are split into vanilla orb sizes.
+//! Orbs fall, are attracted toward the nearest player within [`FOLLOW_RANGE`] blocks, merge with
+//! nearby orbs of the same value, and are picked up by players who touch them.
+//!
+//! Experience is dropped automatically when a player dies and when an entity with an [`XpReward`]
+//! is killed. Games which break blocks can use [`block_xp`] to drop the vanilla amount of
+//! experience for a block.
+
+use std::collections::{HashMap, hash_map::Entry};
+
+use bevy::prelude::*;
+use hyperion::{
+    BlockKind,
+    net::{Compose, ConnectionId, agnostic},
+    simulation::{
+        EntitySize, Position, Velocity, Xp, aabb, blocks::Blocks, entity_kind::EntityKind,
+        game_mode::GameMode, metadata::living_entity::Health, packet_state,
+    },
+};
+use hyperion_combat::{CombatSet, DeathEvent};
+use hyperion_respawn::PlayerDeath;
+use hyperion_utils::EntityExt;
+use valence_protocol::{
+    ByteAngle, VarInt, ident,
+    packets::play::{
+        EntitiesDestroyS2c, EntityPositionS2c, ExperienceOrbSpawnS2c, ItemPickupAnimationS2c,
+        MoveRelativeS2c,
+    },
+};
+
+/// Orbs are attracted toward players within this many blocks
+pub const FOLLOW_RANGE: f32 = 8.0;
+
+/// Orbs despawn after this many ticks
+pub const ORB_LIFETIME: u16 = 6000;
+
+/// Ticks between searching for a player to follow
+const FOLLOW_INTERVAL: u16 = 20;
+
+/// Ticks between merging orbs
+const MERGE_INTERVAL: i64 = 40;
+
+/// Ticks a player must wait between picking up orbs
+const PICKUP_DELAY: u8 = 2;
+
+/// The orb sizes used when splitting experience, from largest to smallest
+const ORB_SIZES: [u16; 11] = [2477, 1237, 617, 307, 149, 73, 37, 17, 7, 3, 1];
+
+/// Drops experience orbs worth `amount` at `position`
+#[derive(Event, Copy, Clone, Debug)]
+pub struct SpawnXp {
+    pub position: Vec3,
+    pub amount: u16,
+}
+
+/// Experience dropped when this entity is killed
+#[derive(Component, Copy, Clone, Debug, Deref)]
+pub struct XpReward(pub u16);
+
+/// An experience orb worth `value` experience. Merged orbs are stored as a single orb with a
+/// `count` above one.
+#[derive(Component, Copy, Clone, Debug)]
+pub struct ExperienceOrb {
+    value: u16,
+    count: u16,
+    age: u16,
+    following: Option<Entity>,
+}
+
+impl ExperienceOrb {
+    #[must_use]
+    pub const fn value(&self) -> u16 {
+        self.value
+    }
+
+    #[must_use]
+    pub const fn count(&self) -> u16 {
+        self.count
+    }
+}
+
+/// Ticks until a player can pick up another orb
+#[derive(Component, Copy, Clone, Debug, Default)]
+struct PickupDelay(u8);
+
+/// Splits `amount` experience into the values of the orbs which should be dropped
+pub fn split(mut amount: u16) -> impl Iterator<Item = u16> {
+    std::iter::from_fn(move || {
+        let size = ORB_SIZES.into_iter().find(|&size| amount >= size)?;
+        amount -= size;
+        Some(size)
+    })
+}
+
+/// The experience dropped by breaking a block, or 0 if the block does not drop experience
+#[must_use]
+pub fn block_xp(kind: BlockKind) -> u16 {
+    match kind {
+        BlockKind::CoalOre | BlockKind::DeepslateCoalOre => fastrand::u16(0..=2),
+        BlockKind::DiamondOre
+        | BlockKind::DeepslateDiamondOre
+        | BlockKind::EmeraldOre
+        | BlockKind::DeepslateEmeraldOre => fastrand::u16(3..=7),
+        BlockKind::LapisOre | BlockKind::DeepslateLapisOre | BlockKind::NetherQuartzOre => {
+            fastrand::u16(2..=5)
+        }
+        BlockKind::RedstoneOre | BlockKind::DeepslateRedstoneOre => fastrand::u16(1..=5),
+        BlockKind::NetherGoldOre => fastrand::u16(0..=1),
+        BlockKind::Spawner => fastrand::u16(15..=43),
+        BlockKind::Sculk => 1,
+        BlockKind::SculkCatalyst | BlockKind::SculkSensor | BlockKind::SculkShrieker => 5,
+        _ => 0,
+    }
+}
+
+fn destroy_orb(compose: &Compose, commands: &mut Commands<'_, '_>, orb: Entity, position: Vec3) {
+    let pkt = EntitiesDestroyS2c {
+        entity_ids: vec![VarInt(orb.minecraft_id())].into(),
+    };
+    compose
+        .broadcast_local(&pkt, Position::from(position).to_chunk())
+        .send()
+        .unwrap();
+
+    commands.entity(orb).despawn();
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    commands
+        .entity(trigger.target())
+        .insert(PickupDelay::default());
+}
+
+fn drop_xp_on_kill(
+    mut events: EventReader<'_, '_, DeathEvent>,
+    query: Query<'_, '_, (&XpReward, &Position)>,
+    mut writer: EventWriter<'_, SpawnXp>,
+) {
+    for event in events.read() {
+        let Ok((reward, position)) = query.get(event.entity) else {
+            continue;
+        };
+
+        writer.write(SpawnXp {
+            position: **position,
+            amount: **reward,
+        });
+    }
+}
+
+fn drop_xp_on_player_death(
+    mut events: EventReader<'_, '_, PlayerDeath>,
+    mut writer: EventWriter<'_, SpawnXp>,
+) {
+    for event in events.read() {
+        writer.write(SpawnXp {
+            position: event.position,
+            amount: event.dropped_xp,
+        });
+    }
+}
+
+fn spawn_orbs(
+    mut events: EventReader<'_, '_, SpawnXp>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for event in events.read() {
+        let chunk = Position::from(event.position).to_chunk();
+
+        for value in split(event.amount) {
+            let velocity = Vec3::new(
+                (fastrand::f32() * 0.2 - 0.1) * 2.0,
+                fastrand::f32() * 0.2 * 2.0,
+                (fastrand::f32() * 0.2 - 0.1) * 2.0,
+            );
+
+            let orb = commands
+                .spawn((
+                    EntityKind::ExperienceOrb,
+                    Position::from(event.position),
+                    Velocity(velocity),
+                    ExperienceOrb {
+                        value,
+                        count: 1,
+                        age: 0,
+                        following: None,
+                    },
+                ))
+                .id();
+
+            let pkt = ExperienceOrbSpawnS2c {
+                entity_id: VarInt(orb.minecraft_id()),
+                position: event.position.as_dvec3(),
+                count: i16::try_from(value).unwrap_or(i16::MAX),
+            };
+            compose.broadcast_local(&pkt, chunk).send().unwrap();
+        }
+    }
+}
+
+fn tick_orbs(
+    mut orbs: Query<'_, '_, (Entity, &mut ExperienceOrb, &mut Position, &mut Velocity)>,
+    players: Query<
+        '_,
+        '_,
+        (Entity, &Position, &EntitySize, &GameMode),
+        (With<packet_state::Play>, Without<ExperienceOrb>),
+    >,
+    blocks: Res<'_, Blocks>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, mut orb, mut position, mut velocity) in &mut orbs {
+        orb.age += 1;
+
+        if orb.age >= ORB_LIFETIME {
+            destroy_orb(&compose, &mut commands, entity, **position);
+            continue;
+        }
+
+        if orb.age % FOLLOW_INTERVAL == 1 {
+            orb.following = players
+                .iter()
+                .filter(|(.., game_mode)| !game_mode.is_spectator())
+                .map(|(player, player_position, ..)| {
+                    (player, player_position.distance_squared(**position))
+                })
+                .filter(|&(_, distance)| distance < FOLLOW_RANGE * FOLLOW_RANGE)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(player, _)| player);
+        }
+
+        let previous = **position;
+
+        velocity.0.y -= 0.03;
+
+        if let Some(player) = orb.following
+            && let Ok((_, player_position, size, _)) = players.get(player)
+        {
+            let target = **player_position + Vec3::new(0.0, size.height / 2.0, 0.0);
+            let delta = target - **position;
+            let distance = delta.length();
+
+            if distance < FOLLOW_RANGE {
+                let strength = 1.0 - distance / FOLLOW_RANGE;
+                velocity.0 += delta.normalize_or_zero() * strength * strength * 0.1;
+            }
+        }
+
+        **position += velocity.0;
+
+        let mut on_ground = false;
+        // Check slightly below the orb so that orbs resting on a block stay on the ground
+        if let Some(ground) = blocks.ground_height(**position - Vec3::new(0.0, 0.001, 0.0))
+            && position.y < ground
+        {
+            position.y = ground;
+            on_ground = true;
+        }
+
+        let friction = if on_ground { 0.98 * 0.6 } else { 0.98 };
+        velocity.0 *= Vec3::new(friction, 0.98, friction);
+
+        if on_ground {
+            velocity.0.y *= -0.9;
+        }
+
+        let delta = **position - previous;
+        if (delta * 4096.0).abs().max_element() < 1.0 {
+            continue;
+        }
+
+        let entity_id = VarInt(entity.minecraft_id());
+        let chunk = position.to_chunk();
+
+        if delta.abs().max_element() >= 8.0 {
+            let pkt = EntityPositionS2c {
+                entity_id,
+                position: position.as_dvec3(),
+                yaw: ByteAngle(0),
+                pitch: ByteAngle(0),
+                on_ground,
+            };
+            compose.broadcast_local(&pkt, chunk).send().unwrap();
+        } else {
+            let pkt = MoveRelativeS2c {
+                entity_id,
+                #[expect(clippy::cast_possible_truncation)]
+                delta: (delta * 4096.0).to_array().map(|x| x as i16),
+                on_ground,
+            };
+            compose.broadcast_local(&pkt, chunk).send().unwrap();
+        }
+    }
+}
+
+fn merge_orbs(
+    mut orbs: Query<'_, '_, (Entity, &mut ExperienceOrb, &Position)>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    if compose.global().tick % MERGE_INTERVAL != 0 {
+        return;
+    }
+
+    // Orbs of the same value in the same block are merged into the first one found
+    let mut merged = HashMap::new();
+    let mut absorbed = Vec::new();
+
+    for (entity, orb, position) in &orbs {
+        match merged.entry((orb.value, position.floor().as_ivec3())) {
+            Entry::Vacant(entry) => {
+                entry.insert(entity);
+            }
+            Entry::Occupied(entry) => absorbed.push((*entry.get(), entity, *orb, **position)),
+        }
+    }
+
+    for (target, entity, other, position) in absorbed {
+        if let Ok((_, mut orb, _)) = orbs.get_mut(target) {
+            orb.count = orb.count.saturating_add(other.count);
+            orb.age = orb.age.min(other.age);
+        }
+
+        destroy_orb(&compose, &mut commands, entity, position);
+    }
+}
+
+fn pick_up_orbs(
+    mut players: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &ConnectionId,
+            &Position,
+            &EntitySize,
+            &GameMode,
+            &Health,
+            &mut Xp,
+            &mut PickupDelay,
+        ),
+    >,
+    mut orbs: Query<'_, '_, (Entity, &mut ExperienceOrb, &Position)>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (player, &connection, position, size, game_mode, health, mut xp, mut delay) in &mut players
+    {
+        if delay.0 > 0 {
+            delay.0 -= 1;
+            continue;
+        }
+
+        if game_mode.is_spectator() || health.is_dead() {
+            continue;
+        }
+
+        // Players pick up orbs within a block horizontally and half a block vertically
+        let reach = aabb(**position - Vec3::new(0.0, 0.5, 0.0), EntitySize {
+            half_width: size.half_width + 1.0,
+            height: size.height + 1.0,
+        });
+
+        // Orbs picked up earlier this tick are only despawned once commands are applied
+        let Some((entity, mut orb, orb_position)) = orbs
+            .iter_mut()
+            .find(|(_, orb, orb_position)| orb.count > 0 && reach.contains_point(***orb_position))
+        else {
+            continue;
+        };
+
+        delay.0 = PICKUP_DELAY;
+
+        // The client plays the pickup sound when it receives this packet
+        let pkt = ItemPickupAnimationS2c {
+            collected_entity_id: VarInt(entity.minecraft_id()),
+            collector_entity_id: VarInt(player.minecraft_id()),
+            pickup_item_count: VarInt(1),
+        };
+        compose
+            .broadcast_local(&pkt, orb_position.to_chunk())
+            .send()
+            .unwrap();
+
+        let previous_level = xp.get_visual().level;
+        xp.amount = xp.amount.saturating_add(orb.value);
+        let level = xp.get_visual().level;
+
+        if level > previous_level && level % 5 == 0 {
+            let sound = agnostic::sound(ident!("minecraft:entity.player.levelup"), **position)
+                .volume(0.75)
+                .build();
+            compose.unicast(&sound, connection).unwrap();
+        }
+
+        orb.count -= 1;
+        if orb.count == 0 {
+            destroy_orb(&compose, &mut commands, entity, **orb_position);
+        }
+    }
+}
+
+pub struct XpPlugin;
+
+impl Plugin for XpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnXp>();
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (
+                (drop_xp_on_kill, drop_xp_on_player_death, spawn_orbs)
+                    .chain()
+                    .after(CombatSet::Apply),
+                (tick_orbs, merge_orbs, pick_up_orbs).chain(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use hyperion::{Global, Shared, net::IoBuf};
+    use valence_protocol::CompressionThreshold;
+
+    use super::*;
+
+    fn compose() -> Compose {
+        let shared = Arc::new(Shared {
+            compression_threshold: CompressionThreshold(256),
+            compression_level: Default::default(),
+        });
+        Compose::new(
+            shared.compression_level,
+            Global::new(shared),
+            IoBuf::default(),
+        )
+    }
+
+    #[test]
+    fn split_uses_largest_orbs() {
+        assert_eq!(split(10).collect::<Vec<_>>(), [7, 3]);
+        assert_eq!(split(2500).collect::<Vec<_>>(), [2477, 17, 3, 3]);
+    }
+
+    #[test]
+    fn split_keeps_total() {
+        for amount in [0, 1, 5, 100, 1000, u16::MAX] {
+            assert_eq!(split(amount).map(u32::from).sum::<u32>(), u32::from(amount));
+        }
+    }
+
+    #[test]
+    fn orbs_are_only_picked_up_once() {
+        let mut app = App::new();
+        app.insert_resource(compose());
+        app.add_systems(Update, pick_up_orbs);
+
+        let world = app.world_mut();
+        let players: Vec<_> = (0..2)
+            .map(|id| {
+                world
+                    .spawn((
+                        ConnectionId::new(id),
+                        Position::new(0.0, 64.0, 0.0),
+                        EntitySize::default(),
+                        GameMode::default(),
+                        Health::default(),
+                        Xp::default(),
+                        PickupDelay::default(),
+                    ))
+                    .id()
+            })
+            .collect();
+
+        world.spawn((
+            ExperienceOrb {
+                value: 5,
+                count: 1,
+                age: 0,
+                following: None,
+            },
+            Position::new(0.0, 64.0, 0.0),
+        ));
+
+        app.update();
+
+        let world = app.world_mut();
+        let total: u16 = players
+            .iter()
+            .map(|&player| world.get::<Xp>(player).unwrap().amount)
+            .sum();
+        assert_eq!(total, 5);
+        assert!(world.query::<&ExperienceOrb>().iter(world).next().is_none());
+    }
+}
@@ -2,18 +2,36 @@
 
 use valence_protocol::{RawBytes, VarInt, packets::play};
 
-/// Packet to show all parts of the skin.
-#[must_use]
-pub fn show_all(id: i32) -> play::EntityTrackerUpdateS2c<'static> {
-    // https://wiki.vg/Entity_metadata#Entity_Metadata_Format
-    // https://wiki.vg/Entity_metadata#Player
-    // 17 = Metadata, type = byte
-    static BYTES: &[u8] = &[17, 0, 0xff, 0xff];
+/// The metadata of every skin part bit mask
+// https://wiki.vg/Entity_metadata#Entity_Metadata_Format
+// https://wiki.vg/Entity_metadata#Player
+// 17 = Metadata, type = byte, then the bit mask and the end of the metadata
+static SKIN_PARTS: [[u8; 4]; 256] = {
+    let mut table = [[0; 4]; 256];
+    let mut parts = 0;
+    while parts < table.len() {
+        #[expect(clippy::cast_possible_truncation, reason = "parts is less than 256")]
+        let mask = parts as u8;
+        table[parts] = [17, 0, mask, 0xff];
+        parts += 1;
+    }
+    table
+};
 
+/// Packet to show the skin parts in the bit mask. See
+/// [`DisplayedSkinParts`](crate::simulation::metadata::player::DisplayedSkinParts) for the bits.
+#[must_use]
+pub fn skin_parts(id: i32, parts: u8) -> play::EntityTrackerUpdateS2c<'static> {
     let entity_id = VarInt(id);
 
     play::EntityTrackerUpdateS2c {
         entity_id,
-        tracked_values: RawBytes(BYTES.into()),
+        tracked_values: RawBytes(SKIN_PARTS[usize::from(parts)].as_slice().into()),
     }
 }
+
+/// Packet to show all parts of the skin.
+#[must_use]
+pub fn show_all(id: i32) -> play::EntityTrackerUpdateS2c<'static> {
+    skin_parts(id, 0xff)
+}
@@ -8,7 +8,7 @@ use hyperion_utils::EntityExt;
 use tracing::{error, info, warn};
 use valence_bytes::{CowBytes, CowUtf8Bytes, Utf8Bytes};
 use valence_protocol::{
-    ByteAngle, GameMode, Ident, PacketEncoder, RawBytes, VarInt, Velocity,
+    ByteAngle, Ident, PacketEncoder, RawBytes, VarInt, Velocity,
     game_mode::OptGameMode,
     ident,
     packets::play::{
@@ -20,17 +20,24 @@ use valence_registry::{BiomeRegistry, RegistryCodec};
 use valence_server::entity::EntityKind;
 use valence_text::IntoText;
 
-use crate::simulation::{MovementTracking, Pitch, packet_state};
+use crate::simulation::{
+    MovementTracking, Pitch,
+    game_mode::GameMode,
+    movement::MovementState,
+    packet_state,
+    tab_list::{self, SentTabListEntry, TabListEntry},
+};
 
 mod list;
 pub use list::*;
 
 use crate::{
     config::Config,
-    egress::metadata::show_all,
+    egress::metadata::skin_parts,
     net::{Compose, ConnectionId, DataBundle},
     simulation::{
-        PendingTeleportation, Position, Uuid, Yaw, skin::PlayerSkin, util::registry_codec_raw,
+        PendingTeleportation, Position, Uuid, Yaw, metadata::player::DisplayedSkinParts,
+        skin::PlayerSkin, util::registry_codec_raw,
     },
 };
 
@@ -60,6 +67,9 @@ fn process_player_join(
             &Yaw,
             &Pitch,
             &PlayerSkin,
+            Option<&GameMode>,
+            Option<&TabListEntry>,
+            Option<&DisplayedSkinParts>,
         ),
     >,
     others_query: Query<
@@ -72,6 +82,10 @@ fn process_player_join(
             &Position,
             &Yaw,
             &Pitch,
+            Option<&GameMode>,
+            Option<&TabListEntry>,
+            Option<&DisplayedSkinParts>,
+            Option<&ConnectionId>,
             // &EntityFlags,
         ),
     >,
@@ -87,14 +101,28 @@ fn process_player_join(
         let entity_id = event.0;
         let id = entity_id.minecraft_id();
 
-        let (uuid, name, &connection_id, position, yaw, pitch, skin) =
-            match target_query.get(entity_id) {
-                Ok(components) => components,
-                Err(e) => {
-                    error!("player_join_world failed: {e}");
-                    return;
-                }
-            };
+        let (
+            uuid,
+            name,
+            &connection_id,
+            position,
+            yaw,
+            pitch,
+            skin,
+            game_mode,
+            tab_list_entry,
+            displayed_skin_parts,
+        ) = match target_query.get(entity_id) {
+            Ok(components) => components,
+            Err(e) => {
+                error!("player_join_world failed: {e}");
+                return;
+            }
+        };
+
+        // The game mode and tab list entry may be chosen by a game before the player joins
+        let game_mode = game_mode.copied().unwrap_or_default();
+        let tab_list_entry = tab_list_entry.cloned().unwrap_or_default();
 
         let registry_codec = registry_codec_raw();
         let codec = RegistryCodec::default();
@@ -120,11 +148,11 @@ fn process_player_join(
             enable_respawn_screen: false,
             dimension_name,
             hashed_seed: 0,
-            game_mode: GameMode::Survival,
+            game_mode: game_mode.into(),
             is_flat: false,
             last_death_location: None,
             portal_cooldown: 60.into(),
-            previous_game_mode: OptGameMode(Some(GameMode::Survival)),
+            previous_game_mode: OptGameMode(None),
             dimension_type_name: ident!("minecraft:overworld"),
             is_debug: false,
         };
@@ -183,30 +211,56 @@ fn process_player_join(
         let others_len = others_query.iter().len() - 1;
         let mut entries = Vec::with_capacity(others_len);
         let mut spawn_packets = Vec::with_capacity(others_len);
-        let mut show_all_packets = Vec::with_capacity(others_len);
+        let mut skin_parts_packets = Vec::with_capacity(others_len);
         let mut all_player_names = Vec::with_capacity(others_len);
+        let mut spectators = Vec::new();
+
+        let default_entry = TabListEntry::default();
 
         let scope = tracing::info_span!("collect_others").entered();
-        for (current_entity, uuid, name, position, yaw, pitch) in others_query {
+        for (
+            current_entity,
+            uuid,
+            name,
+            position,
+            yaw,
+            pitch,
+            other_game_mode,
+            other_entry,
+            other_skin_parts,
+            other_connection,
+        ) in others_query
+        {
             if entity_id == current_entity {
                 continue;
             }
 
+            let other_game_mode = other_game_mode.copied().unwrap_or_default();
+
             // Update player list entries
             let entry = PlayerListEntry {
-                player_uuid: uuid.0,
                 username: CowUtf8Bytes::Borrowed(name),
-                properties: Cow::Owned(Vec::new()),
-                chat_data: None,
-                listed: true,
-                ping: 20,
-                game_mode: GameMode::Creative,
-                display_name: Some(name.to_string().into_cow_text()),
+                ..tab_list::list_entry(
+                    uuid.0,
+                    other_entry.unwrap_or(&default_entry),
+                    other_game_mode,
+                )
             };
 
             entries.push(entry);
             all_player_names.push(name.to_string());
 
+            if other_game_mode.is_spectator()
+                && let Some(&other_connection) = other_connection
+            {
+                spectators.push(other_connection);
+            }
+
+            // Spectators are only visible to other spectators
+            if other_game_mode.is_spectator() && !game_mode.is_spectator() {
+                continue;
+            }
+
             // Spawn the current entity for the player that is joining
             let pkt = play::PlayerSpawnS2c {
                 entity_id: VarInt(current_entity.minecraft_id()),
@@ -217,7 +271,8 @@ fn process_player_join(
             };
 
             spawn_packets.push(pkt);
-            show_all_packets.push(show_all(current_entity.minecraft_id()));
+            let other_skin_parts = other_skin_parts.copied().unwrap_or_default();
+            skin_parts_packets.push(skin_parts(current_entity.minecraft_id(), *other_skin_parts));
         }
         scope.exit();
 
@@ -229,6 +284,7 @@ fn process_player_join(
 
         let actions = PlayerListActions::default()
             .with_add_player(true)
+            .with_update_game_mode(true)
             .with_update_listed(true)
             .with_update_display_name(true);
 
@@ -251,10 +307,10 @@ fn process_player_join(
         }
 
         {
-            let _scope = tracing::info_span!("unicasting_show_all").entered();
+            let _scope = tracing::info_span!("unicasting_skin_parts").entered();
 
-            for show_all in &show_all_packets {
-                bundle.add_packet(show_all).unwrap();
+            for skin_parts in &skin_parts_packets {
+                bundle.add_packet(skin_parts).unwrap();
             }
         }
 
@@ -273,14 +329,9 @@ fn process_player_join(
         let property = &[property];
 
         let singleton_entry = &[PlayerListEntry {
-            player_uuid: **uuid,
             username: CowUtf8Bytes::Borrowed(name),
             properties: Cow::Borrowed(property),
-            chat_data: None,
-            listed: true,
-            ping: 20,
-            game_mode: GameMode::Survival,
-            display_name: Some(name.to_string().into_cow_text()),
+            ..tab_list::list_entry(**uuid, &tab_list_entry, game_mode)
         }];
 
         let pkt = PlayerListS2c {
@@ -314,14 +365,24 @@ fn process_player_join(
             yaw: ByteAngle::from_degrees(**yaw),
             pitch: ByteAngle::from_degrees(**pitch),
         };
-        compose
-            .broadcast(&spawn_player)
-            .exclude(connection_id)
-            .send()
-            .unwrap();
+        let displayed_skin_parts = displayed_skin_parts.copied().unwrap_or_default();
+        let skin_parts = skin_parts(entity_id.minecraft_id(), *displayed_skin_parts);
+
+        if game_mode.is_spectator() {
+            // Players joining in spectator mode are only visible to other spectators
+            for &spectator in &spectators {
+                compose.unicast(&spawn_player, spectator).unwrap();
+                compose.unicast(&skin_parts, spectator).unwrap();
+            }
+        } else {
+            compose
+                .broadcast(&spawn_player)
+                .exclude(connection_id)
+                .send()
+                .unwrap();
 
-        let show_all = show_all(entity_id.minecraft_id());
-        compose.broadcast(&show_all).send().unwrap();
+            compose.broadcast(&skin_parts).send().unwrap();
+        }
 
         bundle
             .add_packet(&play::TeamS2c {
@@ -346,6 +407,9 @@ fn process_player_join(
                     sprinting: false,
                     was_on_ground: false,
                 },
+                MovementState::default(),
+                SentTabListEntry(Some(tab_list_entry.clone())),
+                tab_list_entry,
                 PendingTeleportation::new(position),
                 packet_state::Play(()),
             ));
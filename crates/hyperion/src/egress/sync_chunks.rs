@@ -16,13 +16,19 @@ use crate::{
     simulation::{
         ChunkPosition, Position,
         blocks::{Blocks, GetChunk},
+        client_settings::ClientSettings,
         packet_state,
     },
 };
 
 #[derive(Component, Deref, DerefMut, Default)]
 pub struct ChunkSendQueue {
+    #[deref]
+    #[deref_mut]
     changes: Vec<I16Vec2>,
+    /// The view distance the chunks around the last sent position were sent with, or 0 if no
+    /// chunks were sent yet
+    radius: i16,
 }
 
 pub struct SyncChunksPlugin;
@@ -47,24 +53,38 @@ fn generate_chunk_changes(
             &mut ChunkPosition,
             &mut ChunkSendQueue,
             &Position,
+            Option<&ClientSettings>,
         ),
         With<packet_state::Play>,
     >,
 ) {
     let compose = compose.into_inner();
-    let radius = config.view_distance;
-    let liberal_radius = radius + 2;
-    query
-        .par_iter_mut()
-        .for_each(|(&stream_id, mut last_sent, mut chunk_changes, pose)| {
+    query.par_iter_mut().for_each(
+        |(&stream_id, mut last_sent, mut chunk_changes, pose, settings)| {
             let last_sent_chunk = last_sent.position;
+            let last_radius = chunk_changes.radius;
 
             let current_chunk = pose.to_chunk();
+            let radius = settings.map_or(config.view_distance, |settings| {
+                settings.effective_view_distance(config.view_distance)
+            });
+            let liberal_radius = radius + 2;
 
-            if last_sent_chunk == current_chunk {
+            if last_sent_chunk == current_chunk && last_radius == radius {
                 return;
             }
 
+            if last_radius != radius {
+                let pkt = play::ChunkLoadDistanceS2c {
+                    view_distance: VarInt(i32::from(radius)),
+                };
+
+                if let Err(e) = compose.unicast(&pkt, stream_id) {
+                    error!("failed to send chunk load distance packet: {e}");
+                    return;
+                }
+            }
+
             // center chunk
             let center_chunk = play::ChunkRenderDistanceCenterS2c {
                 chunk_x: VarInt(i32::from(current_chunk.x)),
@@ -80,9 +100,12 @@ fn generate_chunk_changes(
             }
 
             last_sent.position = current_chunk;
+            chunk_changes.radius = radius;
 
-            let last_sent_range_x = (last_sent_chunk.x - radius)..(last_sent_chunk.x + radius);
-            let last_sent_range_z = (last_sent_chunk.y - radius)..(last_sent_chunk.y + radius);
+            let last_sent_range_x =
+                (last_sent_chunk.x - last_radius)..(last_sent_chunk.x + last_radius);
+            let last_sent_range_z =
+                (last_sent_chunk.y - last_radius)..(last_sent_chunk.y + last_radius);
 
             let current_range_x = (current_chunk.x - radius)..(current_chunk.x + radius);
             let current_range_z = (current_chunk.y - radius)..(current_chunk.y + radius);
@@ -154,7 +177,8 @@ fn generate_chunk_changes(
                 });
                 chunk_changes.dedup();
             }
-        });
+        },
+    );
 }
 
 fn send_full_loaded_chunks(
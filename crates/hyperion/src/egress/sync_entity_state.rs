@@ -1,8 +1,6 @@
 use bevy::{ecs::batching::BatchingStrategy, prelude::*};
 use glam::{IVec3, Vec3};
 use hyperion_utils::{EntityExt, Prev, track_prev};
-use itertools::Either;
-use tracing::error;
 use valence_bytes::CowBytes;
 use valence_protocol::{
     ByteAngle, RawBytes, VarInt,
@@ -13,15 +11,14 @@ use crate::{
     Blocks,
     net::{Compose, ConnectionId, DataBundle},
     simulation::{
-        EntitySize, Flight, MovementTracking, Owner, PendingTeleportation, Pitch, Position,
-        Velocity, Xp, Yaw,
+        EntitySize, Flight, MovementTracking, PendingTeleportation, Pitch, Position, Velocity, Xp,
+        Yaw,
         animation::ActiveAnimation,
-        event,
         event::HitGroundEvent,
         handlers::is_grounded,
         metadata::{MetadataChanges, get_and_clear_metadata},
+        movement::{self, MovementState},
     },
-    spatial::{SpatialIndex, get_first_collision},
 };
 
 pub struct EntityStateSyncPlugin;
@@ -104,6 +101,8 @@ fn sync_player_entity(
             &Pitch,
             Option<&mut PendingTeleportation>,
             &mut MovementTracking,
+            &MovementState,
+            &EntitySize,
             &Flight,
         ),
     >,
@@ -128,6 +127,8 @@ fn sync_player_entity(
                 pitch,
                 pending_teleport,
                 mut tracking,
+                state,
+                &size,
                 flight,
             )| {
                 let entity_id = VarInt(entity.minecraft_id());
@@ -160,10 +161,9 @@ fn sync_player_entity(
                         tracking.received_movement_packets = 1;
                     }
 
-                    // Replace 100 by 300 if fall flying (aka elytra)
                     if f64::from(position_delta.length_squared())
                         - tracking.server_velocity.length_squared()
-                        > 100f64 * f64::from(tracking.received_movement_packets)
+                        > state.movement_tolerance() * f64::from(tracking.received_movement_packets)
                     {
                         commands.command_scope(|mut commands| {
                             commands
@@ -188,7 +188,10 @@ fn sync_player_entity(
                         tracking.fall_start_y = position.y;
                     }
 
-                    if (tracking.last_tick_flying && flight.allow) || position_delta.y >= 0. {
+                    if (tracking.last_tick_flying && flight.allow)
+                        || position_delta.y >= 0.
+                        || state.resets_fall()
+                    {
                         tracking.fall_start_y = position.y;
                     }
 
@@ -273,110 +276,25 @@ fn sync_player_entity(
 
                     if let Some(state) = blocks.get_block(IVec3::new(block_x, block_y, block_z)) {
                         let kind = state.to_kind();
-                        friction = f64::from(0.91 * kind.slipperiness() * kind.speed_factor());
+                        // Slowing blocks such as soul sand are part of the movement state
+                        friction = f64::from(0.91 * kind.slipperiness());
                     }
                 }
 
-                tracking.server_velocity.x *= friction * 0.98;
-                tracking.server_velocity.y -= 0.08 * 0.980_000_019_073_486_3;
-                tracking.server_velocity.z *= friction * 0.98;
-
-                if tracking.server_velocity.x.abs() < 0.003 {
-                    tracking.server_velocity.x = 0.;
-                }
-
-                if tracking.server_velocity.y.abs() < 0.003 {
-                    tracking.server_velocity.y = 0.;
-                }
-
-                if tracking.server_velocity.z.abs() < 0.003 {
-                    tracking.server_velocity.z = 0.;
-                }
+                movement::predict_velocity(
+                    &mut tracking,
+                    state,
+                    **position,
+                    size,
+                    friction,
+                    &blocks,
+                );
             },
         );
 
     event_writer.write_batch(events);
 }
 
-fn update_projectile_positions(
-    arrow_query: Query<'_, '_, (Entity, &Owner)>,
-    mut query_set: ParamSet<
-        '_,
-        '_,
-        (
-            Query<'_, '_, (&mut Position, &mut Velocity)>,
-            Query<'_, '_, (&Position, &EntitySize)>,
-        ),
-    >,
-    mut projectile_block_writer: EventWriter<'_, event::ProjectileBlockEvent>,
-    mut projectile_entity_writer: EventWriter<'_, event::ProjectileEntityEvent>,
-    index: Res<'_, SpatialIndex>,
-    blocks: Res<'_, Blocks>,
-) {
-    for (arrow_entity, owner) in arrow_query.iter() {
-        let pv_query = query_set.p0();
-        let (position, velocity) = match pv_query.get(arrow_entity) {
-            Ok(data) => data,
-            Err(e) => {
-                error!("failed to update projectile positions: query failed: {e}");
-                continue;
-            }
-        };
-
-        if velocity.0 == Vec3::ZERO {
-            continue;
-        }
-
-        let center = **position;
-
-        // getting max distance
-        let distance = velocity.0.length();
-
-        let ray = geometry::ray::Ray::new(center, velocity.0) * distance;
-
-        match get_first_collision(ray, &index, &blocks, query_set.p1(), Some(owner.entity)) {
-            Some(Either::Left(entity)) => {
-                // send event
-                projectile_entity_writer.write(event::ProjectileEntityEvent {
-                    client: entity,
-                    projectile: arrow_entity,
-                });
-            }
-            Some(Either::Right(collision)) => {
-                // send event
-                projectile_block_writer.write(event::ProjectileBlockEvent {
-                    collision,
-                    projectile: arrow_entity,
-                });
-            }
-            None => {
-                let mut pv_query = query_set.p0();
-                let (mut position, mut velocity) = match pv_query.get_mut(arrow_entity) {
-                    Ok(data) => data,
-                    Err(e) => {
-                        error!("failed to update projectile positions: query failed: {e}");
-                        continue;
-                    }
-                };
-
-                // Drag (0.99 / 20.0)
-                // 1.0 - (0.99 / 20.0) * 0.05
-                velocity.0 *= 0.997_525;
-
-                // Gravity (20 MPSS)
-                velocity.0.y -= 0.05;
-
-                // Terminal Velocity max (100.0)
-                velocity.0 = velocity.0.clamp_length_max(100.0);
-
-                position.x += velocity.0.x;
-                position.y += velocity.0.y;
-                position.z += velocity.0.z;
-            }
-        }
-    }
-}
-
 impl Plugin for EntityStateSyncPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
@@ -386,7 +304,6 @@ impl Plugin for EntityStateSyncPlugin {
                 entity_metadata_sync,
                 active_animation_sync,
                 sync_player_entity,
-                update_projectile_positions,
             ),
         );
 
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
 use bevy::prelude::*;
 use colored::Colorize;
@@ -20,7 +20,9 @@ use crate::{
     InitializePlayerPosition,
     command_channel::CommandChannel,
     egress::sync_chunks::ChunkSendQueue,
-    net::{Compose, MINECRAFT_VERSION, PROTOCOL_VERSION, PacketDecoder},
+    net::{
+        Compose, MINECRAFT_VERSION, PROTOCOL_VERSION, PacketDecoder, packets::LoginDisconnectS2c,
+    },
     runtime::AsyncRuntime,
     simulation::{
         AiTargetable,
@@ -122,8 +124,19 @@ fn process_status_ping(
             .unwrap();
     }
 }
+
+/// Rejects a player who is logging in. Send this for the sender of a
+/// [`LoginHello`](packet::login::LoginHello) from a system which runs before
+/// [`process_login_hello`]. The player is disconnected with `reason` instead of joining.
+#[derive(Event, Clone, Debug)]
+pub struct DenyLogin {
+    pub entity: Entity,
+    pub reason: hyperion_text::Text<'static>,
+}
+
 pub fn process_login_hello(
     mut packets: EventReader<'_, '_, packet::login::LoginHello>,
+    mut denials: EventReader<'_, '_, DenyLogin>,
     compose: Res<'_, Compose>,
     runtime: Res<'_, AsyncRuntime>,
     skins_collection: Res<'_, SkinHandler>,
@@ -132,8 +145,26 @@ pub fn process_login_hello(
     mut commands: Commands<'_, '_>,
     mut query: Query<'_, '_, &mut PacketDecoder>,
 ) {
+    let denials: HashMap<_, _> = denials
+        .read()
+        .map(|denial| (denial.entity, &denial.reason))
+        .collect();
+
     for packet in packets.read() {
         let sender = packet.sender();
+
+        if let Some(reason) = denials.get(&sender) {
+            info!("Denied login: {}", packet.username);
+
+            let pkt = LoginDisconnectS2c {
+                reason: (*reason).clone(),
+            };
+            compose
+                .unicast_no_compression(&pkt, packet.connection_id())
+                .unwrap();
+            compose.io_buf().shutdown(packet.connection_id());
+            continue;
+        }
         let mut decoder = query
             .get_mut(sender)
             .expect("PacketDecoder must be available for player");
@@ -233,7 +264,8 @@ pub fn process_login_hello(
 }
 
 /// Get a [`uuid::Uuid`] based on the given user's name.
-fn offline_uuid(username: &str) -> uuid::Uuid {
+#[must_use]
+pub fn offline_uuid(username: &str) -> uuid::Uuid {
     let digest = sha2::Sha256::digest(username);
     let digest: [u8; 32] = digest.into();
     let (&digest, ..) = digest.split_array_ref::<16>();
@@ -283,6 +315,7 @@ pub struct IngressPlugin;
 impl Plugin for IngressPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(decode::DecodePlugin);
+        app.add_event::<DenyLogin>();
         app.add_systems(
             FixedUpdate,
             (
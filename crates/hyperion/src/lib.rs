@@ -72,6 +72,15 @@ pub mod storage;
 
 pub trait PacketBundle {
     fn encode_including_ids(self, w: impl Write) -> anyhow::Result<()>;
+
+    /// The packets of the bundle, each of which is sent as its own frame. Bundles made up of
+    /// several packets override this, while single packets are their own bundle.
+    fn packets(self) -> impl Iterator<Item = impl PacketBundle>
+    where
+        Self: Sized,
+    {
+        std::iter::once(self)
+    }
 }
 
 impl<T: Packet + Encode> PacketBundle for &T {
@@ -1,7 +1,16 @@
 //! Agnostic networking primitives. Translates to correct protocol version.
 
 mod chat;
-pub use chat::{Chat, chat};
+pub use chat::{Chat, chat, chat_text};
+
+mod particle;
+pub use particle::{ParticleBuilder, ParticleEffect, Shape, ShapeEmitter, particle};
 
 mod sound;
 pub use sound::{Sound, SoundBuilder, sound};
+
+mod title;
+pub use title::{
+    ActionBar, ClearTitle, Title, TitleBuilder, TitlePacket, action_bar, clear_title, reset_title,
+    title,
+};
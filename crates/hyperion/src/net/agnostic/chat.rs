@@ -1,19 +1,22 @@
 use std::io::Write;
 
-use valence_protocol::packets::play;
-use valence_text::IntoText;
+use hyperion_text::Text;
 
-use crate::PacketBundle;
+use crate::{PacketBundle, net::packets::GameMessageS2c};
 
 pub struct Chat {
-    raw: play::GameMessageS2c<'static>,
+    raw: GameMessageS2c<'static>,
 }
 
 pub fn chat(chat: impl Into<String>) -> Chat {
-    let chat = chat.into();
+    chat_text(Text::from(chat.into()))
+}
+
+/// A chat message with formatting, click events or hover events
+pub fn chat_text(chat: impl Into<Text<'static>>) -> Chat {
     Chat {
-        raw: play::GameMessageS2c {
-            chat: chat.into_cow_text(),
+        raw: GameMessageS2c {
+            chat: chat.into(),
             overlay: false,
         },
     }
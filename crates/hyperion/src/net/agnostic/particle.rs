@@ -0,0 +1,321 @@
+use std::{
+    borrow::Cow,
+    f64::consts::{PI, TAU},
+    io::Write,
+};
+
+use glam::{DVec3, Vec3};
+use valence_protocol::{Particle, packets::play};
+
+use crate::{
+    PacketBundle,
+    net::{Compose, DataBundle},
+    simulation::Position,
+};
+
+#[must_use]
+pub struct ParticleEffect {
+    raw: play::ParticleS2c<'static>,
+}
+
+impl PacketBundle for &ParticleEffect {
+    fn encode_including_ids(self, mut w: impl Write) -> anyhow::Result<()> {
+        self.raw.encode_including_ids(&mut w)
+    }
+}
+
+#[must_use]
+#[derive(Clone, Debug)]
+pub struct ParticleBuilder {
+    particle: Particle,
+    position: DVec3,
+    offset: Vec3,
+    speed: f32,
+    count: i32,
+    long_distance: bool,
+}
+
+impl ParticleBuilder {
+    /// Spreads the particles randomly by up to this distance on each axis. If the count is 0, this
+    /// is the direction the single particle moves in instead.
+    pub const fn offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub const fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub const fn count(mut self, count: i32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Shows the particles from up to 512 blocks away instead of 32, even if the client has
+    /// reduced particles enabled
+    pub const fn long_distance(mut self, long_distance: bool) -> Self {
+        self.long_distance = long_distance;
+        self
+    }
+
+    fn packet_at(&self, position: DVec3) -> play::ParticleS2c<'_> {
+        play::ParticleS2c {
+            particle: Cow::Borrowed(&self.particle),
+            long_distance: self.long_distance,
+            position,
+            offset: self.offset,
+            max_speed: self.speed,
+            count: self.count,
+        }
+    }
+
+    pub fn build(self) -> ParticleEffect {
+        ParticleEffect {
+            raw: play::ParticleS2c {
+                particle: Cow::Owned(self.particle),
+                long_distance: self.long_distance,
+                position: self.position,
+                offset: self.offset,
+                max_speed: self.speed,
+                count: self.count,
+            },
+        }
+    }
+
+    /// Emits the particles along `shape`, placed relative to the position of the builder, with
+    /// at most `spacing` blocks between neighbouring points.
+    ///
+    /// # Panics
+    /// If `spacing` is not positive
+    pub fn shape(self, shape: Shape, spacing: f64) -> ShapeEmitter {
+        assert!(spacing > 0.0, "particle spacing must be positive");
+
+        let points = shape.points(self.position, spacing);
+        ShapeEmitter {
+            builder: self,
+            points,
+        }
+    }
+}
+
+/// The particle `particle` at `position`. Every [`Particle`] variant which carries data, such as
+/// [`Particle::Dust`] or [`Particle::Block`], is supported by setting its fields.
+pub const fn particle(particle: Particle, position: DVec3) -> ParticleBuilder {
+    ParticleBuilder {
+        particle,
+        position,
+        offset: Vec3::ZERO,
+        speed: 0.0,
+        count: 1,
+        long_distance: false,
+    }
+}
+
+/// A shape drawn with particles. Shapes are relative to the position they are drawn at.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    /// A line from the position to the position plus `end`
+    Line { end: DVec3 },
+    /// A horizontal circle centered on the position
+    Circle { radius: f64 },
+    /// The surface of a sphere centered on the position
+    Sphere { radius: f64 },
+    /// A spiral rising from a horizontal circle centered on the position
+    Helix {
+        radius: f64,
+        height: f64,
+        turns: f64,
+    },
+    /// The edges of a box with one corner at the position and the opposite corner at the position
+    /// plus `size`
+    CuboidOutline { size: DVec3 },
+}
+
+/// The most segments a single part of a shape is split into, so that large shapes or tiny
+/// spacings cannot produce a huge number of particle packets
+pub const MAX_SEGMENTS: usize = 256;
+
+/// The number of segments needed to keep points at most `spacing` apart over `length`, up to
+/// [`MAX_SEGMENTS`]. Points are spread further apart if more segments would be needed.
+#[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn segments(length: f64, spacing: f64) -> usize {
+    let segments = (length / spacing).ceil();
+
+    // NaN is treated as a single segment by `max`
+    if segments >= MAX_SEGMENTS as f64 {
+        MAX_SEGMENTS
+    } else {
+        segments.max(1.0) as usize
+    }
+}
+
+impl Shape {
+    /// The points of the shape drawn at `origin`, at most `spacing` blocks apart
+    #[must_use]
+    pub fn points(&self, origin: DVec3, spacing: f64) -> Vec<DVec3> {
+        match *self {
+            Self::Line { end } => {
+                let n = segments(end.length(), spacing);
+                (0..=n)
+                    .map(|i| origin + end * (i as f64 / n as f64))
+                    .collect()
+            }
+            Self::Circle { radius } => {
+                let n = segments(TAU * radius, spacing);
+                (0..n)
+                    .map(|i| {
+                        let (sin, cos) = (TAU * i as f64 / n as f64).sin_cos();
+                        origin + DVec3::new(cos * radius, 0.0, sin * radius)
+                    })
+                    .collect()
+            }
+            Self::Sphere { radius } => {
+                // Points on a Fibonacci spiral are spread evenly over the surface
+                let golden_angle = PI * (3.0 - 5.0_f64.sqrt());
+                let n = segments(4.0 * PI * radius * radius, spacing * spacing);
+                (0..n)
+                    .map(|i| {
+                        let y = 1.0 - 2.0 * (i as f64 + 0.5) / n as f64;
+                        let ring = y.mul_add(-y, 1.0).sqrt();
+                        let (sin, cos) = (golden_angle * i as f64).sin_cos();
+                        origin + DVec3::new(cos * ring, y, sin * ring) * radius
+                    })
+                    .collect()
+            }
+            Self::Helix {
+                radius,
+                height,
+                turns,
+            } => {
+                let length = (TAU * radius * turns).hypot(height);
+                let n = segments(length, spacing);
+                (0..=n)
+                    .map(|i| {
+                        let t = i as f64 / n as f64;
+                        let (sin, cos) = (TAU * turns * t).sin_cos();
+                        origin + DVec3::new(cos * radius, height * t, sin * radius)
+                    })
+                    .collect()
+            }
+            Self::CuboidOutline { size } => {
+                let mut points = Vec::new();
+
+                // Edges along the x axis include the corners, so the other edges skip them
+                for (y, z) in [(0.0, 0.0), (size.y, 0.0), (0.0, size.z), (size.y, size.z)] {
+                    let n = segments(size.x.abs(), spacing);
+                    points.extend(
+                        (0..=n).map(|i| origin + DVec3::new(size.x * i as f64 / n as f64, y, z)),
+                    );
+                }
+
+                for (x, z) in [(0.0, 0.0), (size.x, 0.0), (0.0, size.z), (size.x, size.z)] {
+                    let n = segments(size.y.abs(), spacing);
+                    points.extend(
+                        (1..n).map(|i| origin + DVec3::new(x, size.y * i as f64 / n as f64, z)),
+                    );
+                }
+
+                for (x, y) in [(0.0, 0.0), (size.x, 0.0), (0.0, size.y), (size.x, size.y)] {
+                    let n = segments(size.z.abs(), spacing);
+                    points.extend(
+                        (1..n).map(|i| origin + DVec3::new(x, y, size.z * i as f64 / n as f64)),
+                    );
+                }
+
+                points
+            }
+        }
+    }
+}
+
+/// A particle emitted at every point of a [`Shape`]
+#[must_use]
+pub struct ShapeEmitter {
+    builder: ParticleBuilder,
+    points: Vec<DVec3>,
+}
+
+impl ShapeEmitter {
+    #[must_use]
+    pub fn points(&self) -> &[DVec3] {
+        &self.points
+    }
+
+    /// One packet for every point of the shape
+    pub fn bundle<'a>(&self, compose: &'a Compose) -> anyhow::Result<DataBundle<'a>> {
+        let mut bundle = DataBundle::new(compose);
+        for &point in &self.points {
+            bundle.add_packet(&self.builder.packet_at(point))?;
+        }
+        Ok(bundle)
+    }
+
+    /// Sends the shape to players near the chunk of the position it is drawn at
+    pub fn broadcast_local(&self, compose: &Compose) -> anyhow::Result<()> {
+        let chunk = Position::from(self.builder.position.as_vec3()).to_chunk();
+        self.bundle(compose)?.broadcast_local(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec3;
+
+    use super::{MAX_SEGMENTS, Shape};
+
+    fn max_gap(points: &[DVec3]) -> f64 {
+        points
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn line_includes_both_ends() {
+        let end = DVec3::new(3.0, 4.0, 0.0);
+        let points = Shape::Line { end }.points(DVec3::ONE, 0.5);
+
+        assert_eq!(points.len(), 11);
+        assert_eq!(points[0], DVec3::ONE);
+        assert!(points[10].distance(DVec3::ONE + end) < 1e-9);
+        assert!(max_gap(&points) <= 0.5 + 1e-9);
+    }
+
+    #[test]
+    fn circle_points_are_on_radius() {
+        let points = Shape::Circle { radius: 2.0 }.points(DVec3::ZERO, 0.25);
+
+        assert!(points.len() >= 50);
+        assert!(
+            points
+                .iter()
+                .all(|p| (p.length() - 2.0).abs() < 1e-9 && p.y.abs() < 1e-9)
+        );
+        assert!(max_gap(&points) <= 0.25 + 1e-9);
+    }
+
+    #[test]
+    fn cuboid_outline_has_no_duplicate_corners() {
+        let points = Shape::CuboidOutline {
+            size: DVec3::new(1.0, 1.0, 1.0),
+        }
+        .points(DVec3::ZERO, 1.0);
+
+        // Every edge is one segment long, so only the 8 corners are drawn
+        assert_eq!(points.len(), 8);
+    }
+
+    #[test]
+    fn long_lines_are_limited() {
+        let end = DVec3::new(1_000_000.0, 0.0, 0.0);
+        let points = Shape::Line { end }.points(DVec3::ZERO, 0.1);
+
+        assert_eq!(points.len(), MAX_SEGMENTS + 1);
+        assert!(points[MAX_SEGMENTS].distance(end) < 1e-6);
+
+        let points = Shape::Line { end: DVec3::X }.points(DVec3::ZERO, 0.0);
+        assert_eq!(points.len(), MAX_SEGMENTS + 1);
+    }
+}
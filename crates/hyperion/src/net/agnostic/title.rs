@@ -0,0 +1,196 @@
+use std::io::Write;
+
+use hyperion_text::Text;
+use valence_protocol::packets::play;
+
+use crate::{
+    PacketBundle,
+    net::packets::{OverlayMessageS2c, SubtitleS2c, TitleS2c},
+};
+
+#[must_use]
+pub struct TitleBuilder {
+    title: Text<'static>,
+    subtitle: Option<Text<'static>>,
+    times: Option<play::TitleFadeS2c>,
+}
+
+impl TitleBuilder {
+    pub fn subtitle(mut self, subtitle: impl Into<Text<'static>>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Sets how long the title fades in, stays on screen and fades out for, in ticks. Titles which
+    /// do not set this use the times of the previous title, which default to 10, 70 and 20 ticks.
+    pub const fn times(mut self, fade_in: i32, stay: i32, fade_out: i32) -> Self {
+        self.times = Some(play::TitleFadeS2c {
+            fade_in,
+            stay,
+            fade_out,
+        });
+        self
+    }
+
+    pub fn build(self) -> Title {
+        Title {
+            title: TitleS2c {
+                title_text: self.title,
+            },
+            subtitle: self
+                .subtitle
+                .map(|subtitle_text| SubtitleS2c { subtitle_text }),
+            times: self.times,
+        }
+    }
+}
+
+/// A title shown in the middle of the screen. It is sent as its times, subtitle and title
+/// packets, in that order.
+#[must_use]
+pub struct Title {
+    title: TitleS2c<'static>,
+    subtitle: Option<SubtitleS2c<'static>>,
+    times: Option<play::TitleFadeS2c>,
+}
+
+impl PacketBundle for &Title {
+    fn encode_including_ids(self, mut w: impl Write) -> anyhow::Result<()> {
+        for packet in self.packets() {
+            packet.encode_including_ids(&mut w)?;
+        }
+        Ok(())
+    }
+
+    fn packets(self) -> impl Iterator<Item = impl PacketBundle> {
+        self.times
+            .iter()
+            .map(TitlePacket::Times)
+            .chain(self.subtitle.iter().map(TitlePacket::Subtitle))
+            .chain(std::iter::once(TitlePacket::Title(&self.title)))
+    }
+}
+
+/// One of the packets of a [`Title`]
+pub enum TitlePacket<'a> {
+    Times(&'a play::TitleFadeS2c),
+    Subtitle(&'a SubtitleS2c<'static>),
+    Title(&'a TitleS2c<'static>),
+}
+
+impl PacketBundle for TitlePacket<'_> {
+    fn encode_including_ids(self, mut w: impl Write) -> anyhow::Result<()> {
+        match self {
+            Self::Times(pkt) => pkt.encode_including_ids(&mut w),
+            Self::Subtitle(pkt) => pkt.encode_including_ids(&mut w),
+            Self::Title(pkt) => pkt.encode_including_ids(&mut w),
+        }
+    }
+}
+
+pub fn title(title: impl Into<Text<'static>>) -> TitleBuilder {
+    TitleBuilder {
+        title: title.into(),
+        subtitle: None,
+        times: None,
+    }
+}
+
+/// A message shown above the hotbar
+#[must_use]
+pub struct ActionBar {
+    raw: OverlayMessageS2c<'static>,
+}
+
+pub fn action_bar(text: impl Into<Text<'static>>) -> ActionBar {
+    ActionBar {
+        raw: OverlayMessageS2c {
+            action_bar_text: text.into(),
+        },
+    }
+}
+
+#[macro_export]
+macro_rules! action_bar {
+    ($($arg:tt)*) => {
+        $crate::net::agnostic::action_bar(format!($($arg)*))
+    };
+}
+
+impl PacketBundle for &ActionBar {
+    fn encode_including_ids(self, mut w: impl Write) -> anyhow::Result<()> {
+        self.raw.encode_including_ids(&mut w)
+    }
+}
+
+/// Removes the title on screen
+#[must_use]
+pub struct ClearTitle {
+    raw: play::ClearTitleS2c,
+}
+
+/// Removes the title on screen
+pub const fn clear_title() -> ClearTitle {
+    ClearTitle {
+        raw: play::ClearTitleS2c { reset: false },
+    }
+}
+
+/// Removes the title on screen and resets the subtitle and times
+pub const fn reset_title() -> ClearTitle {
+    ClearTitle {
+        raw: play::ClearTitleS2c { reset: true },
+    }
+}
+
+impl PacketBundle for &ClearTitle {
+    fn encode_including_ids(self, mut w: impl Write) -> anyhow::Result<()> {
+        self.raw.encode_including_ids(&mut w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::{Decode, Packet, VarInt};
+
+    use super::*;
+
+    fn packet_ids(title: &Title) -> Vec<i32> {
+        title
+            .packets()
+            .map(|packet| {
+                let mut bytes = Vec::new();
+                packet.encode_including_ids(&mut bytes).unwrap();
+                VarInt::decode(&mut bytes.as_slice()).unwrap().0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn titles_are_sent_after_their_times_and_subtitle() {
+        let full = title("Title").subtitle("Subtitle").times(5, 40, 5).build();
+        assert_eq!(packet_ids(&full), [
+            play::TitleFadeS2c::ID,
+            SubtitleS2c::ID,
+            TitleS2c::ID
+        ]);
+
+        let plain = title("Title").build();
+        assert_eq!(packet_ids(&plain), [TitleS2c::ID]);
+    }
+
+    #[test]
+    fn encoding_a_title_writes_every_packet() {
+        let full = title("Title").subtitle("Subtitle").times(5, 40, 5).build();
+
+        let mut separate = Vec::new();
+        for packet in full.packets() {
+            packet.encode_including_ids(&mut separate).unwrap();
+        }
+
+        let mut bundle = Vec::new();
+        full.encode_including_ids(&mut bundle).unwrap();
+
+        assert_eq!(bundle, separate);
+    }
+}
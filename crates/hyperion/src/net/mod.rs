@@ -3,6 +3,7 @@
 use std::{
     cell::{Cell, RefCell},
     fmt::Debug,
+    net::IpAddr,
     sync::atomic::{AtomicU32, Ordering},
 };
 
@@ -78,6 +79,18 @@ impl ConnectionId {
     }
 }
 
+/// The IP address a client connected from, as reported by the proxy. Clients which did not connect
+/// over IP, such as through a Unix socket, do not have this component.
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq, Deref)]
+pub struct ClientAddress(IpAddr);
+
+impl ClientAddress {
+    #[must_use]
+    pub const fn new(address: IpAddr) -> Self {
+        Self(address)
+    }
+}
+
 /// A singleton that can be used to compose and encode packets.
 #[derive(Resource)]
 pub struct Compose {
@@ -122,6 +135,15 @@ impl<'a> DataBundle<'a> {
         Ok(())
     }
 
+    pub fn broadcast(&self) -> anyhow::Result<()> {
+        if self.data.is_empty() {
+            return Ok(());
+        }
+
+        self.compose.io_buf.broadcast_raw(&self.data, 0);
+        Ok(())
+    }
+
     // todo: use builder pattern for excluding
     pub fn broadcast_local(&self, center: I16Vec2) -> anyhow::Result<()> {
         if self.data.is_empty() {
@@ -413,10 +435,14 @@ impl IoBuf {
         let scratch = compose.scratch();
         let mut scratch = scratch.borrow_mut();
 
-        let result =
-            compose
-                .encoder()
-                .append_packet(packet, temp_buffer, &mut *scratch, &mut compressor)?;
+        let encoder = compose.encoder();
+        let mut result = BytesMut::new();
+
+        for packet in packet.packets() {
+            let data =
+                encoder.append_packet(packet, temp_buffer, &mut *scratch, &mut compressor)?;
+            result.unsplit(data);
+        }
 
         Ok(result)
     }
@@ -428,7 +454,11 @@ impl IoBuf {
         let temp_buffer = self.temp_buffer.get_or_default();
         let temp_buffer = &mut *temp_buffer.borrow_mut();
 
-        let result = append_packet_without_compression(packet, temp_buffer)?;
+        let mut result = BytesMut::new();
+
+        for packet in packet.packets() {
+            result.unsplit(append_packet_without_compression(packet, temp_buffer)?);
+        }
 
         Ok(result)
     }
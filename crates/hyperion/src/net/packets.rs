@@ -79,3 +79,35 @@ pub enum BossBarAction<'a> {
     UpdateStyle(BossBarColor, BossBarDivision),
     UpdateFlags(BossBarFlags),
 }
+
+#[derive(Clone, Debug, Encode, Packet)]
+pub struct TitleS2c<'a> {
+    pub title_text: hyperion_text::Text<'a>,
+}
+
+#[derive(Clone, Debug, Encode, Packet)]
+pub struct SubtitleS2c<'a> {
+    pub subtitle_text: hyperion_text::Text<'a>,
+}
+
+#[derive(Clone, Debug, Encode, Packet)]
+pub struct GameMessageS2c<'a> {
+    pub chat: hyperion_text::Text<'a>,
+    pub overlay: bool,
+}
+
+#[derive(Clone, Debug, Encode, Packet)]
+pub struct OverlayMessageS2c<'a> {
+    pub action_bar_text: hyperion_text::Text<'a>,
+}
+
+#[derive(Clone, Debug, Encode, Packet)]
+#[packet(state = valence_protocol::PacketState::Login)]
+pub struct LoginDisconnectS2c<'a> {
+    pub reason: hyperion_text::Text<'a>,
+}
+
+#[derive(Clone, Debug, Encode, Packet)]
+pub struct DisconnectS2c<'a> {
+    pub reason: hyperion_text::Text<'a>,
+}
@@ -1,6 +1,10 @@
 //! Communication to a proxy which forwards packets to the players.
 
-use std::{io::Cursor, net::SocketAddr, process::Command};
+use std::{
+    io::Cursor,
+    net::{IpAddr, SocketAddr},
+    process::Command,
+};
 
 use bevy::prelude::*;
 use bytes::{Buf, BytesMut};
@@ -12,7 +16,7 @@ use tracing::{error, info, warn};
 use crate::{
     ConnectionId, PacketDecoder,
     command_channel::CommandChannel,
-    net::Compose,
+    net::{ClientAddress, Compose},
     runtime::AsyncRuntime,
     simulation::{EgressComm, StreamLookup, packet_state},
 };
@@ -60,6 +64,7 @@ async fn handle_proxy_messages(
         match result {
             ArchivedProxyToServerMessage::PlayerConnect(message) => {
                 let Ok(stream) = rkyv::deserialize::<u64, !>(&message.stream);
+                let Ok(address) = rkyv::deserialize::<Option<IpAddr>, !>(&message.address);
 
                 let (sender, receiver) = packet_channel::channel(DEFAULT_FRAGMENT_SIZE);
                 if player_packet_sender.insert(stream, sender).is_some() {
@@ -78,6 +83,9 @@ async fn handle_proxy_messages(
                             receiver,
                         ))
                         .id();
+                    if let Some(address) = address {
+                        world.entity_mut(player).insert(ClientAddress::new(address));
+                    }
                     world
                         .get_resource_mut::<StreamLookup>()
                         .expect("StreamLookup resource should exist")
@@ -0,0 +1,904 @@
+//! Entity attributes such as max health, movement speed, attack damage and armor.
+//!
+//! Each attribute has a base value and a list of modifiers. The final value is computed the same
+//! way as vanilla:
+//!
+//! 1. all [`AttributeOperation::Add`] modifiers are added to the base value
+//! 2. the result is multiplied by `1 + sum(MultiplyBase)`
+//! 3. the result is multiplied by `1 + amount` for every [`AttributeOperation::MultiplyTotal`]
+//!    modifier
+//! 4. the value is clamped to the range of the attribute
+//!
+//! Modifiers from equipment and status effects are managed by the [`AttributePlugin`]. Plugins can
+//! add their own modifiers through [`Attributes::add_modifier`].
+
+use bevy::prelude::*;
+use hyperion_inventory::PlayerInventory;
+use hyperion_utils::EntityExt;
+use uuid::Uuid;
+use valence_protocol::{
+    ItemKind, ItemStack, VarInt,
+    nbt::{Value, list::List},
+    packets::play::{self, entity_attributes_s2c},
+};
+
+use crate::{
+    net::{Compose, ConnectionId},
+    simulation::{
+        RunningSpeed,
+        effect::{self, EffectAdded, EffectKind, EffectRemoved},
+        metadata::living_entity::Health,
+        packet_state,
+    },
+};
+
+/// An attribute of a living entity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeKind {
+    MaxHealth,
+    KnockbackResistance,
+    MovementSpeed,
+    AttackDamage,
+    AttackKnockback,
+    AttackSpeed,
+    Armor,
+    ArmorToughness,
+    Luck,
+}
+
+impl AttributeKind {
+    pub const ALL: [Self; 9] = [
+        Self::MaxHealth,
+        Self::KnockbackResistance,
+        Self::MovementSpeed,
+        Self::AttackDamage,
+        Self::AttackKnockback,
+        Self::AttackSpeed,
+        Self::Armor,
+        Self::ArmorToughness,
+        Self::Luck,
+    ];
+
+    /// The identifier used by the protocol and in item NBT
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::MaxHealth => "minecraft:generic.max_health",
+            Self::KnockbackResistance => "minecraft:generic.knockback_resistance",
+            Self::MovementSpeed => "minecraft:generic.movement_speed",
+            Self::AttackDamage => "minecraft:generic.attack_damage",
+            Self::AttackKnockback => "minecraft:generic.attack_knockback",
+            Self::AttackSpeed => "minecraft:generic.attack_speed",
+            Self::Armor => "minecraft:generic.armor",
+            Self::ArmorToughness => "minecraft:generic.armor_toughness",
+            Self::Luck => "minecraft:generic.luck",
+        }
+    }
+
+    /// Parses an attribute key. The `minecraft:` namespace is optional.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.strip_prefix("minecraft:").unwrap_or(key);
+        Self::ALL
+            .into_iter()
+            .find(|kind| &kind.key()["minecraft:".len()..] == key)
+    }
+
+    /// The base value of this attribute for players
+    #[must_use]
+    pub const fn default_base(self) -> f64 {
+        match self {
+            Self::MaxHealth => 20.0,
+            Self::MovementSpeed => 0.1,
+            Self::AttackDamage => 1.0,
+            Self::AttackSpeed => 4.0,
+            Self::KnockbackResistance
+            | Self::AttackKnockback
+            | Self::Armor
+            | Self::ArmorToughness
+            | Self::Luck => 0.0,
+        }
+    }
+
+    /// The range the final value is clamped to
+    #[must_use]
+    pub const fn range(self) -> (f64, f64) {
+        match self {
+            Self::MaxHealth => (1.0, 1024.0),
+            Self::KnockbackResistance => (0.0, 1.0),
+            Self::MovementSpeed | Self::AttackSpeed => (0.0, 1024.0),
+            Self::AttackDamage => (0.0, 2048.0),
+            Self::AttackKnockback => (0.0, 5.0),
+            Self::Armor => (0.0, 30.0),
+            Self::ArmorToughness => (0.0, 20.0),
+            Self::Luck => (-1024.0, 1024.0),
+        }
+    }
+
+    /// Whether the vanilla client knows about this attribute on players. Attributes which are not
+    /// synced are only used by the server.
+    const fn is_synced(self) -> bool {
+        !matches!(self, Self::AttackKnockback)
+    }
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeOperation {
+    /// Adds the amount to the base value
+    Add,
+    /// Adds `base * amount` to the value
+    MultiplyBase,
+    /// Multiplies the value by `1 + amount`
+    MultiplyTotal,
+}
+
+impl AttributeOperation {
+    #[must_use]
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::Add => 0,
+            Self::MultiplyBase => 1,
+            Self::MultiplyTotal => 2,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(Self::Add),
+            1 => Some(Self::MultiplyBase),
+            2 => Some(Self::MultiplyTotal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AttributeModifier {
+    /// Identifies the modifier. Adding a modifier with the same id replaces the old one.
+    pub id: Uuid,
+    pub amount: f64,
+    pub operation: AttributeOperation,
+}
+
+impl AttributeModifier {
+    #[must_use]
+    pub const fn new(id: Uuid, amount: f64, operation: AttributeOperation) -> Self {
+        Self {
+            id,
+            amount,
+            operation,
+        }
+    }
+}
+
+/// The base value and modifiers of a single attribute
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeInstance {
+    kind: AttributeKind,
+    base: f64,
+    modifiers: Vec<AttributeModifier>,
+}
+
+impl AttributeInstance {
+    #[must_use]
+    pub const fn new(kind: AttributeKind) -> Self {
+        Self {
+            kind,
+            base: kind.default_base(),
+            modifiers: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn base(&self) -> f64 {
+        self.base
+    }
+
+    #[must_use]
+    pub fn modifiers(&self) -> &[AttributeModifier] {
+        &self.modifiers
+    }
+
+    /// The final value of the attribute after applying all modifiers
+    #[must_use]
+    pub fn value(&self) -> f64 {
+        let amounts = |operation| {
+            self.modifiers
+                .iter()
+                .filter(move |modifier| modifier.operation == operation)
+                .map(|modifier| modifier.amount)
+        };
+
+        let base = self.base + amounts(AttributeOperation::Add).sum::<f64>();
+        let mut value = base * (1.0 + amounts(AttributeOperation::MultiplyBase).sum::<f64>());
+
+        for amount in amounts(AttributeOperation::MultiplyTotal) {
+            value *= 1.0 + amount;
+        }
+
+        let (min, max) = self.kind.range();
+        value.clamp(min, max)
+    }
+}
+
+/// The attributes of a living entity
+#[derive(Component, Clone, Debug)]
+pub struct Attributes {
+    instances: [AttributeInstance; AttributeKind::ALL.len()],
+    /// Bitmask of attributes which changed since they were last synced, indexed by
+    /// [`AttributeKind::index`]
+    dirty: u16,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            instances: AttributeKind::ALL.map(AttributeInstance::new),
+            // Everything is sent once when the entity is first synced
+            dirty: u16::MAX,
+        }
+    }
+}
+
+impl Attributes {
+    #[must_use]
+    pub const fn get(&self, kind: AttributeKind) -> &AttributeInstance {
+        &self.instances[kind.index()]
+    }
+
+    /// The final value of the attribute after applying all modifiers
+    #[must_use]
+    pub fn value(&self, kind: AttributeKind) -> f64 {
+        self.get(kind).value()
+    }
+
+    /// The most health the entity can have, from [`AttributeKind::MaxHealth`]
+    #[must_use]
+    pub fn max_health(&self) -> f32 {
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "max health is clamped to 1.0..=1024.0"
+        )]
+        let max_health = self.value(AttributeKind::MaxHealth) as f32;
+        max_health
+    }
+
+    pub fn set_base(&mut self, kind: AttributeKind, base: f64) {
+        self.instance_mut(kind).base = base;
+    }
+
+    /// Adds a modifier, replacing any existing modifier with the same id
+    pub fn add_modifier(&mut self, kind: AttributeKind, modifier: AttributeModifier) {
+        let instance = self.instance_mut(kind);
+        instance
+            .modifiers
+            .retain(|existing| existing.id != modifier.id);
+        instance.modifiers.push(modifier);
+    }
+
+    /// Removes the modifier with the given id, returning it if it existed
+    pub fn remove_modifier(&mut self, kind: AttributeKind, id: Uuid) -> Option<AttributeModifier> {
+        let instance = &self.instances[kind.index()];
+        let index = instance
+            .modifiers
+            .iter()
+            .position(|modifier| modifier.id == id)?;
+
+        Some(self.instance_mut(kind).modifiers.remove(index))
+    }
+
+    /// Removes all modifiers matching the predicate from every attribute
+    pub fn remove_modifiers_where(
+        &mut self,
+        mut predicate: impl FnMut(&AttributeModifier) -> bool,
+    ) {
+        for kind in AttributeKind::ALL {
+            let instance = &self.instances[kind.index()];
+            if instance.modifiers.iter().any(&mut predicate) {
+                self.instance_mut(kind)
+                    .modifiers
+                    .retain(|modifier| !predicate(modifier));
+            }
+        }
+    }
+
+    fn instance_mut(&mut self, kind: AttributeKind) -> &mut AttributeInstance {
+        self.dirty |= 1 << kind.index();
+        &mut self.instances[kind.index()]
+    }
+
+    fn take_dirty(&mut self) -> impl Iterator<Item = &AttributeInstance> {
+        let dirty = std::mem::take(&mut self.dirty);
+        self.instances
+            .iter()
+            .filter(move |instance| dirty & (1 << instance.kind.index()) != 0)
+    }
+}
+
+/// Modifiers added by hyperion use ids in this range so they can be told apart from modifiers added
+/// by plugins. The low bits identify the source.
+const MODIFIER_ID_PREFIX: u128 = 0x4879_7065_7269_6f6e_0000_0000_0000_0000;
+const EQUIPMENT_MODIFIER_ID: u128 = MODIFIER_ID_PREFIX | (1 << 32);
+const EFFECT_MODIFIER_ID: u128 = MODIFIER_ID_PREFIX | (2 << 32);
+
+/// The most health an entity can have, which is 20 for entities without [`Attributes`]
+#[must_use]
+pub fn max_health(attributes: Option<&Attributes>) -> f32 {
+    attributes.map_or(20.0, Attributes::max_health)
+}
+
+const fn is_equipment_modifier(id: Uuid) -> bool {
+    id.as_u128() >> 32 == EQUIPMENT_MODIFIER_ID >> 32
+}
+
+const fn effect_modifier_id(effect_id: i32) -> Uuid {
+    #[expect(clippy::cast_sign_loss, reason = "effect ids are positive")]
+    let effect_id = effect_id as u128;
+    Uuid::from_u128(EFFECT_MODIFIER_ID | effect_id)
+}
+
+/// An equipment slot which can provide attribute modifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeSlot {
+    MainHand,
+    OffHand,
+    Head,
+    Chest,
+    Legs,
+    Feet,
+}
+
+impl AttributeSlot {
+    pub const ALL: [Self; 6] = [
+        Self::MainHand,
+        Self::OffHand,
+        Self::Head,
+        Self::Chest,
+        Self::Legs,
+        Self::Feet,
+    ];
+
+    /// The name used in the `Slot` field of item attribute modifiers
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::MainHand => "mainhand",
+            Self::OffHand => "offhand",
+            Self::Head => "head",
+            Self::Chest => "chest",
+            Self::Legs => "legs",
+            Self::Feet => "feet",
+        }
+    }
+
+    fn stack(self, inventory: &PlayerInventory) -> &ItemStack {
+        let slot = match self {
+            Self::MainHand => inventory.get_cursor(),
+            Self::OffHand => inventory.get_offhand(),
+            Self::Head => inventory.get_helmet(),
+            Self::Chest => inventory.get_chestplate(),
+            Self::Legs => inventory.get_leggings(),
+            Self::Feet => inventory.get_boots(),
+        };
+        &slot.stack
+    }
+}
+
+/// Returns the attribute modifiers an item provides when held or worn in the given slot.
+///
+/// If the item has an `AttributeModifiers` NBT tag, only those modifiers are used, like in
+/// vanilla. Otherwise, the default modifiers of the item kind are used.
+pub fn item_modifiers(
+    stack: &ItemStack,
+    slot: AttributeSlot,
+) -> Vec<(AttributeKind, AttributeModifier)> {
+    let mut modifiers = Vec::new();
+
+    if stack.is_empty() {
+        return modifiers;
+    }
+
+    let slot_id = (slot as u128) << 16;
+
+    if let Some(Value::List(List::Compound(nbt_modifiers))) = stack
+        .nbt
+        .as_ref()
+        .and_then(|nbt| nbt.get("AttributeModifiers"))
+    {
+        for (i, modifier) in nbt_modifiers.iter().enumerate() {
+            if let Some(Value::String(modifier_slot)) = modifier.get("Slot")
+                && modifier_slot != slot.name()
+            {
+                continue;
+            }
+
+            let Some(Value::String(name)) = modifier.get("AttributeName") else {
+                continue;
+            };
+            let Some(kind) = AttributeKind::from_key(name) else {
+                continue;
+            };
+            let amount = match modifier.get("Amount") {
+                Some(Value::Double(amount)) => *amount,
+                Some(Value::Float(amount)) => f64::from(*amount),
+                _ => continue,
+            };
+            let operation = match modifier.get("Operation") {
+                Some(Value::Int(operation)) => AttributeOperation::from_id(*operation),
+                None => Some(AttributeOperation::Add),
+                _ => None,
+            };
+            let Some(operation) = operation else {
+                continue;
+            };
+
+            let id = Uuid::from_u128(EQUIPMENT_MODIFIER_ID | slot_id | i as u128);
+            modifiers.push((kind, AttributeModifier::new(id, amount, operation)));
+        }
+
+        return modifiers;
+    }
+
+    let defaults = default_item_modifiers(stack.item);
+    if defaults.slot != slot {
+        return modifiers;
+    }
+
+    let stats = [
+        (AttributeKind::AttackDamage, defaults.attack_damage),
+        (AttributeKind::AttackSpeed, defaults.attack_speed),
+        (AttributeKind::Armor, defaults.armor),
+        (AttributeKind::ArmorToughness, defaults.armor_toughness),
+        (
+            AttributeKind::KnockbackResistance,
+            defaults.knockback_resistance,
+        ),
+    ];
+
+    for (kind, amount) in stats {
+        if amount == 0.0 {
+            continue;
+        }
+
+        let id = Uuid::from_u128(EQUIPMENT_MODIFIER_ID | slot_id | (0x100 + kind as u128));
+        modifiers.push((
+            kind,
+            AttributeModifier::new(id, amount, AttributeOperation::Add),
+        ));
+    }
+
+    modifiers
+}
+
+struct DefaultItemModifiers {
+    slot: AttributeSlot,
+    attack_damage: f64,
+    attack_speed: f64,
+    armor: f64,
+    armor_toughness: f64,
+    knockback_resistance: f64,
+}
+
+impl DefaultItemModifiers {
+    const NONE: Self = Self::weapon(0.0, 0.0);
+
+    const fn weapon(attack_damage: f64, attack_speed: f64) -> Self {
+        Self {
+            slot: AttributeSlot::MainHand,
+            attack_damage,
+            attack_speed,
+            armor: 0.0,
+            armor_toughness: 0.0,
+            knockback_resistance: 0.0,
+        }
+    }
+
+    const fn armor(slot: AttributeSlot, armor: f64, armor_toughness: f64) -> Self {
+        Self {
+            slot,
+            attack_damage: 0.0,
+            attack_speed: 0.0,
+            armor,
+            armor_toughness,
+            knockback_resistance: 0.0,
+        }
+    }
+
+    const fn netherite(slot: AttributeSlot, armor: f64) -> Self {
+        let mut modifiers = Self::armor(slot, armor, 3.0);
+        modifiers.knockback_resistance = 0.1;
+        modifiers
+    }
+}
+
+/// The modifiers vanilla items have without any `AttributeModifiers` NBT
+const fn default_item_modifiers(item: ItemKind) -> DefaultItemModifiers {
+    use AttributeSlot::{Chest, Feet, Head, Legs};
+    type M = DefaultItemModifiers;
+
+    match item {
+        ItemKind::WoodenSword | ItemKind::GoldenSword => M::weapon(3.0, -2.4),
+        ItemKind::StoneSword => M::weapon(4.0, -2.4),
+        ItemKind::IronSword => M::weapon(5.0, -2.4),
+        ItemKind::DiamondSword => M::weapon(6.0, -2.4),
+        ItemKind::NetheriteSword => M::weapon(7.0, -2.4),
+
+        ItemKind::WoodenAxe => M::weapon(6.0, -3.2),
+        ItemKind::StoneAxe => M::weapon(8.0, -3.2),
+        ItemKind::IronAxe => M::weapon(8.0, -3.1),
+        ItemKind::GoldenAxe => M::weapon(6.0, -3.0),
+        ItemKind::DiamondAxe => M::weapon(8.0, -3.0),
+        ItemKind::NetheriteAxe => M::weapon(9.0, -3.0),
+
+        ItemKind::WoodenPickaxe | ItemKind::GoldenPickaxe => M::weapon(1.0, -2.8),
+        ItemKind::StonePickaxe => M::weapon(2.0, -2.8),
+        ItemKind::IronPickaxe => M::weapon(3.0, -2.8),
+        ItemKind::DiamondPickaxe => M::weapon(4.0, -2.8),
+        ItemKind::NetheritePickaxe => M::weapon(5.0, -2.8),
+
+        ItemKind::WoodenShovel | ItemKind::GoldenShovel => M::weapon(1.5, -3.0),
+        ItemKind::StoneShovel => M::weapon(2.5, -3.0),
+        ItemKind::IronShovel => M::weapon(3.5, -3.0),
+        ItemKind::DiamondShovel => M::weapon(4.5, -3.0),
+        ItemKind::NetheriteShovel => M::weapon(5.5, -3.0),
+
+        ItemKind::WoodenHoe | ItemKind::GoldenHoe => M::weapon(0.0, -3.0),
+        ItemKind::StoneHoe => M::weapon(0.0, -2.0),
+        ItemKind::IronHoe => M::weapon(0.0, -1.0),
+        ItemKind::DiamondHoe | ItemKind::NetheriteHoe => M::weapon(0.0, 0.0),
+
+        ItemKind::Trident => M::weapon(8.0, -2.9),
+
+        ItemKind::LeatherHelmet => M::armor(Head, 1.0, 0.0),
+        ItemKind::LeatherChestplate => M::armor(Chest, 3.0, 0.0),
+        ItemKind::LeatherLeggings => M::armor(Legs, 2.0, 0.0),
+        ItemKind::LeatherBoots => M::armor(Feet, 1.0, 0.0),
+
+        ItemKind::ChainmailHelmet => M::armor(Head, 2.0, 0.0),
+        ItemKind::ChainmailChestplate => M::armor(Chest, 5.0, 0.0),
+        ItemKind::ChainmailLeggings => M::armor(Legs, 4.0, 0.0),
+        ItemKind::ChainmailBoots => M::armor(Feet, 1.0, 0.0),
+
+        ItemKind::GoldenHelmet => M::armor(Head, 2.0, 0.0),
+        ItemKind::GoldenChestplate => M::armor(Chest, 5.0, 0.0),
+        ItemKind::GoldenLeggings => M::armor(Legs, 3.0, 0.0),
+        ItemKind::GoldenBoots => M::armor(Feet, 1.0, 0.0),
+
+        ItemKind::IronHelmet => M::armor(Head, 2.0, 0.0),
+        ItemKind::IronChestplate => M::armor(Chest, 6.0, 0.0),
+        ItemKind::IronLeggings => M::armor(Legs, 5.0, 0.0),
+        ItemKind::IronBoots => M::armor(Feet, 2.0, 0.0),
+
+        ItemKind::TurtleHelmet => M::armor(Head, 2.0, 0.0),
+
+        ItemKind::DiamondHelmet => M::armor(Head, 3.0, 2.0),
+        ItemKind::DiamondChestplate => M::armor(Chest, 8.0, 2.0),
+        ItemKind::DiamondLeggings => M::armor(Legs, 6.0, 2.0),
+        ItemKind::DiamondBoots => M::armor(Feet, 3.0, 2.0),
+
+        ItemKind::NetheriteHelmet => M::netherite(Head, 3.0),
+        ItemKind::NetheriteChestplate => M::netherite(Chest, 8.0),
+        ItemKind::NetheriteLeggings => M::netherite(Legs, 6.0),
+        ItemKind::NetheriteBoots => M::netherite(Feet, 3.0),
+
+        _ => M::NONE,
+    }
+}
+
+/// The attribute modifiers a status effect applies at the given amplifier
+fn effect_modifiers(
+    kind: EffectKind,
+    amplifier: u8,
+) -> Option<(AttributeKind, f64, AttributeOperation)> {
+    let level = f64::from(amplifier) + 1.0;
+
+    let modifier = match kind {
+        EffectKind::Speed => (
+            AttributeKind::MovementSpeed,
+            0.2 * level,
+            AttributeOperation::MultiplyTotal,
+        ),
+        EffectKind::Slowness => (
+            AttributeKind::MovementSpeed,
+            -0.15 * level,
+            AttributeOperation::MultiplyTotal,
+        ),
+        EffectKind::Haste => (
+            AttributeKind::AttackSpeed,
+            0.1 * level,
+            AttributeOperation::MultiplyTotal,
+        ),
+        EffectKind::MiningFatigue => (
+            AttributeKind::AttackSpeed,
+            -0.1 * level,
+            AttributeOperation::MultiplyTotal,
+        ),
+        EffectKind::Strength => (
+            AttributeKind::AttackDamage,
+            3.0 * level,
+            AttributeOperation::Add,
+        ),
+        EffectKind::Weakness => (
+            AttributeKind::AttackDamage,
+            -4.0 * level,
+            AttributeOperation::Add,
+        ),
+        EffectKind::HealthBoost => (
+            AttributeKind::MaxHealth,
+            4.0 * level,
+            AttributeOperation::Add,
+        ),
+        EffectKind::Luck => (AttributeKind::Luck, level, AttributeOperation::Add),
+        EffectKind::Unluck => (AttributeKind::Luck, -level, AttributeOperation::Add),
+        _ => return None,
+    };
+
+    Some(modifier)
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    commands
+        .entity(trigger.target())
+        .insert((Attributes::default(), RunningSpeed::default()));
+}
+
+fn update_equipment_modifiers(
+    mut query: Query<'_, '_, (&PlayerInventory, &mut Attributes), Changed<PlayerInventory>>,
+) {
+    for (inventory, mut attributes) in &mut query {
+        let modifiers: Vec<_> = AttributeSlot::ALL
+            .into_iter()
+            .flat_map(|slot| item_modifiers(slot.stack(inventory), slot))
+            .collect();
+
+        let current: Vec<_> = AttributeKind::ALL
+            .into_iter()
+            .flat_map(|kind| {
+                attributes
+                    .get(kind)
+                    .modifiers()
+                    .iter()
+                    .filter(|modifier| is_equipment_modifier(modifier.id))
+                    .map(move |modifier| (kind, *modifier))
+            })
+            .collect();
+
+        // Avoid resending attributes when an unrelated slot changed
+        let mut sorted = modifiers.clone();
+        sorted.sort_by_key(|(kind, _)| kind.index());
+        if sorted == current {
+            continue;
+        }
+
+        attributes.remove_modifiers_where(|modifier| is_equipment_modifier(modifier.id));
+        for (kind, modifier) in modifiers {
+            attributes.add_modifier(kind, modifier);
+        }
+    }
+}
+
+fn update_effect_modifiers(
+    mut added: EventReader<'_, '_, EffectAdded>,
+    mut removed: EventReader<'_, '_, EffectRemoved>,
+    mut query: Query<'_, '_, &mut Attributes>,
+) {
+    for event in removed.read() {
+        let (Some(effect_id), Some((kind, ..))) =
+            (event.kind.id(), effect_modifiers(event.kind, 0))
+        else {
+            continue;
+        };
+
+        let Ok(mut attributes) = query.get_mut(event.entity) else {
+            continue;
+        };
+
+        attributes.remove_modifier(kind, effect_modifier_id(effect_id));
+    }
+
+    for event in added.read() {
+        let effect = event.effect;
+        let (Some(effect_id), Some((kind, amount, operation))) = (
+            effect.kind().id(),
+            effect_modifiers(effect.kind(), effect.get_amplifier()),
+        ) else {
+            continue;
+        };
+
+        let Ok(mut attributes) = query.get_mut(event.entity) else {
+            continue;
+        };
+
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(effect_modifier_id(effect_id), amount, operation),
+        );
+    }
+}
+
+fn sync_attributes(
+    mut query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &mut Attributes,
+            Option<&ConnectionId>,
+            Option<&mut RunningSpeed>,
+            Option<&mut Health>,
+        ),
+        Changed<Attributes>,
+    >,
+    compose: Res<'_, Compose>,
+) {
+    for (entity, mut attributes, connection, running_speed, health) in &mut query {
+        if attributes.dirty == 0 {
+            continue;
+        }
+
+        // Health is lowered when max health is, such as when health boost runs out
+        if let Some(mut health) = health {
+            let max_health = attributes.max_health();
+            if **health > max_health {
+                **health = max_health;
+            }
+        }
+
+        if let Some(mut running_speed) = running_speed {
+            #[expect(clippy::cast_possible_truncation)]
+            let speed = attributes.value(AttributeKind::MovementSpeed) as f32;
+            if (running_speed.0 - speed).abs() > f32::EPSILON {
+                running_speed.0 = speed;
+            }
+        }
+
+        let properties: Vec<_> = attributes
+            .bypass_change_detection()
+            .take_dirty()
+            .filter(|instance| instance.kind.is_synced())
+            .map(|instance| entity_attributes_s2c::AttributeProperty {
+                key: valence_protocol::Ident::new(instance.kind.key())
+                    .unwrap()
+                    .into(),
+                value: instance.base,
+                modifiers: instance
+                    .modifiers
+                    .iter()
+                    .map(|modifier| entity_attributes_s2c::AttributeModifier {
+                        uuid: modifier.id,
+                        amount: modifier.amount,
+                        operation: modifier.operation.id(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let Some(&connection) = connection else {
+            continue;
+        };
+
+        if properties.is_empty() {
+            continue;
+        }
+
+        let pkt = play::EntityAttributesS2c {
+            entity_id: VarInt(entity.minecraft_id()),
+            properties,
+        };
+
+        compose.unicast(&pkt, connection).unwrap();
+    }
+}
+
+pub struct AttributePlugin;
+
+impl Plugin for AttributePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (
+                update_equipment_modifiers,
+                update_effect_modifiers.after(effect::sync_effects),
+            ),
+        );
+        app.add_systems(FixedPostUpdate, sync_attributes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifier_operations() {
+        let mut attributes = Attributes::default();
+        let kind = AttributeKind::AttackDamage;
+
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(Uuid::from_u128(1), 3.0, AttributeOperation::Add),
+        );
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(Uuid::from_u128(2), 0.5, AttributeOperation::MultiplyBase),
+        );
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(Uuid::from_u128(3), 1.0, AttributeOperation::MultiplyTotal),
+        );
+
+        // (1 + 3) * (1 + 0.5) * (1 + 1)
+        assert!((attributes.value(kind) - 12.0).abs() < f64::EPSILON);
+
+        attributes.remove_modifier(kind, Uuid::from_u128(3));
+        assert!((attributes.value(kind) - 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn same_id_replaces() {
+        let mut attributes = Attributes::default();
+        let kind = AttributeKind::Armor;
+        let id = Uuid::from_u128(1);
+
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(id, 5.0, AttributeOperation::Add),
+        );
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(id, 2.0, AttributeOperation::Add),
+        );
+
+        assert!((attributes.value(kind) - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn value_is_clamped() {
+        let mut attributes = Attributes::default();
+        let kind = AttributeKind::Armor;
+
+        attributes.add_modifier(
+            kind,
+            AttributeModifier::new(Uuid::from_u128(1), 100.0, AttributeOperation::Add),
+        );
+
+        assert!((attributes.value(kind) - 30.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn heal_up_to_max_health() {
+        let mut attributes = Attributes::default();
+        attributes.add_modifier(
+            AttributeKind::MaxHealth,
+            AttributeModifier::new(Uuid::from_u128(1), 8.0, AttributeOperation::Add),
+        );
+
+        let boosted = max_health(Some(&attributes));
+        let mut health = Health::new(19.0);
+
+        health.heal(100.0, boosted);
+        assert!((*health - 28.0).abs() < f32::EPSILON);
+
+        health.heal(1.0, max_health(None));
+        assert!((*health - 28.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn default_item_modifiers_by_slot() {
+        let sword = ItemStack::new(ItemKind::DiamondSword, 1, None);
+        assert_eq!(item_modifiers(&sword, AttributeSlot::MainHand).len(), 2);
+        assert!(item_modifiers(&sword, AttributeSlot::OffHand).is_empty());
+
+        let helmet = ItemStack::new(ItemKind::NetheriteHelmet, 1, None);
+        assert_eq!(item_modifiers(&helmet, AttributeSlot::Head).len(), 3);
+    }
+}
@@ -8,6 +8,7 @@ use valence_server::layer::chunk::Chunk;
 use super::loader::parse::ColumnData;
 use crate::simulation::blocks::loader::parse::section::Section;
 
+/// The lowest y coordinate of the world
 pub const START_Y: i16 = -64;
 
 #[repr(Rust, packed)]
@@ -118,6 +118,32 @@ impl Blocks {
         let bounds_min = IVec3::new(i32::MIN / 2, -64, i32::MIN / 2);
         let bounds_max = IVec3::new(i32::MAX / 2, 320, i32::MAX / 2);
 
+        self.first_collision_within(ray, bounds_min, bounds_max, f32::INFINITY)
+    }
+
+    /// Like [`Self::first_collision`], but only considers the segment of the ray from its origin
+    /// to `ray.at(1.0)`
+    #[must_use]
+    pub fn first_collision_in_segment(&self, ray: Ray) -> Option<RayCollision> {
+        let start = ray.origin().floor().as_ivec3();
+        let end = ray.at(1.0).floor().as_ivec3();
+
+        // Pad the bounds by a block since the traversal starts at the truncated origin
+        self.first_collision_within(
+            ray,
+            start.min(end) - IVec3::ONE,
+            start.max(end) + IVec3::ONE,
+            1.0,
+        )
+    }
+
+    fn first_collision_within(
+        &self,
+        ray: Ray,
+        bounds_min: IVec3,
+        bounds_max: IVec3,
+        max_distance: f32,
+    ) -> Option<RayCollision> {
         // Use voxel traversal to efficiently walk through blocks
         for cell in ray.voxel_traversal(bounds_min, bounds_max) {
             if let Some(block) = self.get_block(cell) {
@@ -135,6 +161,11 @@ impl Blocks {
 
                 if let Some(distance) = collision {
                     let distance = distance.into_inner();
+
+                    if distance > max_distance {
+                        return None;
+                    }
+
                     let collision_point = ray.origin() + ray.direction() * distance;
                     let collision_normal = (collision_point - origin).normalize();
 
@@ -386,6 +417,21 @@ impl Blocks {
         Some(chunk.block_state(x, y, z))
     }
 
+    /// The height of the top of the block containing `position`, if it has collision
+    #[must_use]
+    pub fn ground_height(&self, position: Vec3) -> Option<f32> {
+        let block = position.floor().as_ivec3();
+        let state = self.get_block(block)?;
+
+        #[expect(clippy::cast_possible_truncation)]
+        let top = state
+            .collision_shapes()
+            .map(|shape| shape.max().y as f32)
+            .reduce(f32::max)?;
+
+        Some(block.as_vec3().y + top)
+    }
+
     /// Returns the old block state
     pub fn set_block(
         &mut self,
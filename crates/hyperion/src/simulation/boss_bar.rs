@@ -0,0 +1,330 @@
+//! Boss bars shown at the top of the screen.
+//!
+//! A boss bar is an entity with a [`BossBar`] component. Viewers are sent the boss bar when they
+//! are added, updates whenever it changes, and a removal when they are removed or the boss bar is
+//! despawned. Players who disconnect are removed from every boss bar.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use hyperion_text::Text;
+use uuid::Uuid;
+use valence_protocol::packets::play::boss_bar_s2c::{BossBarColor, BossBarDivision, BossBarFlags};
+
+use crate::{
+    net::{
+        Compose, ConnectionId, DataBundle,
+        packets::{BossBarAction, BossBarS2c},
+    },
+    simulation::packet_state,
+};
+
+/// A boss bar and the players who can see it
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct BossBar {
+    pub title: Text<'static>,
+    /// How full the bar is, from 0 to 1
+    pub progress: f32,
+    pub color: BossBarColor,
+    pub division: BossBarDivision,
+    pub flags: BossBarFlags,
+    viewers: HashSet<Entity>,
+}
+
+impl BossBar {
+    #[must_use]
+    pub fn new(title: impl Into<Text<'static>>) -> Self {
+        Self {
+            title: title.into(),
+            progress: 1.0,
+            color: BossBarColor::White,
+            division: BossBarDivision::NoDivision,
+            flags: BossBarFlags::default(),
+            viewers: HashSet::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn with_progress(mut self, progress: f32) -> Self {
+        self.progress = progress.clamp(0.0, 1.0);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, color: BossBarColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_division(mut self, division: BossBarDivision) -> Self {
+        self.division = division;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_flags(mut self, flags: BossBarFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    #[must_use]
+    pub fn with_viewer(mut self, viewer: Entity) -> Self {
+        self.viewers.insert(viewer);
+        self
+    }
+
+    /// Shows the boss bar to a player. Returns `false` if they could already see it.
+    pub fn add_viewer(&mut self, viewer: Entity) -> bool {
+        self.viewers.insert(viewer)
+    }
+
+    /// Hides the boss bar from a player. Returns `false` if they could not see it.
+    pub fn remove_viewer(&mut self, viewer: Entity) -> bool {
+        self.viewers.remove(&viewer)
+    }
+
+    #[must_use]
+    pub fn is_viewer(&self, viewer: Entity) -> bool {
+        self.viewers.contains(&viewer)
+    }
+
+    pub fn viewers(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.viewers.iter().copied()
+    }
+}
+
+/// The boss bar last sent to viewers
+#[derive(Component, Debug)]
+struct SentBossBar {
+    id: Uuid,
+    bar: Option<BossBar>,
+}
+
+fn add_packet(id: Uuid, bar: &BossBar) -> BossBarS2c<'_> {
+    BossBarS2c {
+        id,
+        action: BossBarAction::Add {
+            title: bar.title.clone(),
+            health: bar.progress,
+            color: bar.color,
+            division: bar.division,
+            flags: bar.flags,
+        },
+    }
+}
+
+fn send_to<'a>(
+    bundle: &DataBundle<'_>,
+    viewers: impl IntoIterator<Item = &'a Entity>,
+    connections: &Query<'_, '_, &ConnectionId>,
+) {
+    for &viewer in viewers {
+        // Viewers which are not players are skipped
+        let Ok(&connection_id) = connections.get(viewer) else {
+            continue;
+        };
+
+        bundle.unicast(connection_id).unwrap();
+    }
+}
+
+fn initialize_boss_bar(trigger: Trigger<'_, OnAdd, BossBar>, mut commands: Commands<'_, '_>) {
+    commands.entity(trigger.target()).insert(SentBossBar {
+        id: Uuid::new_v4(),
+        bar: None,
+    });
+}
+
+fn remove_boss_bar(
+    trigger: Trigger<'_, OnRemove, BossBar>,
+    query: Query<'_, '_, &SentBossBar>,
+    connections: Query<'_, '_, &ConnectionId>,
+    compose: Res<'_, Compose>,
+) {
+    let Ok(sent) = query.get(trigger.target()) else {
+        return;
+    };
+
+    let Some(bar) = &sent.bar else {
+        return;
+    };
+
+    let mut bundle = DataBundle::new(&compose);
+    bundle
+        .add_packet(&BossBarS2c {
+            id: sent.id,
+            action: BossBarAction::Remove,
+        })
+        .unwrap();
+
+    send_to(&bundle, &bar.viewers, &connections);
+}
+
+fn remove_viewer(
+    trigger: Trigger<'_, OnRemove, packet_state::Play>,
+    query: Query<'_, '_, (&mut BossBar, &mut SentBossBar)>,
+) {
+    let viewer = trigger.target();
+
+    for (mut bar, mut sent) in query {
+        if !bar.is_viewer(viewer) {
+            continue;
+        }
+
+        // The client is gone, so nothing needs to be sent
+        bar.bypass_change_detection().remove_viewer(viewer);
+        if let Some(previous) = &mut sent.bar {
+            previous.remove_viewer(viewer);
+        }
+    }
+}
+
+fn sync_boss_bars(
+    query: Query<'_, '_, (&BossBar, &mut SentBossBar), Changed<BossBar>>,
+    connections: Query<'_, '_, &ConnectionId>,
+    compose: Res<'_, Compose>,
+) {
+    for (bar, mut sent) in query {
+        let id = sent.id;
+
+        if let Some(previous) = &sent.bar {
+            if previous == bar {
+                continue;
+            }
+
+            let mut removed = DataBundle::new(&compose);
+            removed
+                .add_packet(&BossBarS2c {
+                    id,
+                    action: BossBarAction::Remove,
+                })
+                .unwrap();
+            send_to(
+                &removed,
+                previous.viewers.difference(&bar.viewers),
+                &connections,
+            );
+
+            let mut updates = DataBundle::new(&compose);
+
+            if previous.title != bar.title {
+                updates
+                    .add_packet(&BossBarS2c {
+                        id,
+                        action: BossBarAction::UpdateTitle(bar.title.clone()),
+                    })
+                    .unwrap();
+            }
+
+            if (previous.progress - bar.progress).abs() > f32::EPSILON {
+                updates
+                    .add_packet(&BossBarS2c {
+                        id,
+                        action: BossBarAction::UpdateHealth(bar.progress),
+                    })
+                    .unwrap();
+            }
+
+            if previous.color != bar.color || previous.division != bar.division {
+                updates
+                    .add_packet(&BossBarS2c {
+                        id,
+                        action: BossBarAction::UpdateStyle(bar.color, bar.division),
+                    })
+                    .unwrap();
+            }
+
+            if previous.flags != bar.flags {
+                updates
+                    .add_packet(&BossBarS2c {
+                        id,
+                        action: BossBarAction::UpdateFlags(bar.flags),
+                    })
+                    .unwrap();
+            }
+
+            send_to(
+                &updates,
+                previous.viewers.intersection(&bar.viewers),
+                &connections,
+            );
+        }
+
+        let mut added = DataBundle::new(&compose);
+        added.add_packet(&add_packet(id, bar)).unwrap();
+
+        match &sent.bar {
+            Some(previous) => send_to(
+                &added,
+                bar.viewers.difference(&previous.viewers),
+                &connections,
+            ),
+            None => send_to(&added, &bar.viewers, &connections),
+        }
+
+        sent.bar = Some(bar.clone());
+    }
+}
+
+pub struct BossBarPlugin;
+
+impl Plugin for BossBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(initialize_boss_bar);
+        app.add_observer(remove_boss_bar);
+        app.add_observer(remove_viewer);
+        app.add_systems(FixedPostUpdate, sync_boss_bars);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewers_are_added_and_removed() {
+        let mut world = World::new();
+        let first = world.spawn_empty().id();
+        let second = world.spawn_empty().id();
+
+        let mut bar = BossBar::new("Boss").with_viewer(first);
+        assert!(bar.is_viewer(first));
+        assert!(!bar.is_viewer(second));
+
+        assert!(bar.add_viewer(second));
+        assert!(!bar.add_viewer(second));
+
+        let mut viewers: Vec<_> = bar.viewers().collect();
+        viewers.sort();
+        assert_eq!(viewers, [first, second]);
+
+        assert!(bar.remove_viewer(first));
+        assert!(!bar.remove_viewer(first));
+        assert_eq!(bar.viewers().collect::<Vec<_>>(), [second]);
+    }
+
+    #[test]
+    fn players_leaving_play_stop_viewing() {
+        let mut app = App::new();
+        app.add_observer(initialize_boss_bar);
+        app.add_observer(remove_viewer);
+
+        let world = app.world_mut();
+        let leaving = world.spawn(packet_state::Play(())).id();
+        let staying = world.spawn(packet_state::Play(())).id();
+        let bar = world
+            .spawn(
+                BossBar::new("Boss")
+                    .with_viewer(leaving)
+                    .with_viewer(staying),
+            )
+            .id();
+
+        world.entity_mut(leaving).remove::<packet_state::Play>();
+
+        let bar = world.get::<BossBar>(bar).unwrap();
+        assert!(!bar.is_viewer(leaving));
+        assert!(bar.is_viewer(staying));
+    }
+}
@@ -0,0 +1,183 @@
+//! Settings sent by the client, such as its language and view distance.
+//!
+//! The client sends its settings when it joins and whenever the player changes them. They are
+//! stored in the [`ClientSettings`] component, and a [`ClientSettingsChanged`] event is sent. The
+//! displayed skin parts and main hand are copied to the player's metadata so other players see
+//! them.
+
+use bevy::prelude::*;
+use tracing::error;
+use valence_protocol::packets::play::client_settings_c2s;
+
+use crate::{
+    ingress,
+    simulation::{
+        metadata::player::{DisplayedSkinParts, MainHand},
+        packet::play,
+    },
+};
+
+/// The smallest view distance sent to a client, like the vanilla server
+pub const MIN_VIEW_DISTANCE: u8 = 2;
+
+/// Which chat messages the client shows
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ChatMode {
+    #[default]
+    Enabled,
+    CommandsOnly,
+    Hidden,
+}
+
+impl From<client_settings_c2s::ChatMode> for ChatMode {
+    fn from(value: client_settings_c2s::ChatMode) -> Self {
+        match value {
+            client_settings_c2s::ChatMode::Enabled => Self::Enabled,
+            client_settings_c2s::ChatMode::CommandsOnly => Self::CommandsOnly,
+            client_settings_c2s::ChatMode::Hidden => Self::Hidden,
+        }
+    }
+}
+
+/// The hand a player uses for their main hand item
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MainArm {
+    Left,
+    #[default]
+    Right,
+}
+
+impl MainArm {
+    /// The id of this arm in entity metadata
+    #[must_use]
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::Left => 0,
+            Self::Right => 1,
+        }
+    }
+}
+
+impl From<client_settings_c2s::MainArm> for MainArm {
+    fn from(value: client_settings_c2s::MainArm) -> Self {
+        match value {
+            client_settings_c2s::MainArm::Left => Self::Left,
+            client_settings_c2s::MainArm::Right => Self::Right,
+        }
+    }
+}
+
+/// The settings of a player's client. Players do not have this component until their client sends
+/// its settings, which it does right after joining.
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct ClientSettings {
+    /// The language of the client in lowercase, such as `en_us`
+    pub locale: String,
+    /// The render distance of the client in chunks
+    pub view_distance: u8,
+    pub chat_mode: ChatMode,
+    /// Whether the client shows chat colours
+    pub chat_colors: bool,
+    /// Bit mask of the skin parts shown. See [`DisplayedSkinParts`] for the bits.
+    pub skin_parts: u8,
+    pub main_arm: MainArm,
+    /// Whether the player allows being listed in the server list ping
+    pub allow_server_listings: bool,
+}
+
+impl ClientSettings {
+    /// The view distance chunks are sent with, which is the smaller of the client and server view
+    /// distances. Clients always get at least [`MIN_VIEW_DISTANCE`] chunks.
+    #[must_use]
+    pub fn effective_view_distance(&self, server: i16) -> i16 {
+        i16::from(self.view_distance.max(MIN_VIEW_DISTANCE)).min(server)
+    }
+}
+
+/// Sent after a player's [`ClientSettings`] changed, including when the client first sends them
+#[derive(Event, Clone, Debug)]
+pub struct ClientSettingsChanged {
+    pub entity: Entity,
+    /// The settings before the change, or `None` if these are the first settings sent
+    pub previous: Option<ClientSettings>,
+}
+
+fn update_client_settings(
+    mut packets: EventReader<'_, '_, play::ClientSettings>,
+    query: Query<'_, '_, Option<&ClientSettings>>,
+    mut writer: EventWriter<'_, ClientSettingsChanged>,
+    mut commands: Commands<'_, '_>,
+) {
+    for packet in packets.read() {
+        let previous = match query.get(packet.sender()) {
+            Ok(previous) => previous,
+            Err(e) => {
+                error!("failed to update client settings: query failed: {e}");
+                continue;
+            }
+        };
+
+        let settings = ClientSettings {
+            locale: packet.locale.to_ascii_lowercase(),
+            view_distance: packet.view_distance,
+            chat_mode: packet.chat_mode.into(),
+            chat_colors: packet.chat_colors,
+            skin_parts: u8::from(packet.displayed_skin_parts),
+            main_arm: packet.main_arm.into(),
+            allow_server_listings: packet.allow_server_listings,
+        };
+
+        if previous == Some(&settings) {
+            continue;
+        }
+
+        // Changing the metadata components sends the skin parts and main hand to other players
+        commands.entity(packet.sender()).insert((
+            DisplayedSkinParts::new(settings.skin_parts),
+            MainHand::new(settings.main_arm.id()),
+            settings,
+        ));
+
+        writer.write(ClientSettingsChanged {
+            entity: packet.sender(),
+            previous: previous.cloned(),
+        });
+    }
+}
+
+pub struct ClientSettingsPlugin;
+
+impl Plugin for ClientSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ClientSettingsChanged>();
+        app.add_systems(
+            FixedUpdate,
+            update_client_settings.after(ingress::decode::play),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(view_distance: u8) -> ClientSettings {
+        ClientSettings {
+            locale: "en_us".to_string(),
+            view_distance,
+            chat_mode: ChatMode::Enabled,
+            chat_colors: true,
+            skin_parts: 0x7f,
+            main_arm: MainArm::Right,
+            allow_server_listings: true,
+        }
+    }
+
+    #[test]
+    fn view_distance_is_clamped() {
+        assert_eq!(settings(8).effective_view_distance(16), 8);
+        assert_eq!(settings(32).effective_view_distance(16), 16);
+        assert_eq!(settings(0).effective_view_distance(16), 2);
+        assert_eq!(settings(1).effective_view_distance(16), 2);
+    }
+}
@@ -0,0 +1,687 @@
+//! Status effects (potion effects) which can be applied to living entities.
+//!
+//! Effects are stored in the [`ActiveEffects`] component. Adding or removing an effect through
+//! [`ActiveEffects`] queues the change, and the [`EffectPlugin`] systems take care of ticking
+//! durations, applying vanilla behavior, and sending the add/remove packets to clients.
+//!
+//! Effects which are not known by the vanilla client can be created with [`EffectKind::Custom`].
+//! These are never sent to clients; instead an [`EffectTick`] event is sent every tick so plugins
+//! can implement the behavior themselves.
+
+use bevy::prelude::*;
+use hyperion_utils::EntityExt;
+use valence_protocol::{
+    VarInt,
+    packets::play::{
+        self,
+        entity_status_effect_s2c::{self, EntityStatusEffectS2c},
+    },
+};
+
+use crate::{
+    ingress,
+    net::{Compose, ConnectionId},
+    simulation::{
+        attribute::{self, Attributes},
+        metadata::{
+            entity::EntityFlags,
+            living_entity::{Health, IsPotionEffectAmbient, PotionEffectColor},
+        },
+        packet_state,
+    },
+};
+
+/// The kind of a status effect. See [`EffectKind::id`] for the protocol id of each kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EffectKind {
+    Speed,
+    Slowness,
+    Haste,
+    MiningFatigue,
+    Strength,
+    InstantHealth,
+    InstantDamage,
+    JumpBoost,
+    Nausea,
+    Regeneration,
+    Resistance,
+    FireResistance,
+    WaterBreathing,
+    Invisibility,
+    Blindness,
+    NightVision,
+    Hunger,
+    Weakness,
+    Poison,
+    Wither,
+    HealthBoost,
+    Absorption,
+    Saturation,
+    Glowing,
+    Levitation,
+    Luck,
+    Unluck,
+    SlowFalling,
+    ConduitPower,
+    DolphinsGrace,
+    BadOmen,
+    HeroOfTheVillage,
+    Darkness,
+    /// An effect implemented by a plugin. This is never sent to the client.
+    Custom(u16),
+}
+
+impl EffectKind {
+    /// Returns the protocol id of this effect, or [`None`] if this is a [`EffectKind::Custom`]
+    /// effect which the client does not know about.
+    #[must_use]
+    pub const fn id(self) -> Option<i32> {
+        let id = match self {
+            Self::Speed => 1,
+            Self::Slowness => 2,
+            Self::Haste => 3,
+            Self::MiningFatigue => 4,
+            Self::Strength => 5,
+            Self::InstantHealth => 6,
+            Self::InstantDamage => 7,
+            Self::JumpBoost => 8,
+            Self::Nausea => 9,
+            Self::Regeneration => 10,
+            Self::Resistance => 11,
+            Self::FireResistance => 12,
+            Self::WaterBreathing => 13,
+            Self::Invisibility => 14,
+            Self::Blindness => 15,
+            Self::NightVision => 16,
+            Self::Hunger => 17,
+            Self::Weakness => 18,
+            Self::Poison => 19,
+            Self::Wither => 20,
+            Self::HealthBoost => 21,
+            Self::Absorption => 22,
+            Self::Saturation => 23,
+            Self::Glowing => 24,
+            Self::Levitation => 25,
+            Self::Luck => 26,
+            Self::Unluck => 27,
+            Self::SlowFalling => 28,
+            Self::ConduitPower => 29,
+            Self::DolphinsGrace => 30,
+            Self::BadOmen => 31,
+            Self::HeroOfTheVillage => 32,
+            Self::Darkness => 33,
+            Self::Custom(_) => return None,
+        };
+
+        Some(id)
+    }
+
+    /// The particle color of this effect as an RGB value.
+    #[must_use]
+    pub const fn color(self) -> u32 {
+        match self {
+            Self::Speed => 0x0033_EBFF,
+            Self::Slowness => 0x008B_AFE0,
+            Self::Haste => 0x00D9_C043,
+            Self::MiningFatigue => 0x004A_4217,
+            Self::Strength => 0x00FF_C700,
+            Self::InstantHealth => 0x00F8_2423,
+            Self::InstantDamage => 0x00A9_656A,
+            Self::JumpBoost => 0x00FD_FF84,
+            Self::Nausea => 0x0055_1D4A,
+            Self::Regeneration => 0x00CD_5CAB,
+            Self::Resistance => 0x0091_46F0,
+            Self::FireResistance => 0x00FF_9900,
+            Self::WaterBreathing => 0x0098_DAC0,
+            Self::Invisibility => 0x00F6_F6F6,
+            Self::Blindness => 0x001F_1F23,
+            Self::NightVision => 0x00C2_FF66,
+            Self::Hunger => 0x0058_7653,
+            Self::Weakness => 0x0048_4D48,
+            Self::Poison => 0x0087_A363,
+            Self::Wither => 0x0073_6156,
+            Self::HealthBoost => 0x00F8_7D23,
+            Self::Absorption => 0x0025_52A5,
+            Self::Saturation => 0x00F8_2423,
+            Self::Glowing => 0x0094_A061,
+            Self::Levitation => 0x00CE_FFFF,
+            Self::Luck => 0x0059_C106,
+            Self::Unluck => 0x00C0_A44D,
+            Self::SlowFalling => 0x00F3_CFB9,
+            Self::ConduitPower => 0x001D_C2D1,
+            Self::DolphinsGrace => 0x0088_A3BE,
+            Self::BadOmen => 0x000B_6138,
+            Self::HeroOfTheVillage => 0x0044_FF44,
+            Self::Darkness => 0x0029_2721,
+            Self::Custom(_) => 0,
+        }
+    }
+
+    /// Whether this effect is applied once when added instead of lasting for a duration
+    #[must_use]
+    pub const fn is_instant(self) -> bool {
+        matches!(self, Self::InstantHealth | Self::InstantDamage)
+    }
+}
+
+/// A single status effect applied to an entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[must_use]
+pub struct Effect {
+    kind: EffectKind,
+    amplifier: u8,
+    duration: i32,
+    ambient: bool,
+    show_particles: bool,
+    show_icon: bool,
+}
+
+impl Effect {
+    /// A duration which never runs out.
+    pub const INFINITE: i32 = -1;
+
+    /// Creates an effect with the given kind which lasts `duration` ticks. The effect starts at
+    /// amplifier 0 (level I) and shows particles and an icon.
+    pub const fn new(kind: EffectKind, duration: i32) -> Self {
+        Self {
+            kind,
+            amplifier: 0,
+            duration,
+            ambient: false,
+            show_particles: true,
+            show_icon: true,
+        }
+    }
+
+    /// Sets the amplifier of the effect. An amplifier of 0 is level I.
+    pub const fn amplifier(mut self, amplifier: u8) -> Self {
+        self.amplifier = amplifier;
+        self
+    }
+
+    /// Whether the effect comes from a beacon or conduit. Ambient effects display fewer particles.
+    pub const fn ambient(mut self, ambient: bool) -> Self {
+        self.ambient = ambient;
+        self
+    }
+
+    pub const fn show_particles(mut self, show_particles: bool) -> Self {
+        self.show_particles = show_particles;
+        self
+    }
+
+    pub const fn show_icon(mut self, show_icon: bool) -> Self {
+        self.show_icon = show_icon;
+        self
+    }
+
+    #[must_use]
+    pub const fn kind(&self) -> EffectKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub const fn get_amplifier(&self) -> u8 {
+        self.amplifier
+    }
+
+    /// The remaining duration in ticks, or [`Effect::INFINITE`]
+    #[must_use]
+    pub const fn duration(&self) -> i32 {
+        self.duration
+    }
+
+    #[must_use]
+    pub const fn is_infinite(&self) -> bool {
+        self.duration == Self::INFINITE
+    }
+
+    #[must_use]
+    pub const fn is_ambient(&self) -> bool {
+        self.ambient
+    }
+
+    /// Whether `self` should replace `current` when both are of the same kind. This follows the
+    /// vanilla rules: a higher amplifier always wins, and with an equal amplifier the longer
+    /// effect wins.
+    const fn overrides(&self, current: &Self) -> bool {
+        if self.amplifier != current.amplifier {
+            return self.amplifier > current.amplifier;
+        }
+
+        if current.is_infinite() {
+            return false;
+        }
+
+        self.is_infinite() || self.duration > current.duration
+    }
+
+    /// Returns true if this effect should apply its periodic behavior on this tick, given that it
+    /// applies every `interval` ticks at amplifier 0 and twice as often per extra level.
+    const fn should_pulse(&self, interval: i32) -> bool {
+        let shift = if self.amplifier > 31 {
+            31
+        } else {
+            self.amplifier
+        };
+        let interval = interval >> shift;
+
+        interval <= 0 || self.duration % interval == 0
+    }
+}
+
+/// The status effects currently applied to an entity.
+///
+/// Changes made through this component are sent to clients by the [`EffectPlugin`].
+#[derive(Component, Debug, Default)]
+pub struct ActiveEffects {
+    effects: Vec<Effect>,
+    added: Vec<Effect>,
+    removed: Vec<EffectKind>,
+}
+
+impl ActiveEffects {
+    /// Adds an effect. If an effect of the same kind is already active, it is only replaced if the
+    /// new effect has a higher amplifier, or the same amplifier and a longer duration.
+    ///
+    /// Returns true if the effect was applied.
+    pub fn add(&mut self, effect: Effect) -> bool {
+        if let Some(current) = self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            if !effect.overrides(current) {
+                return false;
+            }
+            *current = effect;
+        } else {
+            self.effects.push(effect);
+        }
+
+        self.removed.retain(|kind| *kind != effect.kind);
+        self.added.retain(|e| e.kind != effect.kind);
+        self.added.push(effect);
+        true
+    }
+
+    /// Removes the effect of the given kind, returning it if it was active
+    pub fn remove(&mut self, kind: EffectKind) -> Option<Effect> {
+        let index = self.effects.iter().position(|e| e.kind == kind)?;
+        let effect = self.effects.swap_remove(index);
+
+        self.added.retain(|e| e.kind != kind);
+        self.removed.push(kind);
+        Some(effect)
+    }
+
+    /// Removes an instant effect once it has been applied. Unlike [`Self::remove`], its
+    /// [`EffectAdded`] event is still sent and no [`EffectRemoved`] event is sent, since the
+    /// effect never lasted.
+    fn expire_instant(&mut self, kind: EffectKind) {
+        self.effects.retain(|e| e.kind != kind);
+    }
+
+    /// Removes all effects
+    pub fn clear(&mut self) {
+        let kinds: Vec<_> = self.effects.iter().map(Effect::kind).collect();
+        for kind in kinds {
+            self.remove(kind);
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, kind: EffectKind) -> Option<&Effect> {
+        self.effects.iter().find(|e| e.kind == kind)
+    }
+
+    #[must_use]
+    pub fn contains(&self, kind: EffectKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// The amplifier of the effect of the given kind, if it is active
+    #[must_use]
+    pub fn amplifier(&self, kind: EffectKind) -> Option<u8> {
+        self.get(kind).map(Effect::get_amplifier)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Effect> {
+        self.effects.iter()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    fn has_pending_changes(&self) -> bool {
+        !self.added.is_empty() || !self.removed.is_empty()
+    }
+
+    /// The additional vertical velocity given to a jump by jump boost
+    #[must_use]
+    pub fn jump_boost(&self) -> f64 {
+        self.amplifier(EffectKind::JumpBoost)
+            .map_or(0.0, |amp| 0.1 * (f64::from(amp) + 1.0))
+    }
+
+    /// The potion particle color from mixing all visible effects, or 0 if there are none
+    #[must_use]
+    pub fn particle_color(&self) -> i32 {
+        let mut total = [0.0_f32; 3];
+        let mut weight = 0.0_f32;
+
+        for effect in &self.effects {
+            if !effect.show_particles || matches!(effect.kind, EffectKind::Custom(_)) {
+                continue;
+            }
+
+            let [_, r, g, b] = effect.kind.color().to_be_bytes();
+            let level = f32::from(effect.amplifier) + 1.0;
+
+            for (channel, value) in total.iter_mut().zip([r, g, b]) {
+                *channel += f32::from(value) / 255.0 * level;
+            }
+            weight += level;
+        }
+
+        if weight == 0.0 {
+            return 0;
+        }
+
+        #[expect(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "each channel is in the range 0..=255"
+        )]
+        let [r, g, b] = total.map(|channel| (channel / weight * 255.0) as i32);
+
+        (r << 16) | (g << 8) | b
+    }
+
+    /// Whether all visible effects are ambient
+    #[must_use]
+    pub fn is_ambient(&self) -> bool {
+        let mut visible = self.effects.iter().filter(|effect| effect.show_particles);
+        visible.clone().next().is_some() && visible.all(|effect| effect.ambient)
+    }
+}
+
+/// Sent after an effect was added to (or replaced on) an entity
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EffectAdded {
+    pub entity: Entity,
+    pub effect: Effect,
+}
+
+/// Sent after an effect was removed from an entity, either because it expired or because it was
+/// removed manually
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EffectRemoved {
+    pub entity: Entity,
+    pub kind: EffectKind,
+}
+
+/// Sent every tick for each active [`EffectKind::Custom`] effect so that plugins can implement
+/// their behavior.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EffectTick {
+    pub entity: Entity,
+    pub effect: Effect,
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    commands
+        .entity(trigger.target())
+        .insert(ActiveEffects::default());
+}
+
+fn tick_effects(
+    mut query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &mut ActiveEffects,
+            Option<&mut Health>,
+            Option<&Attributes>,
+        ),
+    >,
+    mut tick_writer: EventWriter<'_, EffectTick>,
+) {
+    for (entity, mut effects, mut health, attributes) in &mut query {
+        if effects.is_empty() {
+            continue;
+        }
+
+        let effects = &mut *effects;
+        let max_health = attribute::max_health(attributes);
+        let mut expired = Vec::new();
+        let mut applied = Vec::new();
+
+        for effect in &mut effects.effects {
+            if let Some(health) = health.as_deref_mut() {
+                let level = f32::from(effect.amplifier) + 1.0;
+                match effect.kind {
+                    EffectKind::Regeneration if effect.should_pulse(50) => {
+                        health.heal(1.0, max_health);
+                    }
+                    EffectKind::Poison if effect.should_pulse(25) && **health > 1.0 => {
+                        health.damage(1.0_f32.min(**health - 1.0));
+                    }
+                    EffectKind::Wither if effect.should_pulse(40) => health.damage(1.0),
+                    EffectKind::InstantHealth => {
+                        health.heal(4.0 * 2.0_f32.powf(level - 1.0), max_health);
+                    }
+                    EffectKind::InstantDamage => health.damage(6.0 * 2.0_f32.powf(level - 1.0)),
+                    _ => {}
+                }
+            }
+
+            if matches!(effect.kind, EffectKind::Custom(_)) {
+                tick_writer.write(EffectTick {
+                    entity,
+                    effect: *effect,
+                });
+            }
+
+            if effect.kind.is_instant() {
+                applied.push(effect.kind);
+                continue;
+            }
+
+            if effect.is_infinite() {
+                continue;
+            }
+
+            effect.duration -= 1;
+            if effect.duration <= 0 {
+                expired.push(effect.kind);
+            }
+        }
+
+        for kind in applied {
+            effects.expire_instant(kind);
+        }
+
+        for kind in expired {
+            effects.remove(kind);
+        }
+    }
+}
+
+pub(crate) fn sync_effects(
+    mut query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &mut ActiveEffects,
+            Option<&ConnectionId>,
+            Option<&mut EntityFlags>,
+            Option<&mut PotionEffectColor>,
+            Option<&mut IsPotionEffectAmbient>,
+        ),
+    >,
+    compose: Res<'_, Compose>,
+    mut added_writer: EventWriter<'_, EffectAdded>,
+    mut removed_writer: EventWriter<'_, EffectRemoved>,
+) {
+    for (entity, mut effects, connection, flags, color, ambient) in &mut query {
+        if !effects.has_pending_changes() {
+            continue;
+        }
+
+        let effects = &mut *effects;
+        let entity_id = VarInt(entity.minecraft_id());
+
+        for kind in effects.removed.drain(..) {
+            removed_writer.write(EffectRemoved { entity, kind });
+
+            let (Some(effect_id), Some(&connection)) = (kind.id(), connection) else {
+                continue;
+            };
+
+            let pkt = play::RemoveEntityStatusEffectS2c {
+                entity_id,
+                effect_id: VarInt(effect_id),
+            };
+            compose.unicast(&pkt, connection).unwrap();
+        }
+
+        for effect in effects.added.drain(..) {
+            added_writer.write(EffectAdded { entity, effect });
+
+            if effect.kind.is_instant() {
+                continue;
+            }
+
+            let (Some(effect_id), Some(&connection)) = (effect.kind.id(), connection) else {
+                continue;
+            };
+
+            let pkt = EntityStatusEffectS2c {
+                entity_id,
+                effect_id: VarInt(effect_id),
+                amplifier: effect.amplifier,
+                duration: VarInt(effect.duration),
+                flags: entity_status_effect_s2c::Flags::new()
+                    .with_is_ambient(effect.ambient)
+                    .with_show_particles(effect.show_particles)
+                    .with_show_icon(effect.show_icon),
+                factor_codec: None,
+            };
+            compose.unicast(&pkt, connection).unwrap();
+        }
+
+        if let Some(mut flags) = flags {
+            let invisible = effects.contains(EffectKind::Invisibility);
+            let was_invisible = (*flags & EntityFlags::INVISIBLE) == EntityFlags::INVISIBLE;
+
+            if invisible && !was_invisible {
+                *flags |= EntityFlags::INVISIBLE;
+            } else if !invisible && was_invisible {
+                *flags &= !EntityFlags::INVISIBLE;
+            }
+        }
+
+        if let Some(mut color) = color {
+            let new_color = VarInt(effects.particle_color());
+            if **color != new_color {
+                **color = new_color;
+            }
+        }
+
+        if let Some(mut ambient) = ambient {
+            let is_ambient = effects.is_ambient();
+            if **ambient != is_ambient {
+                **ambient = is_ambient;
+            }
+        }
+    }
+}
+
+pub struct EffectPlugin;
+
+impl Plugin for EffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (tick_effects, sync_effects)
+                .chain()
+                .after(ingress::decode::play),
+        );
+
+        app.add_event::<EffectAdded>();
+        app.add_event::<EffectRemoved>();
+        app.add_event::<EffectTick>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use valence_protocol::CompressionThreshold;
+
+    use super::*;
+    use crate::{Global, Shared, net::IoBuf};
+
+    #[test]
+    fn higher_amplifier_replaces() {
+        let mut effects = ActiveEffects::default();
+        assert!(effects.add(Effect::new(EffectKind::Speed, 100)));
+        assert!(effects.add(Effect::new(EffectKind::Speed, 20).amplifier(1)));
+        assert!(!effects.add(Effect::new(EffectKind::Speed, 1000)));
+        assert_eq!(effects.amplifier(EffectKind::Speed), Some(1));
+    }
+
+    #[test]
+    fn longer_duration_replaces_same_amplifier() {
+        let mut effects = ActiveEffects::default();
+        assert!(effects.add(Effect::new(EffectKind::Poison, 100)));
+        assert!(!effects.add(Effect::new(EffectKind::Poison, 50)));
+        assert!(effects.add(Effect::new(EffectKind::Poison, Effect::INFINITE)));
+        assert!(!effects.add(Effect::new(EffectKind::Poison, 1000)));
+    }
+
+    #[test]
+    fn instant_effects_are_added_but_not_removed() {
+        let shared = Arc::new(Shared {
+            compression_threshold: CompressionThreshold(256),
+            compression_level: Default::default(),
+        });
+
+        let mut app = App::new();
+        app.insert_resource(Compose::new(
+            shared.compression_level,
+            Global::new(shared),
+            IoBuf::default(),
+        ));
+        app.add_event::<EffectAdded>();
+        app.add_event::<EffectRemoved>();
+        app.add_event::<EffectTick>();
+        app.add_systems(Update, (tick_effects, sync_effects).chain());
+
+        let mut effects = ActiveEffects::default();
+        assert!(effects.add(Effect::new(EffectKind::InstantHealth, 1)));
+        let entity = app.world_mut().spawn((effects, Health::new(10.0))).id();
+
+        app.update();
+
+        let world = app.world();
+        let added = world.resource::<Events<EffectAdded>>();
+        let removed = world.resource::<Events<EffectRemoved>>();
+
+        assert!(
+            added
+                .iter_current_update_events()
+                .any(|event| event.entity == entity
+                    && event.effect.kind == EffectKind::InstantHealth)
+        );
+        assert_eq!(removed.iter_current_update_events().count(), 0);
+        assert!(world.get::<ActiveEffects>(entity).unwrap().is_empty());
+        assert!(**world.get::<Health>(entity).unwrap() > 10.0);
+    }
+}
@@ -116,6 +116,7 @@ pub enum Posture {
 /// <https://wiki.vg/index.php?title=Protocol&oldid=18375#Set_Entity_Metadata>
 #[derive(Event, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct PostureUpdate {
+    pub client: Entity,
     /// The new posture of the entity.
     pub state: Posture,
 }
@@ -135,6 +136,13 @@ pub struct ProjectileBlockEvent {
     pub projectile: Entity,
 }
 
+/// Sent when a projectile with loyalty returns to its owner
+#[derive(Event, Clone, Debug)]
+pub struct ProjectileReturnEvent {
+    pub projectile: Entity,
+    pub owner: Entity,
+}
+
 #[derive(Event, Clone, Debug)]
 pub struct ClickSlotEvent {
     pub client: Entity,
@@ -167,6 +175,13 @@ pub struct HitGroundEvent {
     pub fall_distance: f32,
 }
 
+/// Sent when a player leaves the ground by jumping
+#[derive(Event, Clone, Debug)]
+pub struct JumpEvent {
+    pub client: Entity,
+    pub sprinting: bool,
+}
+
 #[derive(Event, Clone, Debug)]
 pub struct InteractEvent {
     pub client: Entity,
@@ -0,0 +1,450 @@
+//! Game modes and the restrictions they place on players.
+//!
+//! Changing the [`GameMode`] component of a player is enough to change their game mode. The client
+//! is told about the change, the player list is updated, and a [`GameModeChanged`] event is sent
+//! at the end of the tick.
+
+use std::borrow::Cow;
+
+use bevy::{ecs::entity::Entities, prelude::*};
+use hyperion_utils::{EntityExt, Prev, track_prev};
+use tracing::error;
+use valence_generated::block::BlockKind;
+use valence_protocol::{
+    ByteAngle, ItemStack, VarInt,
+    nbt::{Value, list::List},
+    packets::play::{
+        EntitiesDestroyS2c, GameStateChangeS2c, PlayerSpawnS2c, SetCameraEntityS2c,
+        client_command_c2s::ClientCommand,
+        game_state_change_s2c::GameEventKind,
+        player_abilities_s2c::{PlayerAbilitiesFlags, PlayerAbilitiesS2c},
+        player_interact_entity_c2s::EntityInteraction,
+    },
+};
+
+use crate::{
+    egress::{
+        metadata::skin_parts,
+        player_join::{PlayerListActions, PlayerListEntry, PlayerListS2c},
+    },
+    ingress,
+    net::{Compose, ConnectionId},
+    simulation::{
+        Flight, FlyingSpeed, PendingTeleportation, Pitch, Position, Uuid, Yaw,
+        metadata::player::DisplayedSkinParts, packet::play, packet_state, tab_list::TabListEntry,
+    },
+};
+
+/// The game mode of a player
+#[derive(Component, Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    /// The id of this game mode in the protocol
+    #[must_use]
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::Survival => 0,
+            Self::Creative => 1,
+            Self::Adventure => 2,
+            Self::Spectator => 3,
+        }
+    }
+
+    /// Whether the player can break and place any block. Players in adventure mode can only
+    /// modify blocks listed in the `CanDestroy` and `CanPlaceOn` tags of the held item.
+    #[must_use]
+    pub const fn can_modify_blocks(self) -> bool {
+        matches!(self, Self::Survival | Self::Creative)
+    }
+
+    /// Whether the player can interact with blocks, items and entities at all
+    #[must_use]
+    pub const fn can_interact(self) -> bool {
+        !matches!(self, Self::Spectator)
+    }
+
+    /// Whether the player only takes damage which bypasses invulnerability, such as the void
+    #[must_use]
+    pub const fn is_invulnerable(self) -> bool {
+        matches!(self, Self::Creative | Self::Spectator)
+    }
+
+    #[must_use]
+    pub const fn allows_flight(self) -> bool {
+        matches!(self, Self::Creative | Self::Spectator)
+    }
+
+    /// Whether blocks break instantly and items are not used up
+    #[must_use]
+    pub const fn is_creative(self) -> bool {
+        matches!(self, Self::Creative)
+    }
+
+    #[must_use]
+    pub const fn is_spectator(self) -> bool {
+        matches!(self, Self::Spectator)
+    }
+
+    /// Whether the player collides with blocks
+    #[must_use]
+    pub const fn has_collision(self) -> bool {
+        !matches!(self, Self::Spectator)
+    }
+
+    /// The abilities sent to the client in this game mode
+    #[must_use]
+    pub const fn abilities(self, flight: &Flight) -> PlayerAbilitiesFlags {
+        PlayerAbilitiesFlags::new()
+            .with_invulnerable(self.is_invulnerable())
+            .with_flying(flight.is_flying)
+            .with_allow_flying(flight.allow)
+            .with_instant_break(self.is_creative())
+    }
+}
+
+impl From<GameMode> for valence_protocol::GameMode {
+    fn from(value: GameMode) -> Self {
+        match value {
+            GameMode::Survival => Self::Survival,
+            GameMode::Creative => Self::Creative,
+            GameMode::Adventure => Self::Adventure,
+            GameMode::Spectator => Self::Spectator,
+        }
+    }
+}
+
+impl From<valence_protocol::GameMode> for GameMode {
+    fn from(value: valence_protocol::GameMode) -> Self {
+        match value {
+            valence_protocol::GameMode::Survival => Self::Survival,
+            valence_protocol::GameMode::Creative => Self::Creative,
+            valence_protocol::GameMode::Adventure => Self::Adventure,
+            valence_protocol::GameMode::Spectator => Self::Spectator,
+        }
+    }
+}
+
+/// Sent after the game mode of a player changed
+#[derive(Event, Copy, Clone, Debug)]
+pub struct GameModeChanged {
+    pub entity: Entity,
+    pub previous: GameMode,
+    pub current: GameMode,
+}
+
+/// The entity a spectator is viewing the world through
+#[derive(Component, Copy, Clone, Debug, Deref)]
+pub struct SpectatorTarget(Entity);
+
+/// Whether a player in adventure mode may destroy or place against the block with the held item.
+///
+/// `key` is either `CanDestroy` or `CanPlaceOn`.
+#[must_use]
+pub fn adventure_allows(stack: &ItemStack, key: &str, block: BlockKind) -> bool {
+    let Some(Value::List(List::String(blocks))) = stack.nbt.as_ref().and_then(|nbt| nbt.get(key))
+    else {
+        return false;
+    };
+
+    let name = block.to_str();
+    blocks
+        .iter()
+        .any(|entry| entry.strip_prefix("minecraft:").unwrap_or(entry) == name)
+}
+
+fn initialize_player(
+    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    mut commands: Commands<'_, '_>,
+) {
+    // A game may have already chosen the game mode before the player joined
+    commands
+        .entity(trigger.target())
+        .insert_if_new(GameMode::default());
+}
+
+fn spawn_packet(
+    entity: Entity,
+    uuid: &Uuid,
+    position: &Position,
+    yaw: &Yaw,
+    pitch: &Pitch,
+) -> PlayerSpawnS2c {
+    PlayerSpawnS2c {
+        entity_id: VarInt(entity.minecraft_id()),
+        player_uuid: **uuid,
+        position: position.as_dvec3(),
+        yaw: ByteAngle::from_degrees(**yaw),
+        pitch: ByteAngle::from_degrees(**pitch),
+    }
+}
+
+fn destroy_packet(entity: Entity) -> EntitiesDestroyS2c<'static> {
+    EntitiesDestroyS2c {
+        entity_ids: vec![VarInt(entity.minecraft_id())].into(),
+    }
+}
+
+fn sync_game_mode(
+    compose: Res<'_, Compose>,
+    query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &ConnectionId,
+            &Uuid,
+            &GameMode,
+            &Prev<GameMode>,
+            (&Flight, &FlyingSpeed),
+            &Position,
+            &Yaw,
+            &Pitch,
+            Has<SpectatorTarget>,
+            Option<&TabListEntry>,
+            Option<&DisplayedSkinParts>,
+        ),
+    >,
+    viewers: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &ConnectionId,
+            &GameMode,
+            &Uuid,
+            &Position,
+            &Yaw,
+            &Pitch,
+            Option<&DisplayedSkinParts>,
+        ),
+    >,
+    mut writer: EventWriter<'_, GameModeChanged>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (
+        entity,
+        &connection,
+        uuid,
+        &current,
+        previous,
+        (flight, flying_speed),
+        position,
+        yaw,
+        pitch,
+        spectating,
+        tab_list_entry,
+        displayed_skin_parts,
+    ) in &query
+    {
+        let previous = **previous;
+        if previous == current {
+            continue;
+        }
+
+        let pkt = GameStateChangeS2c {
+            kind: GameEventKind::ChangeGameMode,
+            value: f32::from(current.id()),
+        };
+        compose.unicast(&pkt, connection).unwrap();
+
+        // The tab list may show a different game mode
+        let shown = tab_list_entry.map_or(current, |entry| entry.shown_game_mode(current));
+        let entries = &[PlayerListEntry {
+            player_uuid: **uuid,
+            game_mode: shown.into(),
+            ..Default::default()
+        }];
+        let pkt = PlayerListS2c {
+            actions: PlayerListActions::new().with_update_game_mode(true),
+            entries: Cow::Borrowed(entries),
+        };
+        compose.broadcast(&pkt).send().unwrap();
+
+        // Flight is only changed if the new game mode allows or forbids it when the old one did
+        // not, so flight granted by the game, such as with /fly, is kept
+        let allow = if previous.allows_flight() == current.allows_flight() {
+            flight.allow
+        } else {
+            current.allows_flight()
+        };
+        let is_flying = current.is_spectator() || (allow && flight.is_flying);
+
+        if allow != flight.allow || is_flying != flight.is_flying {
+            // Inserting the flight component sends the new abilities to the client
+            commands.entity(entity).insert(Flight { allow, is_flying });
+        } else {
+            let pkt = PlayerAbilitiesS2c {
+                flags: current.abilities(flight),
+                flying_speed: flying_speed.speed,
+                fov_modifier: 0.0,
+            };
+            compose.unicast(&pkt, connection).unwrap();
+        }
+
+        // Spectators are only visible to other spectators
+        let others = viewers.iter().filter(|(other, ..)| *other != entity);
+
+        if current.is_spectator() {
+            let pkt_destroy = destroy_packet(entity);
+
+            for (
+                other,
+                &viewer,
+                mode,
+                other_uuid,
+                other_position,
+                other_yaw,
+                other_pitch,
+                other_skin_parts,
+            ) in others
+            {
+                if !mode.is_spectator() {
+                    compose.unicast(&pkt_destroy, viewer).unwrap();
+                    continue;
+                }
+
+                // The new spectator can now see the other spectators
+                let pkt_spawn =
+                    spawn_packet(other, other_uuid, other_position, other_yaw, other_pitch);
+                let other_skin_parts = other_skin_parts.copied().unwrap_or_default();
+                let pkt_skin_parts = skin_parts(other.minecraft_id(), *other_skin_parts);
+
+                compose.unicast(&pkt_spawn, connection).unwrap();
+                compose.unicast(&pkt_skin_parts, connection).unwrap();
+            }
+        } else if previous.is_spectator() {
+            if spectating {
+                let pkt = SetCameraEntityS2c {
+                    entity_id: VarInt(entity.minecraft_id()),
+                };
+                compose.unicast(&pkt, connection).unwrap();
+                commands.entity(entity).remove::<SpectatorTarget>();
+            }
+
+            let pkt_spawn = spawn_packet(entity, uuid, position, yaw, pitch);
+            let displayed_skin_parts = displayed_skin_parts.copied().unwrap_or_default();
+            let pkt_skin_parts = skin_parts(entity.minecraft_id(), *displayed_skin_parts);
+
+            for (other, &viewer, mode, ..) in others {
+                if mode.is_spectator() {
+                    // The other spectators are now hidden from the former spectator
+                    compose.unicast(&destroy_packet(other), connection).unwrap();
+                } else {
+                    compose.unicast(&pkt_spawn, viewer).unwrap();
+                    compose.unicast(&pkt_skin_parts, viewer).unwrap();
+                }
+            }
+        }
+
+        writer.write(GameModeChanged {
+            entity,
+            previous,
+            current,
+        });
+    }
+}
+
+fn start_spectating(
+    mut packets: EventReader<'_, '_, play::PlayerInteractEntity>,
+    entities: &Entities,
+    query: Query<'_, '_, &GameMode>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for packet in packets.read() {
+        if packet.interact != EntityInteraction::Attack {
+            continue;
+        }
+
+        let Ok(mode) = query.get(packet.sender()) else {
+            continue;
+        };
+
+        if !mode.is_spectator() {
+            continue;
+        }
+
+        let id = u32::from_ne_bytes(packet.entity_id.0.to_ne_bytes());
+        let Some(target) = entities.resolve_from_id(id) else {
+            continue;
+        };
+
+        let pkt = SetCameraEntityS2c {
+            entity_id: packet.entity_id,
+        };
+        compose.unicast(&pkt, packet.connection_id()).unwrap();
+
+        commands
+            .entity(packet.sender())
+            .insert(SpectatorTarget(target));
+    }
+}
+
+fn stop_spectating(
+    mut packets: EventReader<'_, '_, play::ClientCommand>,
+    query: Query<'_, '_, (), With<SpectatorTarget>>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for packet in packets.read() {
+        if packet.action != ClientCommand::StartSneaking || !query.contains(packet.sender()) {
+            continue;
+        }
+
+        let pkt = SetCameraEntityS2c {
+            entity_id: VarInt(packet.minecraft_id()),
+        };
+        compose.unicast(&pkt, packet.connection_id()).unwrap();
+
+        commands.entity(packet.sender()).remove::<SpectatorTarget>();
+    }
+}
+
+fn spectator_teleport(
+    mut packets: EventReader<'_, '_, play::SpectatorTeleport>,
+    spectators: Query<'_, '_, &GameMode>,
+    targets: Query<'_, '_, (&Uuid, &Position)>,
+    mut commands: Commands<'_, '_>,
+) {
+    for packet in packets.read() {
+        match spectators.get(packet.sender()) {
+            Ok(mode) if mode.is_spectator() => {}
+            Ok(_) => continue,
+            Err(e) => {
+                error!("failed to handle spectator teleport: query failed: {e}");
+                continue;
+            }
+        }
+
+        let Some((_, position)) = targets.iter().find(|(uuid, _)| ***uuid == packet.target) else {
+            continue;
+        };
+
+        commands
+            .entity(packet.sender())
+            .insert(PendingTeleportation::new(**position));
+    }
+}
+
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameModeChanged>();
+        app.add_observer(initialize_player);
+        app.add_systems(
+            FixedUpdate,
+            (start_spectating, stop_spectating, spectator_teleport).after(ingress::decode::play),
+        );
+        app.add_systems(FixedPostUpdate, sync_game_mode);
+
+        track_prev::<GameMode>(app);
+    }
+}
@@ -8,9 +8,9 @@ use valence_generated::{
     item::ItemKind,
 };
 use valence_protocol::{
-    Hand, VarInt,
+    BlockPos, Hand, VarInt,
     packets::play::{
-        GameMessageS2c, OpenWrittenBookS2c, UpdatePlayerAbilitiesC2s,
+        BlockUpdateS2c, GameMessageS2c, OpenWrittenBookS2c, UpdatePlayerAbilitiesC2s,
         client_command_c2s::ClientCommand, player_action_c2s::PlayerAction,
     },
 };
@@ -25,8 +25,11 @@ use crate::{
         animation::{self, ActiveAnimation},
         block_bounds,
         blocks::Blocks,
+        effect::ActiveEffects,
         event,
-        metadata::{entity::Pose, living_entity::HandStates},
+        game_mode::{self, GameMode},
+        metadata::living_entity::HandStates,
+        movement::{self, MovementState},
         packet::{OrderedPacketRef, play},
     },
 };
@@ -47,7 +50,18 @@ fn position_and_look_updates(
         '_,
         '_,
         (
-            Query<'_, '_, (&EntitySize, &mut MovementTracking, &mut Position, &Yaw)>,
+            Query<
+                '_,
+                '_,
+                (
+                    &EntitySize,
+                    &mut MovementTracking,
+                    &mut Position,
+                    &Yaw,
+                    &GameMode,
+                    Option<&ActiveEffects>,
+                ),
+            >,
             Query<'_, '_, (&mut Yaw, &mut Pitch)>,
             Query<'_, '_, &mut Position>,
         ),
@@ -187,14 +201,25 @@ fn position_and_look_updates(
 fn change_position_or_correct_client(
     client: Entity,
     connection_id: ConnectionId,
-    mut query: Query<'_, '_, (&EntitySize, &mut MovementTracking, &mut Position, &Yaw)>,
+    mut query: Query<
+        '_,
+        '_,
+        (
+            &EntitySize,
+            &mut MovementTracking,
+            &mut Position,
+            &Yaw,
+            &GameMode,
+            Option<&ActiveEffects>,
+        ),
+    >,
     blocks: &Blocks,
     compose: &Compose,
     commands: &mut Commands<'_, '_>,
     proposed: Vec3,
     on_ground: bool,
 ) {
-    let (&size, mut tracking, mut pose, yaw) = match query.get_mut(client) {
+    let (&size, mut tracking, mut pose, yaw, game_mode, effects) = match query.get_mut(client) {
         Ok(data) => data,
         Err(e) => {
             error!("change_position_or_correct_client failed: query failed: {e}");
@@ -202,7 +227,9 @@ fn change_position_or_correct_client(
         }
     };
 
-    if let Err(e) = try_change_position(proposed, &pose, size, blocks) {
+    if game_mode.has_collision()
+        && let Err(e) = try_change_position(proposed, &pose, size, blocks)
+    {
         // Send error message to player
         let msg = format!("§c{e}");
         let pkt = GameMessageS2c {
@@ -223,7 +250,18 @@ fn change_position_or_correct_client(
     let y_delta = proposed.y - pose.y;
 
     if y_delta > 0. && tracking.was_on_ground && !on_ground {
-        tracking.server_velocity.y = 0.419_999_986_886_978_15;
+        let below = (**pose - Vec3::new(0.0, 0.5, 0.0)).floor().as_ivec3();
+        let jump_factor = blocks
+            .get_block(below)
+            .map_or(1.0, |state| movement::jump_factor(state.to_kind()));
+
+        tracking.server_velocity.y =
+            0.419_999_986_886_978_15 * jump_factor + effects.map_or(0.0, ActiveEffects::jump_boost);
+
+        commands.send_event(event::JumpEvent {
+            client,
+            sprinting: tracking.sprinting,
+        });
 
         if tracking.sprinting {
             let smth = yaw.yaw * 0.017_453_292;
@@ -290,6 +328,11 @@ fn has_block_collision(position: &Vec3, size: EntitySize, blocks: &Blocks) -> bo
     let res = blocks.get_blocks(min, max, |pos, block| {
         let pos = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
 
+        // Whether scaffolding can be stood on depends on the player, so players can move through it
+        if block.to_kind() == BlockKind::Scaffolding {
+            return ControlFlow::Continue(());
+        }
+
         for aabb in block.collision_shapes() {
             let aabb = Aabb::new(aabb.min().as_vec3(), aabb.max().as_vec3());
             let aabb = aabb.move_by(pos);
@@ -329,9 +372,32 @@ fn hand_swing(
     }
 }
 
+/// Tells the client that a block change it predicted did not happen. The sequence of the
+/// interaction must still be confirmed.
+fn reject_block_change(
+    compose: &Compose,
+    connection_id: ConnectionId,
+    blocks: &Blocks,
+    position: IVec3,
+) {
+    let Some(block_id) = blocks.get_block(position) else {
+        return;
+    };
+
+    let pkt = BlockUpdateS2c {
+        position: BlockPos::new(position.x, position.y, position.z),
+        block_id,
+    };
+
+    compose.unicast(&pkt, connection_id).unwrap();
+}
+
 // i.e., shooting a bow, digging a block, etc
 fn player_action(
     mut packets: EventReader<'_, '_, play::PlayerAction>,
+    mut query: Query<'_, '_, (&GameMode, &PlayerInventory, &mut ConfirmBlockSequences)>,
+    blocks: Res<'_, Blocks>,
+    compose: Res<'_, Compose>,
     mut start_destroy_writer: EventWriter<'_, event::StartDestroyBlock>,
     mut stop_destroy_writer: EventWriter<'_, event::DestroyBlock>,
     mut release_writer: EventWriter<'_, event::ReleaseUseItem>,
@@ -341,7 +407,47 @@ fn player_action(
         let sequence = packet.sequence.0;
         let position = IVec3::new(packet.position.x, packet.position.y, packet.position.z);
 
+        let (&game_mode, inventory, mut confirm_block_sequences) =
+            match query.get_mut(packet.sender()) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("failed to handle player action: query failed: {e}");
+                    continue;
+                }
+            };
+
+        if matches!(
+            packet.action,
+            PlayerAction::StartDestroyBlock | PlayerAction::StopDestroyBlock
+        ) {
+            let may_destroy = game_mode.can_modify_blocks()
+                || (game_mode == GameMode::Adventure
+                    && blocks.get_block(position).is_some_and(|block| {
+                        game_mode::adventure_allows(
+                            &inventory.get_cursor().stack,
+                            "CanDestroy",
+                            block.to_kind(),
+                        )
+                    }));
+
+            if !may_destroy {
+                confirm_block_sequences.push(sequence);
+                reject_block_change(&compose, packet.connection_id(), &blocks, position);
+                continue;
+            }
+        }
+
         match packet.action {
+            // Blocks are destroyed as soon as they are hit in creative mode
+            PlayerAction::StartDestroyBlock if game_mode.is_creative() => {
+                let event = event::DestroyBlock {
+                    position,
+                    from: packet.sender(),
+                    sequence,
+                };
+
+                stop_destroy_writer.write(event);
+            }
             PlayerAction::StartDestroyBlock => {
                 let event = event::StartDestroyBlock {
                     position,
@@ -370,18 +476,25 @@ fn player_action(
             }
             action => error!("failed to handle player action: unimplemented {action:?}"),
         }
-
-        // todo: implement
     }
 }
 
 // for sneaking/crouching/etc
 fn client_command(
     mut packets: EventReader<'_, '_, play::ClientCommand>,
-    mut query: Query<'_, '_, (&mut Pose, &mut EntitySize, &mut MovementTracking)>,
+    mut query: Query<
+        '_,
+        '_,
+        (
+            &mut MovementState,
+            &mut MovementTracking,
+            &Flight,
+            &PlayerInventory,
+        ),
+    >,
 ) {
     for packet in packets.read() {
-        let (mut pose, mut size, mut tracking) = match query.get_mut(packet.sender()) {
+        let (mut state, mut tracking, flight, inventory) = match query.get_mut(packet.sender()) {
             Ok(data) => data,
             Err(e) => {
                 error!("failed to handle client command: query failed: {e}");
@@ -389,14 +502,13 @@ fn client_command(
             }
         };
 
+        // The pose and hitbox are updated from the movement state
         match packet.action {
             ClientCommand::StartSneaking => {
-                *pose = Pose::Sneaking;
-                size.height = 1.5;
+                state.sneaking = true;
             }
             ClientCommand::StopSneaking | ClientCommand::LeaveBed => {
-                *pose = Pose::Standing;
-                size.height = 1.8;
+                state.sneaking = false;
             }
             ClientCommand::StartSprinting => {
                 tracking.sprinting = true;
@@ -404,10 +516,12 @@ fn client_command(
             ClientCommand::StopSprinting => {
                 tracking.sprinting = false;
             }
+            ClientCommand::StartFlyingWithElytra => {
+                movement::start_gliding(&mut state, &tracking, flight, inventory);
+            }
             ClientCommand::StartJumpWithHorse
             | ClientCommand::StopJumpWithHorse
-            | ClientCommand::OpenHorseInventory
-            | ClientCommand::StartFlyingWithElytra => {}
+            | ClientCommand::OpenHorseInventory => {}
         }
     }
 }
@@ -423,19 +537,23 @@ fn client_command(
 fn player_interact_item(
     mut packets: EventReader<'_, '_, play::PlayerInteractItem>,
     compose: Res<'_, Compose>,
-    query: Query<'_, '_, &PlayerInventory>,
+    query: Query<'_, '_, (&PlayerInventory, &GameMode)>,
     mut interact_event_writer: EventWriter<'_, event::InteractEvent>,
     mut item_interact_writer: EventWriter<'_, event::ItemInteract>,
 ) {
     for packet in packets.read() {
-        let inventory = match query.get(packet.sender()) {
-            Ok(inventory) => inventory,
+        let (inventory, game_mode) = match query.get(packet.sender()) {
+            Ok(data) => data,
             Err(e) => {
                 error!("failed to process player interact item: query failed: {e}");
                 continue;
             }
         };
 
+        if !game_mode.can_interact() {
+            continue;
+        }
+
         let event = event::InteractEvent {
             client: packet.sender(),
             hand: packet.hand,
@@ -475,9 +593,11 @@ fn player_interact_block(
             &PlayerInventory,
             &Position,
             &EntitySize,
+            &GameMode,
         ),
     >,
     blocks: Res<'_, Blocks>,
+    compose: Res<'_, Compose>,
     mut toggle_door_writer: EventWriter<'_, event::ToggleDoor>,
     mut place_block_writer: EventWriter<'_, event::PlaceBlock>,
 ) {
@@ -490,7 +610,7 @@ fn player_interact_block(
         // - inside_block: bool (whether the player's head is inside a block)
         // - sequence: VarInt (sequence number for this interaction)
 
-        let (mut confirm_block_sequences, inventory, client_position, size) =
+        let (mut confirm_block_sequences, inventory, client_position, size, &game_mode) =
             match query.get_mut(packet.sender()) {
                 Ok(data) => data,
                 Err(e) => {
@@ -501,6 +621,10 @@ fn player_interact_block(
 
         confirm_block_sequences.push(packet.sequence.0);
 
+        if !game_mode.can_interact() {
+            continue;
+        }
+
         let interacted_block_pos = packet.position;
         let interacted_block_pos_vec = IVec3::new(
             interacted_block_pos.x,
@@ -543,6 +667,13 @@ fn player_interact_block(
             let position = interacted_block_pos.get_in_direction(packet.face);
             let position = IVec3::new(position.x, position.y, position.z);
 
+            if !game_mode.can_modify_blocks()
+                && !game_mode::adventure_allows(held, "CanPlaceOn", interacted_block.to_kind())
+            {
+                reject_block_change(&compose, packet.connection_id(), &blocks, position);
+                continue;
+            }
+
             let position_dvec3 = position.as_vec3();
 
             // todo(hack): technically players can do some crazy position stuff to abuse this probably
@@ -571,24 +702,27 @@ fn player_interact_block(
 
 fn creative_inventory_action(
     mut packets: EventReader<'_, '_, play::CreativeInventoryAction>,
-    mut query: Query<'_, '_, &mut PlayerInventory>,
+    mut query: Query<'_, '_, (&mut PlayerInventory, &GameMode)>,
 ) {
     for packet in packets.read() {
-        // TODO: Verify that the player is in creative mode
-
         let Ok(slot) = u16::try_from(packet.slot) else {
             warn!("invalid slot {}", packet.slot);
             continue;
         };
 
-        let mut inventory = match query.get_mut(packet.sender()) {
-            Ok(inventory) => inventory,
+        let (mut inventory, game_mode) = match query.get_mut(packet.sender()) {
+            Ok(data) => data,
             Err(e) => {
                 error!("failed to handle creative inventory action: query failed: {e}");
                 continue;
             }
         };
 
+        if !game_mode.is_creative() {
+            warn!("ignoring creative inventory action from a player who is not in creative mode");
+            continue;
+        }
+
         if let Err(e) = inventory.set(slot, packet.clicked_item.clone()) {
             error!("failed to handle creative inventory action: inventory set failed: {e}");
         }
@@ -625,7 +759,7 @@ impl Plugin for HandlersPlugin {
                 position_and_look_updates,
                 hand_swing,
                 player_action,
-                client_command,
+                client_command.before(movement::update_movement_state),
                 player_interact_item,
                 player_interact_block,
                 creative_inventory_action,
@@ -0,0 +1,36 @@
+// Extends Monster.
+//
+// Index	Type	Meaning	Default
+// 16	VarInt (1)	State (-1 = idle, 1 = fuse)	-1
+// 17	Boolean (8)	Is charged	false
+// 18	Boolean (8)	Is ignited	false
+
+use bevy::prelude::*;
+use valence_protocol::VarInt;
+
+use super::Metadata;
+use crate::define_and_register_components;
+
+define_and_register_components! {
+    16, CreeperState -> VarInt,
+    17, Charged -> bool,
+    18, Ignited -> bool,
+}
+
+impl Default for CreeperState {
+    fn default() -> Self {
+        Self::new(VarInt(-1))
+    }
+}
+
+impl Default for Charged {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+impl Default for Ignited {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
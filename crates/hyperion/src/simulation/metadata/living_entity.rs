@@ -82,15 +82,17 @@ impl Health {
     }
 
     pub fn damage(&mut self, damage: f32) {
-        self.update(self.value - damage);
+        self.value = (self.value - damage).max(0.0);
     }
 
-    const fn update(&mut self, value: f32) {
-        self.value = value.clamp(0.0, 20.0);
-    }
-
-    pub fn heal(&mut self, heal: f32) {
-        self.update(self.value + heal);
+    /// Heals the entity up to `max_health`, which is usually
+    /// [`max_health`](crate::simulation::attribute::max_health) of the entity's attributes
+    pub fn heal(&mut self, heal: f32, max_health: f32) {
+        // Health above the maximum, such as from a lost health boost, is never raised or lowered
+        // by healing
+        if self.value < max_health {
+            self.value = (self.value + heal).clamp(0.0, max_health);
+        }
     }
 }
 
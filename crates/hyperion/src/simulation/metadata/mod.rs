@@ -8,10 +8,12 @@ use valence_protocol::{Encode, VarInt};
 use crate::simulation::metadata::entity::{EntityFlags, Pose};
 
 pub mod block_display;
+pub mod creeper;
 pub mod display;
 pub mod entity;
 pub mod living_entity;
 pub mod player;
+pub mod tnt;
 
 /// Set up a system to track metadata changes
 fn component_and_track<T>(app: &mut App)
@@ -63,6 +65,15 @@ fn initialize_entity(
                 block_display::default_components(),
             ));
         }
+        EntityKind::Creeper => {
+            entity.insert((
+                living_entity::default_components(),
+                creeper::default_components(),
+            ));
+        }
+        EntityKind::Tnt => {
+            entity.insert(tnt::default_components());
+        }
         EntityKind::Player => {
             entity.insert((
                 living_entity::default_components(),
@@ -86,6 +97,8 @@ impl Plugin for MetadataPlugin {
         block_display::register(app);
         living_entity::register(app);
         player::register(app);
+        creeper::register(app);
+        tnt::register(app);
     }
 }
 
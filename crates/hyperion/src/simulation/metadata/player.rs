@@ -56,7 +56,8 @@ impl Default for Score {
 
 impl Default for DisplayedSkinParts {
     fn default() -> Self {
-        Self::new(0)
+        // Every skin part is shown until the client sends its settings
+        Self::new(0x7f)
     }
 }
 
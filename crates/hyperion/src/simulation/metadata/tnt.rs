@@ -0,0 +1,20 @@
+// Extends Entity.
+//
+// Index	Type	Meaning	Default
+// 8	VarInt (1)	Fuse time	80
+
+use bevy::prelude::*;
+use valence_protocol::VarInt;
+
+use super::Metadata;
+use crate::define_and_register_components;
+
+define_and_register_components! {
+    8, FuseTicks -> VarInt,
+}
+
+impl Default for FuseTicks {
+    fn default() -> Self {
+        Self::new(VarInt(80))
+    }
+}
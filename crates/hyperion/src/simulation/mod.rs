@@ -15,8 +15,7 @@ use tracing::{error, info};
 use valence_protocol::{
     ByteAngle, VarInt,
     packets::play::{
-        self,
-        player_abilities_s2c::{PlayerAbilitiesFlags, PlayerAbilitiesS2c},
+        self, player_abilities_s2c::PlayerAbilitiesS2c,
         player_position_look_s2c::PlayerPositionLookFlags,
     },
 };
@@ -26,26 +25,42 @@ use crate::{
     Global,
     net::{Compose, ConnectionId, DataBundle},
     simulation::{
+        attribute::AttributePlugin,
+        boss_bar::BossBarPlugin,
+        client_settings::ClientSettingsPlugin,
         command::CommandPlugin,
+        effect::EffectPlugin,
         entity_kind::EntityKind,
+        game_mode::{GameMode, GameModePlugin},
         handlers::HandlersPlugin,
         inventory::InventoryPlugin,
         metadata::{Metadata, MetadataPlugin},
+        movement::MovementPlugin,
         packet::PacketPlugin,
+        projectile::ProjectilePlugin,
+        tab_list::TabListPlugin,
     },
 };
 
 pub mod animation;
+pub mod attribute;
 pub mod blocks;
+pub mod boss_bar;
+pub mod client_settings;
 pub mod command;
+pub mod effect;
 pub mod entity_kind;
 pub mod event;
+pub mod game_mode;
 pub mod handlers;
 pub mod inventory;
 pub mod metadata;
+pub mod movement;
 pub mod packet;
 pub mod packet_state;
+pub mod projectile;
 pub mod skin;
+pub mod tab_list;
 pub mod util;
 
 #[derive(Resource, Default, Debug, Deref, DerefMut)]
@@ -274,7 +289,8 @@ impl Uuid {
 #[derive(Component, Debug)]
 pub struct Npc;
 
-/// The running multiplier of the entity. This defaults to 1.0.
+/// The movement speed of the entity. This defaults to 0.1 and is kept in sync with the movement
+/// speed in [`attribute::Attributes`].
 #[derive(Component, Debug, Copy, Clone)]
 pub struct RunningSpeed(pub f32);
 
@@ -446,6 +462,17 @@ pub fn block_bounds(position: Vec3, size: EntitySize) -> (IVec3, IVec3) {
     (min, max)
 }
 
+/// How far players can interact with blocks from their eyes, matching the Notchian server
+pub const BLOCK_REACH: f32 = 6.0;
+
+/// Whether an entity at `position` is close enough to interact with the block at `block`
+#[must_use]
+pub fn can_reach_block(position: Vec3, size: EntitySize, block: IVec3) -> bool {
+    let eye = position + Vec3::new(0.0, size.height * 0.85, 0.0);
+    let center = block.as_vec3() + Vec3::splat(0.5);
+    eye.distance_squared(center) <= BLOCK_REACH * BLOCK_REACH
+}
+
 /// The initial player spawn position. todo: this should not be a constant
 pub const PLAYER_SPAWN_POSITION: Vec3 = Vec3::new(-8_526_209_f32, 100f32, -6_028_464f32);
 
@@ -716,16 +743,14 @@ fn spawn_entities(
 fn update_flight(
     trigger: Trigger<'_, OnInsert, (FlyingSpeed, Flight)>,
     compose: Res<'_, Compose>,
-    query: Query<'_, '_, (&ConnectionId, &Flight, &FlyingSpeed)>,
+    query: Query<'_, '_, (&ConnectionId, &Flight, &FlyingSpeed, Option<&GameMode>)>,
 ) {
-    let Ok((&connection_id, flight, flying_speed)) = query.get(trigger.target()) else {
+    let Ok((&connection_id, flight, flying_speed, game_mode)) = query.get(trigger.target()) else {
         return;
     };
 
     let pkt = PlayerAbilitiesS2c {
-        flags: PlayerAbilitiesFlags::default()
-            .with_allow_flying(flight.allow)
-            .with_flying(flight.is_flying),
+        flags: game_mode.copied().unwrap_or_default().abilities(flight),
         flying_speed: flying_speed.speed,
         fov_modifier: 0.0,
     };
@@ -744,11 +769,19 @@ impl Plugin for SimPlugin {
         app.add_observer(initialize_uuid);
 
         app.add_plugins((
+            AttributePlugin,
+            BossBarPlugin,
+            ClientSettingsPlugin,
             CommandPlugin,
+            EffectPlugin,
+            GameModePlugin,
             HandlersPlugin,
             PacketPlugin,
             InventoryPlugin,
             MetadataPlugin,
+            MovementPlugin,
+            ProjectilePlugin,
+            TabListPlugin,
         ));
         app.add_systems(FixedUpdate, spawn_entities);
 
@@ -767,10 +800,12 @@ impl Plugin for SimPlugin {
         app.add_event::<event::BlockInteract>();
         app.add_event::<event::ProjectileEntityEvent>();
         app.add_event::<event::ProjectileBlockEvent>();
+        app.add_event::<event::ProjectileReturnEvent>();
         app.add_event::<event::ClickSlotEvent>();
         app.add_event::<event::DropItemStackEvent>();
         app.add_event::<event::UpdateSelectedSlotEvent>();
         app.add_event::<event::HitGroundEvent>();
+        app.add_event::<event::JumpEvent>();
         app.add_event::<event::InteractEvent>();
     }
 }
@@ -0,0 +1,481 @@
+//! The server-side model of player movement.
+//!
+//! Clients simulate their own movement, but the server predicts it to check that movement packets
+//! are plausible. [`MovementState`] tracks what a player is moving through and how they are
+//! moving, which changes their pose, hitbox and physics: swimming, climbing, sneaking, gliding
+//! with an elytra, and moving through or over blocks which slow players down.
+
+use bevy::prelude::*;
+use glam::DVec3;
+use hyperion_inventory::PlayerInventory;
+use valence_generated::{
+    block::{BlockKind, BlockState, PropName, PropValue},
+    item::ItemKind,
+};
+
+use crate::{
+    ingress,
+    simulation::{
+        EntitySize, Flight, MovementTracking, Position, block_bounds,
+        blocks::Blocks,
+        event::{self, Posture},
+        metadata::entity::Pose,
+    },
+};
+
+const STANDING_HEIGHT: f32 = 1.8;
+const SNEAKING_HEIGHT: f32 = 1.5;
+/// The height while swimming or gliding
+const PRONE_HEIGHT: f32 = 0.6;
+
+/// The fastest a player can move horizontally or fall while climbing
+const CLIMB_SPEED: f64 = 0.15;
+
+/// The step used when checking whether a sneaking player would walk off an edge
+const EDGE_STEP: f64 = 0.05;
+
+/// What a player is moving through and how they are moving
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq)]
+pub struct MovementState {
+    /// Whether the feet of the player are in water
+    pub in_water: bool,
+    /// Whether the eyes of the player are in water
+    pub submerged: bool,
+    pub in_lava: bool,
+    /// Whether the player is on a ladder, vine, scaffolding or other climbable block
+    pub climbing: bool,
+    pub sneaking: bool,
+    pub swimming: bool,
+    /// Whether the player is gliding with an elytra
+    pub gliding: bool,
+    /// The velocity multiplier of a block the player is stuck in, such as a cobweb
+    pub stuck: Option<DVec3>,
+    /// The horizontal velocity multiplier of a block the player is walking on, such as soul sand
+    pub speed_factor: Option<f64>,
+}
+
+impl MovementState {
+    /// The posture the player is in
+    #[must_use]
+    pub const fn posture(&self) -> Posture {
+        if self.gliding {
+            Posture::FallFlying
+        } else if self.swimming {
+            Posture::Swimming
+        } else if self.sneaking {
+            Posture::Sneaking
+        } else {
+            Posture::Standing
+        }
+    }
+
+    /// Whether the player takes no fall damage from the distance fallen so far
+    #[must_use]
+    pub const fn resets_fall(&self) -> bool {
+        self.in_water || self.in_lava || self.climbing || self.gliding || self.stuck.is_some()
+    }
+
+    /// How much the most recent movement packets can exceed the predicted velocity, in squared
+    /// blocks per movement packet, before the player is teleported back
+    #[must_use]
+    pub const fn movement_tolerance(&self) -> f64 {
+        if self.gliding { 300.0 } else { 100.0 }
+    }
+}
+
+/// Whether a player can climb the block
+#[must_use]
+pub fn is_climbable(state: BlockState) -> bool {
+    matches!(
+        state.to_kind(),
+        BlockKind::Ladder
+            | BlockKind::Vine
+            | BlockKind::Scaffolding
+            | BlockKind::TwistingVines
+            | BlockKind::TwistingVinesPlant
+            | BlockKind::WeepingVines
+            | BlockKind::WeepingVinesPlant
+            | BlockKind::CaveVines
+            | BlockKind::CaveVinesPlant
+    )
+}
+
+/// Whether the block counts as water for movement, drowning and putting out fire
+#[must_use]
+pub fn is_water(state: BlockState) -> bool {
+    matches!(
+        state.to_kind(),
+        BlockKind::Water
+            | BlockKind::BubbleColumn
+            | BlockKind::Kelp
+            | BlockKind::KelpPlant
+            | BlockKind::Seagrass
+            | BlockKind::TallSeagrass
+    ) || state.get(PropName::Waterlogged) == Some(PropValue::True)
+}
+
+/// The velocity multiplier of a block which players get stuck in, or `None` if the block does not
+/// slow players down this way
+#[must_use]
+pub fn stuck_multiplier(kind: BlockKind) -> Option<DVec3> {
+    match kind {
+        BlockKind::Cobweb => Some(DVec3::new(0.25, 0.05, 0.25)),
+        BlockKind::SweetBerryBush => Some(DVec3::new(0.8, 0.75, 0.8)),
+        BlockKind::PowderSnow => Some(DVec3::new(0.9, 1.5, 0.9)),
+        _ => None,
+    }
+}
+
+/// The horizontal velocity multiplier of a player in or on the block, or `None` if the block does
+/// not slow players down this way
+#[must_use]
+pub fn speed_factor(kind: BlockKind) -> Option<f64> {
+    match kind {
+        BlockKind::SoulSand | BlockKind::HoneyBlock => Some(0.4),
+        _ => None,
+    }
+}
+
+/// The multiplier applied to the jump velocity of a player standing on the block
+#[must_use]
+pub fn jump_factor(kind: BlockKind) -> f64 {
+    match kind {
+        BlockKind::HoneyBlock => 0.5,
+        _ => 1.0,
+    }
+}
+
+const fn height(posture: Posture) -> f32 {
+    match posture {
+        Posture::Sneaking => SNEAKING_HEIGHT,
+        Posture::Swimming | Posture::FallFlying => PRONE_HEIGHT,
+        _ => STANDING_HEIGHT,
+    }
+}
+
+const fn to_pose(posture: Posture) -> Pose {
+    match posture {
+        Posture::Sneaking => Pose::Sneaking,
+        Posture::Swimming => Pose::Swimming,
+        Posture::FallFlying => Pose::FallFlying,
+        _ => Pose::Standing,
+    }
+}
+
+/// Starts gliding if the player is wearing an elytra and can glide
+pub fn start_gliding(
+    state: &mut MovementState,
+    tracking: &MovementTracking,
+    flight: &Flight,
+    inventory: &PlayerInventory,
+) {
+    let can_glide = !tracking.was_on_ground
+        && !flight.is_flying
+        && !state.in_water
+        && !state.in_lava
+        && inventory.get_chestplate().stack.item == ItemKind::Elytra;
+
+    if can_glide {
+        state.gliding = true;
+    }
+}
+
+/// Updates the [`MovementState`] of players from the blocks around them. Client commands which
+/// start or stop sneaking and sprinting are handled before this runs.
+pub fn update_movement_state(
+    mut query: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &Position,
+            &mut EntitySize,
+            &mut Pose,
+            &mut MovementState,
+            &MovementTracking,
+            &Flight,
+            Option<&PlayerInventory>,
+        ),
+    >,
+    blocks: Res<'_, Blocks>,
+    mut writer: EventWriter<'_, event::PostureUpdate>,
+) {
+    for (entity, position, mut size, mut pose, mut state, tracking, flight, inventory) in &mut query
+    {
+        let previous = state.posture();
+
+        let (min, max) = block_bounds(**position, *size);
+        let eyes = (**position + Vec3::new(0.0, size.height * 0.85, 0.0))
+            .floor()
+            .as_ivec3();
+
+        let mut in_water = false;
+        let mut in_lava = false;
+        let mut stuck: Option<DVec3> = None;
+
+        for x in min.x..max.x {
+            for y in min.y..max.y {
+                for z in min.z..max.z {
+                    let Some(state) = blocks.get_block(IVec3::new(x, y, z)) else {
+                        continue;
+                    };
+
+                    in_water |= is_water(state);
+                    in_lava |= state.to_kind() == BlockKind::Lava;
+
+                    if let Some(multiplier) = stuck_multiplier(state.to_kind()) {
+                        // The slowest block wins
+                        stuck = Some(stuck.map_or(multiplier, |current| current.min(multiplier)));
+                    }
+                }
+            }
+        }
+
+        let feet = position.floor().as_ivec3();
+        let climbing = blocks.get_block(feet).is_some_and(is_climbable);
+
+        // Players are slowed by the block they are in, such as soul sand which is lower than a
+        // full block, or otherwise by the block they are standing on
+        let below = (**position - Vec3::new(0.0, 0.5, 0.0)).floor().as_ivec3();
+        let speed = |block| {
+            blocks
+                .get_block(block)
+                .and_then(|state| speed_factor(state.to_kind()))
+        };
+
+        state.in_water = in_water;
+        state.submerged = blocks.get_block(eyes).is_some_and(is_water);
+        state.in_lava = in_lava;
+        state.climbing = climbing && !flight.is_flying;
+        state.stuck = stuck;
+        state.speed_factor = if in_water {
+            None
+        } else {
+            speed(feet).or_else(|| speed(below))
+        };
+
+        // Players start swimming by sprinting underwater and keep swimming while sprinting in water
+        let in_swimmable_water = if state.swimming {
+            state.in_water
+        } else {
+            state.submerged
+        };
+        state.swimming = tracking.sprinting && in_swimmable_water && !flight.is_flying;
+
+        let wearing_elytra = inventory
+            .is_some_and(|inventory| inventory.get_chestplate().stack.item == ItemKind::Elytra);
+        if tracking.was_on_ground || in_water || in_lava || flight.is_flying || !wearing_elytra {
+            state.gliding = false;
+        }
+
+        let posture = state.posture();
+        if posture == previous {
+            continue;
+        }
+
+        *pose = to_pose(posture);
+        size.height = height(posture);
+
+        writer.write(event::PostureUpdate {
+            client: entity,
+            state: posture,
+        });
+    }
+}
+
+/// Whether a player at `position` would be standing on a block. Players are supported as long as
+/// any part of their hitbox is above a block they collide with, so water and plants do not count.
+fn supported(
+    position: DVec3,
+    size: EntitySize,
+    get_block: impl Fn(IVec3) -> Option<BlockState>,
+) -> bool {
+    let half_width = f64::from(size.half_width);
+
+    // A thin box just below the feet of the player
+    let min = position - DVec3::new(half_width, 0.001, half_width);
+    let max = position + DVec3::new(half_width, 0.0, half_width);
+
+    // Blocks such as fences stick up into the block above them
+    let start = min.floor().as_ivec3() - IVec3::Y;
+    let end = max.floor().as_ivec3();
+
+    (start.x..=end.x).any(|x| {
+        (start.y..=end.y).any(|y| {
+            (start.z..=end.z).any(|z| {
+                let block = IVec3::new(x, y, z);
+
+                // Unloaded blocks are treated as solid
+                let Some(state) = get_block(block) else {
+                    return true;
+                };
+
+                let origin = block.as_dvec3();
+                state.collision_shapes().any(|shape| {
+                    (shape.min() + origin).cmplt(max).all()
+                        && (shape.max() + origin).cmpgt(min).all()
+                })
+            })
+        })
+    })
+}
+
+/// Predicts the velocity of a player for the next tick. `friction` is the friction of the block
+/// the player is standing on.
+pub fn predict_velocity(
+    tracking: &mut MovementTracking,
+    state: &MovementState,
+    position: Vec3,
+    size: EntitySize,
+    friction: f64,
+    blocks: &Blocks,
+) {
+    let velocity = &mut tracking.server_velocity;
+
+    if state.stuck.is_some() {
+        // Blocks such as cobwebs slow the player down without letting them keep momentum
+        *velocity = DVec3::ZERO;
+        return;
+    }
+
+    if state.in_water {
+        let drag = if tracking.sprinting { 0.9 } else { 0.8 };
+        velocity.x *= drag;
+        velocity.y *= 0.8;
+        velocity.z *= drag;
+        velocity.y -= 0.02;
+    } else if state.in_lava {
+        *velocity *= 0.5;
+        velocity.y -= 0.02;
+    } else if state.gliding {
+        velocity.x *= 0.99;
+        velocity.y *= 0.98;
+        velocity.z *= 0.99;
+        velocity.y -= 0.08 * 0.75;
+    } else {
+        let speed = state.speed_factor.unwrap_or(1.0);
+        velocity.x *= friction * 0.98 * speed;
+        velocity.y -= 0.08 * 0.980_000_019_073_486_3;
+        velocity.z *= friction * 0.98 * speed;
+    }
+
+    if state.climbing {
+        velocity.x = velocity.x.clamp(-CLIMB_SPEED, CLIMB_SPEED);
+        velocity.z = velocity.z.clamp(-CLIMB_SPEED, CLIMB_SPEED);
+        velocity.y = velocity.y.max(-CLIMB_SPEED);
+
+        // Sneaking players hold on to the ladder
+        if state.sneaking {
+            velocity.y = velocity.y.max(0.0);
+        }
+    }
+
+    // Sneaking players do not walk off edges
+    if state.sneaking && tracking.was_on_ground {
+        let start = position.as_dvec3();
+
+        while velocity.x.abs() > 0.0
+            && !supported(start + DVec3::new(velocity.x, 0.0, 0.0), size, |block| {
+                blocks.get_block(block)
+            })
+        {
+            velocity.x = step_toward_zero(velocity.x);
+        }
+
+        while velocity.z.abs() > 0.0
+            && !supported(start + DVec3::new(0.0, 0.0, velocity.z), size, |block| {
+                blocks.get_block(block)
+            })
+        {
+            velocity.z = step_toward_zero(velocity.z);
+        }
+    }
+
+    for axis in [&mut velocity.x, &mut velocity.y, &mut velocity.z] {
+        if axis.abs() < 0.003 {
+            *axis = 0.0;
+        }
+    }
+}
+
+fn step_toward_zero(value: f64) -> f64 {
+    if value.abs() < EDGE_STEP {
+        0.0
+    } else {
+        value - EDGE_STEP.copysign(value)
+    }
+}
+
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            update_movement_state.after(ingress::decode::play),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posture_priority() {
+        let state = MovementState {
+            sneaking: true,
+            swimming: true,
+            ..MovementState::default()
+        };
+        assert_eq!(state.posture(), Posture::Swimming);
+
+        let state = MovementState {
+            sneaking: true,
+            ..MovementState::default()
+        };
+        assert_eq!(state.posture(), Posture::Sneaking);
+        assert!((height(state.posture()) - SNEAKING_HEIGHT).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn slowing_blocks() {
+        assert_eq!(speed_factor(BlockKind::SoulSand), Some(0.4));
+        assert_eq!(speed_factor(BlockKind::Stone), None);
+        assert!(stuck_multiplier(BlockKind::Cobweb).is_some());
+        assert!((jump_factor(BlockKind::HoneyBlock) - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn edge_steps() {
+        assert!((step_toward_zero(0.12) - 0.07).abs() < 1e-9);
+        assert!((step_toward_zero(-0.12) + 0.07).abs() < 1e-9);
+        assert!(step_toward_zero(0.01).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn only_blocks_with_collision_support_players() {
+        let size = EntitySize::default();
+        let on = |below: BlockState, height: i32| {
+            move |block: IVec3| {
+                Some(if block.y < height {
+                    below
+                } else {
+                    BlockState::AIR
+                })
+            }
+        };
+
+        let position = DVec3::new(0.5, 64.0, 0.5);
+        assert!(supported(position, size, on(BlockState::STONE, 64)));
+        assert!(!supported(position, size, on(BlockState::AIR, 64)));
+
+        // Standing in water is not standing on the ground
+        assert!(!supported(position, size, on(BlockState::WATER, 64)));
+        assert!(!supported(position, size, on(BlockState::WATER, 66)));
+
+        // Fences are taller than a block
+        let position = DVec3::new(0.5, 1.5, 0.5);
+        assert!(supported(position, size, on(BlockState::OAK_FENCE, 1)));
+    }
+}
@@ -0,0 +1,635 @@
+//! Projectiles such as arrows, snowballs and ender pearls.
+//!
+//! Entities spawned with a projectile [`EntityKind`] are given a [`Projectile`] with the vanilla
+//! physics for that kind. Inserting a different [`Projectile`] customizes the projectile.
+//!
+//! Every tick, projectiles move by their [`Velocity`] and check for collisions along the way. A
+//! [`event::ProjectileEntityEvent`] or [`event::ProjectileBlockEvent`] is sent when a projectile
+//! hits something, and the projectile then behaves according to its [`OnHit`]. Projectiles stuck
+//! in a block are removed after a minute unless they return to their owner. Projectiles which are
+//! removed are despawned at the start of the next tick, so handlers of these events can still
+//! query them.
+
+use bevy::prelude::*;
+use geometry::{aabb::Aabb, ray::Ray};
+use glam::Vec3;
+use hyperion_utils::EntityExt;
+use tracing::error;
+use valence_protocol::{
+    ByteAngle, VarInt,
+    packets::play::{
+        EntitiesDestroyS2c, EntityPositionS2c, EntityStatusS2c, EntityVelocityUpdateS2c,
+    },
+};
+
+use crate::{
+    net::{Compose, ConnectionId, DataBundle},
+    simulation::{
+        EntitySize, Owner, PendingTeleportation, Position, Velocity, aabb,
+        blocks::{Blocks, RayCollision},
+        entity_kind::EntityKind,
+        event,
+        game_mode::GameMode,
+    },
+    spatial::SpatialIndex,
+};
+
+/// The fastest a projectile can travel in blocks per tick
+const TERMINAL_VELOCITY: f32 = 100.0;
+
+/// Velocity kept after bouncing off a block
+const BOUNCE_RESTITUTION: f32 = 0.5;
+
+/// Ticks a projectile with loyalty stays stuck in a block before returning to its owner
+const RETURN_DELAY: u16 = 4;
+
+/// Ticks a projectile stays stuck in a block before it is removed, like vanilla arrows
+const STUCK_LIFETIME: u16 = 1200;
+
+/// Distance from its owner at which a returning projectile is picked up
+const RETURN_PICKUP_DISTANCE: f32 = 1.5;
+
+/// The entity status which shows the item break particles of snowballs and eggs
+const BREAK_STATUS: u8 = 3;
+
+/// What happens to a projectile once it hits something
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OnHit {
+    /// Stays where it hit a block, such as arrows and tridents. The projectile is removed when it
+    /// hits an entity.
+    Stick,
+    /// Breaks, such as snowballs and eggs
+    Break,
+    /// Is removed without breaking, such as fireballs and splash potions
+    Discard,
+    /// Teleports its owner to where it hit, then breaks, such as ender pearls
+    Teleport,
+}
+
+enum Hit {
+    /// An entity and the fraction of the ray traveled before hitting it
+    Entity(Entity, f32),
+    Block(RayCollision),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ProjectileState {
+    Flying,
+    Stuck { ticks: u16 },
+    Returning,
+    Removed,
+}
+
+/// The physics and on-hit behaviour of a projectile
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Projectile {
+    /// Subtracted from the vertical velocity every tick
+    pub gravity: f32,
+    /// The velocity is multiplied by this every tick
+    pub drag: f32,
+    /// Half the width of the hitbox used to hit entities
+    pub hitbox: f32,
+    pub on_hit: OnHit,
+    /// The number of entities the projectile passes through before its [`OnHit`] applies
+    pub pierce: u8,
+    /// The number of times the projectile bounces off blocks before its [`OnHit`] applies
+    pub bounces: u8,
+    /// If this is above zero, the projectile flies back to its owner after hitting something.
+    /// Higher levels return faster.
+    pub loyalty: u8,
+    state: ProjectileState,
+    hit_entities: Vec<Entity>,
+}
+
+impl Projectile {
+    #[must_use]
+    pub const fn new(gravity: f32, drag: f32, hitbox: f32, on_hit: OnHit) -> Self {
+        Self {
+            gravity,
+            drag,
+            hitbox,
+            on_hit,
+            pierce: 0,
+            bounces: 0,
+            loyalty: 0,
+            state: ProjectileState::Flying,
+            hit_entities: Vec::new(),
+        }
+    }
+
+    /// The vanilla projectile of the given kind, or `None` if the kind is not a projectile
+    #[must_use]
+    pub const fn vanilla(kind: EntityKind) -> Option<Self> {
+        let projectile = match kind {
+            EntityKind::Arrow | EntityKind::SpectralArrow => {
+                Self::new(0.05, 0.997_525, 0.25, OnHit::Stick)
+            }
+            EntityKind::Trident => Self::new(0.05, 0.99, 0.25, OnHit::Stick),
+            EntityKind::Snowball | EntityKind::Egg => Self::new(0.03, 0.99, 0.125, OnHit::Break),
+            EntityKind::EnderPearl => Self::new(0.03, 0.99, 0.125, OnHit::Teleport),
+            EntityKind::Potion | EntityKind::ExperienceBottle => {
+                Self::new(0.05, 0.99, 0.125, OnHit::Discard)
+            }
+            EntityKind::Fireball | EntityKind::DragonFireball | EntityKind::WitherSkull => {
+                Self::new(0.0, 1.0, 0.5, OnHit::Discard)
+            }
+            EntityKind::SmallFireball => Self::new(0.0, 1.0, 0.156_25, OnHit::Discard),
+            _ => return None,
+        };
+
+        Some(projectile)
+    }
+
+    #[must_use]
+    pub const fn with_pierce(mut self, pierce: u8) -> Self {
+        self.pierce = pierce;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bounces(mut self, bounces: u8) -> Self {
+        self.bounces = bounces;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_loyalty(mut self, loyalty: u8) -> Self {
+        self.loyalty = loyalty;
+        self
+    }
+
+    /// Whether the projectile is stuck in a block
+    #[must_use]
+    pub const fn is_stuck(&self) -> bool {
+        matches!(self.state, ProjectileState::Stuck { .. })
+    }
+
+    /// Whether the projectile is flying back to its owner
+    #[must_use]
+    pub const fn is_returning(&self) -> bool {
+        matches!(self.state, ProjectileState::Returning)
+    }
+
+    /// Whether the projectile will be despawned at the start of the next tick
+    #[must_use]
+    pub const fn is_removed(&self) -> bool {
+        matches!(self.state, ProjectileState::Removed)
+    }
+
+    /// Marks the projectile to be despawned at the start of the next tick
+    pub const fn remove(&mut self) {
+        self.state = ProjectileState::Removed;
+    }
+
+    /// Called after hitting an entity or settling in a block, returning whether the projectile
+    /// is still in the world
+    const fn start_returning(&mut self) -> bool {
+        if self.loyalty == 0 {
+            return false;
+        }
+
+        self.state = ProjectileState::Returning;
+        true
+    }
+
+    /// Advances a stuck projectile by a tick. It starts returning to its owner after
+    /// [`RETURN_DELAY`] ticks if it can, and is removed after [`STUCK_LIFETIME`] ticks.
+    const fn tick_stuck(&mut self, has_owner: bool) {
+        let ProjectileState::Stuck { ticks } = self.state else {
+            return;
+        };
+
+        let ticks = ticks.saturating_add(1);
+        self.state = ProjectileState::Stuck { ticks };
+
+        if ticks >= STUCK_LIFETIME {
+            self.remove();
+        } else if ticks >= RETURN_DELAY && has_owner {
+            self.start_returning();
+        }
+    }
+}
+
+/// Reflects `velocity` off a surface with the given normal
+#[must_use]
+pub fn bounce(velocity: Vec3, normal: Vec3) -> Vec3 {
+    (velocity - 2.0 * velocity.dot(normal) * normal) * BOUNCE_RESTITUTION
+}
+
+fn initialize_projectile(
+    trigger: Trigger<'_, OnInsert, EntityKind>,
+    query: Query<'_, '_, &EntityKind>,
+    mut commands: Commands<'_, '_>,
+) {
+    let Ok(&kind) = query.get(trigger.target()) else {
+        return;
+    };
+
+    if let Some(projectile) = Projectile::vanilla(kind) {
+        commands.entity(trigger.target()).insert_if_new(projectile);
+    }
+}
+
+fn despawn_removed_projectiles(
+    query: Query<'_, '_, (Entity, &Projectile, &Position)>,
+    compose: Res<'_, Compose>,
+    mut commands: Commands<'_, '_>,
+) {
+    for (entity, projectile, position) in &query {
+        if !projectile.is_removed() {
+            continue;
+        }
+
+        let pkt = EntitiesDestroyS2c {
+            entity_ids: vec![VarInt(entity.minecraft_id())].into(),
+        };
+        compose
+            .broadcast_local(&pkt, position.to_chunk())
+            .send()
+            .unwrap();
+
+        commands.entity(entity).despawn();
+    }
+}
+
+/// The entities a projectile with the given hitbox traveling along `ray` hits this tick, sorted by
+/// distance. Spectators cannot be hit.
+fn entity_hits(
+    ray: Ray,
+    hitbox: f32,
+    index: &SpatialIndex,
+    query: Query<'_, '_, (&Position, &EntitySize)>,
+    game_modes: &Query<'_, '_, &GameMode>,
+) -> Vec<(Entity, f32)> {
+    let start = ray.origin();
+    let end = ray.at(1.0);
+    let swept = Aabb::new(start.min(end), start.max(end)).expand(hitbox);
+
+    let mut hits: Vec<_> = index
+        .get_collisions(swept, query)
+        .filter(|&entity| {
+            !game_modes
+                .get(entity)
+                .is_ok_and(|game_mode| game_mode.is_spectator())
+        })
+        .filter_map(|entity| {
+            let (position, size) = query.get(entity).ok()?;
+            let distance = aabb(**position, *size)
+                .expand(hitbox)
+                .intersect_ray(&ray)?
+                .into_inner();
+            (distance <= 1.0).then_some((entity, distance))
+        })
+        .collect();
+
+    hits.sort_unstable_by(|(_, a), (_, b)| a.total_cmp(b));
+    hits
+}
+
+/// The normal of the block face a projectile moving with `velocity` hit
+fn face_normal(collision: &RayCollision, velocity: Vec3) -> Vec3 {
+    const EPSILON: f32 = 1e-4;
+
+    let origin = collision.location.as_vec3();
+    let point = collision.point;
+
+    for shape in collision.block.collision_shapes() {
+        let min = shape.min().as_vec3() + origin;
+        let max = shape.max().as_vec3() + origin;
+
+        for axis in 0..3 {
+            let mut normal = Vec3::ZERO;
+
+            if velocity[axis] > 0.0 && (point[axis] - min[axis]).abs() < EPSILON {
+                normal[axis] = -1.0;
+                return normal;
+            }
+
+            if velocity[axis] < 0.0 && (point[axis] - max[axis]).abs() < EPSILON {
+                normal[axis] = 1.0;
+                return normal;
+            }
+        }
+    }
+
+    -velocity.normalize_or_zero()
+}
+
+fn update_stuck_and_returning(
+    mut projectiles: Query<
+        '_,
+        '_,
+        (
+            Entity,
+            &mut Projectile,
+            &mut Position,
+            &mut Velocity,
+            Option<&Owner>,
+        ),
+    >,
+    owners: Query<'_, '_, (&Position, Option<&EntitySize>), Without<Projectile>>,
+    compose: Res<'_, Compose>,
+    mut return_writer: EventWriter<'_, event::ProjectileReturnEvent>,
+) {
+    for (entity, mut projectile, mut position, mut velocity, owner) in &mut projectiles {
+        match projectile.state {
+            ProjectileState::Stuck { .. } => projectile.tick_stuck(owner.is_some()),
+            ProjectileState::Returning => {
+                let Some(owner) = owner else {
+                    projectile.remove();
+                    continue;
+                };
+
+                let Ok((owner_position, owner_size)) = owners.get(owner.entity) else {
+                    // The owner is gone, so there is nothing to return to
+                    projectile.remove();
+                    continue;
+                };
+
+                let height = owner_size.map_or(0.0, |size| size.height);
+                let target = **owner_position + Vec3::new(0.0, height / 2.0, 0.0);
+                let delta = target - **position;
+
+                if delta.length() < RETURN_PICKUP_DISTANCE {
+                    return_writer.write(event::ProjectileReturnEvent {
+                        projectile: entity,
+                        owner: owner.entity,
+                    });
+                    projectile.remove();
+                    continue;
+                }
+
+                // Returning projectiles fly through blocks and entities
+                velocity.0 = velocity.0 * 0.95
+                    + delta.normalize_or_zero() * 0.05 * f32::from(projectile.loyalty);
+                **position += velocity.0;
+
+                sync_position(entity, &position, &velocity, &compose);
+            }
+            ProjectileState::Flying | ProjectileState::Removed => {}
+        }
+    }
+}
+
+fn update_flying(
+    entities: Query<'_, '_, Entity, With<Projectile>>,
+    mut query_set: ParamSet<
+        '_,
+        '_,
+        (
+            Query<
+                '_,
+                '_,
+                (
+                    &mut Projectile,
+                    &mut Position,
+                    &mut Velocity,
+                    Option<&Owner>,
+                ),
+            >,
+            Query<'_, '_, (&Position, &EntitySize)>,
+        ),
+    >,
+    owners: Query<'_, '_, Has<ConnectionId>, Without<Projectile>>,
+    game_modes: Query<'_, '_, &GameMode>,
+    index: Res<'_, SpatialIndex>,
+    blocks: Res<'_, Blocks>,
+    compose: Res<'_, Compose>,
+    mut entity_writer: EventWriter<'_, event::ProjectileEntityEvent>,
+    mut block_writer: EventWriter<'_, event::ProjectileBlockEvent>,
+    mut commands: Commands<'_, '_>,
+) {
+    for entity in &entities {
+        let (ray, hitbox) = match query_set.p0().get(entity) {
+            Ok((projectile, position, velocity, _)) => {
+                if projectile.state != ProjectileState::Flying || velocity.0 == Vec3::ZERO {
+                    continue;
+                }
+
+                (Ray::new(**position, velocity.0), projectile.hitbox)
+            }
+            Err(e) => {
+                error!("failed to update projectile: query failed: {e}");
+                continue;
+            }
+        };
+
+        let entity_hits = entity_hits(ray, hitbox, &index, query_set.p1(), &game_modes);
+        let block_hit = blocks.first_collision_in_segment(ray);
+
+        let mut query = query_set.p0();
+        let Ok((mut projectile, mut position, mut velocity, owner)) = query.get_mut(entity) else {
+            continue;
+        };
+        let owner = owner.map(|owner| owner.entity);
+
+        let entity_hit = entity_hits.into_iter().find(|&(target, _)| {
+            target != entity && Some(target) != owner && !projectile.hit_entities.contains(&target)
+        });
+
+        let hit = match (entity_hit, block_hit) {
+            (Some((_, distance)), Some(block)) if block.distance <= distance => {
+                Some(Hit::Block(block))
+            }
+            (Some((target, distance)), _) => Some(Hit::Entity(target, distance)),
+            (None, Some(block)) => Some(Hit::Block(block)),
+            (None, None) => None,
+        };
+
+        match hit {
+            Some(Hit::Entity(target, distance)) => {
+                entity_writer.write(event::ProjectileEntityEvent {
+                    client: target,
+                    projectile: entity,
+                });
+                projectile.hit_entities.push(target);
+
+                if projectile.pierce > 0 {
+                    projectile.pierce -= 1;
+                } else {
+                    if projectile.on_hit == OnHit::Teleport {
+                        teleport_owner(owner, ray.at(distance), &owners, &mut commands);
+                    }
+
+                    if projectile.start_returning() {
+                        // Bounce off the entity before returning
+                        velocity.0 *= Vec3::new(-0.01, -0.1, -0.01);
+                    } else {
+                        finish(entity, &mut projectile, &position, &compose);
+                    }
+
+                    continue;
+                }
+            }
+            Some(Hit::Block(collision)) => {
+                let normal = face_normal(&collision, velocity.0);
+                **position = collision.point;
+
+                block_writer.write(event::ProjectileBlockEvent {
+                    collision,
+                    projectile: entity,
+                });
+
+                if projectile.bounces > 0 {
+                    projectile.bounces -= 1;
+                    velocity.0 = bounce(velocity.0, normal);
+                    // Move slightly away from the block to avoid hitting it again
+                    **position += normal * 0.01;
+
+                    sync_position(entity, &position, &velocity, &compose);
+                    continue;
+                }
+
+                match projectile.on_hit {
+                    OnHit::Stick => {
+                        velocity.0 = Vec3::ZERO;
+                        projectile.state = ProjectileState::Stuck { ticks: 0 };
+                    }
+                    OnHit::Teleport => {
+                        teleport_owner(owner, **position, &owners, &mut commands);
+                        finish(entity, &mut projectile, &position, &compose);
+                    }
+                    OnHit::Break | OnHit::Discard => {
+                        finish(entity, &mut projectile, &position, &compose);
+                    }
+                }
+
+                continue;
+            }
+            None => {}
+        }
+
+        velocity.0 *= projectile.drag;
+        velocity.0.y -= projectile.gravity;
+        velocity.0 = velocity.0.clamp_length_max(TERMINAL_VELOCITY);
+
+        **position += velocity.0;
+    }
+}
+
+/// Removes a projectile which hit something, showing break particles if needed
+fn finish(entity: Entity, projectile: &mut Projectile, position: &Position, compose: &Compose) {
+    if matches!(projectile.on_hit, OnHit::Break | OnHit::Teleport) {
+        let pkt = EntityStatusS2c {
+            entity_id: entity.minecraft_id(),
+            entity_status: BREAK_STATUS,
+        };
+        compose
+            .broadcast_local(&pkt, position.to_chunk())
+            .send()
+            .unwrap();
+    }
+
+    projectile.remove();
+}
+
+/// Tells clients where a projectile is after its path changed unexpectedly
+fn sync_position(entity: Entity, position: &Position, velocity: &Velocity, compose: &Compose) {
+    let entity_id = VarInt(entity.minecraft_id());
+
+    let mut bundle = DataBundle::new(compose);
+    bundle
+        .add_packet(&EntityPositionS2c {
+            entity_id,
+            position: position.as_dvec3(),
+            yaw: ByteAngle(0),
+            pitch: ByteAngle(0),
+            on_ground: false,
+        })
+        .unwrap();
+    bundle
+        .add_packet(&EntityVelocityUpdateS2c {
+            entity_id,
+            velocity: velocity.to_packet_units(),
+        })
+        .unwrap();
+    bundle.broadcast_local(position.to_chunk()).unwrap();
+}
+
+fn teleport_owner(
+    owner: Option<Entity>,
+    destination: Vec3,
+    owners: &Query<'_, '_, Has<ConnectionId>, Without<Projectile>>,
+    commands: &mut Commands<'_, '_>,
+) {
+    let Some(owner) = owner else {
+        return;
+    };
+
+    let Ok(is_player) = owners.get(owner) else {
+        return;
+    };
+
+    if is_player {
+        commands
+            .entity(owner)
+            .insert(PendingTeleportation::new(destination));
+    } else {
+        commands.entity(owner).insert(Position::from(destination));
+    }
+}
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(initialize_projectile);
+        app.add_systems(
+            FixedPostUpdate,
+            (
+                despawn_removed_projectiles,
+                update_stuck_and_returning,
+                update_flying,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounce_reflects_off_floor() {
+        let velocity = bounce(Vec3::new(1.0, -2.0, 0.0), Vec3::Y);
+        assert_eq!(velocity, Vec3::new(1.0, 2.0, 0.0) * BOUNCE_RESTITUTION);
+    }
+
+    #[test]
+    fn stuck_projectiles_are_removed() {
+        let mut arrow = Projectile::vanilla(EntityKind::Arrow).unwrap();
+        arrow.state = ProjectileState::Stuck { ticks: 0 };
+
+        for _ in 1..STUCK_LIFETIME {
+            arrow.tick_stuck(true);
+        }
+        assert!(arrow.is_stuck());
+
+        arrow.tick_stuck(true);
+        assert!(arrow.is_removed());
+    }
+
+    #[test]
+    fn stuck_projectiles_with_loyalty_return() {
+        let mut trident = Projectile::vanilla(EntityKind::Trident)
+            .unwrap()
+            .with_loyalty(1);
+        trident.state = ProjectileState::Stuck { ticks: 0 };
+
+        for _ in 0..RETURN_DELAY {
+            trident.tick_stuck(true);
+        }
+        assert!(trident.is_returning());
+    }
+
+    #[test]
+    fn vanilla_projectiles() {
+        assert!(Projectile::vanilla(EntityKind::Arrow).is_some());
+        assert!(Projectile::vanilla(EntityKind::Player).is_none());
+        assert_eq!(
+            Projectile::vanilla(EntityKind::EnderPearl).map(|projectile| projectile.on_hit),
+            Some(OnHit::Teleport)
+        );
+    }
+}
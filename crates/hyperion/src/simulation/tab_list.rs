@@ -0,0 +1,294 @@
+//! The tab list: its header and footer, how players are shown in it, and fake entries.
+//!
+//! Every player has a [`TabListEntry`] controlling how they are shown to everyone else. Changes
+//! are batched into one [`PlayerListS2c`] per tick which only contains the changed fields.
+//!
+//! The client sorts entries by the name of their scoreboard team and then by username, so entries
+//! are grouped by putting them into teams. [`FakeTabListEntry`] adds entries which are not
+//! players, such as blank entries used to fill columns, and can be put into teams by their
+//! username.
+
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+use valence_bytes::{CowUtf8Bytes, Utf8Bytes};
+use valence_protocol::{
+    packets::play::{self, PlayerRemoveS2c},
+    profile::Property,
+};
+use valence_text::Text;
+
+use crate::{
+    egress::player_join::{PlayerListActions, PlayerListEntry, PlayerListS2c},
+    net::{Compose, ConnectionId},
+    simulation::{Uuid, game_mode::GameMode, packet_state, skin::PlayerSkin},
+};
+
+/// The header and footer of the tab list of a player
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct TabListHeader {
+    pub header: Text,
+    pub footer: Text,
+}
+
+impl TabListHeader {
+    #[must_use]
+    pub fn new(header: impl Into<Text>, footer: impl Into<Text>) -> Self {
+        Self {
+            header: header.into(),
+            footer: footer.into(),
+        }
+    }
+}
+
+/// How a player or fake entry is shown in the tab list
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct TabListEntry {
+    /// The name shown instead of the username
+    pub display_name: Option<Text>,
+    /// Whether the entry is shown. Unlisted players can still be seen in the world.
+    pub listed: bool,
+    /// The latency shown by the signal bars, in milliseconds
+    pub latency: i32,
+    /// The game mode shown instead of the actual game mode. Spectators are shown last in grey.
+    pub game_mode: Option<GameMode>,
+}
+
+impl Default for TabListEntry {
+    fn default() -> Self {
+        Self {
+            display_name: None,
+            listed: true,
+            latency: 20,
+            game_mode: None,
+        }
+    }
+}
+
+impl TabListEntry {
+    /// The game mode shown for an entity with the given game mode
+    #[must_use]
+    pub const fn shown_game_mode(&self, actual: GameMode) -> GameMode {
+        match self.game_mode {
+            Some(game_mode) => game_mode,
+            None => actual,
+        }
+    }
+
+    /// The fields which differ between the two entries
+    fn changed_actions(&self, other: &Self) -> PlayerListActions {
+        PlayerListActions::new()
+            .with_update_display_name(self.display_name != other.display_name)
+            .with_update_listed(self.listed != other.listed)
+            .with_update_latency(self.latency != other.latency)
+            .with_update_game_mode(self.game_mode != other.game_mode)
+    }
+}
+
+/// An entry in the tab list which is not a player. The entry is removed from the tab list when
+/// this component is removed. Its [`TabListEntry`] is inserted if it does not have one.
+#[derive(Component, Clone, Debug)]
+pub struct FakeTabListEntry {
+    uuid: uuid::Uuid,
+    username: String,
+    properties: Vec<Property>,
+}
+
+impl FakeTabListEntry {
+    /// Creates an entry with a username of at most 16 characters. The username is not shown if
+    /// the entry has a display name, but is used to sort the entry.
+    #[must_use]
+    pub fn new(username: impl Into<String>) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4(),
+            username: username.into(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Shows `skin` as the head next to the entry
+    #[must_use]
+    pub fn with_skin(mut self, skin: &PlayerSkin) -> Self {
+        self.properties = vec![Property {
+            name: Utf8Bytes::from_static("textures"),
+            value: skin.textures.clone().into(),
+            signature: Some(skin.signature.clone().into()),
+        }];
+        self
+    }
+
+    #[must_use]
+    pub const fn uuid(&self) -> uuid::Uuid {
+        self.uuid
+    }
+
+    #[must_use]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    fn list_entry<'a>(&'a self, entry: &'a TabListEntry) -> PlayerListEntry<'a> {
+        PlayerListEntry {
+            username: CowUtf8Bytes::Borrowed(&self.username),
+            properties: Cow::Borrowed(&self.properties),
+            ..list_entry(self.uuid, entry, GameMode::default())
+        }
+    }
+}
+
+/// Every field of an entry when it is added
+const ADD_ACTIONS: PlayerListActions = PlayerListActions::new()
+    .with_add_player(true)
+    .with_update_game_mode(true)
+    .with_update_listed(true)
+    .with_update_latency(true)
+    .with_update_display_name(true);
+
+/// The entry last sent to clients, or `None` if a fake entry has not been added yet
+#[derive(Component, Debug)]
+pub(crate) struct SentTabListEntry(pub(crate) Option<TabListEntry>);
+
+pub(crate) fn list_entry(
+    uuid: uuid::Uuid,
+    entry: &TabListEntry,
+    game_mode: GameMode,
+) -> PlayerListEntry<'_> {
+    PlayerListEntry {
+        player_uuid: uuid,
+        listed: entry.listed,
+        ping: entry.latency,
+        game_mode: entry.shown_game_mode(game_mode).into(),
+        display_name: entry.display_name.as_ref().map(Cow::Borrowed),
+        ..Default::default()
+    }
+}
+
+fn initialize_fake_entry(
+    trigger: Trigger<'_, OnAdd, FakeTabListEntry>,
+    query: Query<'_, '_, &FakeTabListEntry>,
+    mut commands: Commands<'_, '_>,
+) {
+    let Ok(fake) = query.get(trigger.target()) else {
+        return;
+    };
+
+    commands
+        .entity(trigger.target())
+        .insert((Uuid(fake.uuid), SentTabListEntry(None)))
+        .insert_if_new(TabListEntry::default());
+}
+
+fn remove_fake_entry(
+    trigger: Trigger<'_, OnRemove, FakeTabListEntry>,
+    query: Query<'_, '_, &FakeTabListEntry>,
+    compose: Res<'_, Compose>,
+) {
+    let Ok(fake) = query.get(trigger.target()) else {
+        return;
+    };
+
+    let pkt = PlayerRemoveS2c {
+        uuids: Cow::Borrowed(&[fake.uuid]),
+    };
+    compose.broadcast(&pkt).send().unwrap();
+}
+
+fn sync_headers(
+    query: Query<'_, '_, (&ConnectionId, &TabListHeader), Changed<TabListHeader>>,
+    compose: Res<'_, Compose>,
+) {
+    for (&connection_id, header) in &query {
+        let pkt = play::PlayerListHeaderS2c {
+            header: Cow::Borrowed(&header.header),
+            footer: Cow::Borrowed(&header.footer),
+        };
+        compose.unicast(&pkt, connection_id).unwrap();
+    }
+}
+
+fn sync_entries(
+    mut query: Query<
+        '_,
+        '_,
+        (
+            &Uuid,
+            &TabListEntry,
+            &mut SentTabListEntry,
+            Option<&GameMode>,
+            Option<&FakeTabListEntry>,
+        ),
+        Changed<TabListEntry>,
+    >,
+    fakes: Query<'_, '_, (&FakeTabListEntry, &TabListEntry)>,
+    joined: Query<'_, '_, &ConnectionId, Added<packet_state::Play>>,
+    compose: Res<'_, Compose>,
+) {
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
+    let mut actions = PlayerListActions::new();
+
+    for (uuid, entry, mut sent, game_mode, fake) in &mut query {
+        match (&sent.0, fake) {
+            (None, Some(fake)) => added.push(fake.list_entry(entry)),
+            (Some(previous), _) => {
+                let changed = entry.changed_actions(previous);
+                if changed.into_bits() == 0 {
+                    continue;
+                }
+
+                actions = PlayerListActions::from_bits(actions.into_bits() | changed.into_bits());
+                updated.push(list_entry(
+                    **uuid,
+                    entry,
+                    game_mode.copied().unwrap_or_default(),
+                ));
+            }
+            (None, None) => {}
+        }
+
+        sent.0 = Some(entry.clone());
+    }
+
+    if !added.is_empty() {
+        let pkt = PlayerListS2c {
+            actions: ADD_ACTIONS,
+            entries: Cow::Owned(added),
+        };
+        compose.broadcast(&pkt).send().unwrap();
+    }
+
+    // Entries which did not change a field are sent with their current value
+    if !updated.is_empty() {
+        let pkt = PlayerListS2c {
+            actions,
+            entries: Cow::Owned(updated),
+        };
+        compose.broadcast(&pkt).send().unwrap();
+    }
+
+    if joined.is_empty() || fakes.is_empty() {
+        return;
+    }
+
+    let pkt = PlayerListS2c {
+        actions: ADD_ACTIONS,
+        entries: fakes
+            .iter()
+            .map(|(fake, entry)| fake.list_entry(entry))
+            .collect(),
+    };
+
+    for &connection_id in &joined {
+        compose.unicast(&pkt, connection_id).unwrap();
+    }
+}
+
+pub struct TabListPlugin;
+
+impl Plugin for TabListPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(initialize_fake_entry);
+        app.add_observer(remove_fake_entry);
+        app.add_systems(FixedPostUpdate, (sync_headers, sync_entries));
+    }
+}
@@ -26,7 +26,7 @@ impl LocalDb {
         let env = unsafe {
             EnvOpenOptions::new()
                 .map_size(10 * 1024 * 1024) // 10MB
-                .max_dbs(8) // todo: why is this needed/configurable? ideally would be infinite...
+                .max_dbs(16) // todo: why is this needed/configurable? ideally would be infinite...
                 .open(&path)?
         };
 
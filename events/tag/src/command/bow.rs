@@ -1,7 +1,7 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
 use hyperion::{ItemKind, ItemStack};
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
 use hyperion_inventory::PlayerInventory;
 use tracing::error;
 
@@ -14,6 +14,10 @@ impl MinecraftCommand for BowCommand {
     type State = SystemState<Commands<'static, 'static>>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
         let mut commands = state.get(world);
         commands
             .entity(caller)
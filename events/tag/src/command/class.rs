@@ -1,7 +1,7 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
-use hyperion::net::{Compose, ConnectionId, agnostic};
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
+use hyperion_locale::{Localizer, Message, message};
 use hyperion_rank_tree::{Class, Team};
 use tracing::error;
 
@@ -14,17 +14,21 @@ pub struct ClassCommand {
 }
 impl MinecraftCommand for ClassCommand {
     type State = SystemState<(
-        Res<'static, Compose>,
-        Query<'static, 'static, (&'static ConnectionId, &'static Team, &'static Class)>,
+        Localizer<'static, 'static>,
+        Query<'static, 'static, (&'static Team, &'static Class)>,
         Commands<'static, 'static>,
     )>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
-        let (compose, query, mut commands) = state.get(world);
+        if !require_player(world, caller) {
+            return;
+        }
+
+        let (localizer, query, mut commands) = state.get(world);
         let class_param = self.class;
         let team_param = self.team;
 
-        let (&connection_id, team, class) = match query.get(caller) {
+        let (team, class) = match query.get(caller) {
             Ok(data) => data,
             Err(e) => {
                 error!("class command failed: query failed: {e}");
@@ -33,9 +37,7 @@ impl MinecraftCommand for ClassCommand {
         };
 
         if *team == team_param && *class == class_param {
-            let chat_pkt = agnostic::chat("§cYou’re already using this class!");
-
-            compose.unicast(&chat_pkt, connection_id).unwrap();
+            localizer.chat(caller, &Message::new("tag.class.unchanged"));
             return;
         }
 
@@ -44,8 +46,7 @@ impl MinecraftCommand for ClassCommand {
             .insert(team_param)
             .insert(class_param);
 
-        let msg = format!("Setting rank to {class_param:?}");
-        let chat = agnostic::chat(msg);
-        compose.unicast(&chat, connection_id).unwrap();
+        let class_name = format!("{class_param:?}");
+        localizer.chat(caller, &message!("tag.class.set", class_name));
     }
 }
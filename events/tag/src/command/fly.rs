@@ -1,10 +1,8 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
-use hyperion::{
-    net::{Compose, ConnectionId, DataBundle, agnostic},
-    simulation::Flight,
-};
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion::simulation::Flight;
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
+use hyperion_locale::{Localizer, Message};
 use tracing::error;
 
 #[derive(Parser, CommandPermission, Debug)]
@@ -14,15 +12,19 @@ pub struct FlyCommand;
 
 impl MinecraftCommand for FlyCommand {
     type State = SystemState<(
-        Res<'static, Compose>,
-        Query<'static, 'static, (&'static ConnectionId, &'static Flight)>,
+        Localizer<'static, 'static>,
+        Query<'static, 'static, &'static Flight>,
         Commands<'static, 'static>,
     )>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
-        let (compose, query, mut commands) = state.get(world);
+        if !require_player(world, caller) {
+            return;
+        }
 
-        let (&connection_id, &(mut flight)) = match query.get(caller) {
+        let (localizer, query, mut commands) = state.get(world);
+
+        let &(mut flight) = match query.get(caller) {
             Ok(data) => data,
             Err(e) => {
                 error!("fly command failed: query failed: {e}");
@@ -33,17 +35,12 @@ impl MinecraftCommand for FlyCommand {
         flight.allow = !flight.allow;
         flight.is_flying = flight.allow && flight.is_flying;
 
-        let allow_flight = flight.allow;
-
-        let chat_packet = if allow_flight {
-            agnostic::chat("§aFlying enabled")
+        let key = if flight.allow {
+            "tag.fly.enabled"
         } else {
-            agnostic::chat("§cFlying disabled")
+            "tag.fly.disabled"
         };
-
-        let mut bundle = DataBundle::new(&compose);
-        bundle.add_packet(&chat_packet).unwrap();
-        bundle.unicast(connection_id).unwrap();
+        localizer.chat(caller, &Message::new(key));
 
         commands.entity(caller).insert(flight);
     }
@@ -1,7 +1,7 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
 use hyperion::{ItemKind, ItemStack, simulation::entity_kind::EntityKind};
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
 use hyperion_gui::Gui;
 use hyperion_inventory::Inventory;
 use tracing::debug;
@@ -19,6 +19,10 @@ impl MinecraftCommand for GuiCommand {
     )>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
         let (query, mut commands) = state.get(world);
 
         for gui in &query {
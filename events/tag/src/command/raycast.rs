@@ -5,7 +5,7 @@ use hyperion::{
     simulation::{EntitySize, Pitch, Position, Yaw, blocks::Blocks, entity_kind::EntityKind},
     spatial::{SpatialIndex, get_first_collision},
 };
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
 use rayon::iter::Either;
 use tracing::{debug, error};
 
@@ -51,6 +51,10 @@ impl MinecraftCommand for RaycastCommand {
     )>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
         const EYE_HEIGHT: f32 = 1.62;
         const DISTANCE: f32 = 10.0;
 
@@ -2,7 +2,7 @@ use std::collections::{HashSet, VecDeque};
 
 use bevy::{ecs::system::SystemState, prelude::*};
 use hyperion::{BlockState, glam::IVec3, simulation::blocks::Blocks};
-use hyperion_clap::CommandPermission;
+use hyperion_clap::{CommandPermission, hyperion_command::reply};
 use rayon::iter::ParallelIterator;
 
 use crate::OreVeins;
@@ -145,16 +145,13 @@ impl hyperion_clap::MinecraftCommand for ReplaceCommand {
 
             // 317ms debug
             // -> 37ms release
-            let msg = hyperion::chat!(
-                "Replaced {len} concrete blocks in {elapsed:?} with scan time {scan_time:?}"
+            reply(
+                world,
+                caller,
+                format!(
+                    "Replaced {len} concrete blocks in {elapsed:?} with scan time {scan_time:?}"
+                ),
             );
-
-            let caller = world.entity(caller);
-            let connection_id = *caller.get::<hyperion::net::ConnectionId>().unwrap();
-            let compose = world.resource::<hyperion::net::Compose>();
-            let mut bundle = hyperion::net::DataBundle::new(compose);
-            bundle.add_packet(&msg).unwrap();
-            bundle.unicast(connection_id).unwrap();
         });
     }
 }
@@ -4,7 +4,7 @@ use hyperion::{
     glam::Vec3,
     simulation::{Pitch, Position, SpawnEvent, Uuid, Velocity, Yaw, entity_kind::EntityKind},
 };
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
 use tracing::{debug, error};
 
 #[derive(Parser, CommandPermission, Debug)]
@@ -22,6 +22,10 @@ impl MinecraftCommand for ShootCommand {
     )>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
         const EYE_HEIGHT: f32 = 1.62;
         const BASE_VELOCITY: f32 = 3.0; // Base velocity multiplier for arrows
 
@@ -1,11 +1,8 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
-use hyperion::{
-    net::{Compose, ConnectionId, agnostic},
-    simulation::FlyingSpeed,
-};
-use hyperion_clap::{CommandPermission, MinecraftCommand};
-use tracing::error;
+use hyperion::simulation::FlyingSpeed;
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
+use hyperion_locale::{Localizer, message};
 
 #[derive(Parser, CommandPermission, Debug)]
 #[command(name = "speed")]
@@ -15,26 +12,16 @@ pub struct SpeedCommand {
 }
 
 impl MinecraftCommand for SpeedCommand {
-    type State = SystemState<(
-        Query<'static, 'static, &'static ConnectionId>,
-        Res<'static, Compose>,
-        Commands<'static, 'static>,
-    )>;
+    type State = SystemState<(Localizer<'static, 'static>, Commands<'static, 'static>)>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
-        let (query, compose, mut commands) = state.get(world);
+        if !require_player(world, caller) {
+            return;
+        }
 
-        let &connection_id = match query.get(caller) {
-            Ok(connection_id) => connection_id,
-            Err(e) => {
-                error!("speed command failed: query failed: {e}");
-                return;
-            }
-        };
+        let (localizer, mut commands) = state.get(world);
 
-        let msg = format!("Setting speed to {}", self.amount);
-        let chat = agnostic::chat(msg);
-        compose.unicast(&chat, connection_id).unwrap();
+        localizer.chat(caller, &message!("tag.speed.set", self.amount.to_string()));
 
         commands
             .entity(caller)
@@ -1,7 +1,7 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
 use hyperion::net::{Compose, ConnectionId};
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
 use tracing::error;
 
 use crate::plugin::vanish::Vanished;
@@ -27,6 +27,10 @@ impl MinecraftCommand for VanishCommand {
     )>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
         let (query, compose, mut commands) = state.get(world);
 
         let (&connection_id, name, vanished) = match query.get(caller) {
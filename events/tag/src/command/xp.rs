@@ -1,7 +1,7 @@
 use bevy::{ecs::system::SystemState, prelude::*};
 use clap::Parser;
 use hyperion::simulation::Xp;
-use hyperion_clap::{CommandPermission, MinecraftCommand};
+use hyperion_clap::{CommandPermission, MinecraftCommand, hyperion_command::require_player};
 
 #[derive(Parser, CommandPermission, Debug)]
 #[command(name = "xp")]
@@ -14,6 +14,10 @@ impl MinecraftCommand for XpCommand {
     type State = SystemState<Commands<'static, 'static>>;
 
     fn execute(self, world: &World, state: &mut Self::State, caller: Entity) {
+        if !require_player(world, caller) {
+            return;
+        }
+
         let mut commands = state.get(world);
         commands.entity(caller).insert(Xp {
             amount: self.amount,
@@ -12,12 +12,13 @@ use hyperion::{
     spatial::{Spatial, SpatialIndex},
 };
 use hyperion_proxy_module::SetProxyAddress;
+use hyperion_rcon::StartRcon;
 use tracing::error;
 
 use crate::{
     plugin::{
         attack::AttackPlugin, block::BlockPlugin, bow::BowPlugin, chat::ChatPlugin,
-        damage::DamagePlugin, level::LevelPlugin, regeneration::RegenerationPlugin,
+        level::LevelPlugin, locale::LocalePlugin, regeneration::RegenerationPlugin,
         spawn::SpawnPlugin, stats::StatsPlugin, vanish::VanishPlugin,
     },
     skin::SkinPlugin,
@@ -112,27 +113,48 @@ pub struct TagPlugin;
 impl Plugin for TagPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(OreVeins::default());
+        // Tag has its own faster regeneration
+        app.insert_resource(hyperion_hunger::HungerSettings {
+            natural_regeneration: false,
+        });
+        // Players keep their blocks, tools and levels when they die
+        app.insert_resource(hyperion_respawn::RespawnSettings {
+            keep_inventory: true,
+            keep_xp: true,
+            death_message: plugin::attack::death_message,
+            ..Default::default()
+        });
         app.add_plugins((
             (
                 AttackPlugin,
                 BlockPlugin,
                 BowPlugin,
                 ChatPlugin,
-                DamagePlugin,
                 LevelPlugin,
+                LocalePlugin,
                 RegenerationPlugin,
                 SkinPlugin,
                 SpawnPlugin,
                 StatsPlugin,
                 VanishPlugin,
             ),
-            hyperion_clap::ClapCommandPlugin,
+            hyperion_ban::BanPlugin,
+            hyperion_chat::ChatPlugin,
+            (
+                hyperion_clap::ClapCommandPlugin,
+                hyperion_clap::hyperion_command::ConsolePlugin,
+                hyperion_rcon::RconPlugin,
+            ),
+            hyperion_combat::CombatPlugin,
             hyperion_genmap::GenMapPlugin,
+            hyperion_hunger::HungerPlugin,
             hyperion_item::ItemPlugin,
+            hyperion_locale::LocalePlugin,
             hyperion_permission::PermissionPlugin,
             hyperion_rank_tree::RankTreePlugin,
             hyperion_respawn::RespawnPlugin,
             hyperion_proxy_module::HyperionProxyPlugin,
+            hyperion_xp::XpPlugin,
         ));
         app.add_observer(initialize_player);
         app.add_systems(FixedUpdate, follow_closest_player);
@@ -141,7 +163,7 @@ impl Plugin for TagPlugin {
     }
 }
 
-pub fn init_game(address: SocketAddr) -> anyhow::Result<()> {
+pub fn init_game(address: SocketAddr, rcon: Option<StartRcon>) -> anyhow::Result<()> {
     let mut app = App::new();
 
     app.add_plugins((HyperionCore, TagPlugin));
@@ -151,6 +173,10 @@ pub fn init_game(address: SocketAddr) -> anyhow::Result<()> {
         ..SetProxyAddress::default()
     });
 
+    if let Some(rcon) = rcon {
+        app.world_mut().trigger(rcon);
+    }
+
     app.run();
 
     Ok(())
@@ -1,6 +1,7 @@
 use std::net::SocketAddr;
 
 use clap::Parser;
+use hyperion_rcon::StartRcon;
 use serde::Deserialize;
 use tag::init_game;
 use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt};
@@ -22,8 +23,23 @@ struct Args {
     #[clap(short, long, default_value = "35565")]
     #[serde(default = "default_port")]
     port: u16,
+
+    /// The IP address RCON should listen on. Defaults to 127.0.0.1 so RCON is only reachable
+    /// from this machine
+    #[clap(long, default_value = "127.0.0.1")]
+    #[serde(default = "default_rcon_ip")]
+    rcon_ip: String,
+
+    /// The port RCON should listen on. Defaults to 25575
+    #[clap(long, default_value = "25575")]
+    #[serde(default = "default_rcon_port")]
+    rcon_port: u16,
 }
 
+/// The environment variable holding the password RCON clients log in with. It can also be set in
+/// a `.env` file. RCON is disabled if it is not set.
+const RCON_PASSWORD_VAR: &str = "TAG_RCON_PASSWORD";
+
 fn default_ip() -> String {
     "0.0.0.0".to_string()
 }
@@ -32,6 +48,14 @@ const fn default_port() -> u16 {
     35565
 }
 
+fn default_rcon_ip() -> String {
+    "127.0.0.1".to_string()
+}
+
+const fn default_rcon_port() -> u16 {
+    25575
+}
+
 fn setup_logging() {
     tracing::subscriber::set_global_default(
         Registry::default()
@@ -71,5 +95,16 @@ fn main() {
     let address = format!("{ip}:{port}", ip = args.ip, port = args.port);
     let address = address.parse::<SocketAddr>().unwrap();
 
-    init_game(address).unwrap();
+    let rcon_address = format!("{ip}:{port}", ip = args.rcon_ip, port = args.rcon_port);
+    let rcon_address = rcon_address.parse::<SocketAddr>().unwrap();
+
+    // The password is never taken from the command line, where other users could see it
+    let rcon = std::env::var(RCON_PASSWORD_VAR)
+        .ok()
+        .map(|password| StartRcon {
+            address: rcon_address,
+            password,
+        });
+
+    init_game(address, rcon).unwrap();
 }
@@ -2,8 +2,8 @@ pub mod attack;
 pub mod block;
 pub mod bow;
 pub mod chat;
-pub mod damage;
 pub mod level;
+pub mod locale;
 pub mod regeneration;
 pub mod spawn;
 pub mod stats;
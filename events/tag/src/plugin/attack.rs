@@ -1,35 +1,18 @@
-use std::borrow::Cow;
-
 use bevy::prelude::*;
-use compact_str::format_compact;
-use derive_more::with_trait::Add;
 use glam::IVec3;
 use hyperion::{
-    BlockKind, ingress,
-    net::{
-        Compose, ConnectionId, agnostic,
-        packets::{BossBarAction, BossBarS2c},
-    },
+    BlockKind,
+    net::{Compose, ConnectionId},
     runtime::AsyncRuntime,
-    simulation::{
-        PendingTeleportation, Position, Velocity, Yaw, blocks::Blocks, event,
-        metadata::living_entity::Health, packet::play, packet_state,
-    },
-    uuid::Uuid,
+    simulation::{PendingTeleportation, Position, blocks::Blocks, boss_bar::BossBar, packet_state},
 };
-use hyperion_inventory::PlayerInventory;
+use hyperion_combat::{CombatSet, DamageEvent, DamageSource, DamageType};
 use hyperion_rank_tree::Team;
-use hyperion_utils::{EntityExt, Prev};
+use hyperion_respawn::{PlayerDeath, PlayerRespawn};
 use tracing::error;
 use valence_protocol::{
-    ItemKind, ItemStack, Particle, VarInt, ident,
-    math::{DVec3, Vec3},
-    packets::play::{
-        DamageTiltS2c, DeathMessageS2c, EntityDamageS2c, GameMessageS2c, ParticleS2c,
-        boss_bar_s2c::{BossBarColor, BossBarDivision, BossBarFlags},
-        client_status_c2s::ClientStatusC2s,
-        player_interact_entity_c2s::EntityInteraction,
-    },
+    math::DVec3,
+    packets::play::{GameMessageS2c, boss_bar_s2c::BossBarColor},
     text::IntoText,
 };
 
@@ -37,291 +20,116 @@ use super::spawn::{avoid_blocks, find_spawn_position, is_valid_spawn_block};
 
 pub struct AttackPlugin;
 
-#[derive(Component, Default, Copy, Clone, Debug)]
-pub struct ImmuneUntil {
-    tick: i64,
-}
-
-// Used as a component only for commands, does not include armor or weapons
-#[derive(Component, Default, Copy, Clone, Debug, Add)]
-pub struct CombatStats {
-    pub armor: f32,
-    pub armor_toughness: f32,
-    pub damage: f32,
-    pub protection: f32,
-}
-
 #[derive(Component, Default, Copy, Clone, Debug)]
 pub struct KillCount {
     pub kill_count: u32,
 }
 
-#[derive(Resource)]
-struct KillCountUuid(Uuid);
-
-/// Checks if the entity is immune to attacks and updates the immunity timer if it is
-///
-/// Returns true if the entity is immune, false otherwise
-const fn check_and_update_immunity(tick: i64, immune_until: &mut ImmuneUntil) -> bool {
-    const IMMUNE_TICK_DURATION: i64 = 10;
-    if immune_until.tick > tick {
-        return true;
-    }
-
-    immune_until.tick = tick + IMMUNE_TICK_DURATION;
-
-    false
-}
-
-fn is_critical_hit(prev_position: Prev<Position>, position: Position) -> bool {
-    // TODO: Do not allow critical hits if the player is on a ladder, vine, or water. None of
-    // these special blocks are currently on the map.
-    let position_delta_y = position.y - prev_position.y;
-    position_delta_y < 0.0
-}
-
-fn total_combat_stats(
-    critical_hit: bool,
-    inventory: &PlayerInventory,
-    stats: CombatStats,
-) -> CombatStats {
-    let inventory_combat_stats = calculate_stats(inventory, critical_hit);
-    stats + inventory_combat_stats
-}
+/// The boss bar showing the kill count of a player
+#[derive(Component, Copy, Clone, Debug)]
+struct KillCountBar(Entity);
 
 fn initialize_player(
     trigger: Trigger<'_, OnAdd, packet_state::Play>,
     mut commands: Commands<'_, '_>,
 ) {
-    commands.entity(trigger.target()).insert((
-        ImmuneUntil::default(),
-        CombatStats::default(),
-        KillCount::default(),
-    ));
-}
-
-fn handle_melee_attacks(
-    mut packets: EventReader<'_, '_, play::PlayerInteractEntity>,
-    origin_query: Query<'_, '_, (&Position, &PlayerInventory, &CombatStats)>,
-    target_query: Query<'_, '_, (&Prev<Position>, &Position)>,
-    mut world_and_writer: ParamSet<'_, '_, (&World, EventWriter<'_, event::AttackEntity>)>,
+    let player = trigger.target();
+
+    // The boss bar is despawned with the player
+    let bar = commands
+        .spawn((
+            BossBar::new("0 kills")
+                .with_progress(0.0)
+                .with_color(BossBarColor::Red)
+                .with_viewer(player),
+            ChildOf(player),
+        ))
+        .id();
+
+    commands
+        .entity(player)
+        .insert((KillCount::default(), KillCountBar(bar)));
+}
+
+fn prevent_team_damage(
+    mut events: EventMutator<'_, DamageEvent>,
+    compose: Res<'_, Compose>,
+    query: Query<'_, '_, (&Team, Option<&ConnectionId>)>,
 ) {
-    for packet in packets.read() {
-        if packet.interact != EntityInteraction::Attack {
+    for event in events.read() {
+        let Some(attacker) = event.source.attacker else {
             continue;
-        }
-
-        // Player who is attacking the target
-        let origin = packet.sender();
-
-        // Player who is being attacked by the attacker
-        let target = match Entity::from_minecraft_id(packet.entity_id.0, world_and_writer.p0()) {
-            Ok(target) => target,
-            Err(e) => {
-                error!("handle melee attack failed: target id is invalid: {e}");
-                continue;
-            }
         };
 
-        let (&origin_pos, origin_inventory, &origin_stats) = match origin_query.get(origin) {
-            Ok(data) => data,
-            Err(e) => {
-                error!("handle melee attack failed: query failed: {e}");
-                continue;
-            }
-        };
+        if attacker == event.target {
+            continue;
+        }
 
-        let (&target_prev_pos, &target_pos) = match target_query.get(target) {
-            Ok(data) => data,
-            Err(e) => {
-                error!("handle melee attack failed: query failed: {e}");
-                continue;
-            }
+        let (Ok((attacker_team, attacker_connection)), Ok((target_team, _))) =
+            (query.get(attacker), query.get(event.target))
+        else {
+            continue;
         };
 
-        let is_critical_hit = is_critical_hit(target_prev_pos, target_pos);
-        let combat_stats = total_combat_stats(is_critical_hit, origin_inventory, origin_stats);
-
-        let damage_after_armor =
-            get_damage_left(1.0, combat_stats.armor, combat_stats.armor_toughness);
-        let damage_after_protection =
-            get_inflicted_damage(damage_after_armor, combat_stats.protection);
-
-        world_and_writer.p1().write(event::AttackEntity {
-            origin,
-            target,
-            direction: (target_pos - origin_pos).normalize(),
-            damage: damage_after_protection,
-            sound: if is_critical_hit {
-                ident!("minecraft:entity.player.attack.crit")
-            } else {
-                ident!("minecraft:entity.player.attack.knockback")
-            },
-            particles: is_critical_hit.then(|| ParticleS2c {
-                particle: Cow::Owned(Particle::Crit),
-                long_distance: true,
-                position: target_pos.as_dvec3() + DVec3::new(0.0, 1.0, 0.0),
-                max_speed: 0.5,
-                count: 100,
-                offset: Vec3::new(0.5, 0.5, 0.5),
-            }),
-        });
-    }
-}
-
-fn handle_attacks(
-    mut events: EventReader<'_, '_, event::AttackEntity>,
-    compose: Res<'_, Compose>,
-    mut origin_query: Query<'_, '_, (&Team, &Name, &ConnectionId, &mut KillCount)>,
-    mut target_query: Query<
-        '_,
-        '_,
-        (
-            &Team,
-            &Position,
-            &Yaw,
-            &ConnectionId,
-            &mut ImmuneUntil,
-            &mut Health,
-            &mut Velocity,
-        ),
-    >,
-) {
-    let current_tick = compose.global().tick;
-
-    for event in events.read() {
-        if event.damage <= 0.0 {
+        if attacker_team != target_team {
             continue;
         }
 
-        let (origin_team, origin_name, &origin_connection, mut origin_kill_count) =
-            match origin_query.get_mut(event.origin) {
-                Ok(data) => data,
-                Err(e) => {
-                    error!("handle melee attack failed: query failed: {e}");
-                    continue;
-                }
-            };
+        event.cancel();
 
-        let (
-            target_team,
-            &target_pos,
-            &target_yaw,
-            &target_connection,
-            mut target_immune_until,
-            mut target_health,
-            mut target_velocity,
-        ) = match target_query.get_mut(event.target) {
-            Ok(data) => data,
-            Err(e) => {
-                error!("handle melee attack failed: query failed: {e}");
-                continue;
-            }
-        };
-
-        if origin_team == target_team {
+        if event.source.kind == DamageType::PlayerAttack
+            && let Some(&connection) = attacker_connection
+        {
             let msg = "§cCannot attack teammates";
             let pkt_msg = GameMessageS2c {
                 chat: msg.into_cow_text(),
                 overlay: false,
             };
 
-            compose.unicast(&pkt_msg, origin_connection).unwrap();
-
-            continue;
-        }
-
-        if check_and_update_immunity(current_tick, &mut target_immune_until) {
-            // no damage; the target is immune
-            continue;
+            compose.unicast(&pkt_msg, connection).unwrap();
         }
+    }
+}
 
-        // Broadcast sound
-        let sound = agnostic::sound(event.sound.clone(), *target_pos)
-            .volume(1.)
-            .pitch(1.)
-            .seed(fastrand::i64(..))
-            .build();
-
-        compose.broadcast(&sound).send().unwrap();
-
-        // Broadcast particles
-        if let Some(particles) = &event.particles {
-            compose
-                .broadcast(particles)
-                .exclude(origin_connection)
-                .send()
-                .unwrap();
-        }
-
-        let delta_x: f64 = f64::from(event.direction.x);
-        let delta_z: f64 = f64::from(event.direction.z);
-
-        // Seems that MC generates a random delta if the damage source is too close to the target
-        // let's ignore that for now
-        #[expect(clippy::cast_possible_truncation)]
-        let pkt_hurt = DamageTiltS2c {
-            entity_id: VarInt(event.target.minecraft_id()),
-            yaw: delta_z
-                .atan2(delta_x)
-                .mul_add(57.295_776_367_187_5_f64, -f64::from(*target_yaw)) as f32,
-        };
-
-        compose.unicast(&pkt_hurt, target_connection).unwrap();
-
-        target_health.damage(event.damage);
-
-        if target_health.is_dead() {
-            // Even if enable_respawn_screen is false, the client needs this to send ClientCommandC2s and initiate its respawn
-            let pkt_death_screen = DeathMessageS2c {
-                player_id: VarInt(event.target.minecraft_id()),
-                message: format!("You were killed by {origin_name}").into_cow_text(),
-            };
-            compose
-                .unicast(&pkt_death_screen, target_connection)
-                .unwrap();
-
-            origin_kill_count.kill_count += 1;
-        } else {
-            // Calculate velocity change based on attack direction
-            let knockback_xz = 8.0;
-            let knockback_y = 6.432;
+pub fn death_message(source: &DamageSource, attacker: Option<&str>) -> String {
+    if let Some(name) = attacker {
+        return format!("You were killed by {name}");
+    }
 
-            let new_vel = Vec3::new(
-                event.direction.x * knockback_xz / 20.0,
-                knockback_y / 20.0,
-                event.direction.z * knockback_xz / 20.0,
-            );
+    match source.kind {
+        DamageType::Fall => "You fell from a high place".to_string(),
+        _ => "You died".to_string(),
+    }
+}
 
-            target_velocity.0 += new_vel;
+fn handle_deaths(
+    mut events: EventReader<'_, '_, PlayerDeath>,
+    mut kill_count_query: Query<'_, '_, &mut KillCount>,
+) {
+    for event in events.read() {
+        let attacker = event
+            .source
+            .attacker
+            .filter(|&attacker| attacker != event.entity);
+
+        if let Some(attacker) = attacker
+            && let Ok(mut kill_count) = kill_count_query.get_mut(attacker)
+        {
+            kill_count.kill_count += 1;
         }
-
-        // EntityDamageS2c: display red outline when taking damage (play arrow hit sound?)
-        let pkt_damage_event = EntityDamageS2c {
-            entity_id: VarInt(event.target.minecraft_id()),
-            source_cause_id: VarInt(event.origin.minecraft_id() + 1), // this is an OptVarint
-            source_direct_id: VarInt(event.origin.minecraft_id() + 1), /* if hit by a projectile, it should be the projectile's entity id */
-            source_type_id: VarInt(31),                                // 31 = player_attack
-            source_pos: None,
-        };
-        compose.broadcast(&pkt_damage_event).send().unwrap();
     }
 }
 
 fn handle_respawn(
-    mut packets: EventReader<'_, '_, play::ClientStatus>,
+    mut events: EventReader<'_, '_, PlayerRespawn>,
     query: Query<'_, '_, &Team>,
     candidates_query: Query<'_, '_, (Entity, &Position, &Team)>,
     mut blocks: ResMut<'_, Blocks>,
     runtime: Res<'_, AsyncRuntime>,
     mut commands: Commands<'_, '_>,
 ) {
-    for packet in packets.read() {
-        if !matches!(**packet, ClientStatusC2s::PerformRespawn) {
-            continue;
-        }
-
-        let team = match query.get(packet.sender()) {
+    for event in events.read() {
+        let team = match query.get(event.entity) {
             Ok(team) => team,
             Err(e) => {
                 error!("handle respawn failed: query failed: {e}");
@@ -332,7 +140,7 @@ fn handle_respawn(
         let pos_vec = candidates_query
             .iter()
             .filter(|(candidate_entity, _, candidate_team)| {
-                team == *candidate_team && *candidate_entity != packet.sender()
+                team == *candidate_team && *candidate_entity != event.entity
             })
             .map(|(_, &pos, _)| pos)
             .collect::<Vec<_>>();
@@ -346,37 +154,30 @@ fn handle_respawn(
         };
 
         commands
-            .entity(packet.sender())
+            .entity(event.entity)
             .insert(PendingTeleportation::new(respawn_pos));
     }
 }
 
 fn update_kill_counts(
-    query: Query<'_, '_, (&KillCount, &ConnectionId), Changed<KillCount>>,
-    kill_count_uuid: Res<'_, KillCountUuid>,
-    compose: Res<'_, Compose>,
+    query: Query<'_, '_, (&KillCount, &KillCountBar), Changed<KillCount>>,
+    mut bars: Query<'_, '_, &mut BossBar>,
 ) {
     const MAX_KILLS: usize = 10;
 
-    query.par_iter().for_each(|(kill_count, &connection_id)| {
-        let kills = kill_count.kill_count;
-        let title = format_compact!("{kills} kills");
-        let title = hyperion_text::Text::new(&title);
-        let health = (kill_count.kill_count as f32 / MAX_KILLS as f32).min(1.0);
-
-        let pkt = BossBarS2c {
-            id: kill_count_uuid.0,
-            action: BossBarAction::Add {
-                title,
-                health,
-                color: BossBarColor::Red,
-                division: BossBarDivision::NoDivision,
-                flags: BossBarFlags::default(),
-            },
+    for (kill_count, &KillCountBar(bar)) in &query {
+        let mut bar = match bars.get_mut(bar) {
+            Ok(bar) => bar,
+            Err(e) => {
+                error!("failed to update kill count: query failed: {e}");
+                continue;
+            }
         };
 
-        compose.unicast(&pkt, connection_id).unwrap();
-    });
+        let kills = kill_count.kill_count;
+        bar.title = format!("{kills} kills").into();
+        bar.progress = (kills as f32 / MAX_KILLS as f32).min(1.0);
+    }
 }
 
 #[allow(clippy::cast_possible_truncation)]
@@ -385,15 +186,12 @@ impl Plugin for AttackPlugin {
     #[allow(clippy::cast_sign_loss)]
     fn build(&self, app: &mut App) {
         app.add_observer(initialize_player);
-        app.insert_resource(KillCountUuid(Uuid::new_v4()));
         app.add_systems(
             FixedUpdate,
             (
-                (
-                    (handle_melee_attacks, handle_attacks).chain(),
-                    handle_respawn,
-                )
-                    .after(ingress::decode::play),
+                prevent_team_damage.in_set(CombatSet::Modify),
+                handle_deaths.after(CombatSet::Apply),
+                handle_respawn,
                 update_kill_counts,
             ),
         );
@@ -426,91 +224,3 @@ fn get_respawn_pos(blocks: &Blocks, base_pos: &Position) -> DVec3 {
     }
     base_pos.as_dvec3()
 }
-
-// From minecraft source
-fn get_damage_left(damage: f32, armor: f32, armor_toughness: f32) -> f32 {
-    let f: f32 = 2.0 + armor_toughness / 4.0;
-    let g: f32 = (armor - damage / f).clamp(armor * 0.2, 20.0);
-    damage * (1.0 - g / 25.0)
-}
-
-fn get_inflicted_damage(damage: f32, protection: f32) -> f32 {
-    let f: f32 = protection.clamp(0.0, 20.0);
-    damage * (1.0 - f / 25.0)
-}
-
-const fn calculate_damage(item: &ItemStack) -> f32 {
-    match item.item {
-        ItemKind::WoodenSword | ItemKind::GoldenSword => 4.0,
-        ItemKind::StoneSword => 5.0,
-        ItemKind::IronSword => 6.0,
-        ItemKind::DiamondSword => 7.0,
-        ItemKind::NetheriteSword => 8.0,
-        ItemKind::WoodenPickaxe => 2.0,
-        _ => 1.0,
-    }
-}
-
-const fn calculate_armor(item: &ItemStack) -> f32 {
-    match item.item {
-        ItemKind::LeatherHelmet
-        | ItemKind::LeatherBoots
-        | ItemKind::GoldenHelmet
-        | ItemKind::GoldenBoots
-        | ItemKind::ChainmailHelmet
-        | ItemKind::ChainmailBoots => 1.0,
-        ItemKind::LeatherLeggings
-        | ItemKind::GoldenLeggings
-        | ItemKind::IronHelmet
-        | ItemKind::IronBoots => 2.0,
-        ItemKind::LeatherChestplate
-        | ItemKind::DiamondHelmet
-        | ItemKind::DiamondBoots
-        | ItemKind::NetheriteHelmet
-        | ItemKind::NetheriteBoots => 3.0,
-        ItemKind::ChainmailLeggings => 4.0,
-        ItemKind::IronLeggings | ItemKind::GoldenChestplate | ItemKind::ChainmailChestplate => 5.0,
-        ItemKind::IronChestplate | ItemKind::DiamondLeggings | ItemKind::NetheriteLeggings => 6.0,
-        ItemKind::DiamondChestplate | ItemKind::NetheriteChestplate => 8.0,
-        _ => 0.0,
-    }
-}
-
-const fn calculate_toughness(item: &ItemStack) -> f32 {
-    match item.item {
-        ItemKind::DiamondHelmet
-        | ItemKind::DiamondChestplate
-        | ItemKind::DiamondLeggings
-        | ItemKind::DiamondBoots => 2.0,
-
-        ItemKind::NetheriteHelmet
-        | ItemKind::NetheriteChestplate
-        | ItemKind::NetheriteLeggings
-        | ItemKind::NetheriteBoots => 3.0,
-        _ => 0.0,
-    }
-}
-
-// TODO: split this up into separate functions
-fn calculate_stats(inventory: &PlayerInventory, critical_hit: bool) -> CombatStats {
-    let hand = inventory.get_cursor();
-    let multiplier = if critical_hit { 1.5 } else { 1.0 };
-    let damage = calculate_damage(&hand.stack) * multiplier;
-    let armor = calculate_armor(&inventory.get_helmet().stack)
-        + calculate_armor(&inventory.get_chestplate().stack)
-        + calculate_armor(&inventory.get_leggings().stack)
-        + calculate_armor(&inventory.get_boots().stack);
-
-    let armor_toughness = calculate_toughness(&inventory.get_helmet().stack)
-        + calculate_toughness(&inventory.get_chestplate().stack)
-        + calculate_toughness(&inventory.get_leggings().stack)
-        + calculate_toughness(&inventory.get_boots().stack);
-
-    CombatStats {
-        armor,
-        armor_toughness,
-        damage,
-        // TODO
-        protection: 0.0,
-    }
-}
@@ -1,11 +1,8 @@
-use std::{
-    borrow::Cow,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 
 use bevy::prelude::*;
 use hyperion::{
-    BlockKind, chat,
+    BlockKind, action_bar, chat,
     net::{Compose, ConnectionId, agnostic},
     simulation::{
         Xp,
@@ -18,7 +15,6 @@ use hyperion::{
         ident,
         math::{DVec3, IVec3, Vec3},
         packets::play,
-        text::IntoText,
     },
 };
 use hyperion_inventory::PlayerInventory;
@@ -93,16 +89,11 @@ fn handle_pending_air(
         // Play particle effect for block destruction
         let center_block = destroy.position.as_dvec3() + DVec3::splat(0.5);
 
-        let particle_packet = play::ParticleS2c {
-            particle: Cow::Owned(Particle::Explosion),
-            long_distance: false,
-            position: center_block,
-            offset: Vec3::default(),
-            max_speed: 0.0,
-            count: 0,
-        };
+        let particle = agnostic::particle(Particle::Explosion, center_block)
+            .count(0)
+            .build();
 
-        compose.broadcast(&particle_packet).send().unwrap();
+        compose.broadcast(&particle).send().unwrap();
 
         let sound = agnostic::sound(
             ident!("minecraft:entity.zombie.break_wooden_door"),
@@ -200,16 +191,9 @@ fn handle_destroyed_blocks(
 
         **xp = xp.saturating_add(xp_amount);
 
-        // Create a message about the broken block
-        let msg = format!("{xp_amount}xp");
-
-        let pkt = play::GameMessageS2c {
-            chat: msg.into_cow_text(),
-            overlay: true,
-        };
-
-        // Send the message to the player
-        compose.unicast(&pkt, connection_id).unwrap();
+        // Show the xp gained above the hotbar
+        let msg = action_bar!("{xp_amount}xp");
+        compose.unicast(&msg, connection_id).unwrap();
 
         let position = event.position;
 
@@ -4,7 +4,7 @@ use bevy::prelude::*;
 use hyperion::{
     ItemKind, ItemStack,
     glam::Vec3,
-    net::Compose,
+    net::{Compose, agnostic},
     simulation::{
         Owner, Pitch, Position, SpawnEvent, Uuid, Velocity, Yaw,
         entity_kind::EntityKind,
@@ -13,10 +13,10 @@ use hyperion::{
         packet_state,
     },
 };
+use hyperion_combat::{DamageEvent, DamageSource, DamageType};
 use hyperion_inventory::PlayerInventory;
-use hyperion_utils::EntityExt;
 use tracing::{debug, error};
-use valence_protocol::{VarInt, ident, packets::play};
+use valence_protocol::ident;
 
 #[derive(Component)]
 pub struct LastFireTime {
@@ -201,13 +201,12 @@ fn handle_bow_release(
 fn arrow_entity_hit(
     mut events: EventReader<'_, '_, event::ProjectileEntityEvent>,
     compose: Res<'_, Compose>,
-    arrow_query: Query<'_, '_, (&Velocity, &Owner)>,
+    arrow_query: Query<'_, '_, (&EntityKind, &Velocity, Option<&Owner>, &Position)>,
     mut player_query: Query<'_, '_, (&Position, &mut ArrowsInEntity)>,
-    mut commands: Commands<'_, '_>,
-    mut writer: EventWriter<'_, event::AttackEntity>,
+    mut writer: EventWriter<'_, DamageEvent>,
 ) {
     for event in events.read() {
-        let (velocity, owner) = match arrow_query.get(event.projectile) {
+        let (kind, velocity, owner, &arrow_position) = match arrow_query.get(event.projectile) {
             Ok(data) => data,
             Err(e) => {
                 error!("arrow entity hit failed: arrow query failed: {e}");
@@ -215,6 +214,12 @@ fn arrow_entity_hit(
             }
         };
 
+        if !matches!(kind, EntityKind::Arrow | EntityKind::SpectralArrow) {
+            continue;
+        }
+
+        let owner = owner.map(|owner| owner.entity);
+
         let (position, mut arrows) = match player_query.get_mut(event.client) {
             Ok(data) => data,
             Err(e) => {
@@ -226,46 +231,22 @@ fn arrow_entity_hit(
         let damage = velocity.0.length() * 2.0;
         let chunk_pos = position.to_chunk();
 
-        if damage == 0.0 && owner.entity == event.client {
+        if damage == 0.0 && owner == Some(event.client) {
             continue;
         }
 
         arrows.0 += 1;
 
-        let packet = play::EntitiesDestroyS2c {
-            entity_ids: vec![VarInt(event.projectile.minecraft_id())].into(),
-        };
-
-        compose.broadcast_local(&packet, chunk_pos).send().unwrap();
+        let sound = agnostic::sound(ident!("entity.arrow.hit_player"), **position).build();
+        compose.broadcast_local(&sound, chunk_pos).send().unwrap();
 
-        commands.entity(event.projectile).despawn();
-
-        writer.write(event::AttackEntity {
-            origin: owner.entity,
-            target: event.client,
-            direction: velocity.0.normalize(),
-            damage,
-            sound: ident!("entity.arrow.hit_player"),
-            particles: None,
-        });
-    }
-}
-
-fn arrow_block_hit(
-    mut events: EventReader<'_, '_, event::ProjectileBlockEvent>,
-    mut query: Query<'_, '_, (&mut Position, &mut Velocity)>,
-) {
-    for event in events.read() {
-        let (mut position, mut velocity) = match query.get_mut(event.projectile) {
-            Ok(data) => data,
-            Err(e) => {
-                error!("arrow block hit failed: query failed: {e}");
-                continue;
-            }
+        // The arrow is removed after hitting, so its last position is used for knockback
+        let source = DamageSource {
+            position: Some(*arrow_position),
+            ..DamageSource::projectile(DamageType::Arrow, event.projectile, owner)
         };
 
-        velocity.0 = Vec3::ZERO;
-        **position = event.collision.point;
+        writer.write(DamageEvent::new(event.client, source, damage));
     }
 }
 
@@ -279,7 +260,6 @@ impl Plugin for BowPlugin {
             (
                 (handle_bow_use, handle_bow_release).chain(),
                 arrow_entity_hit,
-                arrow_block_hit,
             ),
         );
     }
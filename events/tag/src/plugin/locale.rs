@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+use hyperion_locale::Catalog;
+use tracing::error;
+
+/// The translations of tag messages, embedded so the server does not need the files at runtime
+const TRANSLATIONS: [(&str, &str); 2] = [
+    ("en_us", include_str!("../../lang/en_us.json")),
+    ("de_de", include_str!("../../lang/de_de.json")),
+];
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Catalog>();
+
+        let mut catalog = app.world_mut().resource_mut::<Catalog>();
+        for (locale, json) in TRANSLATIONS {
+            if let Err(e) = catalog.load_json(locale, json) {
+                error!("failed to load translations: {e}");
+            }
+        }
+    }
+}
@@ -1,11 +1,20 @@
 use bevy::prelude::*;
 use hyperion::{
     net::Compose,
-    simulation::{metadata::living_entity::Health, packet_state},
+    simulation::{
+        attribute::{self, Attributes},
+        effect::{ActiveEffects, Effect, EffectKind, EffectTick},
+        metadata::living_entity::Health,
+    },
 };
+use hyperion_respawn::PlayerRespawn;
 use hyperion_utils::Prev;
+use tracing::error;
 
-const MAX_HEALTH: f32 = 20.0;
+/// Tag's regeneration, which gets faster the longer a player goes without taking damage. It is a
+/// hidden effect which every player has, so it is cleared on death like any other effect and is
+/// given back on respawn.
+pub const TAG_REGENERATION: EffectKind = EffectKind::Custom(0);
 
 pub struct RegenerationPlugin;
 
@@ -14,32 +23,81 @@ pub struct LastDamaged {
     pub tick: i64,
 }
 
+const fn tag_regeneration() -> Effect {
+    Effect::new(TAG_REGENERATION, Effect::INFINITE)
+        .show_particles(false)
+        .show_icon(false)
+}
+
 fn initialize_player(
-    trigger: Trigger<'_, OnAdd, packet_state::Play>,
+    trigger: Trigger<'_, OnAdd, ActiveEffects>,
+    mut query: Query<'_, '_, &mut ActiveEffects>,
     mut commands: Commands<'_, '_>,
 ) {
-    commands
-        .entity(trigger.target())
-        .insert(LastDamaged::default());
+    let entity = trigger.target();
+
+    match query.get_mut(entity) {
+        Ok(mut effects) => {
+            effects.add(tag_regeneration());
+        }
+        Err(e) => {
+            error!("failed to initialize regeneration: query failed: {e}");
+            return;
+        }
+    }
+
+    commands.entity(entity).insert(LastDamaged::default());
+}
+
+fn restore_regeneration(
+    mut events: EventReader<'_, '_, PlayerRespawn>,
+    mut query: Query<'_, '_, &mut ActiveEffects>,
+) {
+    for event in events.read() {
+        match query.get_mut(event.entity) {
+            Ok(mut effects) => {
+                effects.add(tag_regeneration());
+            }
+            Err(e) => error!("failed to restore regeneration: query failed: {e}"),
+        }
+    }
 }
 
-fn regenerate(
-    query: Query<'_, '_, (&mut LastDamaged, &Prev<Health>, &mut Health)>,
+fn track_damage(
+    query: Query<'_, '_, (&mut LastDamaged, &Prev<Health>, &Health)>,
     compose: Res<'_, Compose>,
 ) {
     let current_tick = compose.global().tick;
 
-    for (mut last_damaged, prev_health, mut health) in query {
+    for (mut last_damaged, prev_health, health) in query {
         if *health < **prev_health {
             last_damaged.tick = current_tick;
         }
+    }
+}
 
-        let ticks_since_damage = current_tick - last_damaged.tick;
+fn regenerate(
+    mut ticks: EventReader<'_, '_, EffectTick>,
+    mut query: Query<'_, '_, (&LastDamaged, &mut Health, Option<&Attributes>)>,
+    compose: Res<'_, Compose>,
+) {
+    let current_tick = compose.global().tick;
+
+    for tick in ticks.read() {
+        if tick.effect.kind() != TAG_REGENERATION {
+            continue;
+        }
+
+        let Ok((last_damaged, mut health, attributes)) = query.get_mut(tick.entity) else {
+            continue;
+        };
 
         if health.is_dead() {
-            return;
+            continue;
         }
 
+        let ticks_since_damage = current_tick - last_damaged.tick;
+
         // Calculate regeneration rate based on time since last damage
         let base_regen = 0.01; // Base regeneration per tick
         let ramp_factor = 0.0001_f32; // Increase in regeneration per tick
@@ -49,15 +107,14 @@ fn regenerate(
             .mul_add(ticks_since_damage as f32, base_regen)
             .min(max_regen);
 
-        // Apply regeneration, capped at max health
-        health.heal(regen_rate);
-        **health = health.min(MAX_HEALTH);
+        health.heal(regen_rate, attribute::max_health(attributes));
     }
 }
 
 impl Plugin for RegenerationPlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(initialize_player);
-        app.add_systems(FixedPostUpdate, regenerate);
+        app.add_systems(FixedUpdate, restore_regeneration);
+        app.add_systems(FixedPostUpdate, (track_damage, regenerate).chain());
     }
 }
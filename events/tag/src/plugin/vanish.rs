@@ -1,11 +1,6 @@
 use bevy::prelude::*;
-use hyperion::{
-    net::Compose,
-    simulation::{Uuid, metadata::entity::EntityFlags},
-};
+use hyperion::simulation::{metadata::entity::EntityFlags, tab_list::TabListEntry};
 use tracing::error;
-use valence_protocol::packets::play::{self, player_list_s2c::PlayerListActions};
-use valence_server::GameMode;
 
 pub struct VanishPlugin;
 
@@ -26,10 +21,9 @@ impl Vanished {
 
 fn update_vanish(
     trigger: Trigger<'_, OnInsert, Vanished>,
-    compose: Res<'_, Compose>,
-    mut query: Query<'_, '_, (&Vanished, &Uuid, &mut EntityFlags)>,
+    mut query: Query<'_, '_, (&Vanished, &mut TabListEntry, &mut EntityFlags)>,
 ) {
-    let (vanished, uuid, mut flags) = match query.get_mut(trigger.target()) {
+    let (vanished, mut entry, mut flags) = match query.get_mut(trigger.target()) {
         Ok(data) => data,
         Err(e) => {
             error!("failed to update vanish: query failed: {e}");
@@ -37,41 +31,12 @@ fn update_vanish(
         }
     };
 
-    if vanished.is_vanished() {
-        // Remove from player list and make them invisible
-        let remove_packet = play::PlayerListS2c {
-            actions: PlayerListActions::new()
-                .with_update_listed(true)
-                .with_update_game_mode(true),
-            entries: vec![play::player_list_s2c::PlayerListEntry {
-                player_uuid: uuid.0,
-                listed: false,
-                game_mode: GameMode::Survival,
-                ..Default::default()
-            }]
-            .into(),
-        };
-        compose.broadcast(&remove_packet).send().unwrap();
+    // Vanished players are removed from the player list and made invisible
+    entry.listed = !vanished.is_vanished();
 
-        // Set entity flags to make them invisible
+    if vanished.is_vanished() {
         *flags |= EntityFlags::INVISIBLE;
     } else {
-        // Add back to player list and make them visible
-        let add_packet = play::PlayerListS2c {
-            actions: PlayerListActions::new()
-                .with_update_listed(true)
-                .with_update_game_mode(true),
-            entries: vec![play::player_list_s2c::PlayerListEntry {
-                player_uuid: uuid.0,
-                listed: true,
-                game_mode: GameMode::Survival,
-                ..Default::default()
-            }]
-            .into(),
-        };
-        compose.broadcast(&add_packet).send().unwrap();
-
-        // Clear invisible flag
         *flags &= !EntityFlags::INVISIBLE;
     }
 }
@@ -4,14 +4,14 @@ use bevy::prelude::*;
 use hyperion::{
     egress::player_join::{PlayerListActions, PlayerListEntry, PlayerListS2c},
     net::{Compose, ConnectionId, DataBundle},
-    simulation::event,
+    simulation::{event, game_mode::GameMode},
     valence_ident::ident,
 };
 use hyperion_utils::EntityExt;
 use tracing::error;
 use valence_bytes::Utf8Bytes;
 use valence_protocol::{
-    GameMode, VarInt,
+    VarInt,
     game_mode::OptGameMode,
     packets::play::{EntitiesDestroyS2c, PlayerRemoveS2c, PlayerRespawnS2c},
 };
@@ -27,10 +27,10 @@ impl Plugin for SkinPlugin {
 fn on_set_skin(
     mut events: EventReader<'_, '_, event::SetSkin>,
     compose: Res<'_, Compose>,
-    query: Query<'_, '_, (&ConnectionId, &hyperion::simulation::Uuid)>,
+    query: Query<'_, '_, (&ConnectionId, &hyperion::simulation::Uuid, &GameMode)>,
 ) {
     for event in events.read() {
-        let (&connection_id, uuid) = match query.get(event.by) {
+        let (&connection_id, uuid, &game_mode) = match query.get(event.by) {
             Ok(data) => data,
             Err(e) => {
                 error!("failed to set skin: query failed: {e}");
@@ -74,7 +74,7 @@ fn on_set_skin(
                     chat_data: None,
                     listed: true,
                     ping: 20,
-                    game_mode: GameMode::Survival,
+                    game_mode: game_mode.into(),
                     display_name: None,
                 }]),
             })
@@ -86,7 +86,7 @@ fn on_set_skin(
                 dimension_type_name: ident!("minecraft:overworld"),
                 dimension_name: ident!("minecraft:overworld"),
                 hashed_seed: 0,
-                game_mode: GameMode::Survival,
+                game_mode: game_mode.into(),
                 previous_game_mode: OptGameMode::default(),
                 is_debug: false,
                 is_flat: false,